@@ -99,15 +99,18 @@ impl<V: Vfs + Send + Sync + 'static + oak_vfs::WritableVfs> LanguageService for
         async move {
             let root = self.get_root(&uri).await?;
             let source = self.vfs().get_source(&uri)?;
-            let leaf = root.leaf_at_offset(range.start)?;
-            if !TokenType::is_universal(&leaf.kind, oak_core::language::UniversalTokenRole::Name) {
-                return None;
+            let text = source.get_text_in(Range { start: 0, end: source.length() });
+            let scoped = oak_navigation::ScopedReferenceFinder::find(&root, range.start, &text, uri.clone())?;
+            let name = scoped.name;
+            let mut all_refs: Vec<_> = scoped.locations.into_iter().map(|l| oak_lsp::LocationRange { uri: l.uri, range: l.range }).collect();
+            if scoped.local {
+                return Some(all_refs);
             }
-            let name = source.get_text_in(leaf.span.clone());
-            let name = name.to_string();
-            let mut all_refs = Vec::new();
             let files = self.list_all_files(&uri).await;
             for file_uri in files {
+                if file_uri == uri {
+                    continue;
+                }
                 if let Some(file_root) = self.get_root(&file_uri).await {
                     if let Some(file_source) = self.vfs().get_source(&file_uri) {
                         let full_text = file_source.get_text_in(Range { start: 0, end: file_source.length() });
@@ -105,7 +105,7 @@ impl<V: Vfs + Send + Sync + 'static + oak_vfs::WritableVfs> LanguageService for
             self.folding_provider.folding_ranges(&root).into_iter().map(|f| oak_lsp::FoldingRange { range: f.range, kind: f.kind }).collect()
         }
     }
-    fn semantic_tokens<'a>(&'a self, uri: &'a str) -> impl Future<Output = Option<oak_lsp::SemanticTokens>> + Send + 'a {
+    fn semantic_tokens<'a>(&'a self, uri: &'a str, _encoding: oak_lsp::PositionEncoding) -> impl Future<Output = Option<Vec<oak_lsp::types::SemanticToken>>> + Send + 'a {
         let uri = uri.to_string();
         async move {
             let source = self.vfs().get_source(&uri)?;
@@ -117,10 +117,7 @@ impl<V: Vfs + Send + Sync + 'static + oak_vfs::WritableVfs> LanguageService for
             let root = RedNode { green: root_green, offset: 0 };
             use oak_semantic_tokens::SemanticTokensProvider;
             let tokens = self.semantic_tokens_provider.semantic_tokens(&root, &source, &line_map);
-            Some(oak_lsp::SemanticTokens {
-                result_id: None,
-                data: tokens.into_iter().map(|t| oak_lsp::types::SemanticToken { delta_line: t.delta_line, delta_start: t.delta_start, length: t.length, token_type: t.token_type, token_modifiers_bitset: t.token_modifiers_bitset }).collect(),
-            })
+            Some(tokens.into_iter().map(|t| oak_lsp::types::SemanticToken { delta_line: t.delta_line, delta_start: t.delta_start, length: t.length, token_type: t.token_type, token_modifiers_bitset: t.token_modifiers_bitset }).collect())
         }
     }
 }
@@ -152,31 +152,25 @@ impl<V: Vfs + Send + Sync + 'static + oak_vfs::WritableVfs> LanguageService for
         async move {
             let root = self.get_root(&uri).await?;
             let source = self.vfs().get_source(&uri)?;
-            let leaf = root.leaf_at_offset(range.start)?;
-            if !leaf.kind.is_universal(oak_core::language::UniversalTokenRole::Name) {
-                return None;
+            let text = source.get_text_in(oak_core::Range { start: 0, end: source.length() });
+            let scoped = oak_navigation::ScopedReferenceFinder::find(&root, range.start, text.as_ref(), uri.clone())?;
+            let mut all_refs: Vec<_> = scoped.locations.into_iter().map(|l| oak_lsp::LocationRange { uri: l.uri, range: l.range }).collect();
+            // A name bound in an inner scope cannot be referred to from other files
+            if scoped.local {
+                return Some(all_refs);
             }
-            let name = source.get_text_in(leaf.span.clone());
-            let name = name.to_string();
-            // Search in all files in the workspace
+            // Search in all other files in the workspace
             // Note: In a real LSP, we would use an index for performance
-            let mut all_refs = Vec::new();
-            // Get all workspace folders or just list files from root
-            // For this example, we'll just search the current file and any other files we can find
             let files = self.list_all_files(&uri).await; // This is a bit hacky as it uses current file as root
             for file_uri in files {
+                if file_uri == uri {
+                    continue;
+                }
                 if let Some(file_root) = self.get_root(&file_uri).await {
                     if let Some(file_source) = self.vfs().get_source(&file_uri) {
                         let source_ref: &dyn oak_core::Source = &file_source;
-                        // For SimpleReferenceFinder::find, we need the text source content as a string slice
-                        // But SimpleReferenceFinder might expect different arguments based on the error
-                        // "expected `&str`, found `&<V as Vfs>::Source`"
-                        // This implies SimpleReferenceFinder::find expects `&str` for source.
-                        // We need to get the full text from the source.
-                        let source_len = source_ref.length();
-                        let full_text = source_ref.get_text_in(oak_core::Range { start: 0, end: source_len });
-                        let full_text_str = full_text.as_ref();
-                        let refs = oak_navigation::SimpleReferenceFinder::find(&file_root, &name, full_text_str, file_uri.clone());
+                        let full_text = source_ref.get_text_in(oak_core::Range { start: 0, end: source_ref.length() });
+                        let refs = oak_navigation::SimpleReferenceFinder::find(&file_root, &scoped.name, full_text.as_ref(), file_uri.clone());
                         all_refs.extend(refs.into_iter().map(|l| oak_lsp::LocationRange { uri: l.uri, range: l.range }));
                     }
                 }
@@ -0,0 +1,124 @@
+use oak_core::{
+    Language,
+    errors::OakErrorKind,
+    lexer::{LexOutput, LexerCache, Token},
+    memory::arena::SyntaxArena,
+    parser::{ParseCache, ParseSession, Parser},
+    source::SourceText,
+    tree::GreenNode,
+};
+
+/// What a parser would accept at the end of a text, see [`expected_at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected<K> {
+    /// The token kinds the parser tested for at the end of the text before its first error there.
+    pub kinds: Vec<K>,
+    /// The description of the first error at the end of the text if it is an `expected token` error, like `statement`.
+    pub token: Option<String>,
+    /// The token kind of that error, if the parser expected a specific kind rather than describing what it expected.
+    pub kind: Option<K>,
+    /// Whether the first error at the end of the text is an `expected name` error.
+    pub name: bool,
+}
+
+impl<K> Expected<K> {
+    /// Checks whether nothing was expected, e.g. because the parser ignores the end of the input.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty() && self.token.is_none() && self.kind.is_none() && !self.name
+    }
+}
+
+/// A parse cache that keeps the token kinds the parser expected at the end of the input.
+struct ExpectationCache<L: Language + Send + Sync> {
+    session: ParseSession<L>,
+    expected: Vec<L::TokenType>,
+    kind: Option<L::TokenType>,
+}
+
+impl<L: Language + Send + Sync> LexerCache<L> for ExpectationCache<L> {
+    fn set_lex_output(&mut self, output: LexOutput<L>) {
+        self.session.set_lex_output(output)
+    }
+
+    fn get_token(&self, index: usize) -> Option<Token<L::TokenType>> {
+        self.session.get_token(index)
+    }
+
+    fn count_tokens(&self) -> usize {
+        self.session.count_tokens()
+    }
+
+    fn has_tokens(&self) -> bool {
+        self.session.has_tokens()
+    }
+
+    fn get_tokens(&self) -> Option<&[Token<L::TokenType>]> {
+        self.session.get_tokens()
+    }
+}
+
+impl<L: Language + Send + Sync> ParseCache<L> for ExpectationCache<L> {
+    fn arena(&self) -> &SyntaxArena {
+        self.session.arena()
+    }
+
+    fn old_tree(&self) -> Option<&GreenNode<'_, L>> {
+        self.session.old_tree()
+    }
+
+    fn lex_output(&self) -> Option<&LexOutput<L>> {
+        self.session.lex_output()
+    }
+
+    fn prepare_generation(&mut self) {
+        self.session.prepare_generation()
+    }
+
+    fn commit_generation(&self, root: &GreenNode<L>) {
+        self.session.commit_generation(root)
+    }
+
+    fn wants_expected(&self) -> bool {
+        true
+    }
+
+    fn record_expected(&mut self, expected: &[L::TokenType]) {
+        self.expected = expected.to_vec()
+    }
+
+    fn record_expected_token(&mut self, kind: L::TokenType) {
+        self.kind = Some(kind)
+    }
+}
+
+/// Parses `text`, typically the text before a cursor, and returns what the parser would accept at its end.
+///
+/// The token kinds come from [`ParserState::expected_at_end`](oak_core::parser::ParserState::expected_at_end),
+/// so they are only known for parsers built on [`parse_with_lexer`](oak_core::parser::parse_with_lexer).
+/// The description comes from the first error the parser records at the end of the text with
+/// [`record_expected`](oak_core::parser::ParserState::record_expected) and
+/// [`record_expected_name`](oak_core::parser::ParserState::record_expected_name), and its token kind
+/// from [`ParserState::expected_token_at_end`](oak_core::parser::ParserState::expected_token_at_end).
+pub fn expected_at<L, P>(parser: &P, text: &str) -> Expected<L::TokenType>
+where
+    L: Language + Send + Sync,
+    L::ElementType: From<L::TokenType>,
+    P: Parser<L> + ?Sized,
+{
+    let source = SourceText::new(text);
+    let mut cache = ExpectationCache { session: ParseSession::default(), expected: Vec::new(), kind: None };
+    let mut token = None;
+    let mut name = false;
+    let diagnostics = parser.parse(&source, &[], &mut cache).diagnostics;
+    // Errors after the first one at the end are fallout of the parser recovering from it.
+    let first = diagnostics.iter().find(|error| match error.kind() {
+        OakErrorKind::ExpectedToken { offset, .. } | OakErrorKind::ExpectedName { offset, .. } | OakErrorKind::UnexpectedToken { offset, .. } | OakErrorKind::SyntaxError { offset, .. } | OakErrorKind::UnexpectedEof { offset, .. } => *offset >= text.len(),
+        _ => false,
+    });
+    match first.map(|error| error.kind()) {
+        Some(OakErrorKind::ExpectedToken { expected, .. }) => token = Some(expected.clone()),
+        Some(OakErrorKind::ExpectedName { .. }) => name = true,
+        _ => {}
+    }
+    Expected { kinds: cache.expected, token, kind: cache.kind, name }
+}
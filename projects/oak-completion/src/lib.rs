@@ -0,0 +1,362 @@
+#![feature(new_range_api)]
+#![warn(missing_docs)]
+#![doc = include_str!("../readme.md")]
+//! Completion support for the Oak language framework.
+//!
+//! A [`UniversalCompletionProvider`] offers, at a cursor,
+//! - the keywords the parser would accept there, found by re-parsing the text before the cursor
+//!   with [`expected_at`],
+//! - the names in scope there, from the scope graph of [`UniversalScopeProvider`],
+//! - the [`Snippet`] templates of the language,
+//!
+//! ranked by how well they match the word being typed.
+
+use core::range::Range;
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    parser::Parser,
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_hover::UniversalHoverProvider;
+use oak_resolver::{BindingKind, ScopeGraph, ScopeId, ScopeProvider, UniversalScopeProvider, fuzzy_match};
+use oak_structural_view::StructureKind;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+mod expected;
+pub use expected::{Expected, expected_at};
+
+/// What a completion inserts, which editors show as an icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompletionKind {
+    /// A keyword of the language.
+    Keyword,
+    /// A snippet template.
+    Snippet,
+    /// A function or method.
+    Function,
+    /// A type, like a class, a struct or an enum.
+    Type,
+    /// A module or namespace, or an imported name.
+    Module,
+    /// A local variable or parameter.
+    Variable,
+    /// A field or property.
+    Field,
+    /// A constant.
+    Constant,
+}
+
+impl From<StructureKind> for CompletionKind {
+    fn from(kind: StructureKind) -> Self {
+        match kind {
+            StructureKind::Function | StructureKind::Method | StructureKind::Constructor => CompletionKind::Function,
+            StructureKind::Class | StructureKind::Struct | StructureKind::Enum | StructureKind::Interface => CompletionKind::Type,
+            StructureKind::Module => CompletionKind::Module,
+            StructureKind::Field => CompletionKind::Field,
+            StructureKind::Constant => CompletionKind::Constant,
+            StructureKind::Variable => CompletionKind::Variable,
+        }
+    }
+}
+
+impl From<UniversalElementRole> for CompletionKind {
+    fn from(role: UniversalElementRole) -> Self {
+        StructureKind::from(role).into()
+    }
+}
+
+impl CompletionKind {
+    /// Returns the kind of a definition from its role, like [`StructureKind::of_role`].
+    pub fn of_role<R: ElementRole>(role: R) -> Self {
+        StructureKind::of_role(role).into()
+    }
+}
+
+/// A snippet template, like `fn ${1:name}($2) {\n\t$0\n}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    /// The word the snippet is found by.
+    pub label: String,
+    /// The body in the LSP snippet syntax, with `$1`, `${1:placeholder}` and `$0` tab stops.
+    pub body: String,
+    /// A short description of the snippet.
+    pub detail: Option<String>,
+}
+
+impl Snippet {
+    /// Creates a snippet.
+    pub fn new(label: impl Into<String>, body: impl Into<String>) -> Self {
+        Self { label: label.into(), body: body.into(), detail: None }
+    }
+
+    /// Sets the description of the snippet.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// A completion at a cursor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Completion {
+    /// The text shown in the list, and inserted unless there is an [`insert_text`](Self::insert_text).
+    pub label: String,
+    /// What the completion inserts.
+    pub kind: CompletionKind,
+    /// A short description, like the signature of a function.
+    pub detail: Option<String>,
+    /// The text to insert, a snippet body if [`kind`](Self::kind) is [`Snippet`](CompletionKind::Snippet).
+    pub insert_text: Option<String>,
+    /// The range of the word being typed, which the completion replaces.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// How well the completion matches the context and the word being typed; higher is better.
+    pub score: i32,
+}
+
+/// Trait for languages that support completion.
+pub trait CompletionProvider<L: Language> {
+    /// Returns the completions at `offset`, best first.
+    fn completions<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, offset: usize) -> Vec<Completion>;
+}
+
+/// The bonus of a label that starts with the typed word.
+const PREFIX: i32 = 10_000;
+/// The bonus of a label that starts with the typed word, ignoring case.
+const PREFIX_IGNORE_CASE: i32 = 5_000;
+/// The bonus of a local variable, minus one for every scope between it and the cursor.
+const LOCAL: i32 = 60;
+/// The bonus of a keyword the parser expects.
+const EXPECTED_KEYWORD: i32 = 50;
+/// The bonus of an item, like a function.
+const ITEM: i32 = 40;
+/// The bonus of an imported name.
+const IMPORT: i32 = 30;
+/// The bonus of a snippet.
+const SNIPPET: i32 = 10;
+
+/// Parses the text before a cursor and returns what the parser would accept at its end.
+type Expectations<K> = Arc<dyn Fn(&str) -> Expected<K> + Send + Sync>;
+
+/// A completion provider that works for any language whose kinds implement `role()`.
+///
+/// The word being typed is the run of alphanumeric characters and `_` before the cursor. No
+/// completions are offered inside comments and literals.
+///
+/// With a [parser](Self::with_parser), the text before the word is re-parsed to find what the
+/// parser would accept there:
+/// - the [keywords](Self::with_keyword) whose token kinds it tests for,
+/// - names, if it tests for a [`Name`](UniversalTokenRole::Name) token or records an expected name,
+/// - everything, if it records an expected token that is not a keyword, like `expression`.
+///
+/// Without a parser, or if the parser expects nothing, everything is offered. Without a keyword
+/// table, the keywords are the distinct [`Keyword`](UniversalTokenRole::Keyword) tokens of the file.
+///
+/// Completions that start with the word rank first, then those that start with it ignoring case,
+/// then fuzzy matches by their [`fuzzy_match`] score. Among equal matches, closer locals rank above
+/// expected keywords, items, imports, snippets and other keywords.
+pub struct UniversalCompletionProvider<L: Language> {
+    keywords: Vec<(L::TokenType, String)>,
+    snippets: Vec<Snippet>,
+    expectations: Option<Expectations<L::TokenType>>,
+}
+
+impl<L: Language> Clone for UniversalCompletionProvider<L> {
+    fn clone(&self) -> Self {
+        Self { keywords: self.keywords.clone(), snippets: self.snippets.clone(), expectations: self.expectations.clone() }
+    }
+}
+
+impl<L: Language> Default for UniversalCompletionProvider<L> {
+    fn default() -> Self {
+        Self { keywords: Vec::new(), snippets: Vec::new(), expectations: None }
+    }
+}
+
+impl<L: Language> UniversalCompletionProvider<L> {
+    /// Creates a provider without a parser, keywords or snippets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a keyword and the token kind the parser tests for to accept it.
+    pub fn with_keyword(mut self, kind: L::TokenType, text: impl Into<String>) -> Self {
+        self.keywords.push((kind, text.into()));
+        self
+    }
+
+    /// Adds keywords and their token kinds.
+    pub fn with_keywords<T: Into<String>>(mut self, keywords: impl IntoIterator<Item = (L::TokenType, T)>) -> Self {
+        self.keywords.extend(keywords.into_iter().map(|(kind, text)| (kind, text.into())));
+        self
+    }
+
+    /// Adds a snippet template.
+    pub fn with_snippet(mut self, snippet: Snippet) -> Self {
+        self.snippets.push(snippet);
+        self
+    }
+
+    /// Sets the parser that finds out what is expected at the cursor.
+    pub fn with_parser<P>(mut self, parser: P) -> Self
+    where
+        L: Send + Sync + 'static,
+        L::ElementType: From<L::TokenType>,
+        P: Parser<L> + Send + Sync + 'static,
+    {
+        self.expectations = Some(Arc::new(move |text: &str| expected_at(&parser, text)));
+        self
+    }
+
+    /// Returns the keywords and snippets to offer, and whether to offer names.
+    fn context<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, before: &str) -> (Vec<(String, bool)>, Vec<&Snippet>, bool) {
+        let mut table = self.keywords.clone();
+        if table.is_empty() {
+            let mut found = Vec::new();
+            leaves(root, &mut |leaf| {
+                if leaf.kind.role().universal() == UniversalTokenRole::Keyword {
+                    found.push((leaf.kind, source.get_text_in(leaf.span).into_owned()))
+                }
+            });
+            table = found
+        }
+        let expected = self.expectations.as_ref().map(|expectations| expectations(before)).filter(|expected| !expected.is_empty());
+        let Some(expected) = expected
+        else {
+            return (dedup(table.into_iter().map(|(_, text)| (text, false))), self.snippets.iter().collect(), true);
+        };
+
+        // The parser either expected a token kind, or described what it expected, like `'fn'` or `expression`.
+        let described = |text: &str| expected.token.as_deref().is_some_and(|token| token.trim_matches(['`', '\'', '"']).eq_ignore_ascii_case(text));
+        let broad = expected.kind.is_none() && expected.token.is_some() && !table.iter().any(|(_, text)| described(text));
+        let names = broad || expected.name || expected.kinds.iter().any(|kind| kind.role().universal() == UniversalTokenRole::Name);
+        let keywords: Vec<(String, bool)> = table
+            .into_iter()
+            .filter_map(|(kind, text)| {
+                let wanted = expected.kinds.contains(&kind) || expected.kind == Some(kind) || described(&text);
+                (broad || wanted).then_some((text, wanted))
+            })
+            .collect();
+        let keywords = dedup(keywords);
+        let snippets = self.snippets.iter().filter(|snippet| broad || keywords.iter().any(|(text, _)| *text == snippet.label)).collect();
+        (keywords, snippets, names)
+    }
+}
+
+impl<L: Language> CompletionProvider<L> for UniversalCompletionProvider<L> {
+    fn completions<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, offset: usize) -> Vec<Completion> {
+        let offset = offset.min(source.length());
+        if let Some(leaf) = offset.checked_sub(1).and_then(|before| root.leaf_at_offset(before))
+            && matches!(leaf.kind.role().universal(), UniversalTokenRole::Comment | UniversalTokenRole::Literal)
+            && offset < leaf.span.end
+        {
+            return vec![];
+        }
+        let before = source.get_text_in(Range { start: 0, end: offset });
+        let start = before.char_indices().rev().take_while(|(_, c)| c.is_alphanumeric() || *c == '_').last().map_or(offset, |(index, _)| index);
+        let word = &before[start..];
+        let range = Range { start, end: offset };
+        let (keywords, snippets, names) = self.context(root, source, &before[..start]);
+
+        let mut completions: Vec<Completion> = Vec::new();
+        let mut push = |label: &str, kind: CompletionKind, detail: Option<String>, insert_text: Option<String>, bonus: i32| {
+            if let Some(score) = rank(word, label)
+                && !completions.iter().any(|completion| completion.label == label && completion.kind == kind)
+            {
+                completions.push(Completion { label: label.to_string(), kind, detail, insert_text, range, score: score + bonus })
+            }
+        };
+        if names {
+            let graph = UniversalScopeProvider.scope_graph(root, &source);
+            let scope = graph.scope_at(offset);
+            for id in graph.visible_bindings(offset) {
+                let binding = graph.binding(id);
+                if binding.range.start <= offset && offset <= binding.range.end {
+                    continue;
+                }
+                let (kind, detail, bonus) = match &binding.kind {
+                    BindingKind::Local => (CompletionKind::Variable, None, LOCAL - depth(&graph, scope, binding.scope)),
+                    BindingKind::Item => (item_kind(root, binding.definition_range), UniversalHoverProvider.declaration_signature(root, source, binding.range), ITEM),
+                    BindingKind::Import { path } => (CompletionKind::Module, Some(path.clone()), IMPORT),
+                };
+                push(&binding.name, kind, detail, None, bonus)
+            }
+        }
+        for (text, wanted) in &keywords {
+            push(text, CompletionKind::Keyword, None, None, if *wanted { EXPECTED_KEYWORD } else { 0 })
+        }
+        for snippet in snippets {
+            push(&snippet.label, CompletionKind::Snippet, snippet.detail.clone(), Some(snippet.body.clone()), SNIPPET)
+        }
+        completions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.label.cmp(&b.label)));
+        completions
+    }
+}
+
+/// Scores how well a label matches the typed word, or returns `None` if it does not.
+fn rank(word: &str, label: &str) -> Option<i32> {
+    if word.is_empty() {
+        return Some(0);
+    }
+    let score = fuzzy_match(word, label)?.score;
+    if label.starts_with(word) {
+        Some(PREFIX + score)
+    }
+    else if label.to_lowercase().starts_with(&word.to_lowercase()) {
+        Some(PREFIX_IGNORE_CASE + score)
+    }
+    else {
+        Some(score)
+    }
+}
+
+/// Returns the number of scopes between `inner` and its ancestor `outer`.
+fn depth(graph: &ScopeGraph, inner: ScopeId, outer: ScopeId) -> i32 {
+    let mut depth = 0;
+    let mut current = Some(inner);
+    while let Some(scope) = current
+        && scope != outer
+    {
+        depth += 1;
+        current = graph.scope(scope).parent
+    }
+    depth
+}
+
+/// Removes the keywords with the same text as an earlier one, keeping whether any was expected.
+fn dedup(keywords: impl IntoIterator<Item = (String, bool)>) -> Vec<(String, bool)> {
+    let mut unique: Vec<(String, bool)> = Vec::new();
+    for (text, wanted) in keywords {
+        match unique.iter_mut().find(|(other, _)| *other == text) {
+            Some((_, other)) => *other |= wanted,
+            None => unique.push((text, wanted)),
+        }
+    }
+    unique
+}
+
+/// Calls `f` on every leaf under `node`.
+fn leaves<L: Language>(node: &RedNode<L>, f: &mut impl FnMut(RedLeaf<L>)) {
+    for child in node.children() {
+        match child {
+            RedTree::Node(child) => leaves(&child, f),
+            RedTree::Leaf(leaf) => f(leaf),
+        }
+    }
+}
+
+/// Returns the kind of the definition spanning `range`.
+fn item_kind<L: Language>(root: &RedNode<L>, range: Range<usize>) -> CompletionKind {
+    let mut node = *root;
+    loop {
+        if node.span() == range && node.green.kind.role().universal() == UniversalElementRole::Definition {
+            return CompletionKind::of_role(node.green.kind.role());
+        }
+        match node.children().filter_map(|child| child.as_node()).find(|child| child.span().start <= range.start && range.end <= child.span().end) {
+            Some(child) => node = child,
+            None => return CompletionKind::Function,
+        }
+    }
+}
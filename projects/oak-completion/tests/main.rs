@@ -0,0 +1,369 @@
+use core::range::Range;
+use oak_completion::{CompletionKind, CompletionProvider, Snippet, UniversalCompletionProvider, expected_at};
+use oak_core::{
+    Language,
+    language::{ElementRole, ElementType, TokenType, UniversalElementRole, UniversalTokenRole},
+    lexer::{LexOutput, Lexer, LexerCache, Token},
+    memory::arena::SyntaxArena,
+    parser::{Arc, ParseCache, ParseOutput, ParseSession, Parser, ParserState, parse_with_lexer},
+    source::{Source, SourceText, TextEdit},
+    tree::RedNode,
+};
+
+/// A tiny language with functions, `let`, `return` and `if` statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tok {
+    Fn,
+    Let,
+    Return,
+    If,
+    Name,
+    Number,
+    Str,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Eq,
+    Semi,
+    Whitespace,
+    Comment,
+    Error,
+    Eof,
+}
+
+impl TokenType for Tok {
+    type Role = UniversalTokenRole;
+    const END_OF_STREAM: Self = Tok::Eof;
+
+    fn role(&self) -> Self::Role {
+        match self {
+            Tok::Fn | Tok::Let | Tok::Return | Tok::If => UniversalTokenRole::Keyword,
+            Tok::Name => UniversalTokenRole::Name,
+            Tok::Number | Tok::Str => UniversalTokenRole::Literal,
+            Tok::Eq => UniversalTokenRole::Operator,
+            Tok::Whitespace => UniversalTokenRole::Whitespace,
+            Tok::Comment => UniversalTokenRole::Comment,
+            Tok::Error => UniversalTokenRole::Error,
+            Tok::Eof => UniversalTokenRole::Eof,
+            _ => UniversalTokenRole::Punctuation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    Root,
+    Function,
+    Params,
+    Param,
+    Block,
+    Let,
+    Pattern,
+    Return,
+    If,
+    ExprStmt,
+    NameRef,
+    Literal,
+    Error,
+}
+
+impl ElementType for Node {
+    type Role = UniversalElementRole;
+
+    fn role(&self) -> Self::Role {
+        match self {
+            Node::Root => UniversalElementRole::Root,
+            Node::Function => UniversalElementRole::Definition,
+            Node::Params => UniversalElementRole::Detail,
+            Node::Param | Node::Pattern => UniversalElementRole::Binding,
+            Node::Block => UniversalElementRole::Container,
+            Node::Let | Node::Return | Node::If | Node::ExprStmt => UniversalElementRole::Statement,
+            Node::NameRef => UniversalElementRole::Reference,
+            Node::Literal => UniversalElementRole::Value,
+            Node::Error => UniversalElementRole::Error,
+        }
+    }
+}
+
+impl From<Tok> for Node {
+    fn from(_: Tok) -> Self {
+        Node::Error
+    }
+}
+
+struct Mini;
+
+impl Language for Mini {
+    const NAME: &'static str = "mini";
+    type TokenType = Tok;
+    type ElementType = Node;
+    type TypedRoot = ();
+}
+
+struct MiniLexer;
+
+impl Lexer<Mini> for MiniLexer {
+    fn lex<S: Source + ?Sized>(&self, text: &S, _edits: &[TextEdit], _cache: &mut impl LexerCache<Mini>) -> LexOutput<Mini> {
+        let text = text.get_text_in(Range { start: 0, end: text.length() });
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        while let Some(c) = text[start..].chars().next() {
+            let rest = &text[start..];
+            let run = |f: &dyn Fn(char) -> bool| rest.find(|c: char| !f(c)).unwrap_or(rest.len());
+            let (kind, len) = match c {
+                c if c.is_whitespace() => (Tok::Whitespace, run(&char::is_whitespace)),
+                '/' if rest.starts_with("//") => (Tok::Comment, run(&|c| c != '\n')),
+                '"' => (Tok::Str, rest[1..].find('"').map_or(rest.len(), |end| end + 2)),
+                c if c.is_ascii_digit() => (Tok::Number, run(&|c| c.is_ascii_digit())),
+                c if c.is_alphabetic() || c == '_' => {
+                    let len = run(&|c| c.is_alphanumeric() || c == '_');
+                    let kind = match &rest[..len] {
+                        "fn" => Tok::Fn,
+                        "let" => Tok::Let,
+                        "return" => Tok::Return,
+                        "if" => Tok::If,
+                        _ => Tok::Name,
+                    };
+                    (kind, len)
+                }
+                '(' => (Tok::LParen, 1),
+                ')' => (Tok::RParen, 1),
+                '{' => (Tok::LBrace, 1),
+                '}' => (Tok::RBrace, 1),
+                ',' => (Tok::Comma, 1),
+                '=' => (Tok::Eq, 1),
+                ';' => (Tok::Semi, 1),
+                c => (Tok::Error, c.len_utf8()),
+            };
+            tokens.push(Token { kind, span: Range { start, end: start + len } });
+            start += len
+        }
+        tokens.push(Token { kind: Tok::Eof, span: Range { start: text.len(), end: text.len() } });
+        LexOutput::<Mini> { result: Ok(Arc::from(tokens)), diagnostics: Vec::new() }
+    }
+}
+
+struct MiniParser;
+
+impl Parser<Mini> for MiniParser {
+    fn parse<'a, S: Source + ?Sized>(&self, text: &'a S, edits: &[TextEdit], cache: &'a mut impl ParseCache<Mini>) -> ParseOutput<'a, Mini> {
+        parse_with_lexer(&MiniLexer, text, edits, cache, |state| {
+            let checkpoint = state.checkpoint();
+            items(state, None);
+            Ok(state.finish_at(checkpoint, Node::Root))
+        })
+    }
+}
+
+/// Parses statements until `end` or the end of the input.
+fn items<S: Source + ?Sized>(state: &mut ParserState<'_, Mini, S>, end: Option<Tok>) {
+    loop {
+        if statement(state) {
+            continue;
+        }
+        if end.is_some_and(|end| state.at(end)) || state.at(Tok::Eof) || !state.not_at_end() {
+            break;
+        }
+        let checkpoint = state.checkpoint();
+        state.bump();
+        state.finish_at(checkpoint, Node::Error);
+    }
+}
+
+/// Parses a statement, or returns `false` if none starts here.
+fn statement<S: Source + ?Sized>(state: &mut ParserState<'_, Mini, S>) -> bool {
+    let checkpoint = state.checkpoint();
+    let kind = if state.eat(Tok::Fn) {
+        name(state, None);
+        let params = state.checkpoint();
+        let _ = state.expect(Tok::LParen);
+        while state.at(Tok::Name) {
+            name(state, Some(Node::Param));
+            state.eat(Tok::Comma);
+        }
+        let _ = state.expect(Tok::RParen);
+        state.finish_at(params, Node::Params);
+        block(state);
+        Node::Function
+    }
+    else if state.eat(Tok::Let) {
+        name(state, Some(Node::Pattern));
+        let _ = state.expect(Tok::Eq);
+        expression(state);
+        let _ = state.expect(Tok::Semi);
+        Node::Let
+    }
+    else if state.eat(Tok::Return) {
+        expression(state);
+        let _ = state.expect(Tok::Semi);
+        Node::Return
+    }
+    else if state.eat(Tok::If) {
+        expression(state);
+        block(state);
+        Node::If
+    }
+    else if state.at(Tok::Name) || state.at(Tok::Number) || state.at(Tok::Str) {
+        expression(state);
+        let _ = state.expect(Tok::Semi);
+        Node::ExprStmt
+    }
+    else {
+        return false;
+    };
+    state.finish_at(checkpoint, kind);
+    true
+}
+
+/// Parses a name, wrapped in a `binding` node if given.
+fn name<S: Source + ?Sized>(state: &mut ParserState<'_, Mini, S>, binding: Option<Node>) {
+    if !state.at(Tok::Name) {
+        return state.record_expected_name("name");
+    }
+    let checkpoint = state.checkpoint();
+    state.bump();
+    if let Some(binding) = binding {
+        state.finish_at(checkpoint, binding);
+    }
+}
+
+/// Parses a name or a literal.
+fn expression<S: Source + ?Sized>(state: &mut ParserState<'_, Mini, S>) {
+    let checkpoint = state.checkpoint();
+    if state.eat(Tok::Name) {
+        state.finish_at(checkpoint, Node::NameRef);
+    }
+    else if state.eat(Tok::Number) || state.eat(Tok::Str) {
+        state.finish_at(checkpoint, Node::Literal);
+    }
+    else {
+        state.record_expected("expression")
+    }
+}
+
+/// Parses a braced block of statements.
+fn block<S: Source + ?Sized>(state: &mut ParserState<'_, Mini, S>) {
+    let checkpoint = state.checkpoint();
+    let _ = state.expect(Tok::LBrace);
+    items(state, Some(Tok::RBrace));
+    let _ = state.expect(Tok::RBrace);
+    state.finish_at(checkpoint, Node::Block);
+}
+
+fn provider() -> UniversalCompletionProvider<Mini> {
+    UniversalCompletionProvider::new()
+        .with_parser(MiniParser)
+        .with_keywords([(Tok::Fn, "fn"), (Tok::Let, "let"), (Tok::Return, "return"), (Tok::If, "if")])
+        .with_snippet(Snippet::new("fn", "fn ${1:name}($2) {\n\t$0\n}").with_detail("function"))
+        .with_snippet(Snippet::new("main", "fn main() {\n\t$0\n}"))
+}
+
+/// Returns the labels and kinds of the completions at the `|` in `text`.
+fn complete(provider: &UniversalCompletionProvider<Mini>, text: &str) -> Vec<(String, CompletionKind)> {
+    let offset = text.find('|').unwrap();
+    let text = text.replace('|', "");
+    let source = SourceText::new(text.as_str());
+    let mut session = ParseSession::<Mini>::default();
+    let green = MiniParser.parse(&source, &[], &mut session).result.unwrap();
+    let root = RedNode::new(green, 0);
+    provider.completions(&root, &source, offset).into_iter().map(|completion| (completion.label, completion.kind)).collect()
+}
+
+fn labels(completions: &[(String, CompletionKind)]) -> Vec<&str> {
+    completions.iter().map(|(label, _)| label.as_str()).collect()
+}
+
+#[test]
+fn test_expected_at() {
+    let expected = expected_at(&MiniParser, "fn add(a) { ");
+    for kind in [Tok::Fn, Tok::Let, Tok::Return, Tok::If, Tok::Name, Tok::RBrace] {
+        assert!(expected.kinds.contains(&kind), "{:?}", kind);
+    }
+    assert_eq!(expected.token.as_deref(), Some("RBrace"));
+    assert_eq!(expected.kind, Some(Tok::RBrace));
+    assert!(!expected.name);
+
+    let expected = expected_at(&MiniParser, "let ");
+    assert_eq!(expected.kinds, [Tok::Name]);
+    assert!(expected.name);
+    let expected = expected_at(&MiniParser, "return ");
+    assert_eq!((expected.token.as_deref(), expected.kind), (Some("expression"), None));
+
+    // Parsers only remember what they test for at the end when asked to.
+    let source = SourceText::new("let ");
+    let output = MiniLexer.lex(&source, &[], &mut ParseSession::<Mini>::default());
+    let arena = SyntaxArena::new(16);
+    let mut state = ParserState::<Mini>::new(&arena, output, &source, 16);
+    state.bump();
+    assert!(!state.at(Tok::Name) && state.expected_at_end().is_empty());
+    state.track_expected();
+    assert!(!state.at(Tok::Name));
+    assert_eq!(state.expected_at_end(), [Tok::Name]);
+}
+
+#[test]
+fn test_completion() {
+    let provider = provider();
+    let text = "fn add(a, b) {\n    let total = a;\n    |\n}\n";
+
+    // At the start of a statement: locals, closest first, then the expected keywords, the
+    // function, and the snippet for an expected keyword.
+    let completions = complete(&provider, text);
+    assert_eq!(labels(&completions), ["total", "a", "b", "fn", "if", "let", "return", "add", "fn"]);
+    assert_eq!(completions[0].1, CompletionKind::Variable);
+    assert_eq!(completions[7].1, CompletionKind::Function);
+    assert_eq!(completions[8].1, CompletionKind::Snippet);
+
+    // Prefix matches come first, fuzzy matches last.
+    assert_eq!(labels(&complete(&provider, &text.replace('|', "re|"))), ["return"]);
+    assert_eq!(labels(&complete(&provider, &text.replace('|', "T|"))), ["total", "let", "return"]);
+    assert_eq!(labels(&complete(&provider, "fn add(a) { let x = a; }\nfn other(alpha) { let y = A|; }")), ["alpha", "add", "main"]);
+
+    // After `let` only a name is expected, and in an expression everything is offered.
+    assert!(complete(&provider, "fn f(a) { let | }").iter().all(|(_, kind)| *kind != CompletionKind::Keyword));
+    let completions = complete(&provider, "fn f(a) { return | }");
+    assert!(completions.contains(&("a".to_string(), CompletionKind::Variable)));
+    assert!(completions.contains(&("main".to_string(), CompletionKind::Snippet)));
+
+    // Nothing in comments and strings.
+    assert!(complete(&provider, "fn f() {\n    // ret|urn\n}").is_empty());
+    assert!(complete(&provider, "let s = \"ret|urn\";").is_empty());
+
+    // Without a parser or a keyword table, the keywords of the file are offered.
+    let plain = UniversalCompletionProvider::<Mini>::new();
+    let completions = complete(&plain, "fn f(a) { let b = a; }\n|");
+    assert_eq!(labels(&completions), ["f", "fn", "let"]);
+    assert_eq!(completions[0].1, CompletionKind::Function);
+
+    // Items are described by their signature, up to their last detail.
+    let source = SourceText::new("fn add(a,\n       b) { a; }\nad");
+    let mut session = ParseSession::<Mini>::default();
+    let green = MiniParser.parse(&source, &[], &mut session).result.unwrap();
+    let completions = provider.completions(&RedNode::new(green, 0), &source, source.length());
+    assert_eq!(completions[0].detail.as_deref(), Some("fn add(a, b)"));
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Role(&'static str);
+
+impl ElementRole for Role {
+    fn universal(&self) -> UniversalElementRole {
+        UniversalElementRole::Definition
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+#[test]
+fn test_completion_kind_of_role() {
+    assert_eq!(CompletionKind::of_role(UniversalElementRole::Definition), CompletionKind::Function);
+    assert_eq!(CompletionKind::of_role(UniversalElementRole::Typing), CompletionKind::Type);
+    assert_eq!(CompletionKind::from(UniversalElementRole::Container), CompletionKind::Module);
+    // Later segments of the name are more specific, like in the structure view.
+    assert_eq!(CompletionKind::of_role(Role("entity.name.type.struct")), CompletionKind::Type);
+    assert_eq!(CompletionKind::of_role(Role("type.field")), CompletionKind::Field);
+}
@@ -22,6 +22,26 @@ pub enum LanguageCategory {
     Other,
 }
 
+/// Returns the index of the language a file belongs to by its name, given the file extensions
+/// (without the dot, e.g. `"rs"` or `"d.ts"`) and complete file names (e.g. `"cargo.lock"`) of
+/// each language, in lower case.
+///
+/// The file name is the last segment of `path`, compared ignoring case. When several languages
+/// match, the one with the longest match wins, and among equal matches the first one.
+pub fn language_for_file<'a, E: AsRef<str> + 'a>(languages: impl IntoIterator<Item = &'a [E]>, path: &str) -> Option<usize> {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_lowercase();
+    let mut best: Option<(usize, usize)> = None;
+    for (index, extensions) in languages.into_iter().enumerate() {
+        let length = extensions.iter().map(AsRef::as_ref).filter(|ext| file_name == *ext || (file_name.len() > ext.len() && file_name.ends_with(ext) && file_name.as_bytes()[file_name.len() - ext.len() - 1] == b'.')).map(str::len).max();
+        if let Some(length) = length
+            && best.is_none_or(|(_, best_length)| length > best_length)
+        {
+            best = Some((index, length))
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
 /// Language definition trait that coordinates all language-related types and behaviors.
 ///
 /// This trait serves as the foundation for defining programming languages within the
@@ -394,6 +414,17 @@ impl TokenRole for UniversalTokenRole {
     }
 }
 
+/// Universal token roles can be used as token kinds of their own, for trees and services
+/// that are not tied to a single language.
+impl TokenType for UniversalTokenRole {
+    type Role = UniversalTokenRole;
+    const END_OF_STREAM: Self = UniversalTokenRole::Eof;
+
+    fn role(&self) -> Self::Role {
+        *self
+    }
+}
+
 /// Element type definitions for nodes in the parsed tree.
 ///
 /// While tokens represent the atomic units of a language, elements represent the
@@ -775,3 +806,13 @@ impl ElementRole for UniversalElementRole {
         }
     }
 }
+
+/// Universal element roles can be used as element kinds of their own, for trees and services
+/// that are not tied to a single language.
+impl ElementType for UniversalElementRole {
+    type Role = UniversalElementRole;
+
+    fn role(&self) -> Self::Role {
+        *self
+    }
+}
@@ -44,7 +44,7 @@ pub use core::range::Range;
 pub use crate::{
     builder::{Builder, BuilderCache},
     errors::{OakDiagnostics, OakError, OakErrorKind},
-    language::{ElementRole, ElementType, Language, LanguageCategory, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    language::{ElementRole, ElementType, Language, LanguageCategory, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole, language_for_file},
     lexer::{LexOutput, Lexer, LexerCache, LexerState, Token, TokenStream, Tokens},
     memory::arena::SyntaxArena,
     parser::{Associativity, OperatorInfo, ParseCache, ParseOutput, ParseSession, Parser, ParserState, Pratt, PrattParser, binary, parse, parse_one_pass, postfix, state::TreeSink, unary},
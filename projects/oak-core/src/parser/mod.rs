@@ -101,6 +101,9 @@ where
     // The ParseCache guarantees that the arena and old tree live long enough.
     let arena: &'a crate::memory::arena::SyntaxArena = unsafe { std::mem::transmute(cache.arena()) };
     let mut st = ParserState::new(arena, lex_out, text, capacity_hint);
+    if cache.wants_expected() {
+        st.track_expected()
+    }
 
     if let Some(old) = cache.old_tree() {
         let old: &'a GreenNode<'a, L> = unsafe { std::mem::transmute(old) };
@@ -109,6 +112,12 @@ where
 
     // 4. Run Parser Logic
     let result = run(&mut st);
+    if cache.wants_expected() {
+        cache.record_expected(&st.expected_at_end());
+        if let Some(kind) = st.expected_token_at_end() {
+            cache.record_expected_token(kind)
+        }
+    }
     let output = st.finish(result);
 
     // 5. Commit Generation
@@ -22,6 +22,29 @@ pub trait ParseCache<L: Language>: LexerCache<L> {
 
     /// Commits the result of a parsing generation.
     fn commit_generation(&self, root: &GreenNode<L>);
+
+    /// Checks whether the parser should collect the token kinds for [`record_expected`](Self::record_expected).
+    ///
+    /// The default is `false`, so that ordinary parses skip the bookkeeping.
+    fn wants_expected(&self) -> bool {
+        false
+    }
+
+    /// Receives the token kinds the parser would have accepted at the end of the input, see
+    /// [`ParserState::expected_at_end`](crate::parser::ParserState::expected_at_end).
+    ///
+    /// Only called if [`wants_expected`](Self::wants_expected) is `true`. The default ignores them.
+    fn record_expected(&mut self, expected: &[L::TokenType]) {
+        let _ = expected;
+    }
+
+    /// Receives the token kind of the first error at the end of the input, see
+    /// [`ParserState::expected_token_at_end`](crate::parser::ParserState::expected_token_at_end).
+    ///
+    /// Only called if [`wants_expected`](Self::wants_expected) is `true` and there is one. The default ignores it.
+    fn record_expected_token(&mut self, kind: L::TokenType) {
+        let _ = kind;
+    }
 }
 
 /// A memory pool that manages the lifecycle of parsing generations.
@@ -139,4 +162,16 @@ impl<'a, L: Language, C: ParseCache<L> + ?Sized> ParseCache<L> for &'a mut C {
     fn commit_generation(&self, root: &GreenNode<L>) {
         (**self).commit_generation(root)
     }
+
+    fn wants_expected(&self) -> bool {
+        (**self).wants_expected()
+    }
+
+    fn record_expected(&mut self, expected: &[L::TokenType]) {
+        (**self).record_expected(expected)
+    }
+
+    fn record_expected_token(&mut self, kind: L::TokenType) {
+        (**self).record_expected_token(kind)
+    }
 }
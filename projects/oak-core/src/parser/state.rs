@@ -7,7 +7,10 @@ use crate::{
     source::Source,
     tree::{Cursor, GreenLeaf, GreenNode, GreenTree, TokenProvenance},
 };
-use core::range::Range;
+use core::{
+    cell::{Cell, RefCell},
+    range::Range,
+};
 use triomphe::Arc;
 
 /// Helper function to raise deep clone of a node into an arena.
@@ -209,6 +212,14 @@ pub struct ParserState<'a, L: Language, S: Source + ?Sized = crate::source::Sour
     pub errors: Vec<OakError>,
     /// We keep a reference to help with error reporting and offset calculation.
     pub source: &'a S,
+    /// The token kinds tested for at the end of the input, see [`expected_at_end`](Self::expected_at_end).
+    expected_at_end: RefCell<Vec<L::TokenType>>,
+    /// The number of errors when the parser first reached the end of the input.
+    errors_at_end: Cell<Option<usize>>,
+    /// The token kind of the first error at the end of the input, see [`expected_token_at_end`](Self::expected_token_at_end).
+    expected_token_at_end: Cell<Option<L::TokenType>>,
+    /// Whether [`at`](Self::at) collects [`expected_at_end`](Self::expected_at_end).
+    track_expected: bool,
 }
 
 impl<'a, L: Language, S: Source + ?Sized> ParserState<'a, L, S> {
@@ -236,7 +247,17 @@ impl<'a, L: Language, S: Source + ?Sized> ParserState<'a, L, S> {
         };
         errors.extend(lex_output.diagnostics);
 
-        let mut st = Self { tokens: TokenSource::new(tokens), sink: TreeSink::new(arena, capacity_hint), incremental: None, errors, source };
+        let mut st = Self {
+            tokens: TokenSource::new(tokens),
+            sink: TreeSink::new(arena, capacity_hint),
+            incremental: None,
+            errors,
+            source,
+            expected_at_end: RefCell::new(Vec::new()),
+            errors_at_end: Cell::new(None),
+            expected_token_at_end: Cell::new(None),
+            track_expected: false,
+        };
         st.skip_trivia();
         st
     }
@@ -244,7 +265,17 @@ impl<'a, L: Language, S: Source + ?Sized> ParserState<'a, L, S> {
     /// Creates a nested parser state that shares the same arena and source.
     /// This is useful for parsing sub-structures that should be independent but part of the same overall tree.
     pub fn nested(&self) -> ParserState<'a, L, S> {
-        ParserState { tokens: self.tokens.clone(), sink: TreeSink::new(self.sink.arena, 1024), incremental: None, errors: Vec::new(), source: self.source }
+        ParserState {
+            tokens: self.tokens.clone(),
+            sink: TreeSink::new(self.sink.arena, 1024),
+            incremental: None,
+            errors: Vec::new(),
+            source: self.source,
+            expected_at_end: RefCell::new(Vec::new()),
+            errors_at_end: Cell::new(None),
+            expected_token_at_end: Cell::new(None),
+            track_expected: false,
+        }
     }
 
     /// Returns the text content of the current token.
@@ -376,9 +407,48 @@ impl<'a, L: Language, S: Source + ?Sized> ParserState<'a, L, S> {
     }
 
     /// Checks if the current token is of the specified kind.
+    ///
+    /// At the end of the input, the kind is remembered as one the parser would accept there, until
+    /// the parser records an error there, if [`track_expected`](Self::track_expected) was called.
     #[inline]
     pub fn at(&self, kind: L::TokenType) -> bool {
-        self.peek_kind() == Some(kind)
+        let current = self.peek_kind();
+        if self.track_expected && current.is_none_or(|current| current == L::TokenType::END_OF_STREAM) {
+            self.expect_at_end(kind)
+        }
+        current == Some(kind)
+    }
+
+    /// Remembers a token kind tested for at the end of the input, unless an error was recorded there.
+    #[cold]
+    fn expect_at_end(&self, kind: L::TokenType) {
+        let errors = self.errors_at_end.get().unwrap_or(self.errors.len());
+        self.errors_at_end.set(Some(errors));
+        let mut expected = self.expected_at_end.borrow_mut();
+        if errors == self.errors.len() && !expected.contains(&kind) {
+            expected.push(kind)
+        }
+    }
+
+    /// Makes [`at`](Self::at) remember the token kinds tested for at the end of the input, see
+    /// [`expected_at_end`](Self::expected_at_end). Off by default, since only completion needs them.
+    pub fn track_expected(&mut self) {
+        self.track_expected = true
+    }
+
+    /// Returns the token kinds the parser tested for at the end of the input before recording an
+    /// error there, in the order they were tested.
+    ///
+    /// When the input is the text before a cursor, these are the tokens that could be typed at the
+    /// cursor, which completion engines offer as keywords.
+    pub fn expected_at_end(&self) -> Vec<L::TokenType> {
+        self.expected_at_end.borrow().clone()
+    }
+
+    /// Returns the token kind that [`expect`](Self::expect) failed on at the end of the input, if that
+    /// was the first error there.
+    pub fn expected_token_at_end(&self) -> Option<L::TokenType> {
+        self.expected_token_at_end.get()
     }
 
     /// Checks if the current token is NOT of the specified kind.
@@ -447,6 +517,9 @@ impl<'a, L: Language, S: Source + ?Sized> ParserState<'a, L, S> {
             Ok(())
         }
         else {
+            if self.track_expected && self.peek_kind().is_none_or(|current| current == L::TokenType::END_OF_STREAM) && self.errors_at_end.get() == Some(self.errors.len()) {
+                self.expected_token_at_end.set(Some(kind))
+            }
             let err = OakError::expected_token(format!("{:?}", kind), self.current_offset(), self.source.source_id());
             self.errors.push(err.clone());
             Err(err)
@@ -69,3 +69,30 @@ pub mod option {
         Ok(def.map(|d| Range { start: d.start, end: d.end }))
     }
 }
+
+/// Serde support for `Vec<Range<T>>`.
+#[cfg(feature = "serde")]
+pub mod vec {
+    use super::{RangeDef, RangeDefRef};
+    use core::range::Range;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes a `Vec<Range<T>>`.
+    pub fn serialize<S, T>(value: &[Range<T>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        serializer.collect_seq(value.iter().map(|r| RangeDefRef { start: &r.start, end: &r.end }))
+    }
+
+    /// Deserializes a `Vec<Range<T>>`.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<Range<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        let defs = Vec::<RangeDef<T>>::deserialize(deserializer)?;
+        Ok(defs.into_iter().map(|d| Range { start: d.start, end: d.end }).collect())
+    }
+}
@@ -4,6 +4,8 @@
 //! locations within it.
 
 use core::range::Range;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 mod buffer;
 mod cursor;
@@ -68,15 +70,44 @@ impl<'a> TextChunk<'a> {
 /// let edit = TextEdit {
 ///     span: Range { start: 4, end: 9 }, // Replace characters at positions 4-8
 ///     text: "world".into(),             // With the text "world"
-/// }
+/// };
+/// assert_eq!(edit, TextEdit::replace(Range { start: 4, end: 9 }, "world"));
 /// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TextEdit {
     /// The byte range in the original text to be replaced (start..end)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_range"))]
     pub span: Range<usize>,
     /// The new text to insert in place of the specified range
     pub text: Cow<'static, str>,
 }
 
+impl TextEdit {
+    /// Replaces `span` with `text`.
+    pub fn replace(span: Range<usize>, text: impl Into<Cow<'static, str>>) -> Self {
+        Self { span, text: text.into() }
+    }
+
+    /// Inserts `text` at `offset`.
+    pub fn insert(offset: usize, text: impl Into<Cow<'static, str>>) -> Self {
+        Self { span: Range { start: offset, end: offset }, text: text.into() }
+    }
+
+    /// Deletes `span`.
+    pub fn delete(span: Range<usize>) -> Self {
+        Self { span, text: Cow::Borrowed("") }
+    }
+
+    /// Checks whether two edits touch the same text, so that they cannot both be applied.
+    ///
+    /// Edits that only share an end point do not overlap, except two insertions at the same offset,
+    /// whose order would be ambiguous.
+    pub fn overlaps(&self, other: &TextEdit) -> bool {
+        self.span.start == other.span.start || (self.span.start < other.span.end && other.span.start < self.span.end)
+    }
+}
+
 /// Trait for abstract text sources.
 ///
 /// This trait provides a unified interface for different text sources that may have:
@@ -7,3 +7,15 @@ mod parser;
 fn ready() {
     println!("it works!")
 }
+
+#[test]
+fn test_language_for_file() {
+    use oak_core::language_for_file;
+    let languages: [&[&str]; 3] = [&["ts"], &["d.ts", "tsconfig.json"], &["json"]];
+    assert_eq!(language_for_file(languages, "src/Main.TS"), Some(0));
+    assert_eq!(language_for_file(languages, "types\\index.d.ts"), Some(1));
+    assert_eq!(language_for_file(languages, "tsconfig.json"), Some(1));
+    assert_eq!(language_for_file(languages, "package.json"), Some(2));
+    assert_eq!(language_for_file(languages, "json"), Some(2));
+    assert_eq!(language_for_file(languages, "notjson"), None);
+}
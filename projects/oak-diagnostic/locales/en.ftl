@@ -0,0 +1,36 @@
+# Messages of the errors of oak-core, keyed by `OakErrorKind::key`.
+error.io = I/O error: { $error }
+error.syntax = syntax error: { $message }
+error.unexpected_character = unexpected character `{ $character }`
+error.unexpected_token = unexpected token `{ $token }`
+error.unexpected_eof = unexpected end of file
+error.custom = { $message }
+error.invalid_theme = invalid theme: { $message }
+error.unsupported_format = unsupported format `{ $format }`
+error.color_parse = invalid color `{ $color }`
+error.format = formatting failed: { $message }
+error.semantic = { $message }
+error.protocol = protocol error: { $message }
+error.expected_token = expected `{ $expected }`
+error.expected_name = expected { $name_kind }
+error.trailing_comma_not_allowed = trailing comma is not allowed
+error.test_failure = test failed for { $path }
+error.test_regenerated = regenerated the expected output of { $path }
+error.serde = serialization error: { $message }
+error.deserialize = deserialization error: { $message }
+error.xml = XML error: { $message }
+error.zip = zip error: { $message }
+error.parse = parse error: { $message }
+error.internal = internal error: { $message }
+
+# Counts for summaries.
+summary.errors =
+    { $count ->
+        [one] { $count } error
+       *[other] { $count } errors
+    }
+summary.warnings =
+    { $count ->
+        [one] { $count } warning
+       *[other] { $count } warnings
+    }
@@ -0,0 +1,28 @@
+# oak-core 错误的消息，以 `OakErrorKind::key` 为键。
+error.io = I/O 错误：{ $error }
+error.syntax = 语法错误：{ $message }
+error.unexpected_character = 意外的字符 `{ $character }`
+error.unexpected_token = 意外的记号 `{ $token }`
+error.unexpected_eof = 意外的文件结尾
+error.custom = { $message }
+error.invalid_theme = 无效的主题：{ $message }
+error.unsupported_format = 不支持的格式 `{ $format }`
+error.color_parse = 无效的颜色 `{ $color }`
+error.format = 格式化失败：{ $message }
+error.semantic = { $message }
+error.protocol = 协议错误：{ $message }
+error.expected_token = 此处应为 `{ $expected }`
+error.expected_name = 此处应为{ $name_kind }
+error.trailing_comma_not_allowed = 不允许尾随逗号
+error.test_failure = { $path } 的测试失败
+error.test_regenerated = 已重新生成 { $path } 的预期输出
+error.serde = 序列化错误：{ $message }
+error.deserialize = 反序列化错误：{ $message }
+error.xml = XML 错误：{ $message }
+error.zip = zip 错误：{ $message }
+error.parse = 解析错误：{ $message }
+error.internal = 内部错误：{ $message }
+
+# 汇总中的计数。
+summary.errors = { $count } 个错误
+summary.warnings = { $count } 个警告
@@ -0,0 +1,193 @@
+use crate::Diagnostic;
+use core::range::Range;
+pub use oak_core::source::TextEdit;
+use oak_vfs::LineMap;
+use serde::{Deserialize, Serialize};
+
+/// How confident a [`Fix`] is that it does what the user wants.
+///
+/// Ordered from the most to the least certain, so that `applicability <= threshold` accepts every
+/// fix at least as certain as the threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Applicability {
+    /// The fix is definitely correct and can be applied without review.
+    MachineApplicable,
+    /// The fix is probably correct, but should be reviewed.
+    MaybeIncorrect,
+    /// The fix contains placeholders, like `/* value */`, that the user has to fill in.
+    HasPlaceholders,
+    /// The confidence of the fix is not known.
+    #[default]
+    Unspecified,
+}
+
+/// A suggested change that resolves a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fix {
+    /// A short description of the change, like `remove the unused import`.
+    pub title: String,
+    /// The edits of the change, which must not overlap each other.
+    pub edits: Vec<TextEdit>,
+    /// How confident the fix is.
+    #[serde(default)]
+    pub applicability: Applicability,
+}
+
+impl Fix {
+    /// Creates a fix without edits and with an [unspecified](Applicability::Unspecified) applicability.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self { title: title.into(), edits: Vec::new(), applicability: Applicability::Unspecified }
+    }
+
+    /// Creates a fix that replaces `span` with `new_text`.
+    pub fn replace(title: impl Into<String>, span: Range<usize>, new_text: impl Into<String>) -> Self {
+        Self::new(title).with_edit(TextEdit::replace(span, new_text.into()))
+    }
+
+    /// Adds an edit to the fix.
+    pub fn with_edit(mut self, edit: TextEdit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// Sets the applicability of the fix.
+    pub fn with_applicability(mut self, applicability: Applicability) -> Self {
+        self.applicability = applicability;
+        self
+    }
+
+    /// Returns the edits sorted by position, or `None` if they overlap or do not fit into `text`.
+    fn sorted_edits(&self, text: &str) -> Option<Vec<&TextEdit>> {
+        let mut edits: Vec<_> = self.edits.iter().collect();
+        edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+        let valid = edits.iter().all(|edit| edit.span.start <= edit.span.end && text.is_char_boundary(edit.span.start) && text.is_char_boundary(edit.span.end));
+        if !valid || edits.windows(2).any(|pair| pair[0].overlaps(pair[1])) {
+            return None;
+        }
+        Some(edits)
+    }
+}
+
+/// Applies edits sorted by position to `text`.
+fn apply_edits(text: &str, edits: &[&TextEdit]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for edit in edits {
+        out.push_str(&text[last..edit.span.start]);
+        out.push_str(&edit.text);
+        last = edit.span.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// The lines a fix changes, before and after the change.
+pub(crate) struct FixDiff {
+    /// The number of the first changed line, starting at 0.
+    pub first_line: u32,
+    /// The changed lines before the fix.
+    pub removed: Vec<String>,
+    /// The lines after the fix.
+    pub added: Vec<String>,
+}
+
+impl FixDiff {
+    /// Computes the lines of `text` changed by `fix`, or `None` if the fix cannot be applied.
+    pub(crate) fn new(text: &str, line_map: &LineMap, fix: &Fix) -> Option<Self> {
+        let edits = fix.sorted_edits(text)?;
+        let first_line = line_map.line_of(edits.first()?.span.start);
+        let last = edits.iter().map(|edit| edit.span.end).max()?;
+        let start = line_map.line_start(first_line)?;
+        let end = line_map.line_content_end(line_map.line_of(last)).unwrap_or(text.len()).max(last);
+        let shifted: Vec<_> = edits.iter().map(|edit| TextEdit::replace(Range { start: edit.span.start - start, end: edit.span.end - start }, edit.text.clone())).collect();
+        let before = &text[start..end];
+        let after = apply_edits(before, &shifted.iter().collect::<Vec<_>>());
+        let lines = |text: &str| -> Vec<String> { text.split('\n').map(|line| line.trim_end_matches('\r').to_string()).collect() };
+        Some(Self { first_line, removed: lines(before), added: lines(&after) })
+    }
+}
+
+/// The result of [`FixApplier::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedFixes {
+    /// The text with the fixes applied.
+    pub text: String,
+    /// The number of applied fixes.
+    pub applied: usize,
+    /// The number of fixes left out because they overlap an applied fix or are malformed.
+    pub skipped: usize,
+    /// The number of passes, for [`FixApplier::apply_until_fixpoint`].
+    pub passes: usize,
+}
+
+/// Applies the fixes of diagnostics to a text, like a `--fix` flag.
+///
+/// Only the first acceptable fix of each diagnostic is considered. Fixes are merged in the order of
+/// their diagnostics, and a fix that overlaps one taken earlier is left for the next pass.
+#[derive(Debug, Clone, Copy)]
+pub struct FixApplier {
+    /// The least certain applicability to apply.
+    pub applicability: Applicability,
+    /// The most passes [`apply_until_fixpoint`](Self::apply_until_fixpoint) runs.
+    pub max_passes: usize,
+}
+
+impl Default for FixApplier {
+    fn default() -> Self {
+        Self { applicability: Applicability::MachineApplicable, max_passes: 10 }
+    }
+}
+
+impl FixApplier {
+    /// Creates an applier for [machine applicable](Applicability::MachineApplicable) fixes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the least certain applicability to apply.
+    pub fn with_applicability(mut self, applicability: Applicability) -> Self {
+        self.applicability = applicability;
+        self
+    }
+
+    /// Sets the most passes to run.
+    pub fn with_max_passes(mut self, max_passes: usize) -> Self {
+        self.max_passes = max_passes;
+        self
+    }
+
+    /// Applies the non-overlapping fixes of `diagnostics` to `text` in one pass.
+    pub fn apply(&self, text: &str, diagnostics: &[Diagnostic]) -> AppliedFixes {
+        let mut taken: Vec<&TextEdit> = Vec::new();
+        let mut applied = 0;
+        let mut skipped = 0;
+        for fix in diagnostics.iter().filter_map(|diagnostic| diagnostic.fixes.iter().find(|fix| fix.applicability <= self.applicability)) {
+            match fix.sorted_edits(text) {
+                Some(edits) if !edits.iter().any(|edit| taken.iter().any(|other| edit.overlaps(other))) => {
+                    taken.extend(edits);
+                    applied += 1
+                }
+                _ => skipped += 1,
+            }
+        }
+        taken.sort_by_key(|edit| (edit.span.start, edit.span.end));
+        AppliedFixes { text: apply_edits(text, &taken), applied, skipped, passes: 1 }
+    }
+
+    /// Applies fixes and re-runs `check` on the result until no fix applies or the text stops
+    /// changing, at most [`max_passes`](Self::max_passes) times.
+    pub fn apply_until_fixpoint(&self, text: &str, mut check: impl FnMut(&str) -> Vec<Diagnostic>) -> AppliedFixes {
+        let mut result = AppliedFixes { text: text.to_string(), applied: 0, skipped: 0, passes: 0 };
+        while result.passes < self.max_passes {
+            let pass = self.apply(&result.text, &check(&result.text));
+            result.passes += 1;
+            result.skipped = pass.skipped;
+            if pass.applied == 0 || pass.text == result.text {
+                break;
+            }
+            result.applied += pass.applied;
+            result.text = pass.text
+        }
+        result
+    }
+}
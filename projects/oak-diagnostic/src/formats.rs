@@ -0,0 +1,255 @@
+use crate::{
+    Severity, html_escape,
+    report::{Report, ReportEmitter},
+};
+use serde_json::{Value, json};
+
+/// Emitter for [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html), read by
+/// code scanning dashboards.
+///
+/// Columns are counted in Unicode code points, and the [fixes](crate::Diagnostic::fixes) of each
+/// diagnostic are included as byte based replacements.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SarifEmitter;
+
+impl SarifEmitter {
+    fn level(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Advice => "note",
+        }
+    }
+}
+
+impl ReportEmitter for SarifEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let ids = report.rule_ids();
+        let rules: Vec<Value> = ids
+            .iter()
+            .map(|id| match report.rules.get(*id) {
+                Some(rule) => {
+                    let mut descriptor = json!({
+                        "id": id,
+                        "shortDescription": { "text": rule.description },
+                        "defaultConfiguration": { "level": Self::level(rule.severity) },
+                    });
+                    if let Some(help_uri) = &rule.help_uri {
+                        descriptor["helpUri"] = json!(help_uri);
+                    }
+                    descriptor
+                }
+                None => json!({ "id": id }),
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for file in &report.files {
+            for entry in file.entries() {
+                let diagnostic = entry.diagnostic;
+                let region = |location: crate::Location| json!({ "startLine": location.start_line, "startColumn": location.start_column, "endLine": location.end_line, "endColumn": location.end_column });
+                let mut result = json!({
+                    "level": Self::level(diagnostic.severity),
+                    "message": { "text": diagnostic.message },
+                    "locations": [{ "physicalLocation": { "artifactLocation": { "uri": file.uri }, "region": region(entry.location) } }],
+                    "partialFingerprints": { "oakFingerprint/v1": entry.fingerprint },
+                });
+                if let Some(code) = &diagnostic.code {
+                    result["ruleId"] = json!(code);
+                    result["ruleIndex"] = json!(ids.iter().position(|id| id == code));
+                }
+                let related: Vec<Value> = diagnostic
+                    .labels
+                    .iter()
+                    .skip(1)
+                    .enumerate()
+                    .map(|(id, label)| json!({ "id": id, "message": { "text": label.message.clone().unwrap_or_default() }, "physicalLocation": { "artifactLocation": { "uri": file.uri }, "region": region(file.location(label.span)) } }))
+                    .collect();
+                if !related.is_empty() {
+                    result["relatedLocations"] = Value::Array(related);
+                }
+                let fixes: Vec<Value> = diagnostic
+                    .fixes
+                    .iter()
+                    .map(|fix| {
+                        let replacements: Vec<Value> = fix.edits.iter().map(|edit| json!({ "deletedRegion": { "byteOffset": edit.span.start, "byteLength": edit.span.end - edit.span.start }, "insertedContent": { "text": edit.text } })).collect();
+                        json!({ "description": { "text": fix.title }, "artifactChanges": [{ "artifactLocation": { "uri": file.uri }, "replacements": replacements }] })
+                    })
+                    .collect();
+                if !fixes.is_empty() {
+                    result["fixes"] = Value::Array(fixes);
+                }
+                results.push(result)
+            }
+        }
+
+        let mut driver = json!({ "name": report.tool_name, "rules": rules });
+        if let Some(version) = &report.tool_version {
+            driver["version"] = json!(version);
+        }
+        if let Some(information_uri) = &report.information_uri {
+            driver["informationUri"] = json!(information_uri);
+        }
+        let sarif = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": { "driver": driver },
+                "columnKind": "unicodeCodePoints",
+                "results": results,
+            }],
+        });
+        serde_json::to_string_pretty(&sarif).unwrap_or_default()
+    }
+}
+
+/// Emitter for JUnit XML, read by CI test reports.
+///
+/// Each file is a test suite and each diagnostic a failed test case. A file without diagnostics has
+/// one passing test case, so that it shows up in the report.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JUnitEmitter;
+
+impl ReportEmitter for JUnitEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let total: usize = report.files.iter().map(|file| file.diagnostics.len().max(1)).sum();
+        let failures: usize = report.files.iter().map(|file| file.diagnostics.len()).sum();
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str(&format!("<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n", html_escape(&report.tool_name), total, failures));
+        for file in &report.files {
+            let path = html_escape(file.path());
+            out.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n", path, file.diagnostics.len().max(1), file.diagnostics.len()));
+            if file.diagnostics.is_empty() {
+                out.push_str(&format!("    <testcase name=\"{}\" classname=\"{}\" />\n", path, path));
+            }
+            for entry in file.entries() {
+                let diagnostic = entry.diagnostic;
+                let code = diagnostic.code.as_deref().unwrap_or(severity_name(diagnostic.severity));
+                let name = format!("{} ({}:{})", code, entry.location.start_line, entry.location.start_column);
+                out.push_str(&format!("    <testcase name=\"{}\" classname=\"{}\">\n", html_escape(&name), path));
+                out.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}:{}:{}: {}</failure>\n",
+                    severity_name(diagnostic.severity),
+                    html_escape(&diagnostic.message),
+                    path,
+                    entry.location.start_line,
+                    entry.location.start_column,
+                    html_escape(&diagnostic.message)
+                ));
+                out.push_str("    </testcase>\n");
+            }
+            out.push_str("  </testsuite>\n");
+        }
+        out.push_str("</testsuites>\n");
+        out
+    }
+}
+
+/// Emitter for GitHub Actions workflow commands, like `::error file=src/main.rs,line=3::message`,
+/// which GitHub shows as annotations on the changed lines.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GitHubEmitter;
+
+impl GitHubEmitter {
+    /// Escapes the message of a workflow command.
+    fn escape_data(text: &str) -> String {
+        text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+    }
+
+    /// Escapes a property of a workflow command.
+    fn escape_property(text: &str) -> String {
+        Self::escape_data(text).replace(':', "%3A").replace(',', "%2C")
+    }
+}
+
+impl ReportEmitter for GitHubEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let mut out = String::new();
+        for file in &report.files {
+            for entry in file.entries() {
+                let diagnostic = entry.diagnostic;
+                let command = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Advice => "notice",
+                };
+                let location = entry.location;
+                let mut properties = format!("file={},line={},col={},endLine={},endColumn={}", Self::escape_property(file.path()), location.start_line, location.start_column, location.end_line, location.end_column);
+                if let Some(code) = &diagnostic.code {
+                    properties.push_str(&format!(",title={}", Self::escape_property(code)));
+                }
+                out.push_str(&format!("::{} {}::{}\n", command, properties, Self::escape_data(&diagnostic.message)));
+            }
+        }
+        out
+    }
+}
+
+/// Emitter for [GitLab Code Quality](https://docs.gitlab.com/ee/ci/testing/code_quality.html) JSON.
+///
+/// Errors are `major`, warnings `minor` and advice `info` issues.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GitLabEmitter;
+
+impl ReportEmitter for GitLabEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let mut issues = Vec::new();
+        for file in &report.files {
+            for entry in file.entries() {
+                let diagnostic = entry.diagnostic;
+                let severity = match diagnostic.severity {
+                    Severity::Error => "major",
+                    Severity::Warning => "minor",
+                    Severity::Advice => "info",
+                };
+                issues.push(json!({
+                    "type": "issue",
+                    "check_name": diagnostic.code.as_deref().unwrap_or(severity_name(diagnostic.severity)),
+                    "description": diagnostic.message,
+                    "severity": severity,
+                    "fingerprint": entry.fingerprint,
+                    "location": { "path": file.path(), "lines": { "begin": entry.location.start_line, "end": entry.location.end_line } },
+                }))
+            }
+        }
+        serde_json::to_string_pretty(&issues).unwrap_or_default()
+    }
+}
+
+/// Emitter for Checkstyle XML, read by many CI and review tools.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckstyleEmitter;
+
+impl ReportEmitter for CheckstyleEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<checkstyle version=\"4.3\">\n");
+        for file in &report.files {
+            out.push_str(&format!("  <file name=\"{}\">\n", html_escape(file.path())));
+            for entry in file.entries() {
+                let diagnostic = entry.diagnostic;
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Advice => "info",
+                };
+                out.push_str(&format!("    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\"", entry.location.start_line, entry.location.start_column, severity, html_escape(&diagnostic.message)));
+                if let Some(code) = &diagnostic.code {
+                    out.push_str(&format!(" source=\"{}\"", html_escape(code)));
+                }
+                out.push_str(" />\n");
+            }
+            out.push_str("  </file>\n");
+        }
+        out.push_str("</checkstyle>\n");
+        out
+    }
+}
+
+/// Returns the lower case name of a severity.
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Advice => "advice",
+    }
+}
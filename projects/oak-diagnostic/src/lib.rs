@@ -6,6 +6,22 @@
 //! diagnostics (errors, warnings, advice) in a way that is compatible with
 //! various frontends like LSP or CLI output.
 
+mod fix;
+mod formats;
+mod localize;
+mod report;
+mod snippet;
+
+use crate::{
+    fix::FixDiff,
+    snippet::{Characters, Palette, SnippetRenderer, SourceView},
+};
+pub use crate::{
+    fix::{Applicability, AppliedFixes, Fix, FixApplier, TextEdit},
+    formats::{CheckstyleEmitter, GitHubEmitter, GitLabEmitter, JUnitEmitter, SarifEmitter},
+    localize::{Catalog, CatalogError, CatalogLocalizer},
+    report::{FileReport, Location, Report, ReportEmitter, ReportEntry, RuleMetadata},
+};
 use oak_core::{
     errors::{OakError, OakErrorKind},
     source::Source,
@@ -34,6 +50,13 @@ pub struct Label {
     pub span: core::range::Range<usize>,
     /// The color of the label (optional).
     pub color: Option<String>,
+    /// Whether the label marks the cause of the diagnostic, rather than related code.
+    #[serde(default = "primary")]
+    pub primary: bool,
+}
+
+fn primary() -> bool {
+    true
 }
 
 /// A diagnostic message.
@@ -53,17 +76,20 @@ pub struct Diagnostic {
     pub labels: Vec<Label>,
     /// A help message providing more details or suggestions.
     pub help: Option<String>,
+    /// The suggested fixes, the preferred one first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<Fix>,
 }
 
 impl Diagnostic {
     /// Creates a new error diagnostic with the given message.
     pub fn error(message: impl Into<String>) -> Self {
-        Self { code: None, message: message.into(), i18n_key: None, i18n_args: std::collections::HashMap::new(), severity: Severity::Error, labels: Vec::new(), help: None }
+        Self { code: None, message: message.into(), i18n_key: None, i18n_args: std::collections::HashMap::new(), severity: Severity::Error, labels: Vec::new(), help: None, fixes: Vec::new() }
     }
 
     /// Creates a new warning diagnostic with the given message.
     pub fn warning(message: impl Into<String>) -> Self {
-        Self { code: None, message: message.into(), i18n_key: None, i18n_args: std::collections::HashMap::new(), severity: Severity::Warning, labels: Vec::new(), help: None }
+        Self { code: None, message: message.into(), i18n_key: None, i18n_args: std::collections::HashMap::new(), severity: Severity::Warning, labels: Vec::new(), help: None, fixes: Vec::new() }
     }
 
     /// Sets the internationalization key for the diagnostic.
@@ -85,7 +111,13 @@ impl Diagnostic {
 
     /// Adds a labeled region to the diagnostic.
     pub fn with_label(mut self, span: core::range::Range<usize>, message: impl Into<String>) -> Self {
-        self.labels.push(Label { message: Some(message.into()), span, color: None });
+        self.labels.push(Label { message: Some(message.into()), span, color: None, primary: true });
+        self
+    }
+
+    /// Adds a labeled region of related code, which is drawn less prominently than the primary labels.
+    pub fn with_secondary_label(mut self, span: core::range::Range<usize>, message: impl Into<String>) -> Self {
+        self.labels.push(Label { message: Some(message.into()), span, color: None, primary: false });
         self
     }
 
@@ -100,6 +132,12 @@ impl Diagnostic {
         self.code = Some(code.into());
         self
     }
+
+    /// Adds a suggested fix to the diagnostic.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
 }
 
 impl From<&OakError> for Diagnostic {
@@ -135,43 +173,84 @@ impl DiagnosticProvider for OakError {
         let mut diag = Diagnostic::error(message).with_code(code.unwrap()).with_i18n(kind.key());
 
         match kind {
-            OakErrorKind::IoError { .. } => {}
-            OakErrorKind::SyntaxError { offset, .. } | OakErrorKind::UnexpectedCharacter { offset, .. } => {
-                let start = (*offset).min(source.length());
-                let end = (start + 1).min(source.length());
-                diag = diag.with_label(core::range::Range { start, end }, "here");
+            OakErrorKind::IoError { error, .. } => {
+                diag = diag.with_arg("error", error.to_string());
+            }
+            OakErrorKind::SyntaxError { message, offset, .. } => {
+                diag = diag.with_label(here(source, *offset), "here").with_arg("message", message.clone());
+            }
+            OakErrorKind::UnexpectedCharacter { character, offset, .. } => {
+                diag = diag.with_label(here(source, *offset), "here").with_arg("character", character.to_string());
             }
             OakErrorKind::UnexpectedToken { token, offset, .. } => {
-                let start = (*offset).min(source.length());
-                let end = (start + 1).min(source.length());
-                diag = diag.with_label(core::range::Range { start, end }, "here").with_arg("token", token.clone());
+                diag = diag.with_label(here(source, *offset), "here").with_arg("token", token.clone());
+            }
+            OakErrorKind::UnexpectedEof { offset, .. } => {
+                diag = diag.with_label(here(source, *offset), "here");
             }
             OakErrorKind::ExpectedToken { expected, offset, .. } => {
-                let start = (*offset).min(source.length());
-                let end = (start + 1).min(source.length());
-                diag = diag.with_label(core::range::Range { start, end }, "here").with_arg("expected", expected.clone());
+                diag = diag.with_label(here(source, *offset), "here").with_arg("expected", expected.clone());
             }
             OakErrorKind::ExpectedName { name_kind, offset, .. } => {
-                let start = (*offset).min(source.length());
-                let end = (start + 1).min(source.length());
-                diag = diag.with_label(core::range::Range { start, end }, "here").with_arg("name_kind", name_kind.clone());
+                diag = diag.with_label(here(source, *offset), "here").with_arg("name_kind", name_kind.clone());
             }
             OakErrorKind::TrailingCommaNotAllowed { offset, .. } => {
-                let start = (*offset).min(source.length());
-                let end = (start + 1).min(source.length());
-                diag = diag.with_label(core::range::Range { start, end }, "here");
+                diag = diag.with_label(here(source, *offset), "here");
+            }
+            OakErrorKind::UnsupportedFormat { format } => {
+                diag = diag.with_arg("format", format.clone());
+            }
+            OakErrorKind::ColorParseError { color } => {
+                diag = diag.with_arg("color", color.clone());
+            }
+            OakErrorKind::TestFailure { path, .. } | OakErrorKind::TestRegenerated { path } => {
+                diag = diag.with_arg("path", path.display().to_string());
+            }
+            OakErrorKind::CustomError { message }
+            | OakErrorKind::InvalidTheme { message }
+            | OakErrorKind::FormatError { message }
+            | OakErrorKind::SemanticError { message }
+            | OakErrorKind::ProtocolError { message }
+            | OakErrorKind::SerdeError { message }
+            | OakErrorKind::DeserializeError { message }
+            | OakErrorKind::XmlError { message }
+            | OakErrorKind::ZipError { message }
+            | OakErrorKind::ParseError { message }
+            | OakErrorKind::InternalError { message } => {
+                diag = diag.with_arg("message", message.clone());
             }
-            _ => {}
         }
 
         diag
     }
 }
 
+/// Returns the one byte range at `offset`, clamped to the source.
+fn here<S: Source + ?Sized>(source: &S, offset: usize) -> core::range::Range<usize> {
+    let start = offset.min(source.length());
+    core::range::Range { start, end: (start + 1).min(source.length()) }
+}
+
 /// A trait for localizing diagnostic messages.
 pub trait Localizer {
     /// Localize a message given its key and arguments.
     fn localize(&self, key: &str, args: &std::collections::HashMap<String, String>) -> String;
+
+    /// Localize a message, or return `None` if the localizer has no message for the key.
+    ///
+    /// By default, an empty localized message counts as missing.
+    fn lookup(&self, key: &str, args: &std::collections::HashMap<String, String>) -> Option<String> {
+        Some(self.localize(key, args)).filter(|message| !message.is_empty())
+    }
+}
+
+/// Returns the localized message of a diagnostic, or its message if it has no
+/// [i18n key](Diagnostic::i18n_key) or the localizer does not know the key.
+pub fn localized_message<L: Localizer + ?Sized>(diagnostic: &Diagnostic, localizer: Option<&L>) -> String {
+    match (&diagnostic.i18n_key, localizer) {
+        (Some(key), Some(localizer)) => localizer.lookup(key, &diagnostic.i18n_args).unwrap_or_else(|| diagnostic.message.clone()),
+        _ => diagnostic.message.clone(),
+    }
 }
 
 impl Localizer for () {
@@ -192,25 +271,65 @@ pub trait Emitter {
 }
 
 /// Emitter for ANSI-colored console output.
+///
+/// All labels of a diagnostic are drawn in one snippet of their source: labels on the same line share
+/// it, overlapping labels are underlined on separate rows, messages hang from arms, and lines far from
+/// every label are folded into `...`.
 pub struct ConsoleEmitter {
     /// Whether to use Unicode characters for drawing boxes and lines.
     pub unicode: bool,
+    /// Whether to use ANSI colors.
+    pub color: bool,
+    /// The width of the terminal; longer source lines are cut around their labels.
+    pub width: Option<usize>,
+    /// The number of columns a tab advances to.
+    pub tab_width: usize,
+    /// The number of lines shown before and after each labeled line.
+    pub context_lines: usize,
 }
 
 impl Default for ConsoleEmitter {
+    /// Uses colors unless the `NO_COLOR` environment variable is set, and the terminal width from
+    /// the `COLUMNS` environment variable.
     fn default() -> Self {
-        Self { unicode: true }
+        let color = std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+        let width = std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok());
+        Self { unicode: true, color, width, tab_width: 4, context_lines: 1 }
     }
 }
 
-impl Emitter for ConsoleEmitter {
-    fn render_localized<S: Source + ?Sized, L: Localizer + ?Sized>(&self, source: &S, diagnostic: &Diagnostic, localizer: Option<&L>, uri: Option<&str>) -> String {
-        let mut out = String::new();
+impl ConsoleEmitter {
+    /// Sets whether to use ANSI colors.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets the width of the terminal.
+    pub fn with_width(mut self, width: Option<usize>) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets the number of lines shown before and after each labeled line.
+    pub fn with_context_lines(mut self, context_lines: usize) -> Self {
+        self.context_lines = context_lines;
+        self
+    }
+
+    /// Renders several diagnostics of the same source, reading its lines only once.
+    pub fn render_all<S: Source + ?Sized, L: Localizer + ?Sized>(&self, source: &S, diagnostics: &[Diagnostic], localizer: Option<&L>, uri: Option<&str>) -> String {
         let line_map = LineMap::from_source(source);
-        let full_text = source.get_text_in(core::range::Range { start: 0, end: source.length() }).into_owned();
-        let lines: Vec<&str> = full_text.lines().collect();
+        let text = source.get_text_in(core::range::Range { start: 0, end: source.length() });
+        let view = SourceView { text: &text, line_map: &line_map };
+        diagnostics.iter().map(|diagnostic| self.render_view(&view, diagnostic, localizer, uri)).collect::<Vec<_>>().join("\n")
+    }
+
+    fn render_view<L: Localizer + ?Sized>(&self, view: &SourceView, diagnostic: &Diagnostic, localizer: Option<&L>, uri: Option<&str>) -> String {
+        let mut out = String::new();
+        let palette = Palette { enabled: self.color };
 
-        // 1. Header: [code] severity: message or [severity]: message
+        // 1. Header: [code] message or [severity] message
         let sev_name = match diagnostic.severity {
             Severity::Error => "error",
             Severity::Warning => "warning",
@@ -222,121 +341,67 @@ impl Emitter for ConsoleEmitter {
             Severity::Advice => "\x1b[36;1m",
         };
 
-        let message = if let (Some(key), Some(loc)) = (&diagnostic.i18n_key, localizer) { loc.localize(key, &diagnostic.i18n_args) } else { diagnostic.message.clone() };
+        let message = localized_message(diagnostic, localizer);
+        let title = format!("[{}]", diagnostic.code.as_deref().unwrap_or(sev_name));
+        out.push_str(&format!("{} {}\n", palette.paint(&title, sev_color), message));
 
-        if let Some(code) = &diagnostic.code {
-            out.push_str(&format!("{}[{}]\x1b[0m {}\n", sev_color, code, message));
-        }
-        else {
-            out.push_str(&format!("{}[{}]\x1b[0m {}\n", sev_color, sev_name, message));
-        }
-
-        // 2. Snippets
-        for label in &diagnostic.labels {
-            self.render_snippet(&mut out, source, &line_map, &full_text, &lines, label, uri);
-        }
+        // 2. Snippet
+        let renderer = SnippetRenderer { view, chars: if self.unicode { Characters::unicode() } else { Characters::ascii() }, palette, width: self.width, tab_width: self.tab_width, context_lines: self.context_lines };
+        renderer.render(&mut out, &diagnostic.labels, sev_color, uri);
 
         // 3. Help
         if let Some(help) = &diagnostic.help {
-            out.push_str(&format!("\n\x1b[36;1mhelp\x1b[0m: {}\n", help));
+            out.push_str(&format!("\n{}: {}\n", palette.paint("help", "\x1b[36;1m"), help));
+        }
+
+        // 4. Fixes
+        for fix in &diagnostic.fixes {
+            out.push_str(&format!("\n{}: {}\n", palette.paint("fix", "\x1b[32;1m"), fix.title));
+            if let Some(diff) = FixDiff::new(view.text, view.line_map, fix) {
+                render_diff(&mut out, &diff, self.color.then_some(("\x1b[31m", "\x1b[32m")));
+            }
         }
 
         out
     }
 }
 
-struct Characters {
-    vbar: &'static str,
-    hbar: &'static str,
-    ltop: &'static str,
-    lbot: &'static str,
-}
-
-impl Characters {
-    fn unicode() -> Self {
-        Self { vbar: "│", hbar: "─", ltop: "┌", lbot: "└" }
-    }
-
-    fn ascii() -> Self {
-        Self { vbar: "|", hbar: "_", ltop: "/", lbot: "|" }
+impl Emitter for ConsoleEmitter {
+    fn render_localized<S: Source + ?Sized, L: Localizer + ?Sized>(&self, source: &S, diagnostic: &Diagnostic, localizer: Option<&L>, uri: Option<&str>) -> String {
+        self.render_all(source, core::slice::from_ref(diagnostic), localizer, uri)
     }
 }
 
-impl ConsoleEmitter {
-    fn render_snippet<S: Source + ?Sized>(&self, out: &mut String, source: &S, line_map: &LineMap, full_text: &str, lines: &[&str], label: &Label, uri: Option<&str>) {
-        let chars = if self.unicode { Characters::unicode() } else { Characters::ascii() };
-        let (start_line, _) = line_map.offset_to_line_col_utf16(source, label.span.start);
-        let (end_line, _) = line_map.offset_to_line_col_utf16(source, label.span.end);
-        let start_line = start_line as usize;
-        let end_line = end_line as usize;
-        let start_line_start = line_map.line_start(start_line as u32).unwrap_or(0);
-        let end_line_start = line_map.line_start(end_line as u32).unwrap_or(0);
-        let start_col = full_text.get(start_line_start..label.span.start.min(full_text.len())).unwrap_or("").chars().count();
-        let end_col = full_text.get(end_line_start..label.span.end.min(full_text.len())).unwrap_or("").chars().count();
-
-        let line_num_width = (end_line + 1).to_string().len();
-        let padding = " ".repeat(line_num_width);
-
-        // Location info: ┌ at url:line:col
-        let url_str = uri.unwrap_or("<anonymous>");
-        let pos_str = format!("{}:{}", start_line + 1, start_col + 1);
-        out.push_str(&format!("  \x1b[34m{}\x1b[0m at {}:{}\n", chars.ltop, url_str, pos_str));
-        out.push_str(&format!("{} \x1b[34m{}\x1b[0m\n", padding, chars.vbar));
-
-        if start_line == end_line {
-            // Single line label
-            if let Some(line_text) = lines.get(start_line) {
-                out.push_str(&format!("{:>width$} \x1b[34m{}\x1b[0m {}\n", start_line + 1, chars.vbar, line_text, width = line_num_width));
-
-                let underline_padding = " ".repeat(start_col);
-                let underline_len = full_text.get(label.span.start.min(full_text.len())..label.span.end.min(full_text.len())).unwrap_or("").chars().count().max(1);
-                let underline = "^".repeat(underline_len);
-
-                let color = label.color.as_deref().unwrap_or("\x1b[31;1m");
-                out.push_str(&format!("{} \x1b[34m{}\x1b[0m {}{}{}\x1b[0m", padding, chars.vbar, underline_padding, color, underline));
-
-                if let Some(msg) = &label.message {
-                    out.push_str(&format!(" {}\n", msg));
-                }
-                else {
-                    out.push_str("\n");
-                }
-            }
+impl ReportEmitter for ConsoleEmitter {
+    fn emit(&self, report: &Report) -> String {
+        let mut rendered = Vec::new();
+        for file in &report.files {
+            let view = SourceView { text: file.text(), line_map: file.line_map() };
+            rendered.extend(file.diagnostics.iter().map(|diagnostic| self.render_view::<()>(&view, diagnostic, None, Some(file.path()))));
         }
-        else {
-            // Multi-line label
-            let color = label.color.as_deref().unwrap_or("\x1b[31;1m");
-            for i in start_line..=end_line {
-                if let Some(line_text) = lines.get(i) {
-                    let line_num = i + 1;
-                    if i == start_line {
-                        out.push_str(&format!("{:>width$} \x1b[34m{}\x1b[0m {}{}{} {}\n", line_num, chars.vbar, color, chars.ltop, "\x1b[0m", line_text, width = line_num_width));
-                    }
-                    else if i == end_line {
-                        out.push_str(&format!("{:>width$} \x1b[34m{}\x1b[0m {}{}{} {}\n", line_num, chars.vbar, color, chars.vbar, "\x1b[0m", line_text, width = line_num_width));
-
-                        let underline_len = end_col.max(1);
-                        let underline = "^".repeat(underline_len);
-                        out.push_str(&format!("{} \x1b[34m{}\x1b[0m {}{}{}{}", padding, chars.vbar, color, chars.lbot, chars.hbar.repeat(end_col), underline));
+        rendered.join("\n")
+    }
+}
 
-                        if let Some(msg) = &label.message {
-                            out.push_str(&format!(" {}\x1b[0m\n", msg));
-                        }
-                        else {
-                            out.push_str("\x1b[0m\n");
-                        }
-                    }
-                    else {
-                        out.push_str(&format!("{:>width$} \x1b[34m{}\x1b[0m {}{}{} {}\n", line_num, chars.vbar, color, chars.vbar, "\x1b[0m", line_text, width = line_num_width));
-                    }
-                }
-            }
-        }
-        out.push_str(&format!("{} \x1b[34m{}\x1b[0m\n", padding, chars.vbar));
+/// Renders the lines changed by a fix as removed and added lines, with optional ANSI colors for both.
+fn render_diff(out: &mut String, diff: &FixDiff, colors: Option<(&str, &str)>) {
+    let first = diff.first_line as usize + 1;
+    let width = (first + diff.removed.len().max(diff.added.len())).to_string().len();
+    let (removed, added, reset) = match colors {
+        Some((removed, added)) => (removed, added, "\x1b[0m"),
+        None => ("", "", ""),
+    };
+    for (i, line) in diff.removed.iter().enumerate() {
+        out.push_str(&format!("{:>width$} {}- {}{}\n", first + i, removed, line, reset, width = width));
+    }
+    for (i, line) in diff.added.iter().enumerate() {
+        out.push_str(&format!("{:>width$} {}+ {}{}\n", first + i, added, line, reset, width = width));
     }
 }
 
 /// Emitter for plain text output without colors.
+///
+/// Renders like a [`ConsoleEmitter`] without colors and without a width limit.
 pub struct PlainTextEmitter {
     /// Whether to use Unicode characters for drawing boxes and lines.
     pub unicode: bool,
@@ -350,105 +415,8 @@ impl Default for PlainTextEmitter {
 
 impl Emitter for PlainTextEmitter {
     fn render_localized<S: Source + ?Sized, L: Localizer + ?Sized>(&self, source: &S, diagnostic: &Diagnostic, localizer: Option<&L>, uri: Option<&str>) -> String {
-        let mut out = String::new();
-        let line_map = LineMap::from_source(source);
-        let full_text = source.get_text_in(core::range::Range { start: 0, end: source.length() }).into_owned();
-        let lines: Vec<&str> = full_text.lines().collect();
-
-        // 1. Header: [code] severity: message or [severity]: message
-        let sev_name = match diagnostic.severity {
-            Severity::Error => "error",
-            Severity::Warning => "warning",
-            Severity::Advice => "advice",
-        };
-
-        let message = if let (Some(key), Some(loc)) = (&diagnostic.i18n_key, localizer) { loc.localize(key, &diagnostic.i18n_args) } else { diagnostic.message.clone() };
-
-        if let Some(code) = &diagnostic.code {
-            out.push_str(&format!("[{}] {}\n", code, message));
-        }
-        else {
-            out.push_str(&format!("[{}] {}\n", sev_name, message));
-        }
-
-        // 2. Snippets
-        for label in &diagnostic.labels {
-            self.render_snippet(&mut out, source, &line_map, &full_text, &lines, label, uri);
-        }
-
-        // 3. Help
-        if let Some(help) = &diagnostic.help {
-            out.push_str(&format!("\nhelp: {}\n", help));
-        }
-
-        out
-    }
-}
-
-impl PlainTextEmitter {
-    fn render_snippet<S: Source + ?Sized>(&self, out: &mut String, source: &S, line_map: &LineMap, full_text: &str, lines: &[&str], label: &Label, uri: Option<&str>) {
-        let chars = if self.unicode { Characters::unicode() } else { Characters::ascii() };
-        let (start_line, _) = line_map.offset_to_line_col_utf16(source, label.span.start);
-        let (end_line, _) = line_map.offset_to_line_col_utf16(source, label.span.end);
-        let start_line = start_line as usize;
-        let end_line = end_line as usize;
-        let start_line_start = line_map.line_start(start_line as u32).unwrap_or(0);
-        let end_line_start = line_map.line_start(end_line as u32).unwrap_or(0);
-        let start_col = full_text.get(start_line_start..label.span.start.min(full_text.len())).unwrap_or("").chars().count();
-        let end_col = full_text.get(end_line_start..label.span.end.min(full_text.len())).unwrap_or("").chars().count();
-
-        let line_num_width = (end_line + 1).to_string().len();
-        let padding = " ".repeat(line_num_width);
-
-        // Location info: ┌ at url:line:col
-        let url_str = uri.unwrap_or("<anonymous>");
-        let pos_str = format!("{}:{}", start_line + 1, start_col + 1);
-        out.push_str(&format!("  {} at {}:{}\n", chars.ltop, url_str, pos_str));
-        out.push_str(&format!("{} {}\n", padding, chars.vbar));
-
-        if start_line == end_line {
-            if let Some(line_text) = lines.get(start_line) {
-                out.push_str(&format!("{:>width$} {} {}\n", start_line + 1, chars.vbar, line_text, width = line_num_width));
-                let underline_padding = " ".repeat(start_col);
-                let underline_len = full_text.get(label.span.start.min(full_text.len())..label.span.end.min(full_text.len())).unwrap_or("").chars().count().max(1);
-                let underline = "^".repeat(underline_len);
-                out.push_str(&format!("{} {} {}{}", padding, chars.vbar, underline_padding, underline));
-                if let Some(msg) = &label.message {
-                    out.push_str(&format!(" {}\n", msg));
-                }
-                else {
-                    out.push_str("\n");
-                }
-            }
-        }
-        else {
-            for i in start_line..=end_line {
-                if let Some(line_text) = lines.get(i) {
-                    let line_num = i + 1;
-                    if i == start_line {
-                        out.push_str(&format!("{:>width$} {} {}{}\n", line_num, chars.vbar, chars.ltop, line_text, width = line_num_width));
-                    }
-                    else if i == end_line {
-                        out.push_str(&format!("{:>width$} {} {}{}\n", line_num, chars.vbar, chars.vbar, line_text, width = line_num_width));
-
-                        let underline_len = end_col.max(1);
-                        let underline = "^".repeat(underline_len);
-                        out.push_str(&format!("{} {} {}{}{}", padding, chars.vbar, chars.lbot, chars.hbar.repeat(end_col), underline));
-
-                        if let Some(msg) = &label.message {
-                            out.push_str(&format!(" {}\n", msg));
-                        }
-                        else {
-                            out.push_str("\n");
-                        }
-                    }
-                    else {
-                        out.push_str(&format!("{:>width$} {} {}{}\n", line_num, chars.vbar, chars.vbar, line_text, width = line_num_width));
-                    }
-                }
-            }
-        }
-        out.push_str(&format!("{} {}\n", padding, chars.vbar));
+        let console = ConsoleEmitter { unicode: self.unicode, color: false, width: None, tab_width: 4, context_lines: 1 };
+        console.render_localized(source, diagnostic, localizer, uri)
     }
 }
 
@@ -467,7 +435,7 @@ impl Emitter for HtmlEmitter {
             Severity::Advice => "advice",
         };
 
-        let message = if let (Some(key), Some(loc)) = (&diagnostic.i18n_key, localizer) { loc.localize(key, &diagnostic.i18n_args) } else { diagnostic.message.clone() };
+        let message = localized_message(diagnostic, localizer);
 
         out.push_str("<div class=\"diagnostic\">\n");
         out.push_str(&format!("  <div class=\"header {}\">\n", sev_class));
@@ -585,7 +553,7 @@ impl Emitter for LspEmitter {
             Severity::Advice => 3,  // Information
         };
 
-        let message = if let (Some(key), Some(loc)) = (&diagnostic.i18n_key, localizer) { loc.localize(key, &diagnostic.i18n_args) } else { diagnostic.message.clone() };
+        let message = localized_message(diagnostic, localizer);
 
         let lsp_diag = serde_json::json!({
             "range": {
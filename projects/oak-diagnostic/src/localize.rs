@@ -0,0 +1,463 @@
+use crate::Localizer;
+use std::{collections::HashMap, fmt, path::Path};
+
+/// The English messages bundled with the crate.
+const BUNDLED_EN: &str = include_str!("../locales/en.ftl");
+/// The Chinese messages bundled with the crate.
+const BUNDLED_ZH: &str = include_str!("../locales/zh.ftl");
+
+/// A part of a message pattern.
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    /// Literal text.
+    Text(String),
+    /// The value of an argument, like `{ $name }`.
+    Variable(String),
+    /// A choice between variants by the value of an argument, like `{ $count -> [one] ... *[other] ... }`.
+    Select {
+        /// The argument to select by.
+        variable: String,
+        /// The variants with their keys.
+        variants: Vec<(String, Vec<Element>)>,
+        /// The index of the default variant.
+        default: usize,
+    },
+}
+
+/// Parses the pattern of a message.
+struct PatternParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> PatternParser<'a> {
+    fn parse(text: &'a str) -> Result<Vec<Element>, String> {
+        let mut parser = Self { text, pos: 0 };
+        let pattern = parser.pattern(false)?;
+        match parser.rest().chars().next() {
+            Some(c) => Err(format!("unexpected `{}`", c)),
+            None => Ok(pattern),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_blank(&mut self) {
+        self.pos = self.text.len() - self.rest().trim_start().len()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.rest().strip_prefix(token) {
+            Some(_) => {
+                self.pos += token.len();
+                Ok(())
+            }
+            None => Err(format!("expected `{}`", token)),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))).unwrap_or(rest.len());
+        if len == 0 {
+            return Err("expected an identifier".to_string());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Parses text and placeables up to a closing brace, the end, or in a variant the end of the line.
+    fn pattern(&mut self, variant: bool) -> Result<Vec<Element>, String> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                '}' => break,
+                '\n' if variant => break,
+                '{' => {
+                    self.pos += 1;
+                    if !text.is_empty() {
+                        elements.push(Element::Text(std::mem::take(&mut text)))
+                    }
+                    elements.push(self.placeable()?)
+                }
+                _ => {
+                    text.push(c);
+                    self.pos += c.len_utf8()
+                }
+            }
+        }
+        if !text.is_empty() {
+            elements.push(Element::Text(text))
+        }
+        Ok(elements)
+    }
+
+    /// Parses a placeable after its opening brace.
+    fn placeable(&mut self) -> Result<Element, String> {
+        self.skip_blank();
+        let element = if let Some(rest) = self.rest().strip_prefix('"') {
+            let end = rest.find('"').ok_or("unterminated string literal")?;
+            self.pos += end + 2;
+            Element::Text(rest[..end].to_string())
+        }
+        else if self.rest().starts_with('$') {
+            self.pos += 1;
+            let variable = self.identifier()?.to_string();
+            self.skip_blank();
+            if self.rest().starts_with("->") {
+                self.pos += 2;
+                self.select(variable)?
+            }
+            else {
+                Element::Variable(variable)
+            }
+        }
+        else {
+            return Err("expected a variable or a string literal".to_string());
+        };
+        self.skip_blank();
+        self.expect("}")?;
+        Ok(element)
+    }
+
+    /// Parses the variants of a select expression after its arrow.
+    fn select(&mut self, variable: String) -> Result<Element, String> {
+        let mut variants = Vec::new();
+        let mut default = None;
+        loop {
+            self.skip_blank();
+            let is_default = self.rest().starts_with("*[");
+            if is_default {
+                self.pos += 1
+            }
+            if !self.rest().starts_with('[') {
+                break;
+            }
+            self.pos += 1;
+            self.skip_blank();
+            let key = self.identifier()?.to_string();
+            self.skip_blank();
+            self.expect("]")?;
+            self.pos = self.text.len() - self.rest().trim_start_matches([' ', '\t']).len();
+            let mut pattern = self.pattern(true)?;
+            if let Some(Element::Text(text)) = pattern.last_mut() {
+                text.truncate(text.trim_end().len())
+            }
+            if is_default {
+                default = Some(variants.len())
+            }
+            variants.push((key, pattern))
+        }
+        let default = default.ok_or("a select expression needs a default variant marked with `*`")?;
+        Ok(Element::Select { variable, variants, default })
+    }
+}
+
+/// Returns the plural category of a number in a language, as used by the keys of select expressions.
+fn plural_category(language: &str, number: f64) -> &'static str {
+    match language {
+        "zh" | "ja" | "ko" | "vi" | "th" | "id" | "ms" => "other",
+        "fr" | "pt" if (0.0..2.0).contains(&number) => "one",
+        "fr" | "pt" => "other",
+        _ if number == 1.0 => "one",
+        _ => "other",
+    }
+}
+
+/// The messages of one locale.
+///
+/// Catalogs are read from a subset of the [Fluent](https://projectfluent.org) syntax, where message ids
+/// may contain dots so that they match [`OakErrorKind::key`](oak_core::errors::OakErrorKind::key):
+///
+/// ```ftl
+/// # A comment.
+/// error.unexpected_token = unexpected token `{ $token }`
+/// summary.errors =
+///     { $count ->
+///         [one] { $count } error
+///        *[other] { $count } errors
+///     }
+/// ```
+///
+/// or from TOML, where every string is a pattern in the same syntax and nested tables are joined
+/// with dots:
+///
+/// ```toml
+/// [error]
+/// unexpected_token = "unexpected token `{ $token }`"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<String, Vec<Element>>,
+}
+
+impl Catalog {
+    /// Creates an empty catalog.
+    pub fn new(locale: impl Into<String>) -> Self {
+        Self { locale: locale.into(), messages: HashMap::new() }
+    }
+
+    /// Parses a catalog in the Fluent syntax.
+    pub fn from_ftl(locale: impl Into<String>, text: &str) -> Result<Self, CatalogError> {
+        let mut catalog = Self::new(locale);
+        // The id, the line and the lines of the message being read.
+        let mut current: Option<(String, usize, Vec<&str>)> = None;
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                if let Some((_, _, lines)) = &mut current {
+                    lines.push("")
+                }
+            }
+            else if line.starts_with([' ', '\t']) {
+                let Some((_, _, lines)) = &mut current
+                else {
+                    return Err(CatalogError::Parse { line: index + 1, message: "indented line outside of a message".to_string() });
+                };
+                lines.push(line)
+            }
+            else {
+                if let Some((id, line, lines)) = current.take() {
+                    catalog.insert_lines(id, line, lines)?
+                }
+                if line.starts_with('#') {
+                    continue;
+                }
+                let Some((id, value)) = line.split_once('=')
+                else {
+                    return Err(CatalogError::Parse { line: index + 1, message: "expected `id = message`".to_string() });
+                };
+                current = Some((id.trim().to_string(), index + 1, vec![value.trim_start()]))
+            }
+        }
+        if let Some((id, line, lines)) = current {
+            catalog.insert_lines(id, line, lines)?
+        }
+        Ok(catalog)
+    }
+
+    /// Adds a message from its first line and its indented continuation lines.
+    fn insert_lines(&mut self, id: String, line: usize, lines: Vec<&str>) -> Result<(), CatalogError> {
+        let indent = lines.iter().skip(1).filter(|line| !line.is_empty()).map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+        let value = lines.iter().enumerate().map(|(index, line)| if index == 0 { line } else { line.get(indent..).unwrap_or_default() }).collect::<Vec<_>>().join("\n");
+        self.insert(id, value.trim()).map_err(|error| match error {
+            CatalogError::Parse { message, .. } => CatalogError::Parse { line, message },
+            error => error,
+        })
+    }
+
+    /// Parses a catalog in TOML.
+    pub fn from_toml(locale: impl Into<String>, text: &str) -> Result<Self, CatalogError> {
+        fn walk(catalog: &mut Catalog, prefix: &str, table: &toml::Table) -> Result<(), CatalogError> {
+            for (key, value) in table {
+                let id = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                match value {
+                    toml::Value::String(pattern) => catalog.insert(id, pattern)?,
+                    toml::Value::Table(table) => walk(catalog, &id, table)?,
+                    _ => return Err(CatalogError::Parse { line: 0, message: format!("the message `{}` is not a string", id) }),
+                }
+            }
+            Ok(())
+        }
+        let table: toml::Table = toml::from_str(text).map_err(|error| CatalogError::Parse { line: 0, message: error.to_string() })?;
+        let mut catalog = Self::new(locale);
+        walk(&mut catalog, "", &table)?;
+        Ok(catalog)
+    }
+
+    /// Loads a catalog file, as TOML if its extension is `toml` and as Fluent otherwise.
+    pub fn load(locale: impl Into<String>, path: impl AsRef<Path>) -> Result<Self, CatalogError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(locale, &text),
+            _ => Self::from_ftl(locale, &text),
+        }
+    }
+
+    /// Adds or replaces a message.
+    pub fn insert(&mut self, id: impl Into<String>, pattern: &str) -> Result<(), CatalogError> {
+        let pattern = PatternParser::parse(pattern).map_err(|message| CatalogError::Parse { line: 0, message })?;
+        self.messages.insert(id.into(), pattern);
+        Ok(())
+    }
+
+    /// Adds the messages of another catalog, replacing those with the same id.
+    pub fn extend(&mut self, other: Catalog) {
+        self.messages.extend(other.messages)
+    }
+
+    /// Returns the locale of the catalog.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Checks whether the catalog has a message.
+    pub fn contains(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+
+    /// Returns the ids of the messages.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+
+    /// Formats a message with arguments, or returns `None` if the catalog does not have it.
+    ///
+    /// Missing arguments are rendered as `{$name}`. A select expression picks the variant whose key
+    /// equals the argument, then the one named after the plural category of the argument if it is a
+    /// number, and the default variant otherwise.
+    pub fn format(&self, id: &str, args: &HashMap<String, String>) -> Option<String> {
+        let mut out = String::new();
+        self.write(&mut out, self.messages.get(id)?, args);
+        Some(out)
+    }
+
+    fn write(&self, out: &mut String, pattern: &[Element], args: &HashMap<String, String>) {
+        for element in pattern {
+            match element {
+                Element::Text(text) => out.push_str(text),
+                Element::Variable(name) => match args.get(name) {
+                    Some(value) => out.push_str(value),
+                    None => out.push_str(&format!("{{${}}}", name)),
+                },
+                Element::Select { variable, variants, default } => {
+                    let value = args.get(variable).map(String::as_str);
+                    let category = value.and_then(|value| value.trim().parse::<f64>().ok()).map(|number| plural_category(language(&self.locale), number));
+                    let variant = variants.iter().find(|(key, _)| Some(key.as_str()) == value).or_else(|| variants.iter().find(|(key, _)| Some(key.as_str()) == category)).unwrap_or(&variants[*default]);
+                    self.write(out, &variant.1, args)
+                }
+            }
+        }
+    }
+}
+
+/// Normalizes a locale like `zh_CN.UTF-8` to `zh-cn`.
+fn normalize(locale: &str) -> String {
+    locale.split(['.', '@']).next().unwrap_or_default().replace('_', "-").to_lowercase()
+}
+
+/// Returns the language of a normalized locale.
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or_default()
+}
+
+/// A [`Localizer`] backed by message [`Catalog`]s of several locales.
+///
+/// A message is looked up in the requested locale, then in the locales with fewer subtags, and then
+/// in the fallback locale, so `zh-Hans-CN` tries `zh-Hans-CN`, `zh-Hans`, `zh` and `en`.
+#[derive(Debug, Clone)]
+pub struct CatalogLocalizer {
+    locale: String,
+    fallback: String,
+    chain: Vec<String>,
+    catalogs: HashMap<String, Catalog>,
+}
+
+impl CatalogLocalizer {
+    /// Creates a localizer without catalogs that falls back to English.
+    pub fn new(locale: impl Into<String>) -> Self {
+        let mut localizer = Self { locale: locale.into(), fallback: "en".to_string(), chain: Vec::new(), catalogs: HashMap::new() };
+        localizer.chain = localizer.compute_chain();
+        localizer
+    }
+
+    /// Creates a localizer with the bundled English and Chinese catalogs, which cover every
+    /// [`OakErrorKind`](oak_core::errors::OakErrorKind).
+    pub fn bundled(locale: impl Into<String>) -> Self {
+        let bundled = [Catalog::from_ftl("en", BUNDLED_EN), Catalog::from_ftl("zh", BUNDLED_ZH)];
+        bundled.into_iter().flatten().fold(Self::new(locale), Self::with_catalog)
+    }
+
+    /// Sets the locale used when no other locale of the chain has a message.
+    pub fn with_fallback(mut self, fallback: impl Into<String>) -> Self {
+        self.fallback = fallback.into();
+        self.chain = self.compute_chain();
+        self
+    }
+
+    /// Adds a catalog, merging it into the one of the same locale.
+    pub fn with_catalog(mut self, catalog: Catalog) -> Self {
+        self.add_catalog(catalog);
+        self
+    }
+
+    /// Adds a catalog, merging it into the one of the same locale.
+    pub fn add_catalog(&mut self, catalog: Catalog) {
+        match self.catalogs.get_mut(&normalize(catalog.locale())) {
+            Some(existing) => existing.extend(catalog),
+            None => {
+                self.catalogs.insert(normalize(catalog.locale()), catalog);
+            }
+        }
+    }
+
+    /// Returns the requested locale.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Returns the normalized locales in the order messages are looked up.
+    pub fn fallback_chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    fn compute_chain(&self) -> Vec<String> {
+        let mut chain = Vec::new();
+        for locale in [&self.locale, &self.fallback] {
+            let mut tag = normalize(locale);
+            while !tag.is_empty() {
+                if !chain.contains(&tag) {
+                    chain.push(tag.clone())
+                }
+                tag.truncate(tag.rfind('-').unwrap_or(0))
+            }
+        }
+        chain
+    }
+}
+
+impl Localizer for CatalogLocalizer {
+    fn localize(&self, key: &str, args: &HashMap<String, String>) -> String {
+        self.lookup(key, args).unwrap_or_else(|| key.to_string())
+    }
+
+    fn lookup(&self, key: &str, args: &HashMap<String, String>) -> Option<String> {
+        self.chain.iter().find_map(|locale| self.catalogs.get(locale)?.format(key, args))
+    }
+}
+
+/// An error in a message catalog.
+#[derive(Debug)]
+pub enum CatalogError {
+    /// The catalog file could not be read.
+    Io(std::io::Error),
+    /// The catalog is malformed.
+    Parse {
+        /// The line of the error, starting at 1, or 0 if it is not known.
+        line: usize,
+        /// What is wrong.
+        message: String,
+    },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io(error) => write!(f, "cannot read message catalog: {}", error),
+            CatalogError::Parse { line: 0, message } => write!(f, "invalid message catalog: {}", message),
+            CatalogError::Parse { line, message } => write!(f, "invalid message catalog at line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl From<std::io::Error> for CatalogError {
+    fn from(error: std::io::Error) -> Self {
+        CatalogError::Io(error)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Diagnostic, Severity};
+use core::range::Range;
+use oak_core::source::Source;
+use oak_vfs::{LineMap, PositionEncoding};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A description of a rule that produces diagnostics, keyed by the [code](Diagnostic::code) of its
+/// diagnostics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleMetadata {
+    /// The id of the rule, which is the code of its diagnostics.
+    pub id: String,
+    /// A one-line description of what the rule checks.
+    pub description: String,
+    /// A link to the documentation of the rule.
+    pub help_uri: Option<String>,
+    /// The severity the rule reports by default.
+    pub severity: Severity,
+}
+
+impl RuleMetadata {
+    /// Creates the metadata of a rule that reports warnings.
+    pub fn new(id: impl Into<String>, description: impl Into<String>) -> Self {
+        Self { id: id.into(), description: description.into(), help_uri: None, severity: Severity::Warning }
+    }
+
+    /// Sets the link to the documentation of the rule.
+    pub fn with_help_uri(mut self, help_uri: impl Into<String>) -> Self {
+        self.help_uri = Some(help_uri.into());
+        self
+    }
+
+    /// Sets the default severity of the rule.
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+}
+
+/// A region of a file, with lines and columns starting at 1 and columns counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The line of the start.
+    pub start_line: u32,
+    /// The column of the start.
+    pub start_column: u32,
+    /// The line of the end.
+    pub end_line: u32,
+    /// The column of the end, exclusive.
+    pub end_column: u32,
+}
+
+/// A diagnostic of a [`FileReport`] with its location and fingerprint.
+#[derive(Debug, Clone)]
+pub struct ReportEntry<'a> {
+    /// The diagnostic.
+    pub diagnostic: &'a Diagnostic,
+    /// The location of the first label, or the start of the file.
+    pub location: Location,
+    /// A hash that identifies the diagnostic across runs, see [`FileReport::entries`].
+    pub fingerprint: String,
+}
+
+/// The diagnostics of one file.
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    /// The uri or path of the file.
+    pub uri: String,
+    /// The diagnostics of the file.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The text of the file.
+    text: String,
+    /// The line map of the text.
+    line_map: LineMap,
+}
+
+impl FileReport {
+    /// Creates the report of a file.
+    pub fn new<S: Source + ?Sized>(uri: impl Into<String>, source: &S, diagnostics: Vec<Diagnostic>) -> Self {
+        let text = source.get_text_in(Range { start: 0, end: source.length() }).into_owned();
+        Self { uri: uri.into(), diagnostics, line_map: LineMap::from_source(source), text }
+    }
+
+    /// Returns the path of the file, which is the uri without a `file://` scheme.
+    pub fn path(&self) -> &str {
+        self.uri.strip_prefix("file://").unwrap_or(&self.uri)
+    }
+
+    /// Returns the text of the file.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the line map of the text.
+    pub fn line_map(&self) -> &LineMap {
+        &self.line_map
+    }
+
+    /// Returns the location of a byte range.
+    pub fn location(&self, span: Range<usize>) -> Location {
+        let (start_line, start_column) = self.line_map.offset_to_position(span.start, PositionEncoding::Utf32);
+        let (end_line, end_column) = self.line_map.offset_to_position(span.end, PositionEncoding::Utf32);
+        Location { start_line: start_line + 1, start_column: start_column + 1, end_line: end_line + 1, end_column: end_column + 1 }
+    }
+
+    /// Returns the diagnostics with their locations and fingerprints.
+    ///
+    /// The fingerprint hashes the path, the code, the message and the trimmed text of the first
+    /// line of the diagnostic, but not its position, so it survives edits elsewhere in the file.
+    /// Identical diagnostics are told apart by their order.
+    pub fn entries(&self) -> Vec<ReportEntry<'_>> {
+        let mut seen = HashMap::new();
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic.labels.first().map(|label| label.span).unwrap_or(Range { start: 0, end: 0 });
+                let location = self.location(span);
+                let line = self.line_map.line_of(span.start);
+                let line_text = match (self.line_map.line_start(line), self.line_map.line_content_end(line)) {
+                    (Some(start), Some(end)) => self.text.get(start..end).unwrap_or_default().trim(),
+                    _ => "",
+                };
+                let parts = [self.path(), diagnostic.code.as_deref().unwrap_or_default(), &diagnostic.message, line_text];
+                let occurrence = seen.entry(fnv1a(parts)).or_insert(0u64);
+                *occurrence += 1;
+                let fingerprint = fnv1a(parts.into_iter().chain([occurrence.to_string().as_str()]));
+                ReportEntry { diagnostic, location, fingerprint: format!("{:016x}", fingerprint) }
+            })
+            .collect()
+    }
+}
+
+/// Hashes strings with FNV-1a, which is stable across platforms and compiler versions.
+fn fnv1a<'a>(parts: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for part in parts {
+        for byte in part.bytes().chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3)
+        }
+    }
+    hash
+}
+
+/// The diagnostics of many files from one run of a tool, for the batch formats of [`ReportEmitter`]s.
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// The name of the tool that produced the diagnostics.
+    pub tool_name: String,
+    /// The version of the tool.
+    pub tool_version: Option<String>,
+    /// A link to the homepage of the tool.
+    pub information_uri: Option<String>,
+    /// The rules of the tool by id.
+    pub rules: BTreeMap<String, RuleMetadata>,
+    /// The reports of the checked files.
+    pub files: Vec<FileReport>,
+}
+
+impl Report {
+    /// Creates an empty report of a tool.
+    pub fn new(tool_name: impl Into<String>) -> Self {
+        Self { tool_name: tool_name.into(), tool_version: None, information_uri: None, rules: BTreeMap::new(), files: Vec::new() }
+    }
+
+    /// Sets the version of the tool.
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.tool_version = Some(version.into());
+        self
+    }
+
+    /// Sets the link to the homepage of the tool.
+    pub fn with_information_uri(mut self, uri: impl Into<String>) -> Self {
+        self.information_uri = Some(uri.into());
+        self
+    }
+
+    /// Adds the metadata of rules.
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = RuleMetadata>) -> Self {
+        self.rules.extend(rules.into_iter().map(|rule| (rule.id.clone(), rule)));
+        self
+    }
+
+    /// Adds the diagnostics of a file.
+    pub fn add_file<S: Source + ?Sized>(&mut self, uri: impl Into<String>, source: &S, diagnostics: Vec<Diagnostic>) {
+        self.files.push(FileReport::new(uri, source, diagnostics))
+    }
+
+    /// Returns the number of diagnostics with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.files.iter().flat_map(|file| &file.diagnostics).filter(|diagnostic| diagnostic.severity == severity).count()
+    }
+
+    /// Returns the ids of the known rules and of the codes of all diagnostics, sorted.
+    pub fn rule_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.rules.keys().map(String::as_str).chain(self.files.iter().flat_map(|file| &file.diagnostics).filter_map(|diagnostic| diagnostic.code.as_deref())).collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Renders a whole [`Report`] in a batch format.
+pub trait ReportEmitter {
+    /// Renders the report to a string.
+    fn emit(&self, report: &Report) -> String;
+}
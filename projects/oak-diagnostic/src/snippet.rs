@@ -0,0 +1,429 @@
+use crate::Label;
+use oak_vfs::LineMap;
+use std::collections::BTreeSet;
+use unicode_width::UnicodeWidthChar;
+
+/// The ANSI style of the gutter.
+const GUTTER: &str = "\x1b[34m";
+/// The ANSI style of secondary labels.
+const SECONDARY: &str = "\x1b[36;1m";
+
+/// The text of a source with its line map, shared by all diagnostics rendered for it.
+pub(crate) struct SourceView<'a> {
+    /// The whole text.
+    pub text: &'a str,
+    /// The line map of the text.
+    pub line_map: &'a LineMap,
+}
+
+impl SourceView<'_> {
+    /// Returns the content of a line, without the line terminator.
+    fn line(&self, line: usize) -> &str {
+        match (self.line_map.line_start(line as u32), self.line_map.line_content_end(line as u32)) {
+            (Some(start), Some(end)) => self.text.get(start..end).unwrap_or_default(),
+            _ => "",
+        }
+    }
+}
+
+/// The characters used to draw snippets.
+pub(crate) struct Characters {
+    vbar: &'static str,
+    hbar: &'static str,
+    ltop: &'static str,
+    lbot: &'static str,
+    header: &'static str,
+    ellipsis: &'static str,
+}
+
+impl Characters {
+    pub(crate) fn unicode() -> Self {
+        Self { vbar: "│", hbar: "─", ltop: "┌", lbot: "└", header: "┌─", ellipsis: "…" }
+    }
+
+    pub(crate) fn ascii() -> Self {
+        Self { vbar: "|", hbar: "-", ltop: "/", lbot: "`", header: "-->", ellipsis: "..." }
+    }
+}
+
+/// Adds ANSI styles to text, or nothing if colors are off.
+#[derive(Clone, Copy)]
+pub(crate) struct Palette {
+    pub enabled: bool,
+}
+
+impl Palette {
+    pub(crate) fn paint(&self, text: &str, style: &str) -> String {
+        if self.enabled && !text.trim().is_empty() { format!("{}{}\x1b[0m", style, text) } else { text.to_string() }
+    }
+}
+
+/// A line with tabs expanded, and the display column of each of its bytes.
+struct ExpandedLine {
+    text: String,
+    /// The display column of every byte offset of the original line, plus one past its end.
+    columns: Vec<usize>,
+}
+
+impl ExpandedLine {
+    fn new(line: &str, tab_width: usize) -> Self {
+        let mut text = String::with_capacity(line.len());
+        let mut columns = Vec::with_capacity(line.len() + 1);
+        let mut column = 0;
+        for c in line.chars() {
+            columns.extend(std::iter::repeat_n(column, c.len_utf8()));
+            if c == '\t' {
+                let width = tab_width - column % tab_width.max(1);
+                text.extend(std::iter::repeat_n(' ', width));
+                column += width
+            }
+            else {
+                text.push(c);
+                column += c.width().unwrap_or(0)
+            }
+        }
+        columns.push(column);
+        Self { text, columns }
+    }
+
+    fn width(&self) -> usize {
+        self.columns.last().copied().unwrap_or(0)
+    }
+
+    fn column(&self, byte: usize) -> usize {
+        self.columns[byte.min(self.columns.len() - 1)]
+    }
+}
+
+/// The visible columns of a line that is too wide for the terminal.
+#[derive(Clone, Copy)]
+struct Window {
+    /// The first visible column.
+    start: usize,
+    /// The end of the visible columns.
+    end: usize,
+    /// The width of the ellipsis before the visible columns, if some are hidden.
+    shift: usize,
+}
+
+impl Window {
+    /// Maps a column of the line to a column of the rendered row, clamped into the window.
+    fn map(&self, column: usize) -> usize {
+        column.clamp(self.start, self.end) - self.start + self.shift
+    }
+}
+
+/// A label placed on the lines of the source.
+struct Mark<'l> {
+    label: &'l Label,
+    style: String,
+    primary: bool,
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+    /// The gutter column of a label spanning several lines.
+    slot: usize,
+}
+
+impl Mark<'_> {
+    fn is_multiline(&self) -> bool {
+        self.start_line != self.end_line
+    }
+}
+
+/// A row of cells with styles, for underlines and arms.
+struct Canvas<'s> {
+    cells: Vec<(&'s str, &'s str)>,
+}
+
+impl<'s> Canvas<'s> {
+    fn new() -> Self {
+        Self { cells: Vec::new() }
+    }
+
+    fn put(&mut self, column: usize, text: &'s str, style: &'s str) {
+        if self.cells.len() <= column {
+            self.cells.resize(column + 1, (" ", ""))
+        }
+        self.cells[column] = (text, style)
+    }
+
+    fn is_free(&self, column: usize) -> bool {
+        self.cells.get(column).is_none_or(|(text, _)| *text == " ")
+    }
+
+    fn render(&self, palette: Palette) -> String {
+        self.cells.iter().map(|(text, style)| palette.paint(text, style)).collect()
+    }
+}
+
+/// Renders the labels of one diagnostic as a single snippet of their source.
+pub(crate) struct SnippetRenderer<'a> {
+    pub view: &'a SourceView<'a>,
+    pub chars: Characters,
+    pub palette: Palette,
+    pub width: Option<usize>,
+    pub tab_width: usize,
+    pub context_lines: usize,
+}
+
+impl SnippetRenderer<'_> {
+    /// Renders `labels`, using `primary_style` for the primary labels.
+    pub(crate) fn render(&self, out: &mut String, labels: &[Label], primary_style: &str, uri: Option<&str>) {
+        let marks = self.marks(labels, primary_style);
+        let Some(first) = marks.iter().find(|mark| mark.primary).or(marks.first())
+        else {
+            return;
+        };
+        let lines = self.shown_lines(&marks);
+        let gutter_width = (lines.last().copied().unwrap_or(0) + 1).to_string().len();
+        let slots = marks.iter().filter(|mark| mark.is_multiline()).count();
+        let padding = " ".repeat(gutter_width);
+        let vbar = self.palette.paint(self.chars.vbar, GUTTER);
+
+        // The location of the primary label, with the column counted in characters.
+        let line_start = self.view.line_map.line_start(first.start_line as u32).unwrap_or(0);
+        let offset = first.label.span.start.clamp(line_start, self.view.text.len());
+        let column = self.view.text.get(line_start..offset).unwrap_or_default().chars().count();
+        out.push_str(&format!("{} {} {}:{}:{}\n", padding, self.palette.paint(self.chars.header, GUTTER), uri.unwrap_or("<anonymous>"), first.start_line + 1, column + 1));
+        out.push_str(&format!("{} {}\n", padding, vbar));
+
+        let mut previous: Option<usize> = None;
+        for &line in &lines {
+            if let Some(previous) = previous
+                && line > previous + 1
+            {
+                let bars = self.slots(&marks, slots, |mark| mark.start_line <= previous && line <= mark.end_line, |_| None);
+                push_row(out, format!("{:<width$}{}", "...", bars, width = gutter_width + 3));
+            }
+            previous = Some(line);
+
+            let expanded = ExpandedLine::new(self.view.line(line), self.tab_width);
+            let window = self.window(&marks, line, &expanded, gutter_width + 3 + slots + usize::from(slots > 0));
+            let bars = self.slots(&marks, slots, |mark| mark.start_line <= line && line <= mark.end_line, |mark| (mark.start_line == line).then_some(self.chars.ltop));
+            push_row(out, format!("{:>width$} {} {}{}", line + 1, vbar, bars, self.visible_text(&expanded, window), width = gutter_width));
+
+            let row = |bars: String, canvas: String| format!("{} {} {}{}", padding, vbar, bars, canvas);
+            let active = |mark: &Mark| mark.start_line <= line && line < mark.end_line;
+            for canvas in self.annotations(&marks, line, window) {
+                push_row(out, row(self.slots(&marks, slots, active, |_| None), canvas))
+            }
+            for mark in marks.iter().rev().filter(|mark| mark.is_multiline() && mark.end_line == line) {
+                let bars = self.slots(&marks, slots, active, |other| match other.slot.cmp(&mark.slot) {
+                    core::cmp::Ordering::Equal => Some(self.chars.lbot),
+                    core::cmp::Ordering::Greater => Some(self.chars.hbar),
+                    core::cmp::Ordering::Less => None,
+                });
+                let arm = self.chars.hbar.repeat(window.map(mark.end_column).saturating_sub(1) + usize::from(slots > 0));
+                let pointer = if mark.primary { "^" } else { "-" };
+                let message = mark.label.message.as_deref().map(|message| format!(" {}", message)).unwrap_or_default();
+                push_row(out, row(bars.trim_end().to_string(), self.palette.paint(&format!("{}{}{}", arm, pointer, message), &mark.style)))
+            }
+        }
+        out.push_str(&format!("{} {}\n", padding, vbar));
+    }
+
+    /// Places the labels on the lines of the source.
+    fn marks<'l>(&self, labels: &'l [Label], primary_style: &str) -> Vec<Mark<'l>> {
+        let line_map = self.view.line_map;
+        let len = self.view.text.len();
+        let mut marks: Vec<_> = labels
+            .iter()
+            .map(|label| {
+                let start = label.span.start.min(len);
+                let end = label.span.end.clamp(start, len);
+                let start_line = line_map.line_of(start) as usize;
+                let mut end_line = line_map.line_of(end) as usize;
+                // A span ending right after a line break ends on the line of the break.
+                if end_line > start_line && line_map.line_start(end_line as u32) == Some(end) {
+                    end_line -= 1
+                }
+                let column = |line: usize, offset: usize| {
+                    let line_start = line_map.line_start(line as u32).unwrap_or(0);
+                    ExpandedLine::new(self.view.line(line), self.tab_width).column(offset.saturating_sub(line_start))
+                };
+                let start_column = column(start_line, start);
+                let mut end_column = column(end_line, end);
+                if end_line == start_line {
+                    end_column = end_column.max(start_column + 1)
+                }
+                let style = label.color.clone().unwrap_or_else(|| if label.primary { primary_style.to_string() } else { SECONDARY.to_string() });
+                Mark { label, style, primary: label.primary, start_line, start_column, end_line, end_column, slot: 0 }
+            })
+            .collect();
+        marks.sort_by_key(|mark| (mark.start_line, mark.start_column));
+        for (slot, mark) in marks.iter_mut().filter(|mark| mark.is_multiline()).enumerate() {
+            mark.slot = slot
+        }
+        marks
+    }
+
+    /// Returns the lines to show: the lines of the labels and their context, with gaps of a single
+    /// line filled in, since folding them saves nothing.
+    fn shown_lines(&self, marks: &[Mark]) -> Vec<usize> {
+        let last = self.view.line_map.line_count().saturating_sub(1);
+        let mut lines = BTreeSet::new();
+        for mark in marks {
+            for line in [mark.start_line, mark.end_line] {
+                lines.extend(line.saturating_sub(self.context_lines)..=(line + self.context_lines).min(last))
+            }
+        }
+        let gaps: Vec<_> = lines.iter().zip(lines.iter().skip(1)).filter(|(a, b)| **b == **a + 2).map(|(a, _)| a + 1).collect();
+        lines.extend(gaps);
+        lines.into_iter().collect()
+    }
+
+    /// Renders the gutter columns of the labels spanning several lines.
+    fn slots(&self, marks: &[Mark], slots: usize, active: impl Fn(&Mark) -> bool, special: impl Fn(&Mark) -> Option<&'static str>) -> String {
+        if slots == 0 {
+            return String::new();
+        }
+        let mut out = String::new();
+        for mark in marks.iter().filter(|mark| mark.is_multiline()) {
+            match special(mark) {
+                Some(text) => out.push_str(&self.palette.paint(text, &mark.style)),
+                None if active(mark) => out.push_str(&self.palette.paint(self.chars.vbar, &mark.style)),
+                None => out.push(' '),
+            }
+        }
+        out.push(' ');
+        out
+    }
+
+    /// Chooses the visible columns of a line, keeping its labels in view if it is too wide.
+    fn window(&self, marks: &[Mark], line: usize, expanded: &ExpandedLine, used: usize) -> Window {
+        let total = expanded.width();
+        let full = Window { start: 0, end: total, shift: 0 };
+        let Some(width) = self.width
+        else {
+            return full;
+        };
+        let available = width.saturating_sub(used).max(self.chars.ellipsis.chars().count() * 2 + 8);
+        if total <= available {
+            return full;
+        }
+        let ellipsis = self.chars.ellipsis.chars().count();
+        let mut focus = marks.iter().filter_map(|mark| match (mark.start_line == line, mark.end_line == line) {
+            (true, true) => Some((mark.start_column, mark.end_column)),
+            (true, false) => Some((mark.start_column, mark.start_column + 1)),
+            (false, true) => Some((mark.end_column.saturating_sub(1), mark.end_column)),
+            (false, false) => None,
+        });
+        let (low, high) = focus.next().map(|first| focus.fold(first, |(low, high), (start, end)| (low.min(start), high.max(end)))).unwrap_or((0, 0));
+        let start = if high + ellipsis <= available { 0 } else { low.saturating_sub(available / 4).min(total.saturating_sub(available - ellipsis)) };
+        let shift = if start > 0 { ellipsis } else { 0 };
+        let end = (start + available - shift).min(total);
+        let end = if end < total { end - ellipsis } else { end };
+        Window { start, end, shift }
+    }
+
+    /// Renders the visible part of a line.
+    fn visible_text(&self, expanded: &ExpandedLine, window: Window) -> String {
+        let mut out = String::new();
+        if window.start > 0 {
+            out.push_str(&self.palette.paint(self.chars.ellipsis, GUTTER))
+        }
+        let mut column = 0;
+        for c in expanded.text.chars() {
+            let width = c.width().unwrap_or(0);
+            if column >= window.start && column + width <= window.end {
+                out.push(c)
+            }
+            column += width
+        }
+        if window.end < expanded.width() {
+            out.push_str(&self.palette.paint(self.chars.ellipsis, GUTTER))
+        }
+        out
+    }
+
+    /// Renders the underlines and messages of the single line labels on a line.
+    ///
+    /// Overlapping labels are underlined on separate rows. Every message hangs from an arm at the start
+    /// of its label, except that the rightmost label keeps its message next to its underline.
+    fn annotations(&self, marks: &[Mark], line: usize, window: Window) -> Vec<String> {
+        let marks: Vec<_> = marks.iter().filter(|mark| !mark.is_multiline() && mark.start_line == line).collect();
+        if marks.is_empty() {
+            return Vec::new();
+        }
+        let span = |mark: &Mark| (window.map(mark.start_column), window.map(mark.end_column).max(window.map(mark.start_column) + 1));
+
+        // Spread overlapping labels over layers.
+        let mut layers: Vec<Vec<usize>> = Vec::new();
+        let mut layer_of = vec![0; marks.len()];
+        for (index, mark) in marks.iter().enumerate() {
+            let (start, end) = span(mark);
+            let layer = layers.iter().position(|layer| layer.iter().all(|&other| span(marks[other]).1 <= start || end <= span(marks[other]).0)).unwrap_or(layers.len());
+            if layer == layers.len() {
+                layers.push(Vec::new())
+            }
+            layers[layer].push(index);
+            layer_of[index] = layer
+        }
+
+        // Give every message an arm in its own column.
+        let mut arms: Vec<(usize, usize)> = Vec::new();
+        for (index, mark) in marks.iter().enumerate().filter(|(_, mark)| mark.label.message.is_some()) {
+            let (start, end) = span(mark);
+            let column = (start..end).find(|column| arms.iter().all(|(_, used)| used != column)).unwrap_or(start);
+            arms.push((index, column))
+        }
+        let last_layer = layers.len() - 1;
+        let inline = arms.iter().max_by_key(|(_, column)| *column).copied().filter(|(index, column)| layer_of[*index] == last_layer && arms.iter().all(|(other, other_column)| other == index || other_column < column) && span(marks[*index]).0 == *column);
+        if let Some((index, _)) = inline {
+            arms.retain(|(other, _)| *other != index)
+        }
+
+        let mut rows = Vec::new();
+        for (layer, members) in layers.iter().enumerate() {
+            let mut canvas = Canvas::new();
+            for &index in members {
+                let (start, end) = span(marks[index]);
+                let underline = if marks[index].primary { "^" } else { "-" };
+                for column in start..end {
+                    canvas.put(column, underline, &marks[index].style)
+                }
+            }
+            for &(index, column) in &arms {
+                if layer_of[index] < layer && canvas.is_free(column) {
+                    canvas.put(column, self.chars.vbar, &marks[index].style)
+                }
+            }
+            let mut row = canvas.render(self.palette);
+            if layer == last_layer
+                && let Some((index, _)) = inline
+            {
+                row.push_str(&format!(" {}", self.palette.paint(marks[index].label.message.as_deref().unwrap_or_default(), &marks[index].style)))
+            }
+            rows.push(row)
+        }
+
+        if !arms.is_empty() {
+            let mut canvas = Canvas::new();
+            for &(index, column) in &arms {
+                canvas.put(column, self.chars.vbar, &marks[index].style)
+            }
+            rows.push(canvas.render(self.palette));
+        }
+        arms.sort_by_key(|(_, column)| core::cmp::Reverse(*column));
+        for (position, &(index, column)) in arms.iter().enumerate() {
+            let mut canvas = Canvas::new();
+            for &(other, other_column) in &arms[position + 1..] {
+                canvas.put(other_column, self.chars.vbar, &marks[other].style)
+            }
+            let arm = format!("{}{} {}", self.chars.lbot, self.chars.hbar.repeat(2), marks[index].label.message.as_deref().unwrap_or_default());
+            let mut row = canvas.render(self.palette);
+            row.push_str(&" ".repeat(column.saturating_sub(canvas.cells.len())));
+            row.push_str(&self.palette.paint(&arm, &marks[index].style));
+            rows.push(row)
+        }
+        rows
+    }
+}
+
+/// Pushes a row without trailing whitespace.
+fn push_row(out: &mut String, row: String) {
+    out.push_str(row.trim_end());
+    out.push('\n')
+}
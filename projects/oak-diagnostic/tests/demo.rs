@@ -1,7 +1,13 @@
 #![feature(new_range_api)]
 use core::range::Range;
-use oak_core::SourceText;
-use oak_diagnostic::{ConsoleEmitter, Diagnostic, Emitter, HtmlEmitter, LspEmitter, PlainTextEmitter};
+use oak_core::{
+    SourceText,
+    errors::{OakError, OakErrorKind},
+};
+use oak_diagnostic::{
+    Applicability, Catalog, CatalogLocalizer, CheckstyleEmitter, ConsoleEmitter, Diagnostic, DiagnosticProvider, Emitter, Fix, FixApplier, GitHubEmitter, GitLabEmitter, HtmlEmitter, JUnitEmitter, Localizer, LspEmitter, PlainTextEmitter, Report,
+    ReportEmitter, RuleMetadata, SarifEmitter, Severity, TextEdit, localized_message,
+};
 
 #[test]
 fn test_demo_diagnostic() {
@@ -10,11 +16,11 @@ fn test_demo_diagnostic() {
     let diag = Diagnostic::error("missing semicolon").with_code("E0001").with_label(Range { start: 26, end: 28 }, "expected ';' here").with_help("try adding a semicolon at the end of the line");
 
     println!("--- Console Output (Unicode) ---");
-    let console = ConsoleEmitter { unicode: true }.render(&source, &diag);
+    let console = ConsoleEmitter { unicode: true, ..Default::default() }.render(&source, &diag);
     println!("{}", console);
 
     println!("--- Console Output (ASCII) ---");
-    let console_ascii = ConsoleEmitter { unicode: false }.render(&source, &diag);
+    let console_ascii = ConsoleEmitter { unicode: false, ..Default::default() }.render(&source, &diag);
     println!("{}", console_ascii);
 
     println!("--- Plain Text Output ---");
@@ -49,21 +55,223 @@ fn test_demo_diagnostic() {
     let i18n_diag = Diagnostic::error("default message").with_code("E0001").with_i18n("error.missing_semicolon").with_arg("expected", ";").with_label(Range { start: 26, end: 28 }, "expected ';' here");
 
     println!("--- Localized Console Output ---");
-    println!("{}", ConsoleEmitter { unicode: true }.render_localized(&source, &i18n_diag, Some(&MyLocalizer), None));
+    println!("{}", ConsoleEmitter { unicode: true, ..Default::default() }.render_localized(&source, &i18n_diag, Some(&MyLocalizer), None));
 
     println!("\n=== OakError Diagnostic ===");
     use oak_core::errors::OakError;
     let oak_err = OakError::syntax_error("expected expression", 16, None);
     let oak_diag = Diagnostic::from_provider(&oak_err, &source);
     println!("--- Console Output (from OakError) ---");
-    println!("{}", ConsoleEmitter { unicode: true }.render(&source, &oak_diag));
+    println!("{}", ConsoleEmitter { unicode: true, ..Default::default() }.render(&source, &oak_diag));
 
     println!("\n=== Multi-line Diagnostic ===");
     let multi_diag = Diagnostic::error("unclosed function").with_label(Range { start: 0, end: 32 }, "function starts here and is never closed");
 
     println!("--- Console Output (Unicode) ---");
-    println!("{}", ConsoleEmitter { unicode: true }.render(&source, &multi_diag));
+    println!("{}", ConsoleEmitter { unicode: true, ..Default::default() }.render(&source, &multi_diag));
 
     println!("--- Console Output (ASCII) ---");
-    println!("{}", ConsoleEmitter { unicode: false }.render(&source, &multi_diag))
+    println!("{}", ConsoleEmitter { unicode: false, ..Default::default() }.render(&source, &multi_diag))
+}
+
+#[test]
+fn test_fix_rendering() {
+    let source = SourceText::new("fn main() {\n    let x = 10\n}");
+    let fix = Fix::replace("add a semicolon", Range { start: 26, end: 26 }, ";").with_applicability(Applicability::MachineApplicable);
+    let diag = Diagnostic::error("missing semicolon").with_label(Range { start: 26, end: 27 }, "expected ';' here").with_fix(fix);
+
+    let plain = PlainTextEmitter { unicode: false }.render(&source, &diag);
+    println!("{}", plain);
+    assert!(plain.contains("fix: add a semicolon\n2 -     let x = 10\n2 +     let x = 10;\n"));
+    println!("{}", ConsoleEmitter { unicode: true, ..Default::default() }.render(&source, &diag))
+}
+
+#[test]
+fn test_fix_applier() {
+    let fix = |title: &str, start: usize, end: usize, text: &str| Fix::replace(title, Range { start, end }, text).with_applicability(Applicability::MachineApplicable);
+    let text = "let a = 1\nlet b = 2\n";
+    let diagnostics = vec![
+        Diagnostic::warning("missing semicolon").with_fix(fix("add `;`", 9, 9, ";")),
+        Diagnostic::warning("rename").with_fix(fix("rename to `c`", 4, 5, "c")),
+        // Overlaps the rename, so it is left for the next pass.
+        Diagnostic::warning("rename again").with_fix(fix("rename to `d`", 4, 5, "d")),
+        Diagnostic::warning("unsure").with_fix(fix("remove `b`", 10, 20, "").with_applicability(Applicability::MaybeIncorrect)),
+    ];
+    let applied = FixApplier::new().apply(text, &diagnostics);
+    assert_eq!(applied.text, "let c = 1;\nlet b = 2\n");
+    assert_eq!((applied.applied, applied.skipped), (2, 1));
+    let applied = FixApplier::new().with_applicability(Applicability::MaybeIncorrect).apply(text, &diagnostics[3..]);
+    assert_eq!(applied.text, "let a = 1\n");
+
+    // Adds one semicolon per pass, until every line has one.
+    let check = |text: &str| -> Vec<Diagnostic> {
+        let mut offset = 0;
+        text.split_inclusive('\n')
+            .filter_map(|line| {
+                let end = offset + line.trim_end().len();
+                offset += line.len();
+                (!line.trim_end().ends_with(';')).then(|| Diagnostic::warning("missing semicolon").with_fix(Fix::new("add `;`").with_edit(TextEdit::insert(end, ";")).with_applicability(Applicability::MachineApplicable)))
+            })
+            .take(1)
+            .collect()
+    };
+    let applied = FixApplier::new().apply_until_fixpoint(text, check);
+    assert_eq!(applied.text, "let a = 1;\nlet b = 2;\n");
+    assert_eq!((applied.applied, applied.passes), (2, 3));
+    assert_eq!(FixApplier::new().with_max_passes(1).apply_until_fixpoint(text, check).text, "let a = 1;\nlet b = 2\n")
+}
+
+#[test]
+fn test_report_emitters() {
+    let main = SourceText::new("fn main() {\n    let é = 10\n}");
+    let lib = SourceText::new("// TODO: a, b\n");
+    let mut report = Report::new("oak-lint").with_version("0.0.3").with_rules([RuleMetadata::new("missing-semicolon", "Reports missing semicolons.").with_severity(Severity::Error)]);
+    let missing = || Diagnostic::error("missing semicolon").with_code("missing-semicolon").with_label(Range { start: 25, end: 27 }, "expected ';' here");
+    report.add_file("file:///src/main.rs", &main, vec![missing().with_fix(Fix::replace("add `;`", Range { start: 27, end: 27 }, ";")), missing()]);
+    report.add_file("src/lib.rs", &lib, vec![Diagnostic::warning("`TODO` comment: a, b").with_code("todo-comment").with_label(Range { start: 3, end: 7 }, "here")]);
+    report.add_file("src/empty.rs", &SourceText::new(""), vec![]);
+    assert_eq!((report.count(Severity::Error), report.count(Severity::Warning)), (2, 1));
+
+    let entries = report.files[0].entries();
+    assert_eq!((entries[0].location.start_line, entries[0].location.start_column, entries[0].location.end_column), (2, 13, 15));
+    assert_ne!(entries[0].fingerprint, entries[1].fingerprint);
+    // Fingerprints do not depend on the position.
+    let shifted = SourceText::new("\nfn main() {\n    let é = 10\n}");
+    let mut moved = Report::new("oak-lint");
+    moved.add_file("file:///src/main.rs", &shifted, vec![Diagnostic::error("missing semicolon").with_code("missing-semicolon").with_label(Range { start: 26, end: 28 }, "here")]);
+    assert_eq!(moved.files[0].entries()[0].fingerprint, entries[0].fingerprint);
+
+    let sarif: serde_json::Value = serde_json::from_str(&SarifEmitter.emit(&report)).unwrap();
+    let run = &sarif["runs"][0];
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(run["tool"]["driver"]["rules"][0]["defaultConfiguration"]["level"], "error");
+    assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "todo-comment");
+    assert_eq!(run["results"][0]["locations"][0]["physicalLocation"]["region"]["startColumn"], 13);
+    assert_eq!(run["results"][0]["fixes"][0]["artifactChanges"][0]["replacements"][0]["insertedContent"]["text"], ";");
+    assert_eq!(run["results"][2]["ruleIndex"], 1);
+    assert_eq!(run["results"][2]["level"], "warning");
+    assert_eq!(run["tool"]["driver"]["version"], "0.0.3");
+    // Unset optional keys are left out rather than written as null.
+    let driver = &run["tool"]["driver"];
+    assert!(driver.get("informationUri").is_none() && driver["rules"][0].get("helpUri").is_none());
+    let bare: serde_json::Value = serde_json::from_str(&SarifEmitter.emit(&Report::new("oak-lint"))).unwrap();
+    assert!(bare["runs"][0]["tool"]["driver"].get("version").is_none());
+
+    let github = GitHubEmitter.emit(&report);
+    println!("{}", github);
+    assert!(github.starts_with("::error file=/src/main.rs,line=2,col=13,endLine=2,endColumn=15,title=missing-semicolon::missing semicolon\n"));
+    assert!(github.ends_with("::warning file=src/lib.rs,line=1,col=4,endLine=1,endColumn=8,title=todo-comment::`TODO` comment: a, b\n"));
+
+    let gitlab: serde_json::Value = serde_json::from_str(&GitLabEmitter.emit(&report)).unwrap();
+    assert_eq!(gitlab.as_array().unwrap().len(), 3);
+    assert_eq!(gitlab[2]["severity"], "minor");
+    assert_eq!(gitlab[2]["location"]["lines"]["begin"], 1);
+
+    let junit = JUnitEmitter.emit(&report);
+    println!("{}", junit);
+    assert!(junit.contains("<testsuites name=\"oak-lint\" tests=\"4\" failures=\"3\">"));
+    assert!(junit.contains("<testcase name=\"src/empty.rs\" classname=\"src/empty.rs\" />"));
+
+    let checkstyle = CheckstyleEmitter.emit(&report);
+    println!("{}", checkstyle);
+    assert!(checkstyle.contains("<error line=\"1\" column=\"4\" severity=\"warning\" message=\"`TODO` comment: a, b\" source=\"todo-comment\" />"))
+}
+
+#[test]
+fn test_console_snippet() {
+    let text = "fn add(a: i32, b: i32) -> i32 {\n\tlet 名前 = a + b;\n    x\n    y\n    z\n    return 名前 + \"one\";\n}\n";
+    let source = SourceText::new(text);
+    let find = |needle: &str| {
+        let start = text.find(needle).unwrap();
+        Range { start, end: start + needle.len() }
+    };
+    let diag = Diagnostic::error("mismatched types")
+        .with_label(find("\"one\""), "expected `i32`, found `&str`")
+        .with_secondary_label(find("名前 + \"one\""), "this addition")
+        .with_secondary_label(find("-> i32"), "expected because of this return type")
+        .with_secondary_label(find("名前 = a"), "declared here");
+    let console = ConsoleEmitter { unicode: true, color: false, width: None, tab_width: 4, context_lines: 0 };
+    let expected = "\
+[error] mismatched types
+  ┌─ <anonymous>:6:17
+  │
+1 │ fn add(a: i32, b: i32) -> i32 {
+  │                        ------ expected because of this return type
+2 │     let 名前 = a + b;
+  │         -------- declared here
+...
+6 │     return 名前 + \"one\";
+  │            ------------
+  │            │      ^^^^^ expected `i32`, found `&str`
+  │            │
+  │            └── this addition
+  │
+";
+    assert_eq!(console.render(&source, &diag), expected);
+
+    // Long lines are cut around their labels.
+    let narrow = ConsoleEmitter { unicode: false, color: false, width: Some(24), tab_width: 4, context_lines: 0 }.render(&source, &Diagnostic::error("return type").with_label(find("-> i32"), "here"));
+    assert!(narrow.contains("1 | ... b: i32) -> i32 {\n  |             ^^^^^^ here\n"));
+    assert!(!narrow.contains('\x1b'));
+
+    let diag = Diagnostic::error("unclosed block").with_label(find("{\n\tlet"), "opened here").with_secondary_label(find("a: i32"), "a");
+    let expected = "\
+[error] unclosed block
+  ┌─ <anonymous>:1:31
+  │
+1 │ ┌ fn add(a: i32, b: i32) -> i32 {
+  │ │        ------ a
+2 │ │     let 名前 = a + b;
+  │ └───────^ opened here
+  │
+";
+    assert_eq!(console.render(&source, &diag), expected);
+    assert!(ConsoleEmitter { color: true, ..console }.render(&source, &diag).contains("\x1b[31;1m"))
+}
+
+#[test]
+fn test_catalog_localizer() {
+    let source = SourceText::new("let x = ;");
+    let error = OakError::new(OakErrorKind::UnexpectedToken { token: ";".to_string(), offset: 8, source_id: None });
+    let diag = error.to_diagnostic(&source);
+
+    let en = CatalogLocalizer::bundled("en-US");
+    assert_eq!(localized_message(&diag, Some(&en)), "unexpected token `;`");
+    let zh = CatalogLocalizer::bundled("zh_CN.UTF-8");
+    assert_eq!(zh.fallback_chain(), ["zh-cn", "zh", "en"]);
+    assert_eq!(localized_message(&diag, Some(&zh)), "意外的记号 `;`");
+    let expected = "[error.unexpected_token] 意外的记号 `;`\n  --> <anonymous>:1:9\n  |\n1 | let x = ;\n  |         ^ here\n  |\n";
+    assert_eq!(PlainTextEmitter { unicode: false }.render_localized(&source, &diag, Some(&zh), None), expected);
+
+    // Every kind has a bundled message in both languages.
+    let keys = ["error.io", "error.unexpected_eof", "error.color_parse", "error.test_regenerated", "error.internal"];
+    for locale in ["en", "zh"] {
+        let localizer = CatalogLocalizer::bundled(locale).with_fallback("none");
+        assert!(keys.iter().all(|key| localizer.lookup(key, &Default::default()).is_some()), "{}", locale);
+    }
+
+    let count = |n: &str| [("count".to_string(), n.to_string())].into_iter().collect();
+    assert_eq!(en.localize("summary.errors", &count("1")), "1 error");
+    assert_eq!(en.localize("summary.errors", &count("3")), "3 errors");
+    assert_eq!(zh.localize("summary.errors", &count("1")), "1 个错误");
+
+    // Keys missing from the requested locale fall back along the chain, then to the key itself.
+    let toml = "[lint]\nunused = \"{ $name } 未被使用\"\n[summary]\nfiles = \"\"\"{ $count -> [0] 没有文件\n *[other] { $count } 个文件 }\"\"\"\n";
+    let custom = CatalogLocalizer::bundled("zh-TW").with_catalog(Catalog::from_toml("zh-TW", toml).unwrap());
+    let name = [("name".to_string(), "x".to_string())].into_iter().collect();
+    assert_eq!(custom.localize("lint.unused", &name), "x 未被使用");
+    assert_eq!(custom.localize("lint.unused", &Default::default()), "{$name} 未被使用");
+    assert_eq!(custom.localize("summary.files", &count("0")), "没有文件");
+    assert_eq!(custom.localize("summary.files", &count("2")), "2 个文件");
+    assert_eq!(custom.localize("error.unexpected_eof", &Default::default()), "意外的文件结尾");
+    assert_eq!(CatalogLocalizer::bundled("fr").localize("error.unexpected_eof", &Default::default()), "unexpected end of file");
+    assert_eq!(custom.localize("lint.missing", &Default::default()), "lint.missing");
+
+    let ftl = "greeting = Hello, { $name }!\n  Welcome { \"{\" }here{ \"}\" }.\nbroken = { $n -> [one] x }\n";
+    match Catalog::from_ftl("en", ftl) {
+        Err(error) => assert_eq!(error.to_string(), "invalid message catalog at line 3: a select expression needs a default variant marked with `*`"),
+        Ok(_) => panic!("the catalog should not parse"),
+    }
+    let catalog = Catalog::from_ftl("en", &ftl[..ftl.find("broken").unwrap()]).unwrap();
+    assert_eq!(catalog.format("greeting", &name).unwrap(), "Hello, x!\nWelcome {here}.");
 }
@@ -3,9 +3,15 @@
 //! Code folding support for the Oak language framework.
 //!
 //! This crate provides traits and structures for identifying collapsible
-//! regions in source code, such as functions, comments, or imports.
+//! regions in source code, such as functions, comments, or imports, and a
+//! [`UniversalFoldingProvider`] that finds them from element and token roles.
 use core::range::Range;
-use oak_core::{language::Language, tree::RedNode};
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_vfs::LineMap;
 use serde::{Deserialize, Serialize};
 
 /// Enum of folding range kinds.
@@ -37,4 +43,219 @@ pub trait FoldingProvider<L: Language> {
         let _ = root;
         Vec::new()
     }
+
+    /// Returns all folding ranges for the given document, with access to its text and lines.
+    ///
+    /// The default ignores the text and calls [`folding_ranges`](Self::folding_ranges).
+    fn folding_ranges_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap) -> Vec<FoldingRange> {
+        let _ = (source, line_map);
+        self.folding_ranges(root)
+    }
+}
+
+/// Keywords that start import-like statements.
+const IMPORT_KEYWORDS: &[&str] = &["import", "use", "from", "using", "include", "#include", "require", "extern", "open", "load", "@import", "@use"];
+
+/// A folding provider that works for any language whose kinds implement `role()`.
+///
+/// It folds
+/// - multi-line [`Container`](UniversalElementRole::Container), [`Definition`](UniversalElementRole::Definition)
+///   and [`Statement`](UniversalElementRole::Statement) nodes,
+/// - runs of comment tokens on consecutive lines and multi-line [`Documentation`](UniversalElementRole::Documentation),
+/// - consecutive sibling statements starting with an import keyword like `import`, `use` or `#include`,
+/// - regions between `region` and `endregion` markers, in comments (`// region`, `# region`, `<!-- #region -->`)
+///   or as directives (`#region`, `#pragma region`).
+///
+/// Ranges are collapsed to lines: a range starts where the first line's content starts and ends at the
+/// end of the last folded line. When a node ends with a closing token on its own line, like `}` or `end`,
+/// that line stays visible. Single-line ranges are dropped, and of ranges starting on the same line only
+/// the largest is kept, as editors can only show one per line.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniversalFoldingProvider;
+
+impl UniversalFoldingProvider {
+    /// Creates a new universal folding provider.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<L: Language> FoldingProvider<L> for UniversalFoldingProvider {
+    fn folding_ranges_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap) -> Vec<FoldingRange> {
+        let mut collector = FoldingCollector { source, line_map, ranges: Vec::new(), comments: None, regions: Vec::new() };
+        collector.node(root);
+        collector.flush_comments();
+        collector.finish()
+    }
+}
+
+/// The first and last significant leaves of a node.
+#[derive(Clone, Copy)]
+struct Bounds {
+    first: Range<usize>,
+    last: Range<usize>,
+    /// Whether the last leaf closes the node, like `}` or `end`.
+    closing: bool,
+    /// Whether the first leaf is a keyword.
+    keyword: bool,
+}
+
+struct FoldingCollector<'s, S: ?Sized> {
+    source: &'s S,
+    line_map: &'s LineMap,
+    /// Folding ranges as start offset, last folded line and kind.
+    ranges: Vec<(usize, u32, Option<FoldingRangeKind>)>,
+    /// The start and end offsets of the current run of comments.
+    comments: Option<(usize, usize)>,
+    /// The start offsets of the open regions.
+    regions: Vec<usize>,
+}
+
+impl<'s, S: Source + ?Sized> FoldingCollector<'s, S> {
+    /// Visits a node and returns its significant bounds, if it has any non-trivia leaf.
+    fn node<L: Language>(&mut self, node: &RedNode<L>) -> Option<Bounds> {
+        let mut bounds: Option<Bounds> = None;
+        // Consecutive import-like children, as first start and last end.
+        let mut imports: Option<(usize, usize)> = None;
+        for child in node.children() {
+            let child_bounds = match child {
+                RedTree::Leaf(leaf) => self.leaf(&leaf),
+                RedTree::Node(child) => {
+                    let child_bounds = self.node(&child);
+                    if let Some(b) = child_bounds
+                        && self.is_import(b)
+                    {
+                        imports = Some((imports.map_or(b.first.start, |(start, _)| start), b.last.end));
+                        bounds = Some(merge(bounds, b));
+                        continue;
+                    }
+                    child_bounds
+                }
+            };
+            if let Some(b) = child_bounds {
+                if let Some((start, end)) = imports.take() {
+                    self.push(start, end, None, Some(FoldingRangeKind::Imports))
+                }
+                bounds = Some(merge(bounds, b))
+            }
+        }
+        if let Some((start, end)) = imports {
+            self.push(start, end, None, Some(FoldingRangeKind::Imports))
+        }
+
+        let bounds = bounds?;
+        match node.green.kind.role().universal() {
+            UniversalElementRole::Container | UniversalElementRole::Definition | UniversalElementRole::Statement => self.push(bounds.first.start, bounds.last.end, bounds.closing.then_some(bounds.last.start), None),
+            UniversalElementRole::Documentation => self.push(bounds.first.start, bounds.last.end, None, Some(FoldingRangeKind::Comment)),
+            _ => {}
+        }
+        Some(bounds)
+    }
+
+    /// Visits a leaf and returns its bounds if it is significant.
+    fn leaf<L: Language>(&mut self, leaf: &RedLeaf<L>) -> Option<Bounds> {
+        let role = leaf.kind.role().universal();
+        if role == UniversalTokenRole::Whitespace {
+            return None;
+        }
+        let text = self.source.get_text_in(leaf.span);
+        if let Some(start) = region_marker(&text, role == UniversalTokenRole::Comment) {
+            self.flush_comments();
+            if start {
+                self.regions.push(leaf.span.start)
+            }
+            else if let Some(start) = self.regions.pop() {
+                self.push(start, leaf.span.end, None, Some(FoldingRangeKind::Region))
+            }
+            return if role == UniversalTokenRole::Comment { None } else { Some(Bounds { first: leaf.span, last: leaf.span, closing: false, keyword: false }) };
+        }
+        if role == UniversalTokenRole::Comment {
+            match self.comments {
+                Some((start, end)) if self.line_map.line_of(leaf.span.start) <= self.line_map.line_of(end) + 1 => self.comments = Some((start, leaf.span.end)),
+                _ => {
+                    self.flush_comments();
+                    self.comments = Some((leaf.span.start, leaf.span.end))
+                }
+            }
+            return None;
+        }
+        self.flush_comments();
+        let closing = match role {
+            UniversalTokenRole::Punctuation => matches!(text.trim(), "}" | "]" | ")" | ">" | "/>" | "}}" | "%}"),
+            UniversalTokenRole::Keyword => {
+                let text = text.trim().to_lowercase();
+                text.starts_with("end") || matches!(text.as_str(), "fi" | "done" | "esac")
+            }
+            _ => false,
+        };
+        Some(Bounds { first: leaf.span, last: leaf.span, closing, keyword: role == UniversalTokenRole::Keyword })
+    }
+
+    /// Checks whether a node is an import-like statement, starting with an import keyword or directive.
+    fn is_import(&self, bounds: Bounds) -> bool {
+        let text = self.source.get_text_in(bounds.first);
+        let text = text.trim();
+        (bounds.keyword || text.starts_with(['#', '@'])) && IMPORT_KEYWORDS.iter().any(|keyword| text.eq_ignore_ascii_case(keyword))
+    }
+
+    fn flush_comments(&mut self) {
+        if let Some((start, end)) = self.comments.take() {
+            self.push(start, end, None, Some(FoldingRangeKind::Comment))
+        }
+    }
+
+    /// Records a range from `start` to `end`, collapsed to lines, given where the closing token starts if there is one.
+    fn push(&mut self, start: usize, end: usize, closing: Option<usize>, kind: Option<FoldingRangeKind>) {
+        let start_line = self.line_map.line_of(start);
+        let mut end_line = self.line_map.line_of(end.saturating_sub(1).max(start));
+        // Keep a closing token visible if it is the first thing on its line.
+        if let Some(closing) = closing
+            && end_line > start_line
+            && let Some(line_start) = self.line_map.line_start(end_line)
+            && line_start <= closing
+            && self.source.get_text_in((line_start..closing).into()).trim().is_empty()
+        {
+            end_line -= 1
+        }
+        if end_line > start_line {
+            self.ranges.push((start, end_line, kind))
+        }
+    }
+
+    fn finish(mut self) -> Vec<FoldingRange> {
+        let line_map = self.line_map;
+        // Of ranges starting on the same line, keep the largest, preferring one with a kind, then the outermost.
+        self.ranges.sort_by_key(|(start, end_line, kind)| (line_map.line_of(*start), core::cmp::Reverse(*end_line), kind.is_none(), *start));
+        self.ranges.dedup_by_key(|(start, _, _)| line_map.line_of(*start));
+        self.ranges.into_iter().map(|(start, end_line, kind)| FoldingRange { range: (start..line_map.line_content_end(end_line).unwrap_or(start).max(start)).into(), kind }).collect()
+    }
+}
+
+/// Merges the bounds of a node with those of its next child.
+fn merge(bounds: Option<Bounds>, next: Bounds) -> Bounds {
+    match bounds {
+        Some(bounds) => Bounds { last: next.last, closing: next.closing, ..bounds },
+        None => next,
+    }
+}
+
+/// Recognizes a region marker, returning `true` for a start and `false` for an end marker.
+///
+/// In comments, the comment delimiters are skipped first; otherwise only `#region` style directives are recognized.
+fn region_marker(text: &str, comment: bool) -> Option<bool> {
+    let mut text = text.trim();
+    if comment {
+        text = text.trim_start_matches(['/', '*', '#', '-', ';', '%', '!', '<', '\'']).trim_start();
+        text = text.strip_prefix('#').unwrap_or(text);
+    }
+    else {
+        text = text.strip_prefix('#')?;
+    }
+    let text = text.strip_prefix("pragma").map(str::trim_start).unwrap_or(text);
+    let word: String = text.chars().take_while(|c| c.is_ascii_alphabetic()).collect::<String>().to_ascii_lowercase();
+    match word.as_str() {
+        "region" => Some(true),
+        "endregion" => Some(false),
+        _ => None,
+    }
 }
@@ -0,0 +1,66 @@
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    source::SourceText,
+    tree::RedNode,
+};
+use oak_folding::{FoldingProvider, FoldingRangeKind, UniversalFoldingProvider};
+use oak_testing::building::{
+    Spec::{Leaf, Node},
+    comment, kw, name, punct, ws,
+};
+use oak_vfs::LineMap;
+
+#[test]
+fn test_universal_folding() {
+    let root = Node(
+        E::Root,
+        vec![
+            comment("// region Setup"),
+            ws("\n"),
+            Node(E::Statement, vec![kw("use"), ws(" "), name("a"), punct(";")]),
+            ws("\n"),
+            Node(E::Statement, vec![kw("use"), ws(" "), name("b"), punct("::"), punct("{"), ws("\n    "), name("c"), punct(","), ws("\n"), punct("}"), punct(";")]),
+            ws("\n\n"),
+            comment("/// Docs line 1"),
+            ws("\n"),
+            comment("/// Docs line 2"),
+            ws("\n"),
+            Node(
+                E::Definition,
+                vec![
+                    kw("fn"),
+                    ws(" "),
+                    name("f"),
+                    punct("()"),
+                    ws(" "),
+                    Node(E::Container, vec![punct("{"), ws("\n    "), Node(E::Statement, vec![kw("let"), ws(" "), name("x"), ws(" "), punct("="), ws(" "), Leaf(T::Literal, "1"), punct(";")]), ws("\n"), punct("}")]),
+                ],
+            ),
+            ws("\n"),
+            comment("// endregion"),
+            ws("\n"),
+            Node(E::Definition, vec![kw("fn"), ws(" "), name("g"), punct("()"), ws(" "), Node(E::Container, vec![punct("{"), ws(" "), name("x"), ws(" "), punct("}")])]),
+        ],
+    );
+    let (text, green) = root.build();
+    assert_eq!(text, "// region Setup\nuse a;\nuse b::{\n    c,\n};\n\n/// Docs line 1\n/// Docs line 2\nfn f() {\n    let x = 1;\n}\n// endregion\nfn g() { x }");
+
+    let source = SourceText::new(text.as_str());
+    let line_map = LineMap::from_source(&source);
+    let ranges = UniversalFoldingProvider.folding_ranges_in(&RedNode::new(green, 0), &source, &line_map);
+    let lines: Vec<_> = ranges.iter().map(|f| (line_map.line_of(f.range.start), line_map.line_of(f.range.end), f.kind)).collect();
+    assert_eq!(
+        lines,
+        [
+            (0, 11, Some(FoldingRangeKind::Region)),
+            (1, 4, Some(FoldingRangeKind::Imports)),
+            (2, 4, None),
+            (6, 7, Some(FoldingRangeKind::Comment)),
+            // The closing brace of `f` stays visible.
+            (8, 9, None),
+        ]
+    );
+    // Ranges start at the content of their first line and end at the end of their last line.
+    assert_eq!(ranges[4].range.start, text.find("fn f").unwrap());
+    assert_eq!(ranges[4].range.end, text.find(" = 1;").unwrap() + 5);
+}
@@ -1,4 +1,4 @@
-use crate::exporters::Exporter;
+use crate::{exporters::Exporter, registry::LanguageRegistry};
 use core::range::Range;
 use oak_core::{
     TokenType,
@@ -137,7 +137,7 @@ impl HighlightTheme {
 }
 
 /// Helper to get scopes for a token role.
-fn get_token_scopes<R: TokenRole>(role: R, language: &str, category: oak_core::language::LanguageCategory) -> Vec<String> {
+pub(crate) fn get_token_scopes<R: TokenRole>(role: R, language: &str, category: oak_core::language::LanguageCategory) -> Vec<String> {
     let specific_name = role.name();
     let universal_role = role.universal();
     let universal_name = universal_role.name();
@@ -311,19 +311,35 @@ impl Highlighter for OakHighlighter {
 /// # Example
 ///
 /// ```rust
-/// use oak_highlight::{OakHighlighter, Theme};
+/// use oak_highlight::{LanguageRegistry, OakHighlighter, Theme};
 ///
-/// let highlighter = OakHighlighter::new();
+/// let registry = LanguageRegistry::new().with_language(
+///     "rust",
+///     &[],
+///     &["rs"],
+///     |highlighter, source, theme| {
+///         let language = oak_rust::RustLanguage::default();
+///         highlighter.highlight_with_language(
+///             source,
+///             theme,
+///             &oak_rust::RustParser::new(&language),
+///             &oak_rust::RustLexer::new(&language),
+///         )
+///     },
+/// );
+/// let highlighter = OakHighlighter::new().with_registry(registry);
 /// let result = highlighter.highlight("fn main() {}", "rust", Theme::OneDarkPro).unwrap();
 /// assert!(!result.segments.is_empty());
 /// ```
 pub struct OakHighlighter {
     pub theme: HighlightTheme,
+    /// The languages that can be highlighted by name.
+    registry: LanguageRegistry,
 }
 
 impl Default for OakHighlighter {
     fn default() -> Self {
-        Self { theme: HighlightTheme::default() }
+        Self { theme: HighlightTheme::default(), registry: LanguageRegistry::default() }
     }
 }
 
@@ -343,15 +359,32 @@ impl OakHighlighter {
         self
     }
 
-    /// Main highlight method matching README API.
-    pub fn highlight<'a>(&self, source: &'a str, _language: &str, theme: crate::themes::Theme) -> oak_core::errors::ParseResult<HighlightResult<'a>> {
-        let theme_config = theme.get_theme();
+    /// Sets the languages that can be highlighted by name.
+    pub fn with_registry(mut self, registry: LanguageRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
 
-        // Default implementation just treats everything as a single segment for now
-        // if no specific language parser is used.
-        // In a real scenario, we'd look up a parser from a registry.
-        let segments = vec![HighlightSegment { span: Range { start: 0, end: source.len() }.into(), style: theme_config.resolve_style("none"), text: Cow::Borrowed(source) }];
+    /// Returns the languages that can be highlighted by name.
+    pub fn registry(&self) -> &LanguageRegistry {
+        &self.registry
+    }
 
+    /// Returns the languages that can be highlighted by name, to register more.
+    pub fn registry_mut(&mut self) -> &mut LanguageRegistry {
+        &mut self.registry
+    }
+
+    /// Highlights a source text of a language found in the registry by its name, an alias or an
+    /// extension, see [`LanguageRegistry::resolve`].
+    ///
+    /// If no registered language matches, the whole text is a single unstyled segment.
+    pub fn highlight<'a>(&self, source: &'a str, language: &str, theme: crate::themes::Theme) -> oak_core::errors::ParseResult<HighlightResult<'a>> {
+        if let Some(registered) = self.registry.resolve(language) {
+            return registered.highlight(self, source, theme);
+        }
+        let theme_config = theme.get_theme();
+        let segments = vec![HighlightSegment { span: Range { start: 0, end: source.len() }.into(), style: theme_config.resolve_style("none"), text: Cow::Borrowed(source) }];
         Ok(HighlightResult { segments, source: Cow::Borrowed(source) })
     }
 
@@ -377,6 +410,28 @@ impl OakHighlighter {
         Ok(HighlightResult { segments: visitor.segments, source: Cow::Borrowed(source) })
     }
 
+    /// Highlights a source text by its tokens alone, without parsing.
+    ///
+    /// Faster than [`highlight_with_language`](Self::highlight_with_language) and unaffected by
+    /// syntax errors, but styles tokens by their kinds only; see [`LexicalHighlighter`] to
+    /// re-highlight a changing text incrementally.
+    ///
+    /// [`LexicalHighlighter`]: crate::LexicalHighlighter
+    pub fn highlight_with_lexer<'a, L, LX>(&self, source: &'a str, theme: crate::themes::Theme, lexer: &LX) -> oak_core::errors::ParseResult<HighlightResult<'a>>
+    where
+        L: Language + Send + Sync + 'static,
+        LX: oak_core::Lexer<L>,
+    {
+        let theme_config = theme.get_theme();
+        let tokens = crate::lexical::lex(lexer, source, 0)?;
+        let mut styles = HashMap::new();
+        for token in &tokens {
+            styles.entry(token.kind).or_insert_with(|| crate::lexical::token_style::<L>(&theme_config, token.kind));
+        }
+        let segments = crate::lexical::segments(source, &tokens, 0, source.len(), |kind| styles[&kind].clone(), &theme_config);
+        Ok(HighlightResult { segments, source: Cow::Borrowed(source) })
+    }
+
     /// Highlight and format to a string directly.
     pub fn highlight_format(&self, source: &str, language: &str, theme: crate::themes::Theme, format: crate::exporters::ExportFormat) -> oak_core::errors::ParseResult<String> {
         let result = self.highlight(source, language, theme)?;
@@ -0,0 +1,264 @@
+use crate::{
+    highlighter::{HighlightResult, HighlightSegment, HighlightSpan, HighlightStyle, HighlightTheme, get_token_scopes},
+    themes::Theme,
+};
+use core::range::Range;
+use oak_core::{Lexer, TokenType, errors::ParseResult, language::Language, lexer::Token, parser::session::ParseSession};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Highlights source text by its tokens alone, without parsing, and re-highlights it
+/// incrementally as it changes.
+///
+/// The style of a token follows from the role of its kind, so the text only needs to be lexed.
+/// By default every change relexes the whole text, which is right for any lexer.
+///
+/// Languages whose lexer is in its initial state at the start of every token beginning a line,
+/// as lexers that track no context across lines are, opt in to line checkpoints with
+/// [`with_line_checkpoints`](Self::with_line_checkpoints). After a change, lexing then restarts
+/// at a checkpoint: the last token starting at the start of a line, at or before the first
+/// changed line. The text is lexed a window at a time, and once a token past the change starts a
+/// line where an old token of the same kind did, the old tokens from there on are reused. Editing
+/// a line of a large file thus only lexes the lines around it, and appending to a log only lexes
+/// what was appended. Lexers keeping state across lines, such as indentation in Python, block
+/// structure in YAML or Markdown, must not opt in.
+///
+/// ```rust
+/// # use core::range::Range;
+/// use oak_highlight::{LexicalHighlighter, Theme};
+/// use oak_rust::{RustLanguage, RustLexer};
+///
+/// let language = RustLanguage::default();
+/// let lexer = RustLexer::new(&language);
+/// let mut highlighter =
+///     LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro).with_line_checkpoints();
+///
+/// highlighter.update(&lexer, "let a = 1;\nlet b = 2;\nlet c = 3;\n").unwrap();
+/// let changed = highlighter.update(&lexer, "let a = 1;\nlet b = 20;\nlet c = 3;\n").unwrap();
+/// assert_eq!((changed.start, changed.end), (1, 2));
+/// assert_eq!(highlighter.highlight_lines(Range { start: 1, end: 2 }).source, "let b = 20;\n");
+/// ```
+#[derive(Debug)]
+pub struct LexicalHighlighter<L: Language> {
+    /// The theme used for style resolution.
+    theme: HighlightTheme,
+    /// The style of every token kind seen so far.
+    styles: HashMap<L::TokenType, HighlightStyle>,
+    /// The highlighted text.
+    source: String,
+    /// The tokens of the text, without the end of stream.
+    tokens: Vec<Token<L::TokenType>>,
+    /// The offset of the start of every line.
+    lines: Vec<usize>,
+    /// The number of bytes lexed at once past a change.
+    window: usize,
+    /// Whether lexing may restart at tokens beginning a line.
+    checkpoints: bool,
+}
+
+impl<L: Language + Send + Sync + 'static> LexicalHighlighter<L> {
+    /// Creates a highlighter of an empty text with a predefined theme.
+    pub fn new(theme: Theme) -> Self {
+        Self { theme: theme.get_theme(), styles: HashMap::new(), source: String::new(), tokens: Vec::new(), lines: vec![0], window: 64 * 1024, checkpoints: false }
+    }
+
+    /// Sets the theme used for style resolution.
+    pub fn with_theme(mut self, theme: HighlightTheme) -> Self {
+        self.theme = theme;
+        self.styles = self.tokens.iter().map(|token| (token.kind, token_style::<L>(&self.theme, token.kind))).collect();
+        self
+    }
+
+    /// Sets the number of bytes lexed at once past a change, at least one; the default is 64 KiB.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Lets lexing restart at the tokens beginning a line, and reuse the old tokens once the new
+    /// ones begin a line like them again.
+    ///
+    /// Only correct if the lexer is in its initial state at the start of every token beginning a
+    /// line, so it must not be enabled for lexers keeping state across lines.
+    pub fn with_line_checkpoints(mut self) -> Self {
+        self.checkpoints = true;
+        self
+    }
+
+    /// Returns the highlighted text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the tokens of the highlighted text.
+    pub fn tokens(&self) -> &[Token<L::TokenType>] {
+        &self.tokens
+    }
+
+    /// Returns the number of lines of the highlighted text.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Highlights a new version of the text; with [line checkpoints](Self::with_line_checkpoints),
+    /// lexing only from the checkpoint before the first changed line until the tokens match the
+    /// old ones again.
+    ///
+    /// Returns the lines of the new text whose highlighting may have changed, to redraw.
+    pub fn update<LX: Lexer<L>>(&mut self, lexer: &LX, source: &str) -> ParseResult<Range<usize>> {
+        let old = self.source.as_str();
+        let mut prefix = old.bytes().zip(source.bytes()).take_while(|(a, b)| a == b).count();
+        if prefix == old.len() && prefix == source.len() {
+            return Ok(Range { start: self.lines.len(), end: self.lines.len() });
+        }
+        // Characters sharing their first bytes differ within, so back off to their start.
+        while !old.is_char_boundary(prefix) || !source.is_char_boundary(prefix) {
+            prefix -= 1
+        }
+        let mut suffix = old[prefix..].bytes().rev().zip(source[prefix..].bytes().rev()).take_while(|(a, b)| a == b).count();
+        while !old.is_char_boundary(old.len() - suffix) || !source.is_char_boundary(source.len() - suffix) {
+            suffix -= 1
+        }
+        let changed_end = source.len() - suffix;
+        let delta = source.len() as isize - old.len() as isize;
+
+        // The text before the changed line is the same, so is the checkpoint before it.
+        let changed_line = old[..prefix].rfind('\n').map_or(0, |index| index + 1);
+        let mut kept = self.tokens.partition_point(|token| token.span.start <= changed_line);
+        let mut start = 0;
+        if !self.checkpoints {
+            kept = 0
+        }
+        while kept > 0 {
+            kept -= 1;
+            if is_line_start(old, self.tokens[kept].span.start) {
+                start = self.tokens[kept].span.start;
+                break;
+            }
+        }
+
+        let mut tokens = self.tokens[..kept].to_vec();
+        let mut window = if self.checkpoints { self.window } else { source.len() };
+        let relexed_end = loop {
+            // Up to the end of the line after the window or the change, where the tokens may match again.
+            let end = line_end(source, line_end(source, (start + window).max(changed_end)));
+            let lexed = lex(lexer, &source[start..end], start)?;
+            for token in &lexed {
+                self.styles.entry(token.kind).or_insert_with(|| token_style::<L>(&self.theme, token.kind));
+            }
+
+            // A token past the change starting a line where an old one of the same kind did.
+            let synced = lexed.iter().enumerate().find_map(|(index, token)| {
+                if !self.checkpoints || token.span.start < changed_end || token.span.start == start || !is_line_start(source, token.span.start) {
+                    return None;
+                }
+                let old_start = (token.span.start as isize - delta) as usize;
+                let old_index = self.tokens.partition_point(|old| old.span.start < old_start);
+                self.tokens.get(old_index).filter(|old| old.span.start == old_start && old.kind == token.kind).map(|_| (index, old_index))
+            });
+            if let Some((index, old_index)) = synced {
+                let relexed_end = lexed[index].span.start;
+                tokens.extend_from_slice(&lexed[..index]);
+                tokens.extend(self.tokens[old_index..].iter().map(|old| Token { kind: old.kind, span: Range { start: (old.span.start as isize + delta) as usize, end: (old.span.end as isize + delta) as usize } }));
+                break relexed_end;
+            }
+            if end == source.len() {
+                tokens.extend(lexed);
+                break source.len();
+            }
+
+            // Tokens at the end of the window may be cut off, so go on from the last checkpoint in it.
+            match lexed.iter().rposition(|token| token.span.start > start && is_line_start(source, token.span.start)) {
+                Some(index) => {
+                    start = lexed[index].span.start;
+                    tokens.extend_from_slice(&lexed[..index])
+                }
+                None => window = window.saturating_mul(2),
+            }
+        };
+
+        self.tokens = tokens;
+        self.source = source.to_string();
+        self.lines = line_starts(source);
+        let first = self.lines.partition_point(|&line| line <= changed_line) - 1;
+        let last = self.lines.partition_point(|&line| line < relexed_end).max(first + 1);
+        Ok(Range { start: first, end: last })
+    }
+
+    /// Returns the highlighted text as styled segments.
+    pub fn highlight(&self) -> HighlightResult<'_> {
+        HighlightResult { segments: segments(&self.source, &self.tokens, 0, self.source.len(), |kind| self.style(kind), &self.theme), source: Cow::Borrowed(&self.source) }
+    }
+
+    /// Returns some lines of the highlighted text as styled segments, with spans relative to the start of the first line.
+    pub fn highlight_lines(&self, lines: Range<usize>) -> HighlightResult<'_> {
+        let start = self.lines.get(lines.start).copied().unwrap_or(self.source.len());
+        let end = self.lines.get(lines.end).copied().unwrap_or(self.source.len()).max(start);
+        let mut segments = segments(&self.source, &self.tokens, start, end, |kind| self.style(kind), &self.theme);
+        for segment in &mut segments {
+            segment.span = HighlightSpan { start: segment.span.start - start, end: segment.span.end - start }
+        }
+        HighlightResult { segments, source: Cow::Borrowed(&self.source[start..end]) }
+    }
+
+    /// Returns the style of a token kind.
+    fn style(&self, kind: L::TokenType) -> HighlightStyle {
+        self.styles.get(&kind).cloned().unwrap_or_else(|| token_style::<L>(&self.theme, kind))
+    }
+}
+
+/// Lexes a text, shifting the tokens by `offset` and leaving out the end of stream.
+pub(crate) fn lex<L, LX>(lexer: &LX, text: &str, offset: usize) -> ParseResult<Vec<Token<L::TokenType>>>
+where
+    L: Language + Send + Sync + 'static,
+    LX: Lexer<L>,
+{
+    let mut cache = ParseSession::<L>::default();
+    let tokens = lexer.lex(text, &[], &mut cache).result?;
+    Ok(tokens.iter().filter(|token| token.kind != L::TokenType::END_OF_STREAM).map(|token| Token { kind: token.kind, span: Range { start: token.span.start + offset, end: token.span.end + offset } }).collect())
+}
+
+/// Resolves the style of a token kind from the scopes of its role.
+pub(crate) fn token_style<L: Language>(theme: &HighlightTheme, kind: L::TokenType) -> HighlightStyle {
+    theme.resolve_styles(&get_token_scopes(kind.role(), L::NAME, L::CATEGORY))
+}
+
+/// Returns the segments of the tokens between `start` and `end`, and unstyled ones for the text between tokens.
+pub(crate) fn segments<'a, K: TokenType>(source: &'a str, tokens: &[Token<K>], start: usize, end: usize, style: impl Fn(K) -> HighlightStyle, theme: &HighlightTheme) -> Vec<HighlightSegment<'a>> {
+    let none = theme.resolve_style("none");
+    let mut segments = Vec::new();
+    let mut at = start;
+    let mut push = |from: usize, to: usize, style: HighlightStyle| segments.push(HighlightSegment { span: HighlightSpan { start: from, end: to }, style, text: Cow::Borrowed(&source[from..to]) });
+    for token in &tokens[tokens.partition_point(|token| token.span.end <= start)..] {
+        if token.span.start >= end {
+            break;
+        }
+        let (from, to) = (token.span.start.max(at), token.span.end.min(end));
+        if from > at {
+            push(at, from, none.clone())
+        }
+        if to > from {
+            push(from, to, style(token.kind));
+            at = to
+        }
+    }
+    if at < end {
+        push(at, end, none)
+    }
+    segments
+}
+
+/// Checks whether an offset is at the start of a line.
+fn is_line_start(source: &str, offset: usize) -> bool {
+    offset == 0 || source.as_bytes().get(offset - 1) == Some(&b'\n')
+}
+
+/// Returns the offset of the start of the line after the one containing an offset, or the end of the text.
+fn line_end(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    source.as_bytes()[offset..].iter().position(|&byte| byte == b'\n').map_or(source.len(), |index| offset + index + 1)
+}
+
+/// Returns the offset of the start of every line.
+fn line_starts(source: &str) -> Vec<usize> {
+    std::iter::once(0).chain(source.bytes().enumerate().filter(|&(_, byte)| byte == b'\n').map(|(index, _)| index + 1)).collect()
+}
@@ -11,10 +11,14 @@
 
 pub mod exporters;
 pub mod highlighter;
+pub mod lexical;
+pub mod registry;
 pub mod themes;
 
 pub use crate::{
     exporters::{AnsiExporter, CssExporter, ExportFormat, Exporter, HtmlExporter, JsonExporter},
     highlighter::{HighlightResult, HighlightSegment, HighlightStyle, HighlightTheme, Highlighter, OakHighlighter},
+    lexical::LexicalHighlighter,
+    registry::{HighlightFn, LanguageRegistry, RegisteredLanguage},
     themes::Theme,
 };
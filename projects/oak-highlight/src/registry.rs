@@ -0,0 +1,129 @@
+use crate::{
+    highlighter::{HighlightResult, OakHighlighter},
+    themes::Theme,
+};
+use oak_core::{errors::ParseResult, language_for_file};
+use std::{fmt, sync::Arc};
+
+/// Highlights a source text of one language with a theme.
+pub type HighlightFn = Arc<dyn for<'a> Fn(&OakHighlighter, &'a str, Theme) -> ParseResult<HighlightResult<'a>> + Send + Sync>;
+
+/// A language registered with a [`LanguageRegistry`].
+#[derive(Clone)]
+pub struct RegisteredLanguage {
+    /// The name of the language, in lower case.
+    pub name: String,
+    /// Other names of the language, in lower case.
+    pub aliases: Vec<String>,
+    /// The file extensions (without the dot) and file names of the language, in lower case.
+    pub extensions: Vec<String>,
+    highlight: HighlightFn,
+}
+
+impl fmt::Debug for RegisteredLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredLanguage").field("name", &self.name).field("aliases", &self.aliases).field("extensions", &self.extensions).finish_non_exhaustive()
+    }
+}
+
+impl RegisteredLanguage {
+    /// Highlights a source text of this language.
+    pub fn highlight<'a>(&self, highlighter: &OakHighlighter, source: &'a str, theme: Theme) -> ParseResult<HighlightResult<'a>> {
+        (self.highlight)(highlighter, source, theme)
+    }
+}
+
+/// The languages an [`OakHighlighter`] can highlight by name.
+///
+/// Language crates depend on this one, so it cannot depend on them; the parsers are registered
+/// by the crates that bring the languages together, like `oak-repl` behind a cargo feature per
+/// language.
+///
+/// ```rust
+/// use oak_highlight::{LanguageRegistry, OakHighlighter, Theme};
+///
+/// let registry = LanguageRegistry::new().with_language(
+///     "rust",
+///     &["rs"],
+///     &["rs"],
+///     |highlighter, source, theme| {
+///         let language = oak_rust::RustLanguage::default();
+///         highlighter.highlight_with_language(
+///             source,
+///             theme,
+///             &oak_rust::RustParser::new(&language),
+///             &oak_rust::RustLexer::new(&language),
+///         )
+///     },
+/// );
+/// assert_eq!(
+///     registry.resolve("src/main.rs").map(|language| language.name.as_str()),
+///     Some("rust")
+/// );
+///
+/// let highlighter = OakHighlighter::new().with_registry(registry);
+/// assert!(highlighter.highlight("fn main() {}", "rust", Theme::OneDarkPro).is_ok());
+/// assert_eq!(
+///     highlighter.highlight("fn main() {}", "cobol", Theme::OneDarkPro).unwrap().segments.len(),
+///     1
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LanguageRegistry {
+    languages: Vec<RegisteredLanguage>,
+}
+
+impl LanguageRegistry {
+    /// Creates a registry without languages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a language, see [`register`](Self::register).
+    pub fn with_language<F>(mut self, name: &str, aliases: &[&str], extensions: &[&str], highlight: F) -> Self
+    where
+        F: for<'a> Fn(&OakHighlighter, &'a str, Theme) -> ParseResult<HighlightResult<'a>> + Send + Sync + 'static,
+    {
+        self.register(name, aliases, extensions, highlight);
+        self
+    }
+
+    /// Registers a language under its name and aliases, highlighting its source texts with `highlight`.
+    ///
+    /// `extensions` are file extensions without the leading dot (e.g. `"rs"`, `"d.ts"`) or
+    /// complete file names (e.g. `"Cargo.lock"`). A language registered again replaces the
+    /// previous one of the same name.
+    pub fn register<F>(&mut self, name: &str, aliases: &[&str], extensions: &[&str], highlight: F)
+    where
+        F: for<'a> Fn(&OakHighlighter, &'a str, Theme) -> ParseResult<HighlightResult<'a>> + Send + Sync + 'static,
+    {
+        let name = name.to_lowercase();
+        self.languages.retain(|language| language.name != name);
+        let aliases = aliases.iter().map(|alias| alias.to_lowercase()).collect();
+        let extensions = extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect();
+        self.languages.push(RegisteredLanguage { name, aliases, extensions, highlight: Arc::new(highlight) })
+    }
+
+    /// Returns the registered languages, in the order they were registered.
+    pub fn languages(&self) -> impl Iterator<Item = &RegisteredLanguage> {
+        self.languages.iter()
+    }
+
+    /// Checks whether no language is registered.
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+
+    /// Finds a language by its name or an alias, ignoring case, or else by an extension or file
+    /// name matching `language` as a path (e.g. `"rs"`, `".rs"` or `"src/main.rs"`).
+    ///
+    /// When several languages match a path, the one with the longest match wins, and among
+    /// equal matches the first registered one.
+    pub fn resolve(&self, language: &str) -> Option<&RegisteredLanguage> {
+        let language = language.trim().to_lowercase();
+        if let Some(found) = self.languages.iter().find(|registered| registered.name == language || registered.aliases.contains(&language)) {
+            return Some(found);
+        }
+        language_for_file(self.languages.iter().map(|registered| registered.extensions.as_slice()), &language).map(|index| &self.languages[index])
+    }
+}
@@ -1,4 +1,13 @@
-use oak_highlight::{ExportFormat, OakHighlighter, Theme};
+use core::range::Range;
+use oak_highlight::{ExportFormat, LanguageRegistry, LexicalHighlighter, OakHighlighter, Theme};
+use oak_rust::{RustLanguage, RustLexer};
+
+fn registry() -> LanguageRegistry {
+    LanguageRegistry::new().with_language("rust", &["rs"], &["rs"], |highlighter, source, theme| {
+        let language = oak_rust::RustLanguage::default();
+        highlighter.highlight_with_language(source, theme, &oak_rust::parser::RustParser::new(&language), &oak_rust::lexer::RustLexer::new(&language))
+    })
+}
 
 #[test]
 fn test_rust_highlighting() {
@@ -37,3 +46,124 @@ fn test_format_export() {
     assert!(json.contains("\"source\""));
     assert!(json.contains("segments"))
 }
+
+#[test]
+fn test_resolve_language() {
+    let registry = registry().with_language("toml", &[], &["toml", "Cargo.lock"], |highlighter, source, theme| {
+        let language = oak_toml::language::TomlLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_toml::lexer::TomlLexer::new(&language))
+    });
+
+    for name in ["rust", "Rust", "rs", ".rs", "src/main.rs", "C:\\src\\lib.RS"] {
+        assert_eq!(registry.resolve(name).map(|language| language.name.as_str()), Some("rust"), "{name}")
+    }
+    assert_eq!(registry.resolve("Cargo.lock").map(|language| language.name.as_str()), Some("toml"));
+    let toml = registry.resolve("Cargo.toml").expect("Should resolve by extension").highlight(&OakHighlighter::new(), "name = \"oak\"\n", Theme::OneDarkPro).expect("Should highlight TOML");
+    assert_eq!(toml.segments.iter().map(|segment| segment.text.as_ref()).collect::<String>(), "name = \"oak\"\n");
+    assert!(registry.resolve("trs").is_none());
+    assert!(registry.resolve("main.py").is_none())
+}
+
+#[test]
+fn test_highlight_by_name() {
+    let highlighter = OakHighlighter::new().with_registry(registry());
+    let code = "fn main() { let x = 1; }";
+
+    let result = highlighter.highlight(code, "rs", Theme::OneDarkPro).expect("Should highlight registered language");
+    assert_eq!(result.segments.iter().map(|segment| segment.text.as_ref()).collect::<String>(), code);
+    assert!(result.segments.iter().any(|segment| segment.text == "main"));
+    assert!(result.segments.iter().any(|segment| segment.style != result.segments[0].style));
+
+    let ansi = highlighter.highlight_format(code, "rust", Theme::OneDarkPro, ExportFormat::Ansi).expect("Should export ANSI");
+    assert!(ansi.contains("\x1b["))
+}
+
+#[test]
+fn test_unknown_language() {
+    let highlighter = OakHighlighter::new().with_registry(registry());
+
+    let result = highlighter.highlight("+[-]", "brainfuck", Theme::OneDarkPro).expect("Unknown languages should stay plain");
+    assert_eq!(result.segments.len(), 1);
+    assert_eq!(result.segments[0].text, "+[-]");
+    assert_eq!(result.segments[0].style, Theme::OneDarkPro.get_theme().resolve_style("none"));
+    assert!(OakHighlighter::new().highlight_format("x", "rust", Theme::OneDarkPro, ExportFormat::Html).is_ok())
+}
+
+#[test]
+fn test_lexer_highlighting() {
+    let language = RustLanguage::default();
+    let lexer = RustLexer::new(&language);
+    let code = "fn main() {\n    /* a\n       b */ let s = \"x\";\n}\n";
+
+    let result = OakHighlighter::new().highlight_with_lexer(code, Theme::OneDarkPro, &lexer).expect("Should highlight with Rust lexer");
+    assert_eq!(result.segments.iter().map(|segment| segment.text.as_ref()).collect::<String>(), code);
+    assert!(result.segments.iter().any(|segment| segment.text == "/* a\n       b */" && segment.style.italic));
+
+    let mut highlighter = LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro);
+    highlighter.update(&lexer, code).unwrap();
+    let incremental = highlighter.highlight();
+    assert_eq!(incremental.segments.iter().map(|segment| (segment.span, &segment.style)).collect::<Vec<_>>(), result.segments.iter().map(|segment| (segment.span, &segment.style)).collect::<Vec<_>>());
+
+    let lines = highlighter.highlight_lines(Range { start: 2, end: 3 });
+    assert_eq!(lines.source, "       b */ let s = \"x\";\n");
+    assert_eq!(lines.segments[0].span.start, 0);
+    assert_eq!(lines.segments.iter().map(|segment| segment.text.as_ref()).collect::<String>(), lines.source)
+}
+
+#[test]
+fn test_incremental_rehighlight() {
+    let language = RustLanguage::default();
+    let lexer = RustLexer::new(&language);
+    let base: String = (0..200).map(|i| format!("let x{i} = \"{i}\"; // line {i}\n")).collect();
+    let edits = [
+        base.replacen("x10 ", "renamed ", 1),
+        base.replacen("// line 50\n", "/* open\n", 1),
+        base.replacen("// line 50\n", "/* open\n", 1).replacen("// line 60\n", "*/\n", 1),
+        base.replacen("let x100", "\nlet x100", 1),
+        format!("{base}let tail = 1;\n"),
+        format!("// head\n{base}"),
+        base[..base.len() / 2].to_string(),
+        String::new(),
+        base.clone(),
+    ];
+
+    let mut highlighter = LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro).with_window(64).with_line_checkpoints();
+    highlighter.update(&lexer, &base).unwrap();
+    for text in &edits {
+        highlighter.update(&lexer, text).unwrap();
+        let mut fresh = LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro);
+        fresh.update(&lexer, text).unwrap();
+        assert_eq!(highlighter.source(), text.as_str());
+        assert_eq!(highlighter.tokens(), fresh.tokens())
+    }
+
+    highlighter.update(&lexer, &base).unwrap();
+    let changed = highlighter.update(&lexer, &base.replacen("x10 ", "renamed ", 1)).unwrap();
+    assert_eq!((changed.start, changed.end), (10, 11));
+    let changed = highlighter.update(&lexer, &format!("{}let tail = 1;\n", base.replacen("x10 ", "renamed ", 1))).unwrap();
+    assert_eq!((changed.start, changed.end), (200, 201));
+    let unchanged = highlighter.update(&lexer, &format!("{}let tail = 1;\n", base.replacen("x10 ", "renamed ", 1))).unwrap();
+    assert_eq!(unchanged.start, unchanged.end)
+}
+
+#[test]
+fn test_multibyte_rehighlight() {
+    let language = RustLanguage::default();
+    let lexer = RustLexer::new(&language);
+    let base = "let a = \"é\";\nlet b = 'é';\n";
+    let edited = "let a = \"è\";\nlet b = 'é';\n";
+
+    for checkpoints in [false, true] {
+        let mut highlighter = LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro);
+        if checkpoints {
+            highlighter = highlighter.with_line_checkpoints()
+        }
+        highlighter.update(&lexer, base).unwrap();
+        let changed = highlighter.update(&lexer, edited).unwrap();
+        assert_eq!(changed.start, 0);
+        let mut fresh = LexicalHighlighter::<RustLanguage>::new(Theme::OneDarkPro);
+        fresh.update(&lexer, edited).unwrap();
+        assert_eq!(highlighter.tokens(), fresh.tokens());
+        assert_eq!(highlighter.highlight().source, edited)
+    }
+}
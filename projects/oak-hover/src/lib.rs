@@ -4,10 +4,16 @@
 //! Hover support for the Oak language framework.
 //!
 //! This crate provides traits and structures for providing hover information
-//! (documentation, type info, etc.) for code elements in an editor.
+//! (documentation, type info, etc.) for code elements in an editor, and a
+//! [`UniversalHoverProvider`] that shows the signature and documentation of a definition.
 
 use core::range::Range;
-use oak_core::{language::Language, tree::RedNode};
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_resolver::{ScopeProvider, UniversalScopeProvider};
 use serde::{Deserialize, Serialize};
 
 /// Represents hover information.
@@ -24,4 +30,278 @@ pub struct Hover {
 pub trait HoverProvider<L: Language> {
     /// Returns hover information at the given range.
     fn hover(&self, root: &RedNode<L>, range: Range<usize>) -> Option<Hover>;
+
+    /// Returns hover information at the given range, with access to the text of the document.
+    ///
+    /// The default ignores the text and calls [`hover`](Self::hover).
+    fn hover_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, range: Range<usize>) -> Option<Hover> {
+        let _ = source;
+        self.hover(root, range)
+    }
+}
+
+/// A hover provider that works for any language whose kinds implement `role()`.
+///
+/// The name under the cursor is resolved to its declaration through the scope graph of
+/// [`UniversalScopeProvider`]. The hover shows, in Markdown,
+/// - the signature of the declaration in a code block: its text from the first keyword to the end of
+///   its name and its [`Typing`](UniversalElementRole::Typing) and [`Detail`](UniversalElementRole::Detail)
+///   children, with whitespace collapsed, like `fn len(self) -> f64`,
+/// - its documentation: the comments and [`Documentation`](UniversalElementRole::Documentation) nodes
+///   right before it, without a blank line in between, or a documentation node at the start of its body,
+///   with the comment markers removed by [`normalize_comment`].
+///
+/// The declaration of a name is the [`Definition`](UniversalElementRole::Definition) it names, or else
+/// the innermost [`Statement`](UniversalElementRole::Statement) or [`Binding`](UniversalElementRole::Binding)
+/// around it, like a `let` statement. [`declaration_markdown`](Self::declaration_markdown) describes a
+/// declaration found elsewhere, e.g. by a definition request into another file.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniversalHoverProvider;
+
+impl UniversalHoverProvider {
+    /// Creates a new universal hover provider.
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Returns the Markdown describing the declaration at `range`, which is either the range of its name
+    /// or the range of the whole declaration.
+    pub fn declaration_markdown<L: Language, S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, range: Range<usize>) -> Option<String> {
+        let declaration = declaration(root, range)?;
+        let signature = declaration_signature(&declaration, range, source);
+        let documentation = documentation(root, &declaration, source);
+        let mut markdown = String::new();
+        if !signature.is_empty() {
+            markdown = format!("```{}\n{}\n```", L::NAME.to_lowercase(), signature)
+        }
+        if !documentation.is_empty() {
+            if !markdown.is_empty() {
+                markdown.push_str("\n\n")
+            }
+            markdown.push_str(&documentation)
+        }
+        (!markdown.is_empty()).then_some(markdown)
+    }
+
+    /// Returns the signature of the declaration at `range`, like `fn len(self) -> f64`, which is
+    /// shown in the code block of [`declaration_markdown`](Self::declaration_markdown).
+    pub fn declaration_signature<L: Language, S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, range: Range<usize>) -> Option<String> {
+        let signature = declaration_signature(&declaration(root, range)?, range, source);
+        (!signature.is_empty()).then_some(signature)
+    }
+}
+
+impl<L: Language> HoverProvider<L> for UniversalHoverProvider {
+    /// Returns `None`, as the hover is made of source text; see [`hover_in`](HoverProvider::hover_in).
+    fn hover(&self, _root: &RedNode<L>, _range: Range<usize>) -> Option<Hover> {
+        None
+    }
+
+    fn hover_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, range: Range<usize>) -> Option<Hover> {
+        let graph = UniversalScopeProvider.scope_graph(root, &source);
+        let binding = graph.binding(graph.definition_at(range.start)?);
+        let occurrence = graph.reference_at(range.start).map_or(binding.range, |reference| graph.reference(reference).range);
+        let contents = self.declaration_markdown(root, source, binding.range)?;
+        Some(Hover { contents, range: Some(occurrence) })
+    }
+}
+
+/// Finds the declaration whose name or whole range is `range`.
+fn declaration<'a, L: Language>(root: &RedNode<'a, L>, range: Range<usize>) -> Option<RedNode<'a, L>> {
+    let mut chain = vec![*root];
+    while let Some(child) = chain.last().and_then(|node| node.children().filter_map(|child| child.as_node()).find(|child| child.span().start <= range.start && range.end <= child.span().end)) {
+        chain.push(child)
+    }
+    let role = |node: &RedNode<L>| node.green.kind.role().universal();
+    chain
+        .iter()
+        .rev()
+        .find(|node| role(node) == UniversalElementRole::Definition && (node.span() == range || definition_name(node) == Some(range)))
+        .or_else(|| chain.iter().rev().find(|node| role(node) == UniversalElementRole::Statement))
+        .or_else(|| chain.iter().rev().find(|node| role(node) == UniversalElementRole::Binding))
+        .copied()
+}
+
+/// Returns the signature of a declaration found at `range`.
+fn declaration_signature<L: Language, S: Source + ?Sized>(declaration: &RedNode<L>, range: Range<usize>, source: &S) -> String {
+    let name = match declaration.green.kind.role().universal() {
+        UniversalElementRole::Definition => definition_name(declaration),
+        _ => Some(range),
+    };
+    signature(declaration, name, source)
+}
+
+/// Returns the range of the name of a definition, like the scope graph names it.
+fn definition_name<L: Language>(node: &RedNode<L>) -> Option<Range<usize>> {
+    node.children().find_map(|child| match child {
+        RedTree::Node(child) if matches!(child.green.kind.role().universal(), UniversalElementRole::Binding | UniversalElementRole::Name) => first_name(&child),
+        RedTree::Leaf(leaf) if is_name(&leaf) => Some(leaf.span),
+        _ => None,
+    })
+}
+
+/// Returns the range of the first name token in a node.
+fn first_name<L: Language>(node: &RedNode<L>) -> Option<Range<usize>> {
+    node.children().find_map(|child| match child {
+        RedTree::Leaf(leaf) => is_name(&leaf).then_some(leaf.span),
+        RedTree::Node(child) => first_name(&child),
+    })
+}
+
+fn is_name<L: Language>(leaf: &RedLeaf<L>) -> bool {
+    leaf.kind.role().universal() == UniversalTokenRole::Name
+}
+
+/// Returns the signature of a declaration, from its first significant token to the end of its name or types.
+fn signature<L: Language, S: Source + ?Sized>(declaration: &RedNode<L>, name: Option<Range<usize>>, source: &S) -> String {
+    let Some(start) = head(declaration)
+    else {
+        return String::new();
+    };
+    let mut end = name.map(|name| name.end);
+    for child in declaration.children().filter_map(|child| child.as_node()) {
+        let parts: Vec<_> = match child.green.kind.role().universal() {
+            UniversalElementRole::Typing | UniversalElementRole::Detail => vec![child],
+            UniversalElementRole::Binding | UniversalElementRole::Name => child.children().filter_map(|child| child.as_node()).filter(|child| matches!(child.green.kind.role().universal(), UniversalElementRole::Typing | UniversalElementRole::Detail)).collect(),
+            _ => continue,
+        };
+        if let Some(part) = parts.last() {
+            end = end.max(Some(part.span().end))
+        }
+    }
+    let end = end.unwrap_or_else(|| declaration.children().filter_map(|child| child.as_node()).find(|child| child.green.kind.role().universal() == UniversalElementRole::Container).map_or(declaration.span().end, |body| body.span().start));
+    if end <= start {
+        return String::new();
+    }
+    source.get_text_in((start..end).into()).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the start of the first token of a node that is not trivia, documentation or an annotation.
+fn head<L: Language>(node: &RedNode<L>) -> Option<usize> {
+    node.children().find_map(|child| match child {
+        RedTree::Leaf(leaf) => (!matches!(leaf.kind.role().universal(), UniversalTokenRole::Whitespace | UniversalTokenRole::Comment)).then_some(leaf.span.start),
+        RedTree::Node(child) => match child.green.kind.role().universal() {
+            UniversalElementRole::Documentation | UniversalElementRole::Metadata | UniversalElementRole::Attribute => None,
+            _ => head(&child),
+        },
+    })
+}
+
+/// A piece of the document before a declaration, as seen when looking for its documentation.
+enum Unit {
+    /// Whitespace with the number of line breaks in it.
+    Space(usize),
+    /// A comment token or a documentation node.
+    Doc(Range<usize>),
+    /// An annotation, which may stand between a declaration and its documentation.
+    Annotation,
+    /// Anything else.
+    Other,
+}
+
+/// Collects the units of a node that start before `limit`.
+fn units<L: Language, S: Source + ?Sized>(node: &RedNode<L>, limit: usize, source: &S, out: &mut Vec<Unit>) {
+    for child in node.children() {
+        let span = match &child {
+            RedTree::Leaf(leaf) => leaf.span,
+            RedTree::Node(child) => child.span(),
+        };
+        if span.start >= limit {
+            break;
+        }
+        match child {
+            RedTree::Leaf(leaf) => out.push(match leaf.kind.role().universal() {
+                UniversalTokenRole::Whitespace => Unit::Space(source.get_text_in(leaf.span).matches('\n').count()),
+                UniversalTokenRole::Comment => Unit::Doc(leaf.span),
+                _ => Unit::Other,
+            }),
+            RedTree::Node(child) => match child.green.kind.role().universal() {
+                UniversalElementRole::Documentation => out.push(Unit::Doc(span)),
+                UniversalElementRole::Metadata | UniversalElementRole::Attribute => out.push(Unit::Annotation),
+                _ => units(&child, limit, source, out),
+            },
+        }
+    }
+}
+
+/// Returns the normalized documentation of a declaration.
+fn documentation<L: Language, S: Source + ?Sized>(root: &RedNode<L>, declaration: &RedNode<L>, source: &S) -> String {
+    let Some(head) = head(declaration)
+    else {
+        return String::new();
+    };
+    let mut before = Vec::new();
+    units(root, head, source, &mut before);
+    // Walk back from the declaration over comments, annotations and single line breaks.
+    let mut docs = Vec::new();
+    let mut breaks = 0;
+    for unit in before.into_iter().rev() {
+        match unit {
+            Unit::Space(count) if count >= 2 => break,
+            Unit::Space(count) => breaks += count,
+            Unit::Doc(range) => {
+                docs.push(range);
+                breaks = 0
+            }
+            Unit::Annotation => {}
+            Unit::Other => {
+                // A comment after code on the same line belongs to that code.
+                if breaks == 0 {
+                    docs.pop();
+                }
+                break;
+            }
+        }
+    }
+    docs.reverse();
+    // A documentation node at the start of the body, like a Python docstring.
+    if let Some(body) = declaration.children().filter_map(|child| child.as_node()).find(|child| child.green.kind.role().universal() == UniversalElementRole::Container)
+        && let Some(RedTree::Node(first)) = body.children().find(|child| !matches!(child, RedTree::Leaf(leaf) if matches!(leaf.kind.role().universal(), UniversalTokenRole::Whitespace | UniversalTokenRole::Punctuation)))
+        && first.green.kind.role().universal() == UniversalElementRole::Documentation
+    {
+        docs.push(first.span())
+    }
+    // Empty comments between lines of documentation separate paragraphs.
+    docs.iter().map(|range| normalize_comment(&source.get_text_in(*range))).collect::<Vec<_>>().join("\n").trim_matches('\n').to_string()
+}
+
+/// Block comment delimiters, longest first.
+const BLOCK_COMMENTS: &[(&str, &str)] = &[("/**", "*/"), ("/*!", "*/"), ("/*", "*/"), ("--[[", "]]"), ("\"\"\"", "\"\"\""), ("'''", "'''"), ("(*", "*)"), ("{-|", "-}"), ("{-", "-}"), ("<!--", "-->"), ("#|", "|#"), ("%{", "%}"), ("=begin", "=end")];
+
+/// Line comment markers, longest first.
+const LINE_COMMENTS: &[&str] = &["///", "//!", "//", "#'", "##", "#", "-- |", "-- ^", "---", "--", ";;;", ";;", ";", "%%", "%"];
+
+/// Removes the comment markers from the text of a comment or documentation node.
+///
+/// Handles line comments like `///`, `//!`, `#`, `--` and `;`, where every line is stripped of its
+/// marker and one following space, and block comments like `/** */`, `(* *)`, `{- -}` and `"""`,
+/// where the delimiters, a leading `*` on every line of C-style comments and the common indentation
+/// are removed. Leading and trailing blank lines are dropped.
+pub fn normalize_comment(text: &str) -> String {
+    let text = text.trim();
+    let lines: Vec<String> = match BLOCK_COMMENTS.iter().find(|(open, close)| text.len() >= open.len() + close.len() && text.starts_with(open) && text.ends_with(close)) {
+        Some((open, close)) => {
+            let inner = &text[open.len()..text.len() - close.len()];
+            let lines: Vec<&str> = inner
+                .lines()
+                .map(|line| {
+                    // C-style block comments often start every line with a `*`.
+                    if open.starts_with("/*") { line.trim_start().strip_prefix('*').map_or(line, |rest| rest.strip_prefix(' ').unwrap_or(rest)) } else { line }
+                })
+                .collect();
+            let indent = lines.iter().skip(1).filter(|line| !line.trim().is_empty()).map(|line| line.len() - line.trim_start().len()).min().unwrap_or(0);
+            lines.iter().enumerate().map(|(i, line)| if i == 0 { line.trim_start() } else { line.get(indent..).unwrap_or(line.trim_start()) }.trim_end().to_string()).collect()
+        }
+        None => text
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let rest = LINE_COMMENTS.iter().find_map(|marker| line.strip_prefix(marker)).unwrap_or(line);
+                rest.strip_prefix(' ').unwrap_or(rest).trim_end().to_string()
+            })
+            .collect(),
+    };
+    let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let last = lines.iter().rposition(|line| !line.is_empty()).map_or(first, |last| last + 1);
+    lines[first..last].join("\n")
 }
@@ -0,0 +1,84 @@
+use oak_core::{language::UniversalElementRole as E, source::SourceText, tree::RedNode};
+use oak_hover::{HoverProvider, UniversalHoverProvider, normalize_comment};
+use oak_testing::building::{Spec::Node, comment, kw, name, punct, ws};
+
+#[test]
+fn test_normalize_comment() {
+    assert_eq!(normalize_comment("/// Adds two numbers.\n///\n///   Indented."), "Adds two numbers.\n\n  Indented.");
+    assert_eq!(normalize_comment("/**\n * Adds two numbers.\n *\n * @param a the first\n */"), "Adds two numbers.\n\n@param a the first");
+    assert_eq!(normalize_comment("# A comment"), "A comment");
+    assert_eq!(normalize_comment("-- | Haddock"), "Haddock");
+    assert_eq!(normalize_comment("\"\"\"Docstring.\n\n    More.\n    \"\"\""), "Docstring.\n\nMore.");
+}
+
+#[test]
+fn test_universal_hover() {
+    let root = Node(
+        E::Root,
+        vec![
+            Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("z")]), punct(";")]),
+            ws(" "),
+            comment("// not a doc"),
+            ws("\n"),
+            comment("/// Returns the length."),
+            ws("\n"),
+            comment("///"),
+            ws("\n"),
+            comment("/// Never negative."),
+            ws("\n"),
+            Node(
+                E::Definition,
+                vec![
+                    Node(E::Metadata, vec![punct("#[inline]")]),
+                    ws("\n"),
+                    kw("fn"),
+                    ws(" "),
+                    name("len"),
+                    Node(E::Detail, vec![punct("("), Node(E::Binding, vec![name("v"), punct(":"), ws(" "), Node(E::Typing, vec![name("Vec")])]), punct(")")]),
+                    ws(" "),
+                    punct("->"),
+                    ws("\n    "),
+                    Node(E::Typing, vec![name("f64")]),
+                    ws(" "),
+                    Node(
+                        E::Container,
+                        vec![
+                            punct("{"),
+                            ws(" "),
+                            Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("x")]), punct(":"), ws(" "), Node(E::Typing, vec![name("f64")]), ws(" "), punct("="), ws(" "), Node(E::Reference, vec![name("v")]), punct(";")]),
+                            ws(" "),
+                            Node(E::Reference, vec![name("x")]),
+                            ws(" "),
+                            punct("}"),
+                        ],
+                    ),
+                ],
+            ),
+            ws("\n\n"),
+            Node(E::Statement, vec![Node(E::Reference, vec![name("len")]), punct(";")]),
+        ],
+    );
+    let (text, green) = root.build();
+    assert_eq!(text, "let z; // not a doc\n/// Returns the length.\n///\n/// Never negative.\n#[inline]\nfn len(v: Vec) ->\n    f64 { let x: f64 = v; x }\n\nlen;");
+    let source = SourceText::new(text.as_str());
+    let root = RedNode::new(green, 0);
+    let hover_at = |needle: &str, skip: usize| {
+        let offset = text.match_indices(needle).nth(skip).unwrap().0;
+        UniversalHoverProvider.hover_in(&root, &source, (offset..offset).into())
+    };
+
+    // A use of the function shows its signature and documentation, and applies to the name under the cursor.
+    let hover = hover_at("len;", 0).unwrap();
+    assert_eq!(hover.contents, "```universal\nfn len(v: Vec) -> f64\n```\n\nReturns the length.\n\nNever negative.");
+    let range = hover.range.unwrap();
+    assert_eq!(&text[range.start..range.end], "len");
+    assert_eq!(range.start, text.rfind("len").unwrap());
+    // The declaration itself shows the same.
+    assert_eq!(hover_at("len(", 0).unwrap().contents, hover.contents);
+    // A local shows its statement, without the initializer.
+    assert_eq!(hover_at("x }", 0).unwrap().contents, "```universal\nlet x: f64\n```");
+    // A parameter shows its binding.
+    assert_eq!(hover_at("v;", 0).unwrap().contents, "```universal\nv: Vec\n```");
+    // Keywords have no hover.
+    assert!(hover_at("fn", 0).is_none());
+}
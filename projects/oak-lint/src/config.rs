@@ -0,0 +1,158 @@
+use oak_diagnostic::Severity;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, path::Path};
+
+/// How a rule reports its findings, or whether it runs at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// The rule does not run.
+    #[serde(alias = "allow")]
+    Off,
+    /// The rule reports [advice](Severity::Advice).
+    #[serde(alias = "info", alias = "hint")]
+    Advice,
+    /// The rule reports [warnings](Severity::Warning).
+    #[serde(alias = "warn")]
+    Warning,
+    /// The rule reports [errors](Severity::Error).
+    #[serde(alias = "deny")]
+    Error,
+}
+
+impl RuleLevel {
+    /// Returns the severity of the reports, or `None` if the rule is turned off.
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            RuleLevel::Off => None,
+            RuleLevel::Advice => Some(Severity::Advice),
+            RuleLevel::Warning => Some(Severity::Warning),
+            RuleLevel::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// The configuration of one rule: either just a level, or a table with an optional level and
+/// rule specific options.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RuleSetting {
+    /// Only sets the level, like `todo-comment = "off"`.
+    Level(RuleLevel),
+    /// Sets options and maybe the level, like `long-line = { level = "error", max = 100 }`.
+    Options {
+        /// The level, or `None` to keep the default severity of the rule.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<RuleLevel>,
+        /// The rule specific options.
+        #[serde(flatten)]
+        options: serde_json::Map<String, serde_json::Value>,
+    },
+}
+
+impl RuleSetting {
+    /// Returns the configured level, if any.
+    pub fn level(&self) -> Option<RuleLevel> {
+        match self {
+            RuleSetting::Level(level) => Some(*level),
+            RuleSetting::Options { level, .. } => *level,
+        }
+    }
+
+    /// Returns the rule specific options.
+    pub fn options(&self) -> &serde_json::Map<String, serde_json::Value> {
+        static EMPTY: std::sync::LazyLock<serde_json::Map<String, serde_json::Value>> = std::sync::LazyLock::new(serde_json::Map::new);
+        match self {
+            RuleSetting::Level(_) => &EMPTY,
+            RuleSetting::Options { options, .. } => options,
+        }
+    }
+}
+
+/// The lint configuration of a project, mapping rule ids to their settings.
+///
+/// In TOML:
+///
+/// ```toml
+/// [rules]
+/// todo-comment = "off"
+/// long-line = { level = "error", max = 100 }
+/// ```
+///
+/// and the same shape in JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LintConfig {
+    /// The settings by rule id.
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleSetting>,
+}
+
+impl LintConfig {
+    /// Creates an empty configuration, which keeps the defaults of every rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the configuration of a rule.
+    pub fn with_rule(mut self, id: impl Into<String>, setting: RuleSetting) -> Self {
+        self.rules.insert(id.into(), setting);
+        self
+    }
+
+    /// Parses a configuration from TOML.
+    pub fn from_toml(text: &str) -> Result<Self, LintConfigError> {
+        toml::from_str(text).map_err(|error| LintConfigError::Parse(error.to_string()))
+    }
+
+    /// Parses a configuration from JSON.
+    pub fn from_json(text: &str) -> Result<Self, LintConfigError> {
+        serde_json::from_str(text).map_err(|error| LintConfigError::Parse(error.to_string()))
+    }
+
+    /// Loads a configuration file, as JSON if its extension is `json` and as TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LintConfigError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&text),
+            _ => Self::from_toml(&text),
+        }
+    }
+}
+
+/// An error in a lint configuration.
+#[derive(Debug)]
+pub enum LintConfigError {
+    /// The configuration file could not be read.
+    Io(std::io::Error),
+    /// The configuration is not valid TOML or JSON of the expected shape.
+    Parse(String),
+    /// A setting names a rule that is not registered.
+    UnknownRule(String),
+    /// A rule rejected its options.
+    InvalidOption {
+        /// The id of the rule.
+        rule: String,
+        /// What is wrong with the options.
+        message: String,
+    },
+}
+
+impl fmt::Display for LintConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintConfigError::Io(error) => write!(f, "cannot read lint configuration: {}", error),
+            LintConfigError::Parse(message) => write!(f, "invalid lint configuration: {}", message),
+            LintConfigError::UnknownRule(rule) => write!(f, "unknown lint rule `{}`", rule),
+            LintConfigError::InvalidOption { rule, message } => write!(f, "invalid options for lint rule `{}`: {}", rule, message),
+        }
+    }
+}
+
+impl std::error::Error for LintConfigError {}
+
+impl From<std::io::Error> for LintConfigError {
+    fn from(error: std::io::Error) -> Self {
+        LintConfigError::Io(error)
+    }
+}
@@ -0,0 +1,350 @@
+#![feature(new_range_api)]
+#![warn(missing_docs)]
+#![doc = include_str!("../readme.md")]
+//! Lint support for the Oak language framework.
+//!
+//! A [`Linter`] is a registry of [`LintRule`]s. It walks a syntax tree once, calls every enabled
+//! rule on the nodes and tokens it is [interested](Interest) in, and collects the
+//! [`Diagnostic`]s they report, minus those silenced by [suppression comments](Suppressions).
+//! Rules are configured per id with a [`LintConfig`] loaded from TOML or JSON.
+use core::range::Range;
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_diagnostic::{Diagnostic, Label, OakDiagnosticsProvider, RuleMetadata, Severity};
+use oak_vfs::LineMap;
+use std::borrow::Cow;
+
+mod config;
+pub use config::{LintConfig, LintConfigError, RuleLevel, RuleSetting};
+
+mod suppress;
+pub use suppress::Suppressions;
+
+/// Universal lint rules that work for any language.
+pub mod rules;
+pub use rules::{EmptyBlock, LongLine, MaxNestingDepth, TodoComment};
+
+/// The nodes and tokens a [`LintRule`] wants to be called on.
+///
+/// Nodes and tokens can be selected by their universal role or by their language specific kind.
+pub struct Interest<L: Language> {
+    all_nodes: bool,
+    element_roles: Vec<UniversalElementRole>,
+    element_kinds: Vec<L::ElementType>,
+    all_tokens: bool,
+    token_roles: Vec<UniversalTokenRole>,
+    token_kinds: Vec<L::TokenType>,
+}
+
+impl<L: Language> Interest<L> {
+    /// Selects no node and no token, for rules that only check the whole file.
+    pub fn none() -> Self {
+        Self { all_nodes: false, element_roles: Vec::new(), element_kinds: Vec::new(), all_tokens: false, token_roles: Vec::new(), token_kinds: Vec::new() }
+    }
+
+    /// Selects every node and every token.
+    pub fn all() -> Self {
+        Self { all_nodes: true, all_tokens: true, ..Self::none() }
+    }
+
+    /// Adds the nodes with a universal role.
+    pub fn with_element_role(mut self, role: UniversalElementRole) -> Self {
+        self.element_roles.push(role);
+        self
+    }
+
+    /// Adds the nodes of a kind.
+    pub fn with_element_kind(mut self, kind: L::ElementType) -> Self {
+        self.element_kinds.push(kind);
+        self
+    }
+
+    /// Adds the tokens with a universal role.
+    pub fn with_token_role(mut self, role: UniversalTokenRole) -> Self {
+        self.token_roles.push(role);
+        self
+    }
+
+    /// Adds the tokens of a kind.
+    pub fn with_token_kind(mut self, kind: L::TokenType) -> Self {
+        self.token_kinds.push(kind);
+        self
+    }
+
+    /// Checks whether a node of the given kind is selected.
+    pub fn matches_node(&self, kind: L::ElementType) -> bool {
+        self.all_nodes || self.element_kinds.contains(&kind) || self.element_roles.contains(&kind.role().universal())
+    }
+
+    /// Checks whether a token of the given kind is selected.
+    pub fn matches_token(&self, kind: L::TokenType) -> bool {
+        self.all_tokens || self.token_kinds.contains(&kind) || self.token_roles.contains(&kind.role().universal())
+    }
+}
+
+/// A lint rule: a check over the syntax tree that reports [`Diagnostic`]s.
+///
+/// The [`Linter`] calls [`check_file`](Self::check_file) once per file, then walks the tree and calls
+/// [`check_node`](Self::check_node) and [`check_token`](Self::check_token) on the nodes and tokens
+/// selected by [`interest`](Self::interest). Reports go through the [`LintContext`], which stamps them
+/// with the rule id and the configured severity.
+pub trait LintRule<L: Language>: Send + Sync {
+    /// The unique id of the rule in kebab case, like `max-nesting-depth`.
+    ///
+    /// The id is the [code](Diagnostic::code) of the reported diagnostics and names the rule in
+    /// configuration files and suppression comments.
+    fn id(&self) -> &'static str;
+
+    /// A one line description of what the rule checks.
+    fn description(&self) -> &'static str;
+
+    /// The severity of the rule unless configured otherwise.
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// The nodes and tokens the rule wants to be called on.
+    fn interest(&self) -> Interest<L> {
+        Interest::all()
+    }
+
+    /// Applies the options of the rule from a configuration file.
+    ///
+    /// Returns a message describing the problem if an option is unknown or has the wrong type.
+    fn configure(&mut self, options: &serde_json::Map<String, serde_json::Value>) -> Result<(), String> {
+        match options.keys().next() {
+            Some(key) => Err(format!("unknown option `{}`", key)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the whole file before the tree is walked.
+    fn check_file(&self, root: &RedNode<L>, ctx: &mut LintContext<'_>) {
+        let _ = (root, ctx);
+    }
+
+    /// Checks a node selected by [`interest`](Self::interest).
+    fn check_node(&self, node: &RedNode<L>, ctx: &mut LintContext<'_>) {
+        let _ = (node, ctx);
+    }
+
+    /// Checks a token selected by [`interest`](Self::interest).
+    fn check_token(&self, token: &RedLeaf<L>, ctx: &mut LintContext<'_>) {
+        let _ = (token, ctx);
+    }
+}
+
+/// The state a [`LintRule`] sees while checking a file.
+pub struct LintContext<'a> {
+    uri: &'a str,
+    source: &'a dyn Source,
+    line_map: &'a LineMap,
+    depth: usize,
+    rule: &'static str,
+    severity: Severity,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> LintContext<'a> {
+    /// The URI of the file being checked.
+    pub fn uri(&self) -> &'a str {
+        self.uri
+    }
+
+    /// The source of the file being checked.
+    pub fn source(&self) -> &'a dyn Source {
+        self.source
+    }
+
+    /// Returns the text in a range of the file.
+    pub fn text(&self, range: Range<usize>) -> Cow<'a, str> {
+        self.source.get_text_in(range)
+    }
+
+    /// The lines of the file being checked.
+    pub fn line_map(&self) -> &'a LineMap {
+        self.line_map
+    }
+
+    /// The number of [`Container`](UniversalElementRole::Container) nodes around the current node,
+    /// including the node itself.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The severity configured for the current rule.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Reports a diagnostic of the current rule.
+    ///
+    /// The severity is replaced by the configured one, and the code is set to the rule id unless the
+    /// rule set one. Diagnostics without labels can only be suppressed for the whole file.
+    pub fn report(&mut self, mut diagnostic: Diagnostic) {
+        diagnostic.severity = self.severity;
+        if diagnostic.code.is_none() {
+            diagnostic.code = Some(self.rule.to_string())
+        }
+        self.diagnostics.push(diagnostic)
+    }
+
+    /// Reports a message about a range of the file.
+    pub fn report_at(&mut self, range: Range<usize>, message: impl Into<String>) {
+        let mut diagnostic = Diagnostic::warning(message);
+        diagnostic.labels.push(Label { message: None, span: range, color: None, primary: true });
+        self.report(diagnostic)
+    }
+}
+
+/// A rule registered on a [`Linter`], with its configured severity.
+struct RegisteredRule<L: Language> {
+    rule: Box<dyn LintRule<L>>,
+    /// The configured severity, or `None` if the rule is turned off.
+    severity: Option<Severity>,
+}
+
+/// A registry of [`LintRule`]s that checks files with the enabled ones.
+pub struct Linter<L: Language> {
+    rules: Vec<RegisteredRule<L>>,
+}
+
+impl<L: Language> Default for Linter<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: Language> Linter<L> {
+    /// Creates a linter without rules.
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Creates a linter with the [universal rules](rules).
+    pub fn with_universal_rules() -> Self {
+        Self::new().with_rule(MaxNestingDepth::default()).with_rule(LongLine::default()).with_rule(TodoComment::default()).with_rule(EmptyBlock)
+    }
+
+    /// Registers a rule, replacing a registered rule with the same id.
+    pub fn with_rule(mut self, rule: impl LintRule<L> + 'static) -> Self {
+        self.register(Box::new(rule));
+        self
+    }
+
+    /// Registers a rule, replacing a registered rule with the same id.
+    pub fn register(&mut self, rule: Box<dyn LintRule<L>>) {
+        let severity = Some(rule.default_severity());
+        match self.rules.iter_mut().find(|registered| registered.rule.id() == rule.id()) {
+            Some(registered) => *registered = RegisteredRule { rule, severity },
+            None => self.rules.push(RegisteredRule { rule, severity }),
+        }
+    }
+
+    /// Returns the registered rules.
+    pub fn rules(&self) -> impl Iterator<Item = &dyn LintRule<L>> {
+        self.rules.iter().map(|registered| registered.rule.as_ref())
+    }
+
+    /// Returns the metadata of the enabled rules with their configured severity, for a
+    /// [`Report`](oak_diagnostic::Report).
+    pub fn rule_metadata(&self) -> Vec<RuleMetadata> {
+        self.rules.iter().filter_map(|registered| Some(RuleMetadata::new(registered.rule.id(), registered.rule.description()).with_severity(registered.severity?))).collect()
+    }
+
+    /// Returns the configured severity of a rule, or `None` if it is turned off or not registered.
+    pub fn severity(&self, id: &str) -> Option<Severity> {
+        self.rules.iter().find(|registered| registered.rule.id() == id)?.severity
+    }
+
+    /// Applies a configuration to the registered rules.
+    ///
+    /// Fails on the first setting for an unknown rule or with invalid options; the settings before it
+    /// stay applied.
+    pub fn configure(&mut self, config: &LintConfig) -> Result<(), LintConfigError> {
+        for (id, setting) in &config.rules {
+            let Some(registered) = self.rules.iter_mut().find(|registered| registered.rule.id() == id)
+            else {
+                return Err(LintConfigError::UnknownRule(id.clone()));
+            };
+            if let Some(level) = setting.level() {
+                registered.severity = level.severity()
+            }
+            registered.rule.configure(setting.options()).map_err(|message| LintConfigError::InvalidOption { rule: id.clone(), message })?
+        }
+        Ok(())
+    }
+
+    /// Checks a file with the enabled rules, returning the unsuppressed diagnostics in source order.
+    pub fn lint(&self, uri: &str, root: &RedNode<L>, source: &dyn Source) -> Vec<Diagnostic> {
+        let line_map = LineMap::from_source(source);
+        let rules: Vec<_> = self.rules.iter().filter_map(|registered| Some((registered.rule.as_ref(), registered.severity?, registered.rule.interest()))).collect();
+        let mut walker = Walker { rules: &rules, ctx: LintContext { uri, source, line_map: &line_map, depth: 0, rule: "", severity: Severity::Warning, diagnostics: Vec::new() }, suppressions: Suppressions::default() };
+        for (rule, severity, _) in &rules {
+            walker.ctx.rule = rule.id();
+            walker.ctx.severity = *severity;
+            rule.check_file(root, &mut walker.ctx)
+        }
+        walker.node(root);
+
+        let Walker { ctx, suppressions, .. } = walker;
+        let mut diagnostics: Vec<_> = ctx.diagnostics.into_iter().filter(|diagnostic| !suppressions.is_suppressed(diagnostic, &line_map)).collect();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.labels.first().map_or(0, |label| label.span.start));
+        diagnostics
+    }
+}
+
+impl<L: Language> OakDiagnosticsProvider<L> for Linter<L> {
+    fn emit_diagnostics<S: Source + ?Sized>(&self, uri: &str, root: &RedNode<L>, source: &S) -> Vec<Diagnostic> {
+        self.lint(uri, root, &source)
+    }
+}
+
+/// Walks a tree once, dispatching to the interested rules and collecting suppression comments.
+struct Walker<'r, 'a, L: Language> {
+    rules: &'r [(&'r dyn LintRule<L>, Severity, Interest<L>)],
+    ctx: LintContext<'a>,
+    suppressions: Suppressions,
+}
+
+impl<L: Language> Walker<'_, '_, L> {
+    fn node(&mut self, node: &RedNode<L>) {
+        let kind = node.green.kind;
+        let container = kind.role().universal() == UniversalElementRole::Container;
+        if container {
+            self.ctx.depth += 1
+        }
+        for (rule, severity, interest) in self.rules {
+            if interest.matches_node(kind) {
+                self.ctx.rule = rule.id();
+                self.ctx.severity = *severity;
+                rule.check_node(node, &mut self.ctx)
+            }
+        }
+        for child in node.children() {
+            match child {
+                RedTree::Node(child) => self.node(&child),
+                RedTree::Leaf(leaf) => self.token(&leaf),
+            }
+        }
+        if container {
+            self.ctx.depth -= 1
+        }
+    }
+
+    fn token(&mut self, token: &RedLeaf<L>) {
+        if token.kind.role().universal() == UniversalTokenRole::Comment {
+            self.suppressions.scan(&self.ctx.text(token.span), token.span, self.ctx.line_map)
+        }
+        for (rule, severity, interest) in self.rules {
+            if interest.matches_token(token.kind) {
+                self.ctx.rule = rule.id();
+                self.ctx.severity = *severity;
+                rule.check_token(token, &mut self.ctx)
+            }
+        }
+    }
+}
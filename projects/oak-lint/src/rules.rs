@@ -0,0 +1,224 @@
+//! Each rule here only looks at the universal roles of the tree, so it works for every language.
+use crate::{Interest, LintContext, LintRule};
+use core::range::Range;
+use oak_core::{
+    language::{Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_diagnostic::Severity;
+use serde_json::{Map, Value};
+
+/// Reads a positive integer option.
+fn positive(options: &Map<String, Value>, key: &str) -> Result<Option<usize>, String> {
+    match options.get(key) {
+        None => Ok(None),
+        Some(value) => value.as_u64().filter(|value| *value > 0).map(|value| Some(value as usize)).ok_or_else(|| format!("`{}` must be a positive integer", key)),
+    }
+}
+
+/// Rejects options other than the known ones.
+fn known(options: &Map<String, Value>, keys: &[&str]) -> Result<(), String> {
+    match options.keys().find(|key| !keys.contains(&key.as_str())) {
+        Some(key) => Err(format!("unknown option `{}`", key)),
+        None => Ok(()),
+    }
+}
+
+/// `max-nesting-depth`: blocks nested deeper than `max` levels.
+///
+/// Counts nested [`Container`](UniversalElementRole::Container) nodes and reports the first one past
+/// the limit, but not the blocks inside it.
+#[derive(Debug, Clone)]
+pub struct MaxNestingDepth {
+    /// The deepest allowed nesting, 4 by default.
+    pub max: usize,
+}
+
+impl Default for MaxNestingDepth {
+    fn default() -> Self {
+        Self { max: 4 }
+    }
+}
+
+impl<L: Language> LintRule<L> for MaxNestingDepth {
+    fn id(&self) -> &'static str {
+        "max-nesting-depth"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports blocks that are nested too deeply."
+    }
+
+    fn interest(&self) -> Interest<L> {
+        Interest::none().with_element_role(UniversalElementRole::Container)
+    }
+
+    fn configure(&mut self, options: &Map<String, Value>) -> Result<(), String> {
+        known(options, &["max"])?;
+        if let Some(max) = positive(options, "max")? {
+            self.max = max
+        }
+        Ok(())
+    }
+
+    fn check_node(&self, node: &RedNode<L>, ctx: &mut LintContext<'_>) {
+        if ctx.depth() != self.max + 1 {
+            return;
+        }
+        // Only point at the first line of the block.
+        let span = node.span();
+        let line_end = ctx.line_map().line_content_end(ctx.line_map().line_of(span.start)).unwrap_or(span.end);
+        ctx.report_at((span.start..line_end.clamp(span.start, span.end)).into(), format!("this block is nested {} levels deep, more than the maximum of {}", ctx.depth(), self.max))
+    }
+}
+
+/// `long-line`: lines longer than `max` characters.
+#[derive(Debug, Clone)]
+pub struct LongLine {
+    /// The longest allowed line in characters, 120 by default.
+    pub max: usize,
+}
+
+impl Default for LongLine {
+    fn default() -> Self {
+        Self { max: 120 }
+    }
+}
+
+impl<L: Language> LintRule<L> for LongLine {
+    fn id(&self) -> &'static str {
+        "long-line"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports lines that are too long."
+    }
+
+    fn interest(&self) -> Interest<L> {
+        Interest::none()
+    }
+
+    fn configure(&mut self, options: &Map<String, Value>) -> Result<(), String> {
+        known(options, &["max"])?;
+        if let Some(max) = positive(options, "max")? {
+            self.max = max
+        }
+        Ok(())
+    }
+
+    fn check_file(&self, _root: &RedNode<L>, ctx: &mut LintContext<'_>) {
+        let line_map = ctx.line_map();
+        for line in 0..line_map.line_count() as u32 {
+            let (Some(start), Some(end)) = (line_map.line_start(line), line_map.line_content_end(line))
+            else {
+                continue;
+            };
+            let text = ctx.text((start..end).into());
+            let length = text.chars().count();
+            // Report the part past the limit.
+            if let Some((overflow, _)) = text.char_indices().nth(self.max) {
+                ctx.report_at((start + overflow..end).into(), format!("this line is {} characters long, more than the maximum of {}", length, self.max))
+            }
+        }
+    }
+}
+
+/// `todo-comment`: comments with a `TODO`, `FIXME`, `XXX` or `HACK` marker.
+///
+/// Markers are matched as upper case words. Reports [advice](Severity::Advice) by default.
+#[derive(Debug, Clone)]
+pub struct TodoComment {
+    /// The markers to look for.
+    pub keywords: Vec<String>,
+}
+
+impl Default for TodoComment {
+    fn default() -> Self {
+        Self { keywords: ["TODO", "FIXME", "XXX", "HACK"].map(String::from).to_vec() }
+    }
+}
+
+impl<L: Language> LintRule<L> for TodoComment {
+    fn id(&self) -> &'static str {
+        "todo-comment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports comments with TODO markers."
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Advice
+    }
+
+    fn interest(&self) -> Interest<L> {
+        Interest::none().with_token_role(UniversalTokenRole::Comment)
+    }
+
+    fn configure(&mut self, options: &Map<String, Value>) -> Result<(), String> {
+        known(options, &["keywords"])?;
+        if let Some(keywords) = options.get("keywords") {
+            self.keywords = keywords.as_array().and_then(|keywords| keywords.iter().map(|keyword| keyword.as_str().map(String::from)).collect()).ok_or("`keywords` must be a list of strings")?
+        }
+        Ok(())
+    }
+
+    fn check_token(&self, token: &RedLeaf<L>, ctx: &mut LintContext<'_>) {
+        let text = ctx.text(token.span);
+        for keyword in &self.keywords {
+            let Some(index) = find_word(&text, keyword)
+            else {
+                continue;
+            };
+            let start = token.span.start + index;
+            let note = text[index + keyword.len()..].lines().next().unwrap_or_default().trim_start_matches([':', ' ', '(']).trim_end().trim_end_matches("*/").trim_end();
+            let message = if note.is_empty() { format!("`{}` comment", keyword) } else { format!("`{}` comment: {}", keyword, note) };
+            ctx.report_at((start..start + keyword.len()).into(), message);
+            return;
+        }
+    }
+}
+
+/// Finds `word` in `text` where it is not part of a longer word.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    text.match_indices(word).map(|(index, _)| index).find(|&index| {
+        let before = text[..index].chars().next_back();
+        let after = text[index + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// `empty-block`: blocks without content.
+///
+/// A [`Container`](UniversalElementRole::Container) is empty if it only has punctuation, like its
+/// braces, and whitespace. A comment explaining why a block is empty makes it non-empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptyBlock;
+
+impl<L: Language> LintRule<L> for EmptyBlock {
+    fn id(&self) -> &'static str {
+        "empty-block"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports empty blocks."
+    }
+
+    fn interest(&self) -> Interest<L> {
+        Interest::none().with_element_role(UniversalElementRole::Container)
+    }
+
+    fn check_node(&self, node: &RedNode<L>, ctx: &mut LintContext<'_>) {
+        let span: Range<usize> = node.span();
+        if span.start == span.end {
+            return;
+        }
+        let empty = node.children().all(|child| match child {
+            RedTree::Leaf(leaf) => matches!(leaf.kind.role().universal(), UniversalTokenRole::Punctuation | UniversalTokenRole::Whitespace),
+            RedTree::Node(_) => false,
+        });
+        if empty {
+            ctx.report_at(span, "empty block")
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use core::range::Range;
+use oak_diagnostic::Diagnostic;
+use oak_vfs::LineMap;
+use std::collections::HashMap;
+
+/// The directive that silences rules on the line after the comment.
+const IGNORE_NEXT_LINE: &str = "oak-ignore-next-line";
+/// The directive that silences rules in the whole file.
+const IGNORE_FILE: &str = "oak-ignore-file";
+
+/// The rules silenced by suppression comments in a file.
+///
+/// A comment containing `oak-ignore-next-line` silences the rules listed after it, separated by
+/// spaces or commas, on the line after the comment; `oak-ignore-file` silences them in the whole
+/// file. Without a list, every rule is silenced:
+///
+/// ```text
+/// // oak-ignore-next-line long-line, todo-comment
+/// # oak-ignore-file max-nesting-depth
+/// ```
+///
+/// A diagnostic is matched by its [code](Diagnostic::code) and the line of its first label.
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    /// The silenced rules by line.
+    lines: HashMap<u32, Silenced>,
+    /// The rules silenced in the whole file.
+    file: Option<Silenced>,
+}
+
+/// The rules silenced by one or more directives.
+#[derive(Debug, Clone)]
+enum Silenced {
+    /// Every rule.
+    All,
+    /// The listed rules.
+    Rules(Vec<String>),
+}
+
+impl Silenced {
+    /// Parses the rule list after a directive, up to the end of the comment.
+    fn parse(rest: &str) -> Self {
+        let rest = rest.trim_end().trim_end_matches("*/").trim_end_matches("-->").trim_end_matches("-}").trim_end_matches("*)");
+        let rules: Vec<_> = rest.split(|c: char| c.is_whitespace() || c == ',').filter(|rule| !rule.is_empty()).map(str::to_string).collect();
+        if rules.is_empty() { Silenced::All } else { Silenced::Rules(rules) }
+    }
+
+    /// Adds the rules of another directive for the same place.
+    fn merge(&mut self, other: Silenced) {
+        match (self, other) {
+            (Silenced::Rules(rules), Silenced::Rules(other)) => rules.extend(other),
+            (this, Silenced::All) => *this = Silenced::All,
+            (Silenced::All, _) => {}
+        }
+    }
+
+    fn contains(&self, code: &str) -> bool {
+        match self {
+            Silenced::All => true,
+            Silenced::Rules(rules) => rules.iter().any(|rule| rule == code),
+        }
+    }
+}
+
+impl Suppressions {
+    /// Reads the directives in the text of a comment at `range`.
+    ///
+    /// The line after a directive is counted from the start of the comment, so a comment token
+    /// that ends with its newline still silences the line right after it.
+    pub fn scan(&mut self, comment: &str, range: Range<usize>, line_map: &LineMap) {
+        let first_line = line_map.line_of(range.start);
+        for (index, line) in comment.lines().enumerate() {
+            let (place, rest) = if let Some((_, rest)) = line.split_once(IGNORE_NEXT_LINE) {
+                (Some(first_line + index as u32 + 1), rest)
+            }
+            else if let Some((_, rest)) = line.split_once(IGNORE_FILE) {
+                (None, rest)
+            }
+            else {
+                continue;
+            };
+            let silenced = Silenced::parse(rest);
+            let entry = match place {
+                Some(line) => self.lines.entry(line).or_insert(Silenced::Rules(Vec::new())),
+                None => self.file.get_or_insert(Silenced::Rules(Vec::new())),
+            };
+            entry.merge(silenced)
+        }
+    }
+
+    /// Checks whether a diagnostic is silenced.
+    pub fn is_suppressed(&self, diagnostic: &Diagnostic, line_map: &LineMap) -> bool {
+        let code = diagnostic.code.as_deref().unwrap_or_default();
+        if self.file.as_ref().is_some_and(|silenced| silenced.contains(code)) {
+            return true;
+        }
+        diagnostic.labels.first().and_then(|label| self.lines.get(&line_map.line_of(label.span.start))).is_some_and(|silenced| silenced.contains(code))
+    }
+}
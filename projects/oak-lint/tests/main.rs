@@ -0,0 +1,113 @@
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    source::SourceText,
+    tree::RedNode,
+};
+use oak_diagnostic::Severity;
+use oak_lint::{Interest, LintConfig, LintConfigError, LintContext, LintRule, Linter};
+use oak_testing::building::{
+    Spec::{self, Leaf, Node},
+    Universal, comment, kw, name, punct, ws,
+};
+
+fn block(children: Vec<Spec>) -> Spec {
+    let mut all = vec![punct("{")];
+    all.extend(children);
+    all.push(punct("}"));
+    Node(E::Container, all)
+}
+
+fn lint(linter: &Linter<Universal>, spec: &Spec) -> (String, Vec<(String, Severity, String)>) {
+    let (text, green) = spec.build();
+    let source = SourceText::new(text.as_str());
+    let diagnostics = linter.lint("file:///a.txt", &RedNode::new(green, 0), &source);
+    let found = diagnostics.into_iter().map(|d| (d.code.unwrap_or_default(), d.severity, text[d.labels[0].span.start..d.labels[0].span.end].to_string())).collect();
+    (text, found)
+}
+
+#[test]
+fn test_universal_rules() {
+    let root = Node(
+        E::Root,
+        vec![
+            comment("// TODO: split this up"),
+            ws("\n"),
+            kw("fn"),
+            ws(" "),
+            block(vec![ws(" "), block(vec![block(vec![kw("if"), ws(" "), block(vec![ws("\n"), comment("// oak-ignore-next-line empty-block"), ws("\n"), block(vec![]), ws("\n")])])]), ws(" ")]),
+            ws("\n"),
+            Leaf(T::Literal, "\"a very long string literal\""),
+            ws("\n"),
+            block(vec![ws(" ")]),
+        ],
+    );
+    let mut linter = Linter::with_universal_rules();
+    assert_eq!(linter.rules().map(|rule| rule.id()).collect::<Vec<_>>(), ["max-nesting-depth", "long-line", "todo-comment", "empty-block"]);
+    let config = LintConfig::from_toml("[rules]\nmax-nesting-depth = { max = 3 }\nlong-line = { level = \"error\", max = 20 }\n").unwrap();
+    linter.configure(&config).unwrap();
+
+    let (text, found) = lint(&linter, &root);
+    assert_eq!(text, "// TODO: split this up\nfn { {{if {\n// oak-ignore-next-line empty-block\n{}\n}}} }\n\"a very long string literal\"\n{ }");
+    let found: Vec<_> = found.iter().map(|(code, severity, text)| (code.as_str(), *severity, text.as_str())).collect();
+    assert_eq!(
+        found,
+        [
+            ("todo-comment", Severity::Advice, "TODO"),
+            ("long-line", Severity::Error, "up"),
+            ("max-nesting-depth", Severity::Warning, "{"),
+            ("long-line", Severity::Error, "ine empty-block"),
+            ("long-line", Severity::Error, "literal\""),
+            ("empty-block", Severity::Warning, "{ }"),
+        ]
+    );
+
+    // A directive without rules silences all of them; `off` turns a rule off.
+    let config = LintConfig::from_json(r#"{ "rules": { "todo-comment": "off", "long-line": "off" } }"#).unwrap();
+    linter.configure(&config).unwrap();
+    let root = Node(E::Root, vec![comment("# oak-ignore-file"), ws("\n"), block(vec![])]);
+    assert!(lint(&linter, &root).1.is_empty());
+    let root = Node(E::Root, vec![comment("# TODO"), ws("\n"), block(vec![])]);
+    assert_eq!(lint(&linter, &root).1.len(), 1);
+
+    assert!(matches!(linter.configure(&LintConfig::from_toml("[rules]\nno-such-rule = \"error\"").unwrap()), Err(LintConfigError::UnknownRule(rule)) if rule == "no-such-rule"));
+    assert!(matches!(linter.configure(&LintConfig::from_toml("[rules]\nlong-line = { max = 0 }").unwrap()), Err(LintConfigError::InvalidOption { .. })));
+}
+
+/// A language specific rule, selecting tokens by kind.
+struct NoUse;
+
+impl LintRule<Universal> for NoUse {
+    fn id(&self) -> &'static str {
+        "no-use"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports `use` keywords."
+    }
+
+    fn interest(&self) -> Interest<Universal> {
+        Interest::none().with_token_kind(T::Keyword)
+    }
+
+    fn check_token(&self, token: &oak_core::tree::RedLeaf<Universal>, ctx: &mut LintContext<'_>) {
+        if ctx.text(token.span) == "use" {
+            ctx.report_at(token.span, "`use` is not allowed")
+        }
+    }
+}
+
+#[test]
+fn test_custom_rule() {
+    let linter = Linter::new().with_rule(NoUse);
+    let root = Node(E::Root, vec![kw("use"), ws(" "), name("a"), ws("\n"), comment("// oak-ignore-next-line no-use, other"), ws("\n"), kw("use"), ws(" "), name("b")]);
+    let (_, found) = lint(&linter, &root);
+    assert_eq!(found, [("no-use".to_string(), Severity::Warning, "use".to_string())]);
+
+    // A comment token may end with its newline; the directive still silences the line after it.
+    let root = Node(E::Root, vec![comment("// oak-ignore-next-line no-use\n"), kw("use"), ws(" "), name("a"), ws("\n"), kw("use"), ws(" "), name("b")]);
+    let (text, found) = lint(&linter, &root);
+    assert_eq!(text, "// oak-ignore-next-line no-use\nuse a\nuse b");
+    assert_eq!(found, [("no-use".to_string(), Severity::Warning, "use".to_string())]);
+    let root = Node(E::Root, vec![comment("/* notes\n   oak-ignore-next-line no-use */"), ws("\n"), kw("use"), ws(" "), name("a")]);
+    assert!(lint(&linter, &root).1.is_empty());
+}
@@ -5,10 +5,15 @@
 
 /// Handlers for LSP requests and notifications.
 pub mod handlers;
+/// Routing of requests to the services of several languages.
+pub mod router;
 /// LSP server implementation.
 pub mod server;
 /// Language service trait and utilities.
 pub mod service;
+/// Network transports (TCP and WebSocket) for the LSP server.
+#[cfg(feature = "axum")]
+pub mod transport;
 /// LSP-specific type definitions.
 pub mod types;
 /// Workspace and file management for LSP.
@@ -16,6 +21,7 @@ pub mod workspace;
 
 pub use handlers::*;
 pub use oak_vfs::{FileMetadata, FileType, MemoryVfs, Vfs};
+pub use router::{DynLanguageService, MultiLanguage, MultiLanguageService};
 pub use server::LspServer;
 pub use service::LanguageService;
 pub use types::*;
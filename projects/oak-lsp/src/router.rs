@@ -0,0 +1,674 @@
+use crate::{
+    service::LanguageService,
+    types::{
+        CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction, CodeLens, CompletionItem, Diagnostic, DocumentHighlight, DocumentLink, FoldingRange, Hover, InitializeParams, InlayHint, LinkedEditingRanges, LocationRange,
+        PositionEncoding, SelectionRange, SemanticTokensLegend, SignatureHelp, StructureItem, TextEdit, TypeHierarchyItem, WorkspaceEdit, WorkspaceSymbol,
+    },
+    workspace::WorkspaceManager,
+};
+use core::range::Range;
+use dashmap::DashMap;
+use futures::future::{BoxFuture, join_all};
+use oak_core::language::{Language, UniversalElementRole, UniversalTokenRole, language_for_file};
+use oak_resolver::{SymbolMatch, SymbolQuery, fuzzy_match};
+use oak_semantic_tokens::SemanticToken;
+use oak_vfs::WritableVfs;
+use serde_json::{Value, json};
+use std::{future::Future, sync::Arc};
+
+/// An object-safe view of a [`LanguageService`], so that services of different languages
+/// can be stored side by side.
+///
+/// It is implemented for every [`LanguageService`] and forwards to it.
+pub trait DynLanguageService: Send + Sync {
+    /// See [`LanguageService::workspace`].
+    fn workspace(&self) -> &WorkspaceManager;
+    /// See [`LanguageService::syntax_tree`].
+    fn syntax_tree<'a>(&'a self, uri: &'a str, max_depth: usize) -> BoxFuture<'a, Option<String>>;
+    /// See [`LanguageService::hover`].
+    fn hover<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<Hover>>;
+    /// See [`LanguageService::folding_ranges`].
+    fn folding_ranges<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<FoldingRange>>;
+    /// See [`LanguageService::document_symbols`].
+    fn document_symbols<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<StructureItem>>;
+    /// See [`LanguageService::breadcrumbs`].
+    fn breadcrumbs<'a>(&'a self, uri: &'a str, offset: usize) -> BoxFuture<'a, Vec<StructureItem>>;
+    /// See [`LanguageService::workspace_symbols`].
+    fn workspace_symbols(&self, query: String) -> BoxFuture<'_, Vec<WorkspaceSymbol>>;
+    /// See [`LanguageService::search_symbols`].
+    fn search_symbols(&self, query: SymbolQuery) -> BoxFuture<'_, Vec<SymbolMatch>>;
+    /// See [`LanguageService::definition`].
+    fn definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>>;
+    /// See [`LanguageService::document_highlight`].
+    fn document_highlight<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<DocumentHighlight>>;
+    /// See [`LanguageService::code_action`].
+    fn code_action<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<CodeAction>>;
+    /// See [`LanguageService::formatting`].
+    fn formatting<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<TextEdit>>;
+    /// See [`LanguageService::range_formatting`].
+    fn range_formatting<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<TextEdit>>;
+    /// See [`LanguageService::rename`].
+    fn rename<'a>(&'a self, uri: &'a str, range: Range<usize>, new_name: String) -> BoxFuture<'a, Option<WorkspaceEdit>>;
+    /// See [`LanguageService::semantic_tokens_legend`].
+    fn semantic_tokens_legend(&self) -> SemanticTokensLegend;
+    /// See [`LanguageService::capabilities`].
+    fn capabilities(&self) -> Value;
+    /// See [`LanguageService::semantic_tokens`].
+    fn semantic_tokens<'a>(&'a self, uri: &'a str, encoding: PositionEncoding) -> BoxFuture<'a, Option<Vec<SemanticToken>>>;
+    /// See [`LanguageService::semantic_tokens_range`].
+    fn semantic_tokens_range<'a>(&'a self, uri: &'a str, range: Range<usize>, encoding: PositionEncoding) -> BoxFuture<'a, Option<Vec<SemanticToken>>>;
+    /// See [`LanguageService::inlay_hint`].
+    fn inlay_hint<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<InlayHint>>;
+    /// See [`LanguageService::selection_range`].
+    fn selection_range<'a>(&'a self, uri: &'a str, positions: Vec<usize>) -> BoxFuture<'a, Vec<SelectionRange>>;
+    /// See [`LanguageService::signature_help`].
+    fn signature_help<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<SignatureHelp>>;
+    /// See [`LanguageService::completion`].
+    fn completion<'a>(&'a self, uri: &'a str, offset: usize) -> BoxFuture<'a, Vec<CompletionItem>>;
+    /// See [`LanguageService::references`].
+    fn references<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>>;
+    /// See [`LanguageService::type_definition`].
+    fn type_definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>>;
+    /// See [`LanguageService::implementation`].
+    fn implementation<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>>;
+    /// See [`LanguageService::prepare_call_hierarchy`].
+    fn prepare_call_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<CallHierarchyItem>>;
+    /// See [`LanguageService::incoming_calls`].
+    fn incoming_calls(&self, item: CallHierarchyItem) -> BoxFuture<'_, Vec<CallHierarchyIncomingCall>>;
+    /// See [`LanguageService::outgoing_calls`].
+    fn outgoing_calls(&self, item: CallHierarchyItem) -> BoxFuture<'_, Vec<CallHierarchyOutgoingCall>>;
+    /// See [`LanguageService::prepare_type_hierarchy`].
+    fn prepare_type_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<TypeHierarchyItem>>;
+    /// See [`LanguageService::supertypes`].
+    fn supertypes(&self, item: TypeHierarchyItem) -> BoxFuture<'_, Vec<TypeHierarchyItem>>;
+    /// See [`LanguageService::subtypes`].
+    fn subtypes(&self, item: TypeHierarchyItem) -> BoxFuture<'_, Vec<TypeHierarchyItem>>;
+    /// See [`LanguageService::code_lens`].
+    fn code_lens<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<CodeLens>>;
+    /// See [`LanguageService::document_links`].
+    fn document_links<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<DocumentLink>>;
+    /// See [`LanguageService::linked_editing_range`].
+    fn linked_editing_range<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<LinkedEditingRanges>>;
+    /// See [`LanguageService::initialize`].
+    fn initialize(&self, params: InitializeParams) -> BoxFuture<'_, ()>;
+    /// See [`LanguageService::initialized`].
+    fn initialized(&self) -> BoxFuture<'_, ()>;
+    /// See [`LanguageService::shutdown`].
+    fn shutdown(&self) -> BoxFuture<'_, ()>;
+    /// See [`LanguageService::did_open`].
+    fn did_open<'a>(&'a self, uri: &'a str, language_id: &'a str) -> BoxFuture<'a, ()>;
+    /// See [`LanguageService::did_save`].
+    fn did_save<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, ()>;
+    /// See [`LanguageService::did_close`].
+    fn did_close<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, ()>;
+    /// See [`LanguageService::diagnostics`].
+    fn diagnostics<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<Diagnostic>>;
+}
+
+impl<S: LanguageService> DynLanguageService for S {
+    fn workspace(&self) -> &WorkspaceManager {
+        LanguageService::workspace(self)
+    }
+
+    fn syntax_tree<'a>(&'a self, uri: &'a str, max_depth: usize) -> BoxFuture<'a, Option<String>> {
+        Box::pin(LanguageService::syntax_tree(self, uri, max_depth))
+    }
+
+    fn hover<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<Hover>> {
+        Box::pin(LanguageService::hover(self, uri, range))
+    }
+
+    fn folding_ranges<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<FoldingRange>> {
+        Box::pin(LanguageService::folding_ranges(self, uri))
+    }
+
+    fn document_symbols<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<StructureItem>> {
+        Box::pin(LanguageService::document_symbols(self, uri))
+    }
+
+    fn breadcrumbs<'a>(&'a self, uri: &'a str, offset: usize) -> BoxFuture<'a, Vec<StructureItem>> {
+        Box::pin(LanguageService::breadcrumbs(self, uri, offset))
+    }
+
+    fn workspace_symbols(&self, query: String) -> BoxFuture<'_, Vec<WorkspaceSymbol>> {
+        Box::pin(LanguageService::workspace_symbols(self, query))
+    }
+
+    fn search_symbols(&self, query: SymbolQuery) -> BoxFuture<'_, Vec<SymbolMatch>> {
+        Box::pin(LanguageService::search_symbols(self, query))
+    }
+
+    fn definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>> {
+        Box::pin(LanguageService::definition(self, uri, range))
+    }
+
+    fn document_highlight<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<DocumentHighlight>> {
+        Box::pin(LanguageService::document_highlight(self, uri, range))
+    }
+
+    fn code_action<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<CodeAction>> {
+        Box::pin(LanguageService::code_action(self, uri, range))
+    }
+
+    fn formatting<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<TextEdit>> {
+        Box::pin(LanguageService::formatting(self, uri))
+    }
+
+    fn range_formatting<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<TextEdit>> {
+        Box::pin(LanguageService::range_formatting(self, uri, range))
+    }
+
+    fn rename<'a>(&'a self, uri: &'a str, range: Range<usize>, new_name: String) -> BoxFuture<'a, Option<WorkspaceEdit>> {
+        Box::pin(LanguageService::rename(self, uri, range, new_name))
+    }
+
+    fn semantic_tokens_legend(&self) -> SemanticTokensLegend {
+        LanguageService::semantic_tokens_legend(self)
+    }
+
+    fn capabilities(&self) -> Value {
+        LanguageService::capabilities(self)
+    }
+
+    fn semantic_tokens<'a>(&'a self, uri: &'a str, encoding: PositionEncoding) -> BoxFuture<'a, Option<Vec<SemanticToken>>> {
+        Box::pin(LanguageService::semantic_tokens(self, uri, encoding))
+    }
+
+    fn semantic_tokens_range<'a>(&'a self, uri: &'a str, range: Range<usize>, encoding: PositionEncoding) -> BoxFuture<'a, Option<Vec<SemanticToken>>> {
+        Box::pin(LanguageService::semantic_tokens_range(self, uri, range, encoding))
+    }
+
+    fn inlay_hint<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<InlayHint>> {
+        Box::pin(LanguageService::inlay_hint(self, uri, range))
+    }
+
+    fn selection_range<'a>(&'a self, uri: &'a str, positions: Vec<usize>) -> BoxFuture<'a, Vec<SelectionRange>> {
+        Box::pin(LanguageService::selection_range(self, uri, positions))
+    }
+
+    fn signature_help<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<SignatureHelp>> {
+        Box::pin(LanguageService::signature_help(self, uri, range))
+    }
+
+    fn completion<'a>(&'a self, uri: &'a str, offset: usize) -> BoxFuture<'a, Vec<CompletionItem>> {
+        Box::pin(LanguageService::completion(self, uri, offset))
+    }
+
+    fn references<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>> {
+        Box::pin(LanguageService::references(self, uri, range))
+    }
+
+    fn type_definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>> {
+        Box::pin(LanguageService::type_definition(self, uri, range))
+    }
+
+    fn implementation<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<LocationRange>> {
+        Box::pin(LanguageService::implementation(self, uri, range))
+    }
+
+    fn prepare_call_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<CallHierarchyItem>> {
+        Box::pin(LanguageService::prepare_call_hierarchy(self, uri, range))
+    }
+
+    fn incoming_calls(&self, item: CallHierarchyItem) -> BoxFuture<'_, Vec<CallHierarchyIncomingCall>> {
+        Box::pin(LanguageService::incoming_calls(self, item))
+    }
+
+    fn outgoing_calls(&self, item: CallHierarchyItem) -> BoxFuture<'_, Vec<CallHierarchyOutgoingCall>> {
+        Box::pin(LanguageService::outgoing_calls(self, item))
+    }
+
+    fn prepare_type_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Vec<TypeHierarchyItem>> {
+        Box::pin(LanguageService::prepare_type_hierarchy(self, uri, range))
+    }
+
+    fn supertypes(&self, item: TypeHierarchyItem) -> BoxFuture<'_, Vec<TypeHierarchyItem>> {
+        Box::pin(LanguageService::supertypes(self, item))
+    }
+
+    fn subtypes(&self, item: TypeHierarchyItem) -> BoxFuture<'_, Vec<TypeHierarchyItem>> {
+        Box::pin(LanguageService::subtypes(self, item))
+    }
+
+    fn code_lens<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<CodeLens>> {
+        Box::pin(LanguageService::code_lens(self, uri))
+    }
+
+    fn document_links<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<DocumentLink>> {
+        Box::pin(LanguageService::document_links(self, uri))
+    }
+
+    fn linked_editing_range<'a>(&'a self, uri: &'a str, range: Range<usize>) -> BoxFuture<'a, Option<LinkedEditingRanges>> {
+        Box::pin(LanguageService::linked_editing_range(self, uri, range))
+    }
+
+    fn initialize(&self, params: InitializeParams) -> BoxFuture<'_, ()> {
+        Box::pin(LanguageService::initialize(self, params))
+    }
+
+    fn initialized(&self) -> BoxFuture<'_, ()> {
+        Box::pin(LanguageService::initialized(self))
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, ()> {
+        Box::pin(LanguageService::shutdown(self))
+    }
+
+    fn did_open<'a>(&'a self, uri: &'a str, language_id: &'a str) -> BoxFuture<'a, ()> {
+        Box::pin(LanguageService::did_open(self, uri, language_id))
+    }
+
+    fn did_save<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, ()> {
+        Box::pin(LanguageService::did_save(self, uri))
+    }
+
+    fn did_close<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, ()> {
+        Box::pin(LanguageService::did_close(self, uri))
+    }
+
+    fn diagnostics<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Vec<Diagnostic>> {
+        Box::pin(LanguageService::diagnostics(self, uri))
+    }
+}
+
+/// The language of a [`MultiLanguageService`].
+///
+/// The service has no syntax tree of its own, so its kinds are the universal roles.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultiLanguage;
+
+impl Language for MultiLanguage {
+    const NAME: &'static str = "multi";
+    type TokenType = UniversalTokenRole;
+    type ElementType = UniversalElementRole;
+    type TypedRoot = ();
+}
+
+/// A language registered with a [`MultiLanguageService`].
+struct LanguageEntry {
+    /// The LSP `languageId` of the language.
+    id: String,
+    /// The file extensions (without the dot) and file names of the language, in lower case.
+    extensions: Vec<String>,
+    service: Arc<dyn DynLanguageService>,
+    /// Maps the token type indices of the service legend to the combined legend.
+    token_types: Vec<u32>,
+    /// Maps the token modifier bits of the service legend to the combined legend.
+    token_modifiers: Vec<u32>,
+}
+
+impl LanguageEntry {
+    /// Rewrites the token types and modifiers of the service to the combined legend.
+    fn remap(&self, mut tokens: Vec<SemanticToken>) -> Vec<SemanticToken> {
+        for token in &mut tokens {
+            remap_token(token, &self.token_types, &self.token_modifiers)
+        }
+        tokens
+    }
+}
+
+fn remap_token(token: &mut SemanticToken, token_types: &[u32], token_modifiers: &[u32]) {
+    token.token_type = token_types.get(token.token_type as usize).copied().unwrap_or(token.token_type);
+    let mut bitset = 0;
+    for (bit, target) in token_modifiers.iter().enumerate() {
+        if bit < 32 && token.token_modifiers_bitset & (1 << bit) != 0 {
+            bitset |= 1 << target
+        }
+    }
+    token.token_modifiers_bitset = bitset
+}
+
+/// Finds the index of a name in a legend, appending it if it is missing.
+fn legend_index(legend: &mut Vec<String>, name: &str) -> u32 {
+    match legend.iter().position(|n| n == name) {
+        Some(index) => index as u32,
+        None => {
+            legend.push(name.to_string());
+            (legend.len() - 1) as u32
+        }
+    }
+}
+
+/// Merges the capabilities of a service into the combined ones, so that a feature is
+/// advertised if any service offers it.
+///
+/// Objects are merged key by key and lists of kinds are united. An options object wins over
+/// `true`, which stands for the default options.
+fn merge_capabilities(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_capabilities(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(source)) => {
+            for value in source {
+                if !target.contains(&value) {
+                    target.push(value)
+                }
+            }
+        }
+        (target, source) => {
+            if matches!(target, Value::Null | Value::Bool(false)) || (*target == Value::Bool(true) && source.is_object()) {
+                *target = source
+            }
+        }
+    }
+}
+
+/// A language service that serves several languages by routing each request to the
+/// service of the language of the file it is about.
+///
+/// Files are routed by the `languageId` reported when they were opened, or else by their
+/// extension. Workspace-wide requests such as workspace symbols are sent to every service
+/// and their results are merged. Semantic tokens are rewritten to a legend that combines
+/// the legends of all services.
+///
+/// The services should share the VFS of this service (e.g. clones of one [`MemoryVfs`](oak_vfs::MemoryVfs)),
+/// so that documents the client opens are visible to all of them.
+///
+/// # Examples
+///
+/// ```ignore
+/// let vfs = MemoryVfs::new();
+/// let service = MultiLanguageService::new(vfs.clone())
+///     .with_language("json", &["json"], JsonLanguageService::new(vfs.clone()))
+///     .with_language("toml", &["toml"], TomlLanguageService::new(vfs.clone()));
+/// LspServer::new(Arc::new(service)).run(stdin, stdout).await?;
+/// ```
+pub struct MultiLanguageService<V: WritableVfs> {
+    vfs: V,
+    workspace: WorkspaceManager,
+    languages: Vec<LanguageEntry>,
+    /// The languages of the documents opened by the client, by URI.
+    documents: DashMap<String, usize>,
+    legend: SemanticTokensLegend,
+}
+
+impl<V: WritableVfs> MultiLanguageService<V> {
+    /// Creates a service without languages on top of the given VFS.
+    pub fn new(vfs: V) -> Self {
+        Self { vfs, workspace: WorkspaceManager::new(), languages: Vec::new(), documents: DashMap::new(), legend: SemanticTokensLegend { token_types: Vec::new(), token_modifiers: Vec::new() } }
+    }
+
+    /// Registers the service of a language.
+    ///
+    /// `extensions` are file extensions without the leading dot (e.g. `"json"`, `"d.ts"`)
+    /// or complete file names (e.g. `"Dockerfile"`). When several languages match a file,
+    /// the one with the longest match wins, and among equal matches the first registered one.
+    pub fn with_language<S: LanguageService + 'static>(mut self, id: &str, extensions: &[&str], service: S) -> Self {
+        let legend = LanguageService::semantic_tokens_legend(&service);
+        let token_types = legend.token_types.iter().map(|name| legend_index(&mut self.legend.token_types, name)).collect();
+        let token_modifiers = legend.token_modifiers.iter().map(|name| legend_index(&mut self.legend.token_modifiers, name)).collect();
+        let extensions = extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect();
+        self.languages.push(LanguageEntry { id: id.to_string(), extensions, service: Arc::new(service), token_types, token_modifiers });
+        self
+    }
+
+    /// Returns the `languageId`s of the registered languages.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.iter().map(|language| language.id.as_str())
+    }
+
+    /// Returns the index of the language a file belongs to.
+    fn language_index(&self, uri: &str) -> Option<usize> {
+        if let Some(index) = self.documents.get(uri) {
+            return Some(*index);
+        }
+        let path = uri.split(['?', '#']).next().unwrap_or(uri);
+        language_for_file(self.languages.iter().map(|language| language.extensions.as_slice()), path)
+    }
+
+    fn route(&self, uri: &str) -> Option<&LanguageEntry> {
+        self.language_index(uri).map(|index| &self.languages[index])
+    }
+
+    /// Sends a request to the service of the file's language, or returns the default result
+    /// if no language handles the file.
+    async fn routed<'a, T: Default, F>(&'a self, uri: &str, f: impl FnOnce(&'a dyn DynLanguageService) -> F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        match self.route(uri) {
+            Some(language) => f(language.service.as_ref()).await,
+            None => T::default(),
+        }
+    }
+
+    /// Collects the diagnostics of every file of the workspace that a registered language handles.
+    pub async fn workspace_diagnostics(&self) -> Vec<(String, Vec<Diagnostic>)> {
+        let mut files: Vec<String> = Vec::new();
+        for (folder, _) in self.workspace.list_folders() {
+            files.extend(self.list_all_files(&folder).await)
+        }
+        files.extend(self.documents.iter().map(|entry| entry.key().clone()));
+        files.sort();
+        files.dedup();
+
+        let mut result = Vec::new();
+        for uri in files {
+            if let Some(language) = self.route(&uri) {
+                let diagnostics = language.service.diagnostics(&uri).await;
+                result.push((uri, diagnostics))
+            }
+        }
+        result
+    }
+}
+
+impl<V: WritableVfs + Send + Sync> LanguageService for MultiLanguageService<V> {
+    type Lang = MultiLanguage;
+    type Vfs = V;
+
+    fn vfs(&self) -> &Self::Vfs {
+        &self.vfs
+    }
+
+    fn workspace(&self) -> &WorkspaceManager {
+        &self.workspace
+    }
+
+    fn language_id(&self, uri: &str) -> &str {
+        self.route(uri).map(|language| language.id.as_str()).unwrap_or("plaintext")
+    }
+
+    fn syntax_tree<'a>(&'a self, uri: &'a str, max_depth: usize) -> impl Future<Output = Option<String>> + Send + 'a {
+        self.routed(uri, move |service| service.syntax_tree(uri, max_depth))
+    }
+
+    fn hover(&self, uri: &str, range: Range<usize>) -> impl Future<Output = Option<Hover>> + Send + '_ {
+        let uri = uri.to_string();
+        async move { self.routed(&uri, |service| service.hover(&uri, range)).await }
+    }
+
+    fn folding_ranges(&self, uri: &str) -> impl Future<Output = Vec<FoldingRange>> + Send + '_ {
+        let uri = uri.to_string();
+        async move { self.routed(&uri, |service| service.folding_ranges(&uri)).await }
+    }
+
+    fn document_symbols<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<StructureItem>> + Send + 'a {
+        self.routed(uri, move |service| service.document_symbols(uri))
+    }
+
+    fn breadcrumbs<'a>(&'a self, uri: &'a str, offset: usize) -> impl Future<Output = Vec<StructureItem>> + Send + 'a {
+        self.routed(uri, move |service| service.breadcrumbs(uri, offset))
+    }
+
+    async fn workspace_symbols(&self, query: String) -> Vec<WorkspaceSymbol> {
+        let mut symbols: Vec<_> = join_all(self.languages.iter().map(|language| language.service.workspace_symbols(query.clone()))).await.into_iter().flatten().collect();
+        // Each language ranks its own symbols; rank them again across languages.
+        symbols.sort_by_cached_key(|symbol| core::cmp::Reverse(fuzzy_match(&query, &symbol.name).map(|m| m.score)));
+        symbols
+    }
+
+    async fn search_symbols(&self, query: SymbolQuery) -> Vec<SymbolMatch> {
+        let mut matches: Vec<_> = join_all(self.languages.iter().map(|language| language.service.search_symbols(query.clone()))).await.into_iter().flatten().collect();
+        matches.sort_by_key(|m| core::cmp::Reverse(m.score));
+        matches.truncate(query.limit);
+        matches
+    }
+
+    fn definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
+        self.routed(uri, move |service| service.definition(uri, range))
+    }
+
+    fn document_highlight<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<DocumentHighlight>> + Send + 'a {
+        self.routed(uri, move |service| service.document_highlight(uri, range))
+    }
+
+    fn code_action<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<CodeAction>> + Send + 'a {
+        self.routed(uri, move |service| service.code_action(uri, range))
+    }
+
+    fn formatting<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<TextEdit>> + Send + 'a {
+        self.routed(uri, move |service| service.formatting(uri))
+    }
+
+    fn range_formatting<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<TextEdit>> + Send + 'a {
+        self.routed(uri, move |service| service.range_formatting(uri, range))
+    }
+
+    fn rename<'a>(&'a self, uri: &'a str, range: Range<usize>, new_name: String) -> impl Future<Output = Option<WorkspaceEdit>> + Send + 'a {
+        self.routed(uri, move |service| service.rename(uri, range, new_name))
+    }
+
+    fn semantic_tokens_legend(&self) -> SemanticTokensLegend {
+        self.legend.clone()
+    }
+
+    /// Advertises what any of the registered services offers, with the combined semantic tokens legend.
+    fn capabilities(&self) -> Value {
+        let mut capabilities = json!({});
+        for language in &self.languages {
+            merge_capabilities(&mut capabilities, language.service.capabilities())
+        }
+        if let Some(provider) = capabilities.get_mut("semanticTokensProvider")
+            && provider.is_object()
+        {
+            provider["legend"] = json!({ "tokenTypes": self.legend.token_types, "tokenModifiers": self.legend.token_modifiers })
+        }
+        capabilities
+    }
+
+    /// Provides the semantic tokens of the file's language, rewritten to the combined legend.
+    async fn semantic_tokens(&self, uri: &str, encoding: PositionEncoding) -> Option<Vec<SemanticToken>> {
+        let language = self.route(uri)?;
+        Some(language.remap(language.service.semantic_tokens(uri, encoding).await?))
+    }
+
+    async fn semantic_tokens_range(&self, uri: &str, range: Range<usize>, encoding: PositionEncoding) -> Option<Vec<SemanticToken>> {
+        let language = self.route(uri)?;
+        Some(language.remap(language.service.semantic_tokens_range(uri, range, encoding).await?))
+    }
+
+    fn inlay_hint<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<InlayHint>> + Send + 'a {
+        self.routed(uri, move |service| service.inlay_hint(uri, range))
+    }
+
+    fn selection_range<'a>(&'a self, uri: &'a str, positions: Vec<usize>) -> impl Future<Output = Vec<SelectionRange>> + Send + 'a {
+        self.routed(uri, move |service| service.selection_range(uri, positions))
+    }
+
+    fn signature_help<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Option<SignatureHelp>> + Send + 'a {
+        self.routed(uri, move |service| service.signature_help(uri, range))
+    }
+
+    fn completion<'a>(&'a self, uri: &'a str, offset: usize) -> impl Future<Output = Vec<CompletionItem>> + Send + 'a {
+        self.routed(uri, move |service| service.completion(uri, offset))
+    }
+
+    fn references<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
+        self.routed(uri, move |service| service.references(uri, range))
+    }
+
+    fn type_definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
+        self.routed(uri, move |service| service.type_definition(uri, range))
+    }
+
+    fn implementation<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
+        self.routed(uri, move |service| service.implementation(uri, range))
+    }
+
+    fn prepare_call_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<CallHierarchyItem>> + Send + 'a {
+        self.routed(uri, move |service| service.prepare_call_hierarchy(uri, range))
+    }
+
+    fn incoming_calls<'a>(&'a self, item: CallHierarchyItem) -> impl Future<Output = Vec<CallHierarchyIncomingCall>> + Send + 'a {
+        let uri = item.uri.to_string();
+        async move { self.routed(&uri, move |service| service.incoming_calls(item)).await }
+    }
+
+    fn outgoing_calls<'a>(&'a self, item: CallHierarchyItem) -> impl Future<Output = Vec<CallHierarchyOutgoingCall>> + Send + 'a {
+        let uri = item.uri.to_string();
+        async move { self.routed(&uri, move |service| service.outgoing_calls(item)).await }
+    }
+
+    fn prepare_type_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        self.routed(uri, move |service| service.prepare_type_hierarchy(uri, range))
+    }
+
+    fn supertypes<'a>(&'a self, item: TypeHierarchyItem) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        let uri = item.uri.to_string();
+        async move { self.routed(&uri, move |service| service.supertypes(item)).await }
+    }
+
+    fn subtypes<'a>(&'a self, item: TypeHierarchyItem) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        let uri = item.uri.to_string();
+        async move { self.routed(&uri, move |service| service.subtypes(item)).await }
+    }
+
+    fn code_lens<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<CodeLens>> + Send + 'a {
+        self.routed(uri, move |service| service.code_lens(uri))
+    }
+
+    fn document_links<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<DocumentLink>> + Send + 'a {
+        self.routed(uri, move |service| service.document_links(uri))
+    }
+
+    fn linked_editing_range<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Option<LinkedEditingRanges>> + Send + 'a {
+        self.routed(uri, move |service| service.linked_editing_range(uri, range))
+    }
+
+    /// Initializes the workspace of every service with the client's parameters, so that they
+    /// agree on workspace folders.
+    async fn initialize(&self, params: InitializeParams) {
+        for language in &self.languages {
+            language.service.workspace().initialize(&params);
+            language.service.initialize(params.clone()).await
+        }
+    }
+
+    async fn initialized(&self) {
+        join_all(self.languages.iter().map(|language| language.service.initialized())).await;
+    }
+
+    async fn shutdown(&self) {
+        join_all(self.languages.iter().map(|language| language.service.shutdown())).await;
+    }
+
+    /// Remembers the language the client reported for the file, if it is registered.
+    async fn did_open(&self, uri: &str, language_id: &str) {
+        if let Some(index) = self.languages.iter().position(|language| language.id.eq_ignore_ascii_case(language_id)) {
+            self.documents.insert(uri.to_string(), index);
+        }
+        self.routed(uri, move |service| service.did_open(uri, language_id)).await
+    }
+
+    fn did_save<'a>(&'a self, uri: &'a str) -> impl Future<Output = ()> + Send + 'a {
+        self.routed(uri, move |service| service.did_save(uri))
+    }
+
+    async fn did_close(&self, uri: &str) {
+        self.routed(uri, move |service| service.did_close(uri)).await;
+        self.documents.remove(uri);
+    }
+
+    fn diagnostics<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<Diagnostic>> + Send + 'a {
+        self.routed(uri, move |service| service.diagnostics(uri))
+    }
+}
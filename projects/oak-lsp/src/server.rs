@@ -1,10 +1,23 @@
-use crate::service::LanguageService;
+use crate::{
+    service::LanguageService,
+    types::{CallHierarchyItem, CodeAction, CodeLens, Diagnostic, LocationRange, InitializeParams, PositionEncoding, SemanticToken, SemanticTokens, StructureItem, TextEdit, TypeHierarchyItem, WorkspaceEdit, WorkspaceFolder},
+};
+use core::range::Range;
+use dashmap::DashMap;
+use oak_core::source::Source;
+use oak_semantic_tokens::SemanticTokensCache;
+use oak_vfs::{LineMap, Vfs, WritableVfs};
+use serde_json::{Value, json};
 use std::{
+    collections::HashMap,
     error::Error,
     fmt::{Display, Formatter},
-    sync::Arc,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
 };
-use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 
 /// Errors that can occur during LSP communication.
 #[derive(Debug)]
@@ -50,24 +63,546 @@ impl From<serde_json::Error> for LspError {
     }
 }
 
+/// A JSON-RPC error returned in response to a request.
+#[derive(Debug)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    const INVALID_REQUEST: i64 = -32600;
+    const METHOD_NOT_FOUND: i64 = -32601;
+    const INVALID_PARAMS: i64 = -32602;
+    const SERVER_NOT_INITIALIZED: i64 = -32002;
+    const REQUEST_FAILED: i64 = -32803;
+
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
 /// A language server that handles LSP requests and notifications.
+///
+/// The server speaks JSON-RPC with `Content-Length` framing and translates between LSP
+/// line/character positions and the byte offsets used by [`LanguageService`]. Columns are
+/// measured in the position encoding negotiated during `initialize`.
+///
+/// A server is one client session: several servers may share a service, e.g. one per TCP
+/// connection, each with its own encoding, open documents and semantic tokens. The buffer of
+/// a document is only dropped from the VFS once no session has it open.
 pub struct LspServer<S: LanguageService> {
-    _service: Arc<S>,
+    service: Arc<S>,
+    /// Line maps of the documents opened by the client, kept in sync with their content.
+    line_maps: DashMap<String, Arc<LineMap>>,
+    /// The position encoding negotiated with the client.
+    position_encoding: RwLock<PositionEncoding>,
+    /// The last semantic tokens reported to the client for each document, used for delta requests.
+    semantic_tokens: SemanticTokensCache,
+    /// Whether the `initialize` request has been handled.
+    initialized: AtomicBool,
+    /// Whether the `shutdown` request has been handled.
+    shutdown: AtomicBool,
 }
 
 impl<S: LanguageService> LspServer<S> {
     /// Creates a new `LspServer` with the given language service.
     pub fn new(service: Arc<S>) -> Self {
-        Self { _service: service }
+        Self { service, line_maps: DashMap::new(), position_encoding: RwLock::new(PositionEncoding::Utf16), semantic_tokens: SemanticTokensCache::new(), initialized: AtomicBool::new(false), shutdown: AtomicBool::new(false) }
+    }
+
+    /// Returns the position encoding negotiated with the client.
+    ///
+    /// This is UTF-16 until the client has sent `initialize`.
+    pub fn position_encoding(&self) -> PositionEncoding {
+        self.position_encoding.read().map(|e| *e).unwrap_or_default()
     }
 
     /// Runs the language server on the given input and output streams.
-    pub async fn run<R, W>(&self, mut _read: R, mut _write: W) -> Result<(), LspError>
+    ///
+    /// Returns when the client sends the `exit` notification or closes the input stream.
+    pub async fn run<R, W>(&self, read: R, mut write: W) -> Result<(), LspError>
     where
         R: AsyncRead + Unpin,
         W: AsyncWrite + Unpin,
     {
-        // Basic skeleton for now to satisfy compilation
+        let mut reader = BufReader::new(read);
+        while let Some(message) = read_message(&mut reader).await? {
+            let exit = message.get("method").and_then(Value::as_str) == Some("exit");
+            for outgoing in self.handle_message(message).await {
+                write_message(&mut write, &outgoing).await?
+            }
+            if exit {
+                break;
+            }
+        }
         Ok(())
     }
+
+    /// Handles a single JSON-RPC message and returns the messages to send back.
+    ///
+    /// Requests produce exactly one response; notifications may produce notifications
+    /// such as `textDocument/publishDiagnostics`.
+    pub async fn handle_message(&self, message: Value) -> Vec<Value> {
+        let Some(method) = message.get("method").and_then(Value::as_str)
+        else {
+            // Responses to server-initiated requests are not used.
+            return vec![];
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match message.get("id") {
+            Some(id) => {
+                let response = match self.dispatch_request(method, params).await {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+                };
+                vec![response]
+            }
+            None => self.handle_notification(method, params).await,
+        }
+    }
+
+    async fn dispatch_request(&self, method: &str, params: Value) -> Result<Value, ResponseError> {
+        if method == "initialize" {
+            return Ok(self.initialize(params).await);
+        }
+        if !self.initialized.load(Ordering::SeqCst) {
+            return Err(ResponseError::new(ResponseError::SERVER_NOT_INITIALIZED, "server not initialized"));
+        }
+        if self.shutdown.load(Ordering::SeqCst) {
+            return Err(ResponseError::new(ResponseError::INVALID_REQUEST, "server is shutting down"));
+        }
+        self.handle_request(method, params).await
+    }
+
+    /// Negotiates the position encoding and reports the server capabilities.
+    async fn initialize(&self, params: Value) -> Value {
+        let position_encodings: Vec<_> = params.pointer("/capabilities/general/positionEncodings").and_then(Value::as_array).map(|names| names.iter().filter_map(Value::as_str).filter_map(PositionEncoding::from_name).collect()).unwrap_or_default();
+        let workspace_folders = params.get("workspaceFolders").and_then(Value::as_array).map(|folders| folders.iter().filter_map(|f| Some(WorkspaceFolder { uri: f.get("uri")?.as_str()?.to_string(), name: f.get("name").and_then(Value::as_str).unwrap_or_default().to_string() })).collect()).unwrap_or_default();
+        let params = InitializeParams { root_uri: params.get("rootUri").and_then(Value::as_str).map(String::from), workspace_folders };
+        if let Ok(mut encoding) = self.position_encoding.write() {
+            *encoding = PositionEncoding::negotiate(&position_encodings)
+        }
+
+        self.service.workspace().initialize(&params);
+        self.service.initialize(params).await;
+        self.initialized.store(true, Ordering::SeqCst);
+        json!({ "capabilities": self.capabilities(), "serverInfo": { "name": "oak-lsp", "version": env!("CARGO_PKG_VERSION") } })
+    }
+
+    /// Returns the capabilities advertised to the client.
+    fn capabilities(&self) -> Value {
+        let mut capabilities = self.service.capabilities();
+        if let Value::Object(map) = &mut capabilities {
+            map.insert("positionEncoding".to_string(), json!(self.position_encoding().as_str()));
+        }
+        capabilities
+    }
+
+    async fn handle_request(&self, method: &str, params: Value) -> Result<Value, ResponseError> {
+        let service = &self.service;
+        let mut p = Positions::new(self);
+        let uri = params.pointer("/textDocument/uri").and_then(Value::as_str).map(String::from);
+        let unknown = |uri: &str| ResponseError::new(ResponseError::REQUEST_FAILED, format!("unknown document: {}", uri));
+        let at = |p: &mut Positions<S>| -> Result<(String, Range<usize>), ResponseError> {
+            let uri = uri.clone().ok_or_else(|| ResponseError::new(ResponseError::INVALID_PARAMS, "missing textDocument.uri"))?;
+            let position = params.get("position").ok_or_else(|| ResponseError::new(ResponseError::INVALID_PARAMS, "missing position"))?;
+            let offset = p.offset(&uri, position).ok_or_else(|| unknown(&uri))?;
+            Ok((uri, Range { start: offset, end: offset }))
+        };
+        let span = |p: &mut Positions<S>, uri: &str| p.byte_range(uri, params.get("range").unwrap_or(&Value::Null)).ok_or_else(|| unknown(uri));
+        let document = || uri.clone().ok_or_else(|| ResponseError::new(ResponseError::INVALID_PARAMS, "missing textDocument.uri"));
+
+        let result = match method {
+            "shutdown" => {
+                service.shutdown().await;
+                self.shutdown.store(true, Ordering::SeqCst);
+                Value::Null
+            }
+            "textDocument/hover" => {
+                let (uri, range) = at(&mut p)?;
+                match service.hover(&uri, range).await {
+                    Some(hover) => json!({ "contents": { "kind": "markdown", "value": hover.contents }, "range": hover.range.map(|r| p.range(&uri, r)) }),
+                    None => Value::Null,
+                }
+            }
+            "textDocument/definition" | "textDocument/typeDefinition" | "textDocument/implementation" | "textDocument/references" => {
+                let (uri, range) = at(&mut p)?;
+                let locations = match method {
+                    "textDocument/definition" => service.definition(&uri, range).await,
+                    "textDocument/typeDefinition" => service.type_definition(&uri, range).await,
+                    "textDocument/implementation" => service.implementation(&uri, range).await,
+                    _ => service.references(&uri, range).await,
+                };
+                Value::Array(locations.into_iter().map(|l| p.location(&l.uri, l.range)).collect())
+            }
+            "textDocument/documentHighlight" => {
+                let (uri, range) = at(&mut p)?;
+                Value::Array(service.document_highlight(&uri, range).await.into_iter().map(|h| json!({ "range": p.range(&uri, h.range), "kind": h.kind.map(|k| k as u8) })).collect())
+            }
+            "textDocument/completion" => {
+                let (uri, range) = at(&mut p)?;
+                let items = service.completion(&uri, range.start).await;
+                Value::Array(
+                    items
+                        .into_iter()
+                        .map(|i| {
+                            let text_edit = i.text_edit.map(|edit| p.text_edit(&uri, edit));
+                            json!({ "label": i.label, "kind": i.kind.map(|k| k as u8), "detail": i.detail, "documentation": i.documentation, "insertText": i.insert_text, "insertTextFormat": i.insert_text_format.map(|f| f as u8), "textEdit": text_edit, "sortText": i.sort_text })
+                        })
+                        .collect(),
+                )
+            }
+            "textDocument/documentSymbol" => {
+                let uri = document()?;
+                Value::Array(service.document_symbols(&uri).await.into_iter().map(|item| p.document_symbol(&uri, item)).collect())
+            }
+            "workspace/symbol" => {
+                let query = params.get("query").and_then(Value::as_str).unwrap_or_default().to_string();
+                let symbols = service.workspace_symbols(query).await;
+                Value::Array(symbols.into_iter().map(|s| json!({ "name": s.name, "kind": s.kind as u8, "location": p.location(&s.location.uri, s.location.range), "containerName": s.container_name })).collect())
+            }
+            "textDocument/foldingRange" => {
+                let uri = document()?;
+                let ranges = service.folding_ranges(&uri).await;
+                Value::Array(
+                    ranges
+                        .into_iter()
+                        .map(|f| {
+                            let (start_line, start_character) = p.line_column(&uri, f.range.start);
+                            let (end_line, end_character) = p.line_column(&uri, f.range.end);
+                            json!({ "startLine": start_line, "startCharacter": start_character, "endLine": end_line, "endCharacter": end_character, "kind": f.kind })
+                        })
+                        .collect(),
+                )
+            }
+            "textDocument/formatting" => {
+                let uri = document()?;
+                Value::Array(service.formatting(&uri).await.into_iter().map(|e| p.text_edit(&uri, e)).collect())
+            }
+            "textDocument/rangeFormatting" => {
+                let uri = document()?;
+                let range = span(&mut p, &uri)?;
+                Value::Array(service.range_formatting(&uri, range).await.into_iter().map(|e| p.text_edit(&uri, e)).collect())
+            }
+            "textDocument/rename" => {
+                let (uri, range) = at(&mut p)?;
+                let new_name = params.get("newName").and_then(Value::as_str).unwrap_or_default().to_string();
+                service.rename(&uri, range, new_name).await.map(|edit| p.workspace_edit(edit)).unwrap_or(Value::Null)
+            }
+            "textDocument/codeAction" => {
+                let uri = document()?;
+                let range = span(&mut p, &uri)?;
+                Value::Array(service.code_action(&uri, range).await.into_iter().map(|action| p.code_action(&uri, action)).collect())
+            }
+            "textDocument/codeLens" => {
+                let uri = document()?;
+                Value::Array(service.code_lens(&uri).await.into_iter().map(|lens| p.code_lens(&uri, lens)).collect())
+            }
+            "textDocument/documentLink" => {
+                let uri = document()?;
+                Value::Array(service.document_links(&uri).await.into_iter().map(|link| json!({ "range": p.range(&uri, link.range), "target": link.target, "tooltip": link.tooltip })).collect())
+            }
+            "textDocument/linkedEditingRange" => {
+                let (uri, range) = at(&mut p)?;
+                match service.linked_editing_range(&uri, range).await {
+                    Some(linked) => json!({ "ranges": linked.ranges.into_iter().map(|r| p.range(&uri, r)).collect::<Vec<_>>(), "wordPattern": linked.word_pattern }),
+                    None => Value::Null,
+                }
+            }
+            "textDocument/prepareCallHierarchy" => {
+                let (uri, range) = at(&mut p)?;
+                Value::Array(service.prepare_call_hierarchy(&uri, range).await.into_iter().map(|item| p.call_item(item)).collect())
+            }
+            "callHierarchy/incomingCalls" | "callHierarchy/outgoingCalls" => {
+                let item: CallHierarchyItem = params.pointer("/item/data").cloned().and_then(|data| serde_json::from_value(data).ok()).ok_or_else(|| ResponseError::new(ResponseError::INVALID_PARAMS, "unknown call hierarchy item"))?;
+                if method == "callHierarchy/incomingCalls" {
+                    let calls = service.incoming_calls(item).await;
+                    Value::Array(calls.into_iter().map(|c| json!({ "fromRanges": c.from_ranges.iter().map(|r| p.range(&c.from.uri, *r)).collect::<Vec<_>>(), "from": p.call_item(c.from) })).collect())
+                }
+                else {
+                    let caller = item.uri.clone();
+                    let calls = service.outgoing_calls(item).await;
+                    Value::Array(calls.into_iter().map(|c| json!({ "fromRanges": c.from_ranges.iter().map(|r| p.range(&caller, *r)).collect::<Vec<_>>(), "to": p.call_item(c.to) })).collect())
+                }
+            }
+            "textDocument/prepareTypeHierarchy" => {
+                let (uri, range) = at(&mut p)?;
+                Value::Array(service.prepare_type_hierarchy(&uri, range).await.into_iter().map(|item| p.type_item(item)).collect())
+            }
+            "typeHierarchy/supertypes" | "typeHierarchy/subtypes" => {
+                let item: TypeHierarchyItem = params.pointer("/item/data").cloned().and_then(|data| serde_json::from_value(data).ok()).ok_or_else(|| ResponseError::new(ResponseError::INVALID_PARAMS, "unknown type hierarchy item"))?;
+                let items = if method == "typeHierarchy/supertypes" { service.supertypes(item).await } else { service.subtypes(item).await };
+                Value::Array(items.into_iter().map(|item| p.type_item(item)).collect())
+            }
+            "textDocument/semanticTokens/full" => {
+                let uri = document()?;
+                service.semantic_tokens(&uri, p.encoding).await.map(|data| semantic_tokens(self.store_semantic_tokens(&uri, data))).unwrap_or(Value::Null)
+            }
+            "textDocument/semanticTokens/full/delta" => {
+                let uri = document()?;
+                // Falls back to the full token set when the previous result is unknown.
+                let previous = params.get("previousResultId").and_then(Value::as_str).and_then(|id| self.semantic_tokens.previous(&uri, id));
+                match (service.semantic_tokens(&uri, p.encoding).await, previous) {
+                    (Some(data), Some(previous)) => {
+                        let edits = oak_semantic_tokens::diff(&previous, &data);
+                        let tokens = self.store_semantic_tokens(&uri, data);
+                        json!({ "resultId": tokens.result_id, "edits": edits.into_iter().map(|e| json!({ "start": e.start, "deleteCount": e.delete_count, "data": e.data })).collect::<Vec<_>>() })
+                    }
+                    (Some(data), None) => semantic_tokens(self.store_semantic_tokens(&uri, data)),
+                    (None, _) => Value::Null,
+                }
+            }
+            "textDocument/semanticTokens/range" => {
+                let uri = document()?;
+                let range = span(&mut p, &uri)?;
+                service.semantic_tokens_range(&uri, range, p.encoding).await.map(|data| semantic_tokens(SemanticTokens { result_id: None, data })).unwrap_or(Value::Null)
+            }
+            _ => return Err(ResponseError::new(ResponseError::METHOD_NOT_FOUND, format!("method not found: {}", method))),
+        };
+        Ok(result)
+    }
+
+    async fn handle_notification(&self, method: &str, params: Value) -> Vec<Value> {
+        let uri = params.pointer("/textDocument/uri").and_then(Value::as_str).map(String::from);
+        match (method, uri) {
+            ("initialized", _) => self.service.initialized().await,
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params.pointer("/textDocument/text").and_then(Value::as_str).unwrap_or_default();
+                let language_id = params.pointer("/textDocument/languageId").and_then(Value::as_str).unwrap_or_default();
+                if !self.line_maps.contains_key(&uri) {
+                    self.service.workspace().open_document(&uri)
+                }
+                self.set_content(&uri, text.to_string());
+                self.service.did_open(&uri, language_id).await;
+                return self.publish_diagnostics(&uri).await;
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                let mut text = self.service.get_source(&uri).map(|s| s.get_text_in(Range { start: 0, end: s.length() }).into_owned()).unwrap_or_default();
+                for change in params.get("contentChanges").and_then(Value::as_array).into_iter().flatten() {
+                    let new_text = change.get("text").and_then(Value::as_str).unwrap_or_default();
+                    match change.get("range") {
+                        Some(range) => {
+                            let line_map = LineMap::from_source(text.as_str());
+                            let range = byte_range(&line_map, range, self.position_encoding());
+                            text.replace_range(range.start..range.end, new_text)
+                        }
+                        None => text = new_text.to_string(),
+                    }
+                }
+                self.set_content(&uri, text);
+                return self.publish_diagnostics(&uri).await;
+            }
+            ("textDocument/didSave", Some(uri)) => self.service.did_save(&uri).await,
+            ("textDocument/didClose", Some(uri)) => {
+                self.semantic_tokens.remove(&uri);
+                if self.line_maps.remove(&uri).is_some() && self.release(&uri) {
+                    self.service.did_close(&uri).await
+                }
+                return vec![json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": { "uri": uri, "diagnostics": [] } })];
+            }
+            _ => {}
+        }
+        vec![]
+    }
+
+    /// Stores the content of an open document and refreshes its line map.
+    fn set_content(&self, uri: &str, text: String) {
+        self.line_maps.insert(uri.to_string(), Arc::new(LineMap::from_source(text.as_str())));
+        self.service.vfs().write_file(uri, text.into())
+    }
+
+    async fn publish_diagnostics(&self, uri: &str) -> Vec<Value> {
+        let diagnostics = self.service.diagnostics(uri).await;
+        let mut p = Positions::new(self);
+        let diagnostics: Vec<_> = diagnostics.into_iter().map(|d| p.diagnostic(uri, d)).collect();
+        vec![json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": { "uri": uri, "diagnostics": diagnostics } })]
+    }
+
+    /// Records the tokens reported for a document, so later delta requests of this session can refer to them.
+    fn store_semantic_tokens(&self, uri: &str, data: Vec<SemanticToken>) -> SemanticTokens {
+        let result_id = self.semantic_tokens.store(uri, data.clone());
+        SemanticTokens { result_id: Some(result_id), data }
+    }
+
+    /// Releases a document this session had open, dropping its buffer if no other session has it open.
+    ///
+    /// Returns whether the buffer was dropped.
+    fn release(&self, uri: &str) -> bool {
+        let last = self.service.workspace().close_document(uri);
+        if last {
+            self.service.vfs().remove_file(uri)
+        }
+        last
+    }
+
+    /// Returns the line map of a document, preferring the one kept for open documents.
+    fn line_map(&self, uri: &str) -> Option<Arc<LineMap>> {
+        match self.line_maps.get(uri) {
+            Some(line_map) => Some(line_map.clone()),
+            None => self.service.vfs().line_map(uri).map(Arc::new),
+        }
+    }
+}
+
+impl<S: LanguageService> Drop for LspServer<S> {
+    /// Releases the documents the client left open, e.g. when its connection dropped.
+    fn drop(&mut self) {
+        for entry in self.line_maps.iter() {
+            self.release(entry.key());
+        }
+    }
+}
+
+/// Converts between byte offsets and LSP positions for the duration of one request,
+/// reusing the line map of each document it touches.
+struct Positions<'s, S: LanguageService> {
+    server: &'s LspServer<S>,
+    encoding: PositionEncoding,
+    line_maps: HashMap<String, Option<Arc<LineMap>>>,
+}
+
+impl<'s, S: LanguageService> Positions<'s, S> {
+    fn new(server: &'s LspServer<S>) -> Self {
+        Self { server, encoding: server.position_encoding(), line_maps: HashMap::new() }
+    }
+
+    fn line_map(&mut self, uri: &str) -> Option<Arc<LineMap>> {
+        let server = self.server;
+        self.line_maps.entry(uri.to_string()).or_insert_with(|| server.line_map(uri)).clone()
+    }
+
+    fn line_column(&mut self, uri: &str, offset: usize) -> (u32, u32) {
+        self.line_map(uri).map(|m| m.offset_to_position(offset, self.encoding)).unwrap_or_default()
+    }
+
+    fn position(&mut self, uri: &str, offset: usize) -> Value {
+        let (line, character) = self.line_column(uri, offset);
+        json!({ "line": line, "character": character })
+    }
+
+    fn range(&mut self, uri: &str, range: Range<usize>) -> Value {
+        json!({ "start": self.position(uri, range.start), "end": self.position(uri, range.end) })
+    }
+
+    fn location(&mut self, uri: &str, range: Range<usize>) -> Value {
+        json!({ "uri": uri, "range": self.range(uri, range) })
+    }
+
+    /// Returns the byte offset of a position, or `None` if the document cannot be read.
+    fn offset(&mut self, uri: &str, position: &Value) -> Option<usize> {
+        self.line_map(uri).map(|m| offset(&m, position, self.encoding))
+    }
+
+    /// Returns the byte range of a range, or `None` if the document cannot be read.
+    fn byte_range(&mut self, uri: &str, range: &Value) -> Option<Range<usize>> {
+        self.line_map(uri).map(|m| byte_range(&m, range, self.encoding))
+    }
+
+    fn text_edit(&mut self, uri: &str, edit: TextEdit) -> Value {
+        json!({ "range": self.range(uri, edit.range), "newText": edit.new_text })
+    }
+
+    fn workspace_edit(&mut self, edit: WorkspaceEdit) -> Value {
+        let changes: serde_json::Map<String, Value> = edit.changes.into_iter().map(|(uri, edits)| (uri.clone(), Value::Array(edits.into_iter().map(|e| self.text_edit(&uri, e)).collect()))).collect();
+        json!({ "changes": changes })
+    }
+
+    fn diagnostic(&mut self, uri: &str, diagnostic: Diagnostic) -> Value {
+        json!({ "range": self.range(uri, diagnostic.range), "severity": diagnostic.severity.map(|s| s as u8), "code": diagnostic.code, "source": diagnostic.source, "message": diagnostic.message })
+    }
+
+    fn code_action(&mut self, uri: &str, action: CodeAction) -> Value {
+        let diagnostics = action.diagnostics.map(|diagnostics| diagnostics.into_iter().map(|d| self.diagnostic(uri, d)).collect::<Vec<_>>());
+        let edit = action.edit.map(|edit| self.workspace_edit(edit));
+        json!({ "title": action.title, "kind": action.kind, "diagnostics": diagnostics, "edit": edit, "command": action.command, "isPreferred": action.is_preferred, "disabled": action.disabled })
+    }
+
+    /// Converts a code lens, turning the byte offset and locations passed to `editor.action.showReferences`
+    /// into the position and locations the client expects.
+    fn code_lens(&mut self, uri: &str, lens: CodeLens) -> Value {
+        let command = lens.command.map(|mut command| {
+            if command.command == "editor.action.showReferences"
+                && let Some([target, position, references]) = command.arguments.as_deref_mut()
+                && let (Some(target), Some(offset)) = (target.as_str().map(str::to_string), position.as_u64())
+                && let Ok(locations) = serde_json::from_value::<Vec<LocationRange>>(references.clone())
+            {
+                *position = self.position(&target, offset as usize);
+                *references = Value::Array(locations.into_iter().map(|l| self.location(&l.uri, l.range)).collect())
+            }
+            command
+        });
+        json!({ "range": self.range(uri, lens.range), "command": command, "data": lens.data })
+    }
+
+    fn document_symbol(&mut self, uri: &str, item: StructureItem) -> Value {
+        let children: Vec<_> = item.children.into_iter().map(|child| self.document_symbol(uri, child)).collect();
+        let tags: Vec<u8> = if item.deprecated { vec![1] } else { vec![] };
+        json!({ "name": item.name, "detail": item.detail, "kind": item.kind as u8, "tags": tags, "range": self.range(uri, item.range), "selectionRange": self.range(uri, item.selection_range), "children": children })
+    }
+
+    /// Converts a call hierarchy item, keeping the original in `data` so that follow-up requests can use it.
+    fn call_item(&mut self, item: CallHierarchyItem) -> Value {
+        let data = serde_json::to_value(&item).unwrap_or_default();
+        json!({ "name": item.name, "kind": item.kind as u8, "detail": item.detail, "uri": &*item.uri, "range": self.range(&item.uri, item.range), "selectionRange": self.range(&item.uri, item.selection_range), "data": data })
+    }
+
+    /// Converts a type hierarchy item, keeping the original in `data` so that follow-up requests can use it.
+    fn type_item(&mut self, item: TypeHierarchyItem) -> Value {
+        let data = serde_json::to_value(&item).unwrap_or_default();
+        json!({ "name": item.name, "kind": item.kind as u8, "detail": item.detail, "uri": &*item.uri, "range": self.range(&item.uri, item.range), "selectionRange": self.range(&item.uri, item.selection_range), "data": data })
+    }
+}
+
+/// Converts an LSP position to a byte offset.
+fn offset(line_map: &LineMap, position: &Value, encoding: PositionEncoding) -> usize {
+    let line = position.get("line").and_then(Value::as_u64).unwrap_or(0) as u32;
+    let character = position.get("character").and_then(Value::as_u64).unwrap_or(0) as u32;
+    line_map.position_to_offset(line, character, encoding)
+}
+
+/// Converts an LSP range to a byte range.
+fn byte_range(line_map: &LineMap, range: &Value, encoding: PositionEncoding) -> Range<usize> {
+    let start = offset(line_map, range.get("start").unwrap_or(&Value::Null), encoding);
+    let end = offset(line_map, range.get("end").unwrap_or(&Value::Null), encoding);
+    Range { start, end: end.max(start) }
+}
+
+fn semantic_tokens(tokens: SemanticTokens) -> Value {
+    json!({ "resultId": tokens.result_id, "data": oak_semantic_tokens::flatten(&tokens.data) })
+}
+
+/// Reads one `Content-Length` framed message, or `None` at the end of the stream.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Value>, LspError> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = Some(value.trim().parse::<usize>().map_err(|e| LspError::Other(format!("invalid Content-Length: {}", e)))?)
+        }
+    }
+    let mut body = vec![0; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Writes one message with `Content-Length` framing.
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<(), LspError> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
 }
@@ -1,13 +1,22 @@
-use crate::types::{CodeAction, CompletionItem, Diagnostic, DocumentHighlight, FoldingRange, Hover, InitializeParams, InlayHint, LocationRange, SelectionRange, SemanticTokens, SignatureHelp, StructureItem, TextEdit, WorkspaceEdit, WorkspaceSymbol};
+use crate::types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction, CodeLens, Command, CompletionItem, Diagnostic, DocumentHighlight, DocumentLink, FoldingRange, Hover, InitializeParams, InlayHint, LinkedEditingRanges, LocationRange,
+    PositionEncoding, SelectionRange, SemanticToken, SemanticTokensLegend, SignatureHelp, StructureItem, SymbolKind, TextEdit, TypeHierarchyItem, WorkspaceEdit, WorkspaceSymbol,
+};
 use core::range::Range;
+use oak_completion::{CompletionProvider, UniversalCompletionProvider};
 use oak_core::{
-    language::{ElementRole, ElementType, Language},
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
     source::Source,
-    tree::RedNode,
+    tree::{RedNode, RedTree},
 };
-use oak_resolver::ModuleResolver;
+use oak_diagnostic::Applicability;
+use oak_folding::{FoldingProvider, UniversalFoldingProvider};
+use oak_hover::{HoverProvider, UniversalHoverProvider};
+use oak_resolver::{BindingKind, ImportResolver, ModuleResolver, PathStrategy, ScopeGraph, ScopeProvider, SymbolMatch, SymbolQuery, UniversalScopeProvider};
+use oak_semantic_tokens::{SemanticTokensProvider, UniversalSemanticTokensProvider};
+use oak_structural_view::{StructureProvider, UniversalStructureProvider};
 use oak_vfs::{Vfs, WritableVfs};
-use std::future::Future;
+use std::{collections::HashMap, fmt::Write, future::Future, sync::Arc};
 
 /// A trait that defines the capabilities and behavior of a language-specific service.
 ///
@@ -31,6 +40,13 @@ pub trait LanguageService: Send + Sync {
     /// Returns a reference to the workspace manager.
     fn workspace(&self) -> &crate::workspace::WorkspaceManager;
 
+    /// Returns the identifier of the language of a file, as used in LSP `languageId`s.
+    ///
+    /// Services that serve several languages return the language of the given file.
+    fn language_id(&self, _uri: &str) -> &str {
+        Self::Lang::NAME
+    }
+
     /// Retrieves the source content for a given URI from the VFS.
     fn get_source(&self, uri: &str) -> Option<<Self::Vfs as Vfs>::Source> {
         self.vfs().get_source(uri)
@@ -78,50 +94,110 @@ pub trait LanguageService: Send + Sync {
         async move { futures::future::join_all(futures).await.into_iter().flatten().collect() }
     }
 
+    /// Renders the syntax tree of a file as indented text, one element per line.
+    ///
+    /// Each line shows the kind, the universal role and the byte range of an element,
+    /// and leaves also show their text. Nodes deeper than `max_depth` are omitted.
+    fn syntax_tree<'a>(&'a self, uri: &'a str, max_depth: usize) -> impl Future<Output = Option<String>> + Send + 'a {
+        async move {
+            let source = self.get_source(uri)?;
+            self.with_root(uri, move |root| dump_tree(root, &source, max_depth)).await
+        }
+    }
+
     /// Provides hover information for a specific range in a file.
     ///
     /// # Arguments
     /// * `uri` - The URI of the file.
     /// * `range` - The byte range to provide information for.
-    fn hover(&self, _uri: &str, _range: Range<usize>) -> impl Future<Output = Option<Hover>> + Send + '_ {
-        async { None }
+    ///
+    /// The default shows the signature and documentation of the declaration of the name at `range`
+    /// with [`UniversalHoverProvider`], following the [`definition`](Self::definition) of names that
+    /// are not declared in the file.
+    fn hover(&self, uri: &str, range: Range<usize>) -> impl Future<Output = Option<Hover>> + Send + '_ {
+        let uri = uri.to_string();
+        async move {
+            let source = self.get_source(&uri)?;
+            let (hover, name) = self
+                .with_root(&uri, move |root| {
+                    let name = root.leaf_at_offset(range.start).filter(|leaf| leaf.kind.role().universal() == UniversalTokenRole::Name).map(|leaf| leaf.span);
+                    (UniversalHoverProvider.hover_in(&root, &source, range), name)
+                })
+                .await?;
+            if let Some(hover) = hover {
+                return Some(Hover { contents: hover.contents, range: hover.range });
+            }
+            let name = name?;
+            let target = self.definition(&uri, range).await.into_iter().next()?;
+            let target_source = self.get_source(&target.uri)?;
+            let contents = self.with_root(&target.uri, move |root| UniversalHoverProvider.declaration_markdown(&root, &target_source, target.range)).await.flatten()?;
+            Some(Hover { contents, range: Some(name) })
+        }
     }
 
     /// Provides folding ranges for a file.
-    fn folding_ranges(&self, _uri: &str) -> impl Future<Output = Vec<FoldingRange>> + Send + '_ {
-        async { vec![] }
+    ///
+    /// The default derives them from the universal roles of the syntax tree with [`UniversalFoldingProvider`].
+    fn folding_ranges(&self, uri: &str) -> impl Future<Output = Vec<FoldingRange>> + Send + '_ {
+        let uri = uri.to_string();
+        async move {
+            let Some(source) = self.get_source(&uri)
+            else {
+                return vec![];
+            };
+            let Some(line_map) = self.vfs().line_map(&uri)
+            else {
+                return vec![];
+            };
+            self.with_root(&uri, move |root| UniversalFoldingProvider.folding_ranges_in(&root, &source, &line_map)).await.unwrap_or_default()
+        }
     }
 
     /// Provides document symbols (structure) for a file.
     ///
-    /// This method extracts structural elements like classes, functions, and variables
-    /// from the source file. It first tries to query the workspace symbol index,
-    /// falling back to parsing the file if necessary.
+    /// The default builds the nested outline from the definitions of the syntax tree with
+    /// [`UniversalStructureProvider`], falling back to the flat symbols of the file in the workspace
+    /// symbol index if that finds nothing.
     fn document_symbols<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<StructureItem>> + Send + 'a {
         let uri = uri.to_string();
         async move {
-            let _source = match self.get_source(&uri) {
-                Some(s) => s,
-                None => return vec![],
+            let Some(source) = self.get_source(&uri)
+            else {
+                return vec![];
             };
-            let _root = match self.get_root(&uri).await {
-                Some(r) => r,
-                None => return vec![],
-            };
-            let symbols = self.workspace().symbols.query_file(&uri);
-            if !symbols.is_empty() {
-                return symbols.into_iter().map(StructureItem::from).collect();
+            let items = self.with_root(&uri, move |root| UniversalStructureProvider.structure_in(&root, &source)).await.unwrap_or_default();
+            if !items.is_empty() {
+                return items.into_iter().map(StructureItem::from).collect();
             }
-            vec![]
+            self.workspace().symbols.query_file(&uri).into_iter().map(StructureItem::from).collect()
+        }
+    }
+
+    /// Provides the breadcrumbs at an offset: the chain of document symbols enclosing it, outermost first.
+    ///
+    /// The default walks the [`document_symbols`](Self::document_symbols) of the file with
+    /// [`oak_structural_view::breadcrumbs`]. The returned items have no children.
+    fn breadcrumbs<'a>(&'a self, uri: &'a str, offset: usize) -> impl Future<Output = Vec<StructureItem>> + Send + 'a {
+        async move {
+            let items = self.document_symbols(uri).await;
+            oak_structural_view::breadcrumbs(&items, offset).into_iter().map(|item| StructureItem { children: Vec::new(), ..item.clone() }).collect()
         }
     }
 
     /// Provides workspace-wide symbol search based on a query string.
     ///
     /// This method searches across all files in the workspace for symbols
-    /// that match the given query string.
+    /// that fuzzily match the given query string, best first, through
+    /// [`search_symbols`](Self::search_symbols).
     fn workspace_symbols<'a>(&'a self, query: String) -> impl Future<Output = Vec<WorkspaceSymbol>> + Send + 'a {
-        async move { self.workspace().symbols.query(&query).into_iter().map(|s| WorkspaceSymbol::from(s)).collect() }
+        async move { self.search_symbols(SymbolQuery::new(query)).await.into_iter().map(|m| WorkspaceSymbol::from(m.symbol)).collect() }
+    }
+
+    /// Searches the symbols of the workspace, filtered by role and ranked by how well their names match.
+    ///
+    /// The default queries the fuzzy symbol index of the [workspace](Self::workspace).
+    fn search_symbols<'a>(&'a self, query: SymbolQuery) -> impl Future<Output = Vec<SymbolMatch>> + Send + 'a {
+        async move { self.workspace().symbols.search(&query) }
     }
 
     /// Recursively lists all files in the VFS starting from the given root URI.
@@ -147,16 +223,50 @@ pub trait LanguageService: Send + Sync {
         }
     }
 
+    /// Resolves an import written in a file to the URI of the module it names.
+    ///
+    /// The default tries the language specific strategies of [`ImportResolver::with_defaults`] on the
+    /// [VFS](Self::vfs), then the import path relative to every workspace folder.
+    fn resolve_import(&self, uri: &str, path: &str) -> Option<String> {
+        let roots = self.workspace().list_folders().into_iter().fold(PathStrategy::new(), |strategy, (folder, _)| strategy.with_root(folder));
+        ImportResolver::with_defaults(self.vfs()).with_strategy(roots).resolve(uri, path)
+    }
+
+    /// Builds the scope graph of a file, which resolves names local to the file.
+    ///
+    /// The default derives it from the universal roles of the syntax tree with [`UniversalScopeProvider`].
+    fn scope_graph<'a>(&'a self, uri: &'a str) -> impl Future<Output = Option<ScopeGraph>> + Send + 'a {
+        async move {
+            let source = self.get_source(uri)?;
+            self.with_root(uri, move |root| UniversalScopeProvider.scope_graph(&root, &source)).await
+        }
+    }
+
     /// Finds the definition(s) of a symbol at the specified range.
     ///
     /// This method attempts to resolve the symbol under the cursor to its
     /// original definition. It handles:
-    /// 1. Local symbol resolution (language-specific).
+    /// 1. Local name resolution through the [scope graph](Self::scope_graph) of the file,
+    ///    following imports to the definition they import.
     /// 2. Global symbol lookup via the workspace index.
     /// 3. Module/file import resolution.
     fn definition<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
         let uri = uri.to_string();
         async move {
+            // 1. Resolve local names, following imports to the imported definition
+            let mut graphs = ScopeGraphs::new(self);
+            if let Some(graph) = graphs.get(&uri).await
+                && let Some(binding) = graph.definition_at(range.start)
+            {
+                let binding = graph.binding(binding);
+                if let BindingKind::Import { path } = &binding.kind
+                    && let Some(target) = import_target(&mut graphs, &uri, path, &binding.name).await
+                {
+                    return vec![target];
+                }
+                return vec![LocationRange { uri: uri.into(), range: binding.range }];
+            }
+
             let root = match self.get_root(&uri).await {
                 Some(r) => r,
                 None => return vec![],
@@ -166,32 +276,25 @@ pub trait LanguageService: Send + Sync {
                 None => return vec![],
             };
 
-            // 1. Identify token at range
-            use oak_core::tree::RedTree;
-            let node = match root.child_at_offset(range.start) {
-                Some(RedTree::Node(n)) => n,
-                Some(RedTree::Leaf(l)) => return vec![LocationRange { uri: uri.clone().into(), range: l.span }],
-                None => root,
-            };
-
-            // 2. If it's a reference, try to resolve it
-            let role = node.green.kind.role();
-            if role.universal() == oak_core::language::UniversalElementRole::Reference {
-                let name = &source.get_text_in(node.span());
-
-                // Try local symbols first (not implemented here, should be done by lang-specific logic)
-
-                // Try global symbols
-                if let Some(sym) = self.workspace().symbols.lookup(name) {
-                    return vec![LocationRange { uri: sym.uri, range: sym.range }];
+            // 2. Identify the innermost reference or name at range
+            let mut node = root;
+            let name = loop {
+                match node.child_at_offset(range.start) {
+                    Some(RedTree::Node(n)) if n.green.kind.role().universal() == UniversalElementRole::Reference => break source.get_text_in(n.span()),
+                    Some(RedTree::Node(n)) => node = n,
+                    Some(RedTree::Leaf(l)) if l.kind.role().universal() == UniversalTokenRole::Name => break source.get_text_in(l.span),
+                    _ => return vec![],
                 }
+            };
 
-                // Try as a module import
-                if let Some(resolved_uri) = self.workspace().resolver.resolve(&uri, name) {
-                    return vec![LocationRange { uri: resolved_uri.into(), range: (0..0).into() }];
-                }
+            // 3. Try global symbols
+            if let Some(sym) = self.workspace().symbols.lookup(&name) {
+                return vec![LocationRange { uri: sym.uri, range: sym.range }];
+            }
 
-                // Try local symbols (TODO)
+            // 4. Try as a module import
+            if let Some(resolved_uri) = self.resolve_import(&uri, &name) {
+                return vec![LocationRange { uri: resolved_uri.into(), range: (0..0).into() }];
             }
 
             vec![]
@@ -204,8 +307,31 @@ pub trait LanguageService: Send + Sync {
     }
 
     /// Provides code actions for a specific range in a file.
-    fn code_action<'a>(&'a self, _uri: &'a str, _range: Range<usize>) -> impl Future<Output = Vec<CodeAction>> + Send + 'a {
-        async { vec![] }
+    ///
+    /// The default offers the [fixes](Diagnostic::fixes) of the [diagnostics](Self::diagnostics) touching the range as quick fixes.
+    fn code_action<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<CodeAction>> + Send + 'a {
+        async move {
+            let mut actions = Vec::new();
+            for diagnostic in self.diagnostics(uri).await {
+                if diagnostic.range.start > range.end || diagnostic.range.end < range.start {
+                    continue;
+                }
+                let linked = Diagnostic { fixes: vec![], ..diagnostic.clone() };
+                for (index, fix) in diagnostic.fixes.into_iter().enumerate() {
+                    let edits = fix.edits.into_iter().map(TextEdit::from).collect();
+                    actions.push(CodeAction {
+                        title: fix.title,
+                        kind: Some("quickfix".to_string()),
+                        diagnostics: Some(vec![linked.clone()]),
+                        edit: Some(WorkspaceEdit { changes: std::collections::HashMap::from([(uri.to_string(), edits)]) }),
+                        command: None,
+                        is_preferred: Some(index == 0 && fix.applicability == Applicability::MachineApplicable),
+                        disabled: None,
+                    })
+                }
+            }
+            actions
+        }
     }
 
     /// Provides formatting edits for a file.
@@ -219,13 +345,78 @@ pub trait LanguageService: Send + Sync {
     }
 
     /// Provides rename edits for a symbol at the specified range.
-    fn rename<'a>(&'a self, _uri: &'a str, _range: Range<usize>, _new_name: String) -> impl Future<Output = Option<WorkspaceEdit>> + Send + 'a {
-        async { None }
+    ///
+    /// The default renames all [references](Self::references) of the symbol.
+    fn rename<'a>(&'a self, uri: &'a str, range: Range<usize>, new_name: String) -> impl Future<Output = Option<WorkspaceEdit>> + Send + 'a {
+        async move {
+            let references = self.references(uri, range).await;
+            if references.is_empty() {
+                return None;
+            }
+            let mut changes = std::collections::HashMap::new();
+            for reference in references {
+                changes.entry(reference.uri.to_string()).or_insert_with(Vec::new).push(TextEdit { range: reference.range, new_text: new_name.clone() });
+            }
+            Some(WorkspaceEdit { changes })
+        }
     }
 
-    /// Provides semantic tokens for a file.
-    fn semantic_tokens<'a>(&'a self, _uri: &'a str) -> impl Future<Output = Option<SemanticTokens>> + Send + 'a {
-        async { None }
+    /// Returns the legend that the semantic token type indices and modifier bits refer to.
+    fn semantic_tokens_legend(&self) -> SemanticTokensLegend {
+        SemanticTokensLegend::standard()
+    }
+
+    /// Returns the LSP server capabilities of this service, without the position encoding.
+    ///
+    /// The default advertises every request the server dispatches, with the
+    /// [semantic tokens legend](Self::semantic_tokens_legend) of this service.
+    fn capabilities(&self) -> serde_json::Value {
+        let legend = self.semantic_tokens_legend();
+        serde_json::json!({
+            "textDocumentSync": { "openClose": true, "change": 2, "save": true },
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "typeDefinitionProvider": true,
+            "implementationProvider": true,
+            "referencesProvider": true,
+            "documentHighlightProvider": true,
+            "documentSymbolProvider": true,
+            "workspaceSymbolProvider": true,
+            "foldingRangeProvider": true,
+            "documentFormattingProvider": true,
+            "documentRangeFormattingProvider": true,
+            "renameProvider": true,
+            "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+            "completionProvider": {},
+            "codeLensProvider": {},
+            "documentLinkProvider": {},
+            "linkedEditingRangeProvider": true,
+            "callHierarchyProvider": true,
+            "typeHierarchyProvider": true,
+            "semanticTokensProvider": { "legend": { "tokenTypes": legend.token_types, "tokenModifiers": legend.token_modifiers }, "full": { "delta": true }, "range": true },
+        })
+    }
+
+    /// Provides semantic tokens for a file, with columns measured in the given position encoding.
+    ///
+    /// The default implementation classifies tokens by their universal roles with
+    /// [`UniversalSemanticTokensProvider`]. Result IDs and deltas are kept by the session
+    /// that asked, see [`LspServer`](crate::LspServer).
+    fn semantic_tokens<'a>(&'a self, uri: &'a str, encoding: PositionEncoding) -> impl Future<Output = Option<Vec<SemanticToken>>> + Send + 'a {
+        async move {
+            let source = self.get_source(uri)?;
+            let line_map = self.vfs().line_map(uri)?;
+            self.with_root(uri, |root| UniversalSemanticTokensProvider::with_encoding(encoding).semantic_tokens(&root, &source, &line_map)).await
+        }
+    }
+
+    /// Provides semantic tokens for the visible byte range of a file.
+    fn semantic_tokens_range<'a>(&'a self, uri: &'a str, range: Range<usize>, encoding: PositionEncoding) -> impl Future<Output = Option<Vec<SemanticToken>>> + Send + 'a {
+        async move {
+            let source = self.get_source(uri)?;
+            let line_map = self.vfs().line_map(uri)?;
+            self.with_root(uri, |root| UniversalSemanticTokensProvider::with_encoding(encoding).semantic_tokens_range(&root, &source, &line_map, range)).await
+        }
     }
 
     /// Provides inlay hints for a file.
@@ -243,14 +434,76 @@ pub trait LanguageService: Send + Sync {
         async { None }
     }
 
+    /// Returns the completion engine of the language.
+    ///
+    /// The default only offers the names in scope and the keywords found in the file; override it to
+    /// add the parser, a keyword table and snippets with the builders of [`UniversalCompletionProvider`].
+    fn completion_provider(&self) -> UniversalCompletionProvider<Self::Lang> {
+        UniversalCompletionProvider::new()
+    }
+
     /// Provides completion items for a file at the specified position.
-    fn completion<'a>(&'a self, _uri: &'a str, _offset: usize) -> impl Future<Output = Vec<CompletionItem>> + Send + 'a {
-        async { vec![] }
+    ///
+    /// The default asks the [`completion_provider`](Self::completion_provider), keeping its order
+    /// with the sort text.
+    fn completion<'a>(&'a self, uri: &'a str, offset: usize) -> impl Future<Output = Vec<CompletionItem>> + Send + 'a {
+        async move {
+            let Some(source) = self.get_source(uri)
+            else {
+                return vec![];
+            };
+            let provider = self.completion_provider();
+            let completions = self.with_root(uri, move |root| provider.completions(&root, &source, offset)).await.unwrap_or_default();
+            completions.into_iter().enumerate().map(|(index, completion)| CompletionItem { sort_text: Some(format!("{:05}", index)), ..completion.into() }).collect()
+        }
     }
 
     /// Finds all references to a symbol at the specified range.
-    fn references<'a>(&'a self, _uri: &'a str, _range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
-        async { vec![] }
+    ///
+    /// The default resolves the name through the [scope graph](Self::scope_graph) of the file,
+    /// following imports to the definition they import. A definition at the top level of its file
+    /// is also found through the imports of it in the other files known to the symbol index.
+    fn references<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<LocationRange>> + Send + 'a {
+        async move {
+            let mut graphs = ScopeGraphs::new(self);
+            let Some(graph) = graphs.get(uri).await
+            else {
+                return vec![];
+            };
+            let Some(binding) = graph.definition_at(range.start)
+            else {
+                return vec![];
+            };
+            let local = || graph.occurrences(binding, true).into_iter().map(|range| LocationRange { uri: uri.into(), range }).collect();
+            let (target_uri, target_graph, target) = match &graph.binding(binding).kind {
+                BindingKind::Import { path } => {
+                    let Some(target) = import_target(&mut graphs, uri, path, &graph.binding(binding).name).await
+                    else {
+                        return local();
+                    };
+                    let Some(target_graph) = graphs.get(&target.uri).await
+                    else {
+                        return local();
+                    };
+                    let Some(id) = target_graph.bindings().iter().position(|binding| binding.range == target.range)
+                    else {
+                        return local();
+                    };
+                    (target.uri.to_string(), target_graph, id)
+                }
+                _ => (uri.to_string(), graph.clone(), binding),
+            };
+
+            let mut references: Vec<LocationRange> = target_graph.occurrences(target, true).into_iter().map(|range| LocationRange { uri: target_uri.as_str().into(), range }).collect();
+            // Only names bound at the top level of a file can be imported.
+            if target_graph.binding(target).scope == target_graph.root() {
+                let name = &target_graph.binding(target).name;
+                for (file, occurrences) in importers(&mut graphs, &target_uri, &[name.as_str()], Some(uri)).await {
+                    references.extend(occurrences[0].iter().map(|range| LocationRange { uri: file.as_str().into(), range: *range }))
+                }
+            }
+            references
+        }
     }
 
     /// Finds the type definition of a symbol at the specified range.
@@ -263,6 +516,225 @@ pub trait LanguageService: Send + Sync {
         async { vec![] }
     }
 
+    /// Prepares a call hierarchy for the symbol at the specified range.
+    ///
+    /// The default implementation returns the innermost [`UniversalElementRole::Definition`]
+    /// node enclosing the range.
+    fn prepare_call_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<CallHierarchyItem>> + Send + 'a {
+        async move {
+            let Some(source) = self.get_source(uri)
+            else {
+                return vec![];
+            };
+            self.with_root(uri, |root| enclosing_definition(root, range.start).map(|def| call_hierarchy_item(uri, &def, &source)).into_iter().collect()).await.unwrap_or_default()
+        }
+    }
+
+    /// Finds all callers of the given call hierarchy item.
+    ///
+    /// The default implementation scans every file known to the symbol index for
+    /// [`UniversalElementRole::Call`] nodes whose callee name matches the item.
+    fn incoming_calls<'a>(&'a self, item: CallHierarchyItem) -> impl Future<Output = Vec<CallHierarchyIncomingCall>> + Send + 'a {
+        async move {
+            let mut files = self.workspace().symbols.files();
+            if !files.iter().any(|f| f.as_str() == &*item.uri) {
+                files.push(item.uri.to_string());
+            }
+
+            let mut incoming: Vec<CallHierarchyIncomingCall> = Vec::new();
+            for file in files {
+                let Some(source) = self.get_source(&file)
+                else {
+                    continue;
+                };
+                let found = self
+                    .with_root(&file, |root| {
+                        let mut calls = Vec::new();
+                        collect_calls(root, &source, None, true, &mut calls);
+                        calls
+                            .into_iter()
+                            .filter(|(_, name, _)| *name == item.name)
+                            .map(|(caller, _, range)| {
+                                let from = match caller {
+                                    Some(def) => call_hierarchy_item(&file, &def, &source),
+                                    None => CallHierarchyItem { name: file.clone(), kind: SymbolKind::File, detail: None, uri: file.as_str().into(), range: root.span(), selection_range: Range { start: 0, end: 0 } },
+                                };
+                                (from, range)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .await
+                    .unwrap_or_default();
+
+                for (from, range) in found {
+                    match incoming.iter_mut().find(|c| c.from.uri == from.uri && c.from.range == from.range) {
+                        Some(call) => call.from_ranges.push(range),
+                        None => incoming.push(CallHierarchyIncomingCall { from, from_ranges: vec![range] }),
+                    }
+                }
+            }
+            incoming
+        }
+    }
+
+    /// Finds all callees of the given call hierarchy item.
+    ///
+    /// The default implementation collects the [`UniversalElementRole::Call`] nodes inside the
+    /// item's [`UniversalElementRole::Definition`] node (excluding nested definitions) and resolves
+    /// each callee against the definitions of the same file, then the workspace symbol index.
+    fn outgoing_calls<'a>(&'a self, item: CallHierarchyItem) -> impl Future<Output = Vec<CallHierarchyOutgoingCall>> + Send + 'a {
+        async move {
+            let Some(source) = self.get_source(&item.uri)
+            else {
+                return vec![];
+            };
+            let uri = item.uri.clone();
+            let mut outgoing = self
+                .with_root(&uri, |root| {
+                    let Some(def) = definition_with_range(root, item.range)
+                    else {
+                        return vec![];
+                    };
+                    let mut calls = Vec::new();
+                    collect_calls(def, &source, Some(def), false, &mut calls);
+
+                    let mut local_definitions = Vec::new();
+                    collect_definitions(root, &mut local_definitions);
+
+                    let mut outgoing: Vec<(Option<CallHierarchyItem>, String, Vec<Range<usize>>)> = Vec::new();
+                    for (_, name, range) in calls {
+                        if let Some(entry) = outgoing.iter_mut().find(|(_, n, _)| *n == name) {
+                            entry.2.push(range);
+                            continue;
+                        }
+                        let target = local_definitions.iter().find(|d| definition_name(d, &source).as_deref() == Some(name.as_str())).map(|d| call_hierarchy_item(&uri, d, &source));
+                        outgoing.push((target, name, vec![range]))
+                    }
+                    outgoing
+                })
+                .await
+                .unwrap_or_default();
+
+            let mut result = Vec::new();
+            for (target, name, from_ranges) in outgoing.drain(..) {
+                let to = match target {
+                    Some(to) => to,
+                    None => match self.workspace().symbols.lookup(&name) {
+                        Some(sym) => CallHierarchyItem { name: sym.name, kind: SymbolKind::from(sym.role), detail: sym.container_name, uri: sym.uri, range: sym.range, selection_range: sym.range },
+                        None => continue,
+                    },
+                };
+                result.push(CallHierarchyOutgoingCall { to, from_ranges })
+            }
+            result
+        }
+    }
+
+    /// Prepares a type hierarchy for the symbol at the specified range.
+    ///
+    /// The default implementation returns the innermost [`UniversalElementRole::Definition`]
+    /// node enclosing the range.
+    fn prepare_type_hierarchy<'a>(&'a self, uri: &'a str, range: Range<usize>) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        async move {
+            let Some(source) = self.get_source(uri)
+            else {
+                return vec![];
+            };
+            self.with_root(uri, |root| {
+                enclosing_definition(root, range.start)
+                    .map(|def| {
+                        let item = call_hierarchy_item(uri, &def, &source);
+                        TypeHierarchyItem { name: item.name, kind: item.kind, detail: item.detail, uri: item.uri, range: item.range, selection_range: item.selection_range }
+                    })
+                    .into_iter()
+                    .collect()
+            })
+            .await
+            .unwrap_or_default()
+        }
+    }
+
+    /// Finds the supertypes of the given type hierarchy item.
+    ///
+    /// Inheritance is a semantic relationship that universal roles do not capture,
+    /// so the default implementation returns nothing.
+    fn supertypes<'a>(&'a self, _item: TypeHierarchyItem) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        async { vec![] }
+    }
+
+    /// Finds the subtypes of the given type hierarchy item.
+    ///
+    /// Inheritance is a semantic relationship that universal roles do not capture,
+    /// so the default implementation returns nothing.
+    fn subtypes<'a>(&'a self, _item: TypeHierarchyItem) -> impl Future<Output = Vec<TypeHierarchyItem>> + Send + 'a {
+        async { vec![] }
+    }
+
+    /// Provides code lenses for a file.
+    ///
+    /// The default implementation attaches a reference count lens to every symbol
+    /// of the file found in the workspace symbol index. Its `editor.action.showReferences`
+    /// arguments are the URI, the byte offset of the name and the [`LocationRange`]s of the
+    /// references, which the server sends as an LSP position and locations.
+    fn code_lens<'a>(&'a self, uri: &'a str) -> impl Future<Output = Vec<CodeLens>> + Send + 'a {
+        async move {
+            let symbols = self.workspace().symbols.query_file(uri);
+            if symbols.is_empty() {
+                return vec![];
+            }
+            let name_ranges: Vec<Range<usize>> = self.with_root(uri, |root| symbols.iter().filter_map(|sym| definition_with_range(root, sym.range).and_then(|def| definition_name_range(&def))).collect()).await.unwrap_or_default();
+
+            // The references of every symbol are counted at once, building each scope graph a single time.
+            let mut graphs = ScopeGraphs::new(self);
+            let graph = graphs.get(uri).await;
+            let bindings: Vec<Option<usize>> = name_ranges.iter().map(|range| graph.as_ref().and_then(|graph| graph.definition_at(range.start))).collect();
+            let mut references: Vec<Vec<LocationRange>> = vec![Vec::new(); bindings.len()];
+            if let Some(graph) = &graph {
+                for (references, binding) in references.iter_mut().zip(&bindings) {
+                    if let Some(binding) = binding {
+                        references.extend(graph.occurrences(*binding, true).into_iter().map(|range| LocationRange { uri: uri.into(), range }))
+                    }
+                }
+                // Only names bound at the top level of a file can be imported.
+                let exported: Vec<(usize, &str)> = bindings
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, binding)| {
+                        let binding = graph.binding((*binding)?);
+                        (binding.scope == graph.root()).then_some((i, binding.name.as_str()))
+                    })
+                    .collect();
+                let names: Vec<&str> = exported.iter().map(|(_, name)| *name).collect();
+                let importers = if names.is_empty() { vec![] } else { importers(&mut graphs, uri, &names, None).await };
+                for (file, occurrences) in importers {
+                    for ((i, _), ranges) in exported.iter().zip(occurrences) {
+                        references[*i].extend(ranges.into_iter().map(|range| LocationRange { uri: file.as_str().into(), range }))
+                    }
+                }
+            }
+
+            let mut lenses = Vec::new();
+            for (range, references) in name_ranges.into_iter().zip(references) {
+                let references: Vec<LocationRange> = references.into_iter().filter(|r| !(&*r.uri == uri && r.range == range)).collect();
+                let title = if references.len() == 1 { "1 reference".to_string() } else { format!("{} references", references.len()) };
+                let arguments = vec![serde_json::json!(uri), serde_json::json!(range.start), serde_json::to_value(&references).unwrap_or_default()];
+                lenses.push(CodeLens { range, command: Some(Command { title, command: "editor.action.showReferences".to_string(), arguments: Some(arguments) }), data: None })
+            }
+            lenses
+        }
+    }
+
+    /// Provides document links (e.g. import paths or URLs) for a file.
+    fn document_links<'a>(&'a self, _uri: &'a str) -> impl Future<Output = Vec<DocumentLink>> + Send + 'a {
+        async { vec![] }
+    }
+
+    /// Provides ranges that can be edited together with the symbol at the specified range,
+    /// such as matching opening and closing tags.
+    fn linked_editing_range<'a>(&'a self, _uri: &'a str, _range: Range<usize>) -> impl Future<Output = Option<LinkedEditingRanges>> + Send + 'a {
+        async { None }
+    }
+
     /// Handles an LSP initialize request.
     fn initialize<'a>(&'a self, _params: InitializeParams) -> impl Future<Output = ()> + Send + 'a {
         async {}
@@ -278,6 +750,11 @@ pub trait LanguageService: Send + Sync {
         async {}
     }
 
+    /// Called when a file is opened in the editor, with the `languageId` the client reported.
+    fn did_open<'a>(&'a self, _uri: &'a str, _language_id: &'a str) -> impl Future<Output = ()> + Send + 'a {
+        async {}
+    }
+
     /// Called when a file is saved in the editor.
     fn did_save<'a>(&'a self, _uri: &'a str) -> impl Future<Output = ()> + Send + 'a {
         async {}
@@ -293,3 +770,229 @@ pub trait LanguageService: Send + Sync {
         async { vec![] }
     }
 }
+
+/// Renders a syntax tree as indented text, one element per line.
+fn dump_tree<L: Language, S: Source + ?Sized>(root: RedNode<L>, source: &S, max_depth: usize) -> String {
+    fn walk<L: Language, S: Source + ?Sized>(node: RedNode<L>, source: &S, depth: usize, max_depth: usize, out: &mut String) {
+        let span = node.span();
+        let _ = writeln!(out, "{}{:?} [{:?}] {}..{}", "  ".repeat(depth), node.green.kind, node.green.kind.role().universal(), span.start, span.end);
+        if depth >= max_depth {
+            return;
+        }
+        for child in node.children() {
+            match child {
+                RedTree::Node(child) => walk(child, source, depth + 1, max_depth, out),
+                RedTree::Leaf(leaf) => {
+                    let _ = writeln!(out, "{}{:?} [{:?}] {}..{} {:?}", "  ".repeat(depth + 1), leaf.kind, leaf.kind.role().universal(), leaf.span.start, leaf.span.end, source.get_text_in(leaf.span));
+                }
+            }
+        }
+    }
+    let mut out = String::new();
+    walk(root, source, 0, max_depth, &mut out);
+    out
+}
+
+/// The scope graphs built while answering one request, so each file's graph is built at most once.
+struct ScopeGraphs<'s, S: LanguageService + ?Sized> {
+    service: &'s S,
+    graphs: HashMap<String, Option<Arc<ScopeGraph>>>,
+}
+
+impl<'s, S: LanguageService + ?Sized> ScopeGraphs<'s, S> {
+    fn new(service: &'s S) -> Self {
+        Self { service, graphs: HashMap::new() }
+    }
+
+    /// Returns the scope graph of a file, building it on first use.
+    async fn get(&mut self, uri: &str) -> Option<Arc<ScopeGraph>> {
+        if let Some(graph) = self.graphs.get(uri) {
+            return graph.clone();
+        }
+        let graph = self.service.scope_graph(uri).await.map(Arc::new);
+        self.graphs.insert(uri.to_string(), graph.clone());
+        graph
+    }
+}
+
+/// Finds the imports of the given top-level names of a module in the other files known to the
+/// symbol index, and `extra` if it is not known to it.
+///
+/// Returns the occurrences of the imports in each importing file, in the order of `names`.
+async fn importers<S: LanguageService + ?Sized>(graphs: &mut ScopeGraphs<'_, S>, module: &str, names: &[&str], extra: Option<&str>) -> Vec<(String, Vec<Vec<Range<usize>>>)> {
+    let service = graphs.service;
+    let mut files = service.workspace().symbols.files();
+    if let Some(extra) = extra
+        && !files.iter().any(|file| file == extra)
+    {
+        files.push(extra.to_string())
+    }
+    let mut importers = Vec::new();
+    for file in files.into_iter().filter(|file| file != module) {
+        let Some(graph) = graphs.get(&file).await
+        else {
+            continue;
+        };
+        let mut occurrences = vec![Vec::new(); names.len()];
+        let mut found = false;
+        for (id, import) in graph.bindings().iter().enumerate() {
+            if let BindingKind::Import { path } = &import.kind
+                && let Some(index) = names.iter().position(|name| *name == import.name)
+                && service.resolve_import(&file, path).as_deref() == Some(module)
+            {
+                occurrences[index].extend(graph.occurrences(id, true));
+                found = true
+            }
+        }
+        if found {
+            importers.push((file, occurrences))
+        }
+    }
+    importers
+}
+
+/// Finds the definition an import refers to: the binding of the imported name at the top level of
+/// the imported module, following re-exports, or the start of the module if it binds no such name.
+async fn import_target<S: LanguageService + ?Sized>(graphs: &mut ScopeGraphs<'_, S>, uri: &str, path: &str, name: &str) -> Option<LocationRange> {
+    let service = graphs.service;
+    let mut module = service.resolve_import(uri, path)?;
+    // Re-exports are followed a few times, which also stops import cycles.
+    for _ in 0..8 {
+        let Some(graph) = graphs.get(&module).await
+        else {
+            break;
+        };
+        let Some(binding) = graph.lookup(graph.root(), name, usize::MAX)
+        else {
+            break;
+        };
+        let binding = graph.binding(binding);
+        match &binding.kind {
+            BindingKind::Import { path } => match service.resolve_import(&module, path) {
+                Some(next) => module = next,
+                None => return Some(LocationRange { uri: module.into(), range: binding.range }),
+            },
+            _ => return Some(LocationRange { uri: module.into(), range: binding.range }),
+        }
+    }
+    Some(LocationRange { uri: module.into(), range: Range { start: 0, end: 0 } })
+}
+
+/// Finds the innermost [`UniversalElementRole::Definition`] node containing the offset.
+fn enclosing_definition<'a, L: Language>(root: RedNode<'a, L>, offset: usize) -> Option<RedNode<'a, L>> {
+    let mut current = root;
+    let mut found = None;
+    loop {
+        if current.green.kind.is_universal(UniversalElementRole::Definition) {
+            found = Some(current)
+        }
+        match current.child_at_offset(offset) {
+            Some(RedTree::Node(child)) => current = child,
+            _ => return found,
+        }
+    }
+}
+
+/// Finds the [`UniversalElementRole::Definition`] node spanning exactly the given range.
+fn definition_with_range<'a, L: Language>(root: RedNode<'a, L>, range: Range<usize>) -> Option<RedNode<'a, L>> {
+    let mut current = root;
+    loop {
+        if current.span() == range && current.green.kind.is_universal(UniversalElementRole::Definition) {
+            return Some(current);
+        }
+        match current.child_at_offset(range.start) {
+            Some(RedTree::Node(child)) if child.span().end >= range.end => current = child,
+            _ => return None,
+        }
+    }
+}
+
+/// Collects every [`UniversalElementRole::Definition`] node below `node`.
+fn collect_definitions<'a, L: Language>(node: RedNode<'a, L>, definitions: &mut Vec<RedNode<'a, L>>) {
+    for child in node.children() {
+        if let RedTree::Node(child) = child {
+            if child.green.kind.is_universal(UniversalElementRole::Definition) {
+                definitions.push(child)
+            }
+            collect_definitions(child, definitions)
+        }
+    }
+}
+
+/// Returns the range of the name of a definition node.
+///
+/// The name is the first direct `Name` token, or the first direct child node
+/// with the `Name` or `Binding` role.
+fn definition_name_range<L: Language>(node: &RedNode<L>) -> Option<Range<usize>> {
+    node.children().find_map(|child| match child {
+        RedTree::Leaf(leaf) if leaf.kind.is_universal(UniversalTokenRole::Name) => Some(leaf.span),
+        RedTree::Node(n) if matches!(n.green.kind.role().universal(), UniversalElementRole::Name | UniversalElementRole::Binding) => Some(n.span()),
+        _ => None,
+    })
+}
+
+/// Returns the name of a definition node.
+fn definition_name<L: Language, S: Source + ?Sized>(node: &RedNode<L>, source: &S) -> Option<String> {
+    definition_name_range(node).map(|range| source.get_text_in(range).trim().to_string())
+}
+
+/// Builds a [`CallHierarchyItem`] from a definition node.
+fn call_hierarchy_item<L: Language, S: Source + ?Sized>(uri: &str, node: &RedNode<L>, source: &S) -> CallHierarchyItem {
+    let selection_range = definition_name_range(node).unwrap_or_else(|| node.span());
+    let name = definition_name(node, source).unwrap_or_else(|| format!("<{:?}>", node.green.kind));
+    CallHierarchyItem { name, kind: SymbolKind::from(node.green.kind.role().universal()), detail: None, uri: uri.into(), range: node.span(), selection_range }
+}
+
+/// Returns the callee name of a [`UniversalElementRole::Call`] node and its range.
+///
+/// The callee is the last `Name` token before the opening parenthesis of the argument list
+/// (so `a.b(x)` calls `b`), or the first `Name` token if the call has no parentheses.
+fn callee<L: Language, S: Source + ?Sized>(call: RedNode<L>, source: &S) -> Option<(String, Range<usize>)> {
+    let mut first = None;
+    let mut last = None;
+    for element in oak_core::visitor::PreOrder::new(RedTree::Node(call)) {
+        let RedTree::Leaf(leaf) = element
+        else {
+            continue;
+        };
+        if leaf.kind.is_universal(UniversalTokenRole::Name) {
+            first.get_or_insert(leaf.span);
+            last = Some(leaf.span)
+        }
+        else if leaf.kind.is_universal(UniversalTokenRole::Punctuation) && source.get_text_in(leaf.span).as_ref() == "(" {
+            break;
+        }
+    }
+    let range = last.or(first)?;
+    Some((source.get_text_in(range).to_string(), range))
+}
+
+/// A call site: the enclosing definition (if any), the callee name and the callee range.
+type CallSite<'a, L> = (Option<RedNode<'a, L>>, String, Range<usize>);
+
+/// Collects the [`UniversalElementRole::Call`] nodes below `node` together with their
+/// innermost enclosing definition.
+///
+/// When `descend_definitions` is false, nested definitions are skipped.
+fn collect_calls<'a, L: Language, S: Source + ?Sized>(node: RedNode<'a, L>, source: &S, caller: Option<RedNode<'a, L>>, descend_definitions: bool, calls: &mut Vec<CallSite<'a, L>>) {
+    for child in node.children() {
+        let RedTree::Node(child) = child
+        else {
+            continue;
+        };
+        match child.green.kind.role().universal() {
+            UniversalElementRole::Definition => {
+                if descend_definitions {
+                    collect_calls(child, source, Some(child), descend_definitions, calls)
+                }
+            }
+            UniversalElementRole::Call => {
+                if let Some((name, range)) = callee(child, source) {
+                    calls.push((caller, name, range))
+                }
+                collect_calls(child, source, caller, descend_definitions, calls)
+            }
+            _ => collect_calls(child, source, caller, descend_definitions, calls),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    server::{LspError, LspServer},
+    service::LanguageService,
+};
+use axum::{
+    Router,
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    response::Response,
+    routing::get,
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+impl<S: LanguageService + 'static> LspServer<S> {
+    /// Runs the language server over a TCP connection, using `Content-Length` framing as on stdio.
+    pub async fn run_tcp(&self, stream: TcpStream) -> Result<(), LspError> {
+        let (read, write) = stream.into_split();
+        self.run(read, write).await
+    }
+
+    /// Runs the language server over a WebSocket.
+    ///
+    /// Every text or binary frame carries exactly one JSON-RPC message without a
+    /// `Content-Length` header, which is what browser-based clients such as Monaco send.
+    pub async fn run_websocket(&self, mut socket: WebSocket) -> Result<(), LspError> {
+        while let Some(frame) = socket.recv().await {
+            let text = match frame.map_err(|e| LspError::Other(e.to_string()))? {
+                Message::Text(text) => text.to_string(),
+                Message::Binary(bytes) => String::from_utf8(bytes.to_vec())?,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let message: Value = serde_json::from_str(&text)?;
+            let exit = message.get("method").and_then(Value::as_str) == Some("exit");
+            for outgoing in self.handle_message(message).await {
+                socket.send(Message::Text(serde_json::to_string(&outgoing)?.into())).await.map_err(|e| LspError::Other(e.to_string()))?
+            }
+            if exit {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accepts LSP clients on a TCP listener.
+///
+/// Each connection gets its own [`LspServer`], so sessions do not share initialization
+/// state, while all of them share the same language service and workspace.
+pub async fn serve_tcp<S: LanguageService + 'static>(listener: TcpListener, service: Arc<S>) -> Result<(), LspError> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = LspServer::new(service.clone());
+        tokio::spawn(async move { server.run_tcp(stream).await });
+    }
+}
+
+/// Creates a router that upgrades requests on `/` to LSP WebSocket sessions.
+///
+/// The router can be nested into a larger application, e.g. next to static editor assets.
+pub fn websocket_router<S: LanguageService + 'static>(service: Arc<S>) -> Router {
+    Router::new().route("/", get(upgrade::<S>)).with_state(service)
+}
+
+/// Accepts LSP clients over WebSocket on the given listener.
+pub async fn serve_websocket<S: LanguageService + 'static>(listener: TcpListener, service: Arc<S>) -> Result<(), LspError> {
+    axum::serve(listener, websocket_router(service)).await?;
+    Ok(())
+}
+
+async fn upgrade<S: LanguageService + 'static>(State(service): State<Arc<S>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| async move {
+        let _ = LspServer::new(service).run_websocket(socket).await;
+    })
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 
 pub use core::range::Range;
 pub use oak_folding::{FoldingRange, FoldingRangeKind};
+pub use oak_vfs::PositionEncoding;
+pub use oak_semantic_tokens::{SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit, SemanticTokensLegend};
 
 /// Represents a position in a source file (line and character).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -115,8 +117,9 @@ pub enum DocumentHighlightKind {
 /// Represents a document highlight.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DocumentHighlight {
-    /// The range this highlight applies to.
-    pub range: LspRange,
+    /// The byte range this highlight applies to.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
     /// The highlight kind, default is DocumentHighlightKind.Text.
     pub kind: Option<DocumentHighlightKind>,
 }
@@ -308,6 +311,21 @@ pub struct CompletionItem {
     pub documentation: Option<String>,
     /// The text that should be inserted when selecting this completion item.
     pub insert_text: Option<String>,
+    /// Whether the inserted text is plain text or a snippet.
+    pub insert_text_format: Option<InsertTextFormat>,
+    /// The edit applied when selecting this completion item, replacing the word being typed.
+    pub text_edit: Option<TextEdit>,
+    /// The string used to sort this item among the others, which clients compare instead of the label.
+    pub sort_text: Option<String>,
+}
+
+/// How the inserted text of a completion item is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InsertTextFormat {
+    /// The text is inserted as is.
+    PlainText = 1,
+    /// The text is a snippet with tab stops like `$1` and placeholders like `${1:name}`.
+    Snippet = 2,
 }
 
 /// Represents a completion item kind.
@@ -379,6 +397,9 @@ pub struct Diagnostic {
     pub source: Option<String>,
     /// The diagnostic's message.
     pub message: String,
+    /// The suggested fixes, offered as quick fix [code actions](CodeAction).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<oak_diagnostic::Fix>,
 }
 
 /// Represents a diagnostic severity.
@@ -394,21 +415,6 @@ pub enum DiagnosticSeverity {
     Hint = 4,
 }
 
-/// Represents a semantic token.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SemanticToken {
-    /// The line delta relative to the previous token.
-    pub delta_line: u32,
-    /// The start character delta relative to the previous token.
-    pub delta_start: u32,
-    /// The length of the token.
-    pub length: u32,
-    /// The token type index.
-    pub token_type: u32,
-    /// The token modifiers bitset.
-    pub token_modifiers_bitset: u32,
-}
-
 /// Represents semantic tokens.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SemanticTokens {
@@ -418,6 +424,27 @@ pub struct SemanticTokens {
     pub data: Vec<SemanticToken>,
 }
 
+/// Represents the edits to a previously reported set of semantic tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticTokensDelta {
+    /// The result ID of the new token set.
+    pub result_id: Option<String>,
+    /// The edits to transform the previous token set into the new one.
+    pub edits: Vec<SemanticTokensEdit>,
+}
+
+/// The result of a `semanticTokens/full/delta` request.
+///
+/// A full token set is returned when the previous result is no longer known.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SemanticTokensDeltaResult {
+    /// The full set of semantic tokens.
+    Tokens(SemanticTokens),
+    /// The edits relative to the previous result.
+    Delta(SemanticTokensDelta),
+}
+
 /// Represents a selection range.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SelectionRange {
@@ -524,6 +551,102 @@ pub struct Command {
     pub arguments: Option<Vec<serde_json::Value>>,
 }
 
+/// Represents a programming construct (e.g. a function) in the context of call hierarchy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyItem {
+    /// The name of this item.
+    pub name: String,
+    /// The kind of this item.
+    pub kind: SymbolKind,
+    /// More detail for this item, e.g. the signature of a function.
+    pub detail: Option<String>,
+    /// The URI of the resource containing this item.
+    #[serde(with = "oak_core::serde_arc_str")]
+    pub uri: Arc<str>,
+    /// The range enclosing this symbol, including its body.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// The range that should be selected and revealed when this item is picked, e.g. the name of a function.
+    #[serde(with = "oak_core::serde_range")]
+    pub selection_range: Range<usize>,
+}
+
+/// Represents an incoming call, e.g. a caller of a method or constructor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyIncomingCall {
+    /// The item that makes the call.
+    pub from: CallHierarchyItem,
+    /// The ranges at which the calls appear, relative to the caller denoted by `from`.
+    #[serde(with = "oak_core::serde_range::vec")]
+    pub from_ranges: Vec<Range<usize>>,
+}
+
+/// Represents an outgoing call, e.g. calling a getter from a method or a method from a constructor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallHierarchyOutgoingCall {
+    /// The item that is called.
+    pub to: CallHierarchyItem,
+    /// The ranges at which this item is called, relative to the caller.
+    #[serde(with = "oak_core::serde_range::vec")]
+    pub from_ranges: Vec<Range<usize>>,
+}
+
+/// Represents a type (e.g. a class or an interface) in the context of type hierarchy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeHierarchyItem {
+    /// The name of this item.
+    pub name: String,
+    /// The kind of this item.
+    pub kind: SymbolKind,
+    /// More detail for this item, e.g. the signature of a function.
+    pub detail: Option<String>,
+    /// The URI of the resource containing this item.
+    #[serde(with = "oak_core::serde_arc_str")]
+    pub uri: Arc<str>,
+    /// The range enclosing this symbol, including its body.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// The range that should be selected and revealed when this item is picked, e.g. the name of a class.
+    #[serde(with = "oak_core::serde_range")]
+    pub selection_range: Range<usize>,
+}
+
+/// Represents a command that should be shown along with source text, like the number of references.
+///
+/// A code lens is _unresolved_ when no command is associated to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeLens {
+    /// The range in which this code lens is valid. Should only span a single line.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// The command this code lens represents.
+    pub command: Option<Command>,
+    /// A data entry field that is preserved between a code lens and a code lens resolve request.
+    pub data: Option<serde_json::Value>,
+}
+
+/// Represents a link inside a document, e.g. an import path or a URL in a comment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentLink {
+    /// The range this link applies to.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// The URI this link points to. If missing, a resolve request is sent later.
+    pub target: Option<String>,
+    /// The tooltip text when hovering over this link.
+    pub tooltip: Option<String>,
+}
+
+/// Represents a list of ranges that have the same content and can be edited simultaneously.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedEditingRanges {
+    /// The ranges that can be edited together. They must all have the same length and text content.
+    #[serde(with = "oak_core::serde_range::vec")]
+    pub ranges: Vec<Range<usize>>,
+    /// An optional word pattern (regular expression) that describes valid contents for the given ranges.
+    pub word_pattern: Option<String>,
+}
+
 impl From<UniversalElementRole> for SymbolKind {
     fn from(role: UniversalElementRole) -> Self {
         match role {
@@ -557,3 +680,98 @@ impl From<oak_symbols::SymbolInformation> for StructureItem {
         Self { name: s.name, detail: None, role: s.role, kind: SymbolKind::from(s.role), range: s.range.clone(), selection_range: s.range.clone(), deprecated: false, children: vec![] }
     }
 }
+
+impl From<oak_structural_view::StructureKind> for SymbolKind {
+    fn from(kind: oak_structural_view::StructureKind) -> Self {
+        use oak_structural_view::StructureKind;
+        match kind {
+            StructureKind::Module => SymbolKind::Module,
+            StructureKind::Class => SymbolKind::Class,
+            StructureKind::Struct => SymbolKind::Struct,
+            StructureKind::Enum => SymbolKind::Enum,
+            StructureKind::Interface => SymbolKind::Interface,
+            StructureKind::Function => SymbolKind::Function,
+            StructureKind::Method => SymbolKind::Method,
+            StructureKind::Constructor => SymbolKind::Constructor,
+            StructureKind::Field => SymbolKind::Field,
+            StructureKind::Constant => SymbolKind::Constant,
+            StructureKind::Variable => SymbolKind::Variable,
+        }
+    }
+}
+
+impl oak_structural_view::OutlineItem for StructureItem {
+    fn range(&self) -> Range<usize> {
+        self.range
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.children
+    }
+}
+
+impl From<oak_structural_view::StructureItem> for StructureItem {
+    fn from(item: oak_structural_view::StructureItem) -> Self {
+        Self {
+            name: item.name,
+            detail: item.detail,
+            role: item.role,
+            kind: SymbolKind::from(item.kind),
+            range: item.range,
+            selection_range: item.selection_range,
+            deprecated: item.deprecated,
+            children: item.children.into_iter().map(StructureItem::from).collect(),
+        }
+    }
+}
+
+impl From<oak_completion::CompletionKind> for CompletionItemKind {
+    fn from(kind: oak_completion::CompletionKind) -> Self {
+        use oak_completion::CompletionKind;
+        match kind {
+            CompletionKind::Keyword => CompletionItemKind::Keyword,
+            CompletionKind::Snippet => CompletionItemKind::Snippet,
+            CompletionKind::Function => CompletionItemKind::Function,
+            CompletionKind::Type => CompletionItemKind::Class,
+            CompletionKind::Module => CompletionItemKind::Module,
+            CompletionKind::Variable => CompletionItemKind::Variable,
+            CompletionKind::Field => CompletionItemKind::Field,
+            CompletionKind::Constant => CompletionItemKind::Constant,
+        }
+    }
+}
+
+impl From<oak_completion::Completion> for CompletionItem {
+    fn from(completion: oak_completion::Completion) -> Self {
+        let snippet = completion.kind == oak_completion::CompletionKind::Snippet;
+        let new_text = completion.insert_text.clone().unwrap_or_else(|| completion.label.clone());
+        Self {
+            kind: Some(completion.kind.into()),
+            detail: completion.detail,
+            documentation: None,
+            insert_text: completion.insert_text,
+            insert_text_format: Some(if snippet { InsertTextFormat::Snippet } else { InsertTextFormat::PlainText }),
+            text_edit: Some(TextEdit { range: completion.range, new_text }),
+            sort_text: None,
+            label: completion.label,
+        }
+    }
+}
+
+impl From<oak_diagnostic::Diagnostic> for Diagnostic {
+    fn from(diagnostic: oak_diagnostic::Diagnostic) -> Self {
+        let severity = match diagnostic.severity {
+            oak_diagnostic::Severity::Error => DiagnosticSeverity::Error,
+            oak_diagnostic::Severity::Warning => DiagnosticSeverity::Warning,
+            oak_diagnostic::Severity::Advice => DiagnosticSeverity::Information,
+        };
+        let range = diagnostic.labels.first().map(|label| label.span).unwrap_or(Range { start: 0, end: 0 });
+        Self { range, severity: Some(severity), code: diagnostic.code, source: Some("oak".to_string()), message: diagnostic.message, fixes: diagnostic.fixes }
+    }
+}
+
+impl From<oak_core::source::TextEdit> for TextEdit {
+    fn from(edit: oak_core::source::TextEdit) -> Self {
+        Self { range: edit.span, new_text: edit.text.into_owned() }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::types::InitializeParams;
-use dashmap::DashMap;
+use dashmap::{DashMap, mapref::entry::Entry};
 use oak_resolver::{GlobalSymbolTable, StandardResolver};
 use std::path::PathBuf;
 use url::Url;
@@ -12,12 +12,14 @@ pub struct WorkspaceManager {
     pub symbols: GlobalSymbolTable,
     /// The resolver for the workspace.
     pub resolver: StandardResolver,
+    /// The number of sessions that have each document open.
+    open_documents: DashMap<String, usize>,
 }
 
 impl WorkspaceManager {
     /// Creates a new workspace manager.
     pub fn new() -> Self {
-        Self { folders: DashMap::new(), symbols: GlobalSymbolTable::new(), resolver: StandardResolver::new(Vec::new()) }
+        Self { folders: DashMap::new(), symbols: GlobalSymbolTable::new(), resolver: StandardResolver::new(Vec::new()), open_documents: DashMap::new() }
     }
 
     /// Initialize the workspace manager with parameters from the client.
@@ -44,6 +46,26 @@ impl WorkspaceManager {
         self.resolver.set_root_dirs(root_dirs);
     }
 
+    /// Records that a session opened a document.
+    pub fn open_document(&self, uri: &str) {
+        *self.open_documents.entry(uri.to_string()).or_default() += 1
+    }
+
+    /// Records that a session closed a document, and returns whether no session has it open anymore.
+    pub fn close_document(&self, uri: &str) -> bool {
+        match self.open_documents.entry(uri.to_string()) {
+            Entry::Occupied(mut entry) if *entry.get() > 1 => {
+                *entry.get_mut() -= 1;
+                false
+            }
+            Entry::Occupied(entry) => {
+                entry.remove();
+                true
+            }
+            Entry::Vacant(_) => true,
+        }
+    }
+
     /// Add a workspace folder.
     pub fn add_folder(&self, uri: String, _name: String) {
         if let Ok(url) = Url::parse(&uri) {
@@ -0,0 +1,448 @@
+#![feature(new_range_api)]
+
+use core::range::Range;
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    tree::{GreenNode, RedNode},
+};
+use oak_lsp::{CallHierarchyItem, DocumentHighlight, DocumentLink, LanguageService, LinkedEditingRanges, LocationRange, LspServer, MemoryVfs, MultiLanguageService, SymbolKind, Vfs, WorkspaceManager};
+use oak_resolver::{ScopeGraph, ScopeProvider, UniversalScopeProvider};
+use oak_symbols::SymbolInformation;
+use oak_testing::building::{
+    Spec::{Leaf, Node},
+    Universal, kw, name, punct, ws,
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A service over hand-written trees, with hand-written scope graphs for the files that need imports.
+#[derive(Default)]
+struct TestService {
+    vfs: MemoryVfs,
+    workspace: WorkspaceManager,
+    roots: HashMap<String, &'static GreenNode<'static, Universal>>,
+    graphs: HashMap<String, ScopeGraph>,
+    links: HashMap<String, Vec<DocumentLink>>,
+    /// The number of scope graphs built so far.
+    builds: AtomicUsize,
+}
+
+impl TestService {
+    fn with_file(mut self, uri: &str, root: &'static GreenNode<'static, Universal>) -> Self {
+        self.workspace.symbols.update_file_symbols(uri.to_string(), vec![]);
+        self.roots.insert(uri.to_string(), root);
+        self
+    }
+
+    fn with_link(mut self, uri: &str, link: DocumentLink) -> Self {
+        self.links.entry(uri.to_string()).or_default().push(link);
+        self
+    }
+
+    fn with_graph(mut self, uri: &str, graph: ScopeGraph) -> Self {
+        self.workspace.symbols.update_file_symbols(uri.to_string(), vec![]);
+        self.graphs.insert(uri.to_string(), graph);
+        self
+    }
+}
+
+impl LanguageService for TestService {
+    type Lang = Universal;
+    type Vfs = MemoryVfs;
+
+    fn vfs(&self) -> &MemoryVfs {
+        &self.vfs
+    }
+
+    fn workspace(&self) -> &WorkspaceManager {
+        &self.workspace
+    }
+
+    fn get_root(&self, uri: &str) -> impl Future<Output = Option<RedNode<'_, Universal>>> + Send + '_ {
+        let root = self.roots.get(uri).copied();
+        async move { root.map(|green| RedNode::new(green, 0)) }
+    }
+
+    async fn scope_graph(&self, uri: &str) -> Option<ScopeGraph> {
+        self.builds.fetch_add(1, Ordering::SeqCst);
+        if let Some(graph) = self.graphs.get(uri) {
+            return Some(graph.clone());
+        }
+        let source = self.get_source(uri)?;
+        self.with_root(uri, move |root| UniversalScopeProvider.scope_graph(&root, &source)).await
+    }
+
+    async fn document_highlight(&self, uri: &str, range: Range<usize>) -> Vec<DocumentHighlight> {
+        self.references(uri, range).await.into_iter().filter(|l| &*l.uri == uri).map(|l| DocumentHighlight { range: l.range, kind: None }).collect()
+    }
+
+    async fn document_links(&self, uri: &str) -> Vec<DocumentLink> {
+        self.links.get(uri).cloned().unwrap_or_default()
+    }
+
+    async fn linked_editing_range(&self, uri: &str, range: Range<usize>) -> Option<LinkedEditingRanges> {
+        let ranges: Vec<_> = self.references(uri, range).await.into_iter().map(|l| l.range).collect();
+        (!ranges.is_empty()).then_some(LinkedEditingRanges { ranges, word_pattern: None })
+    }
+}
+
+/// A service that only advertises the given capabilities.
+#[derive(Default)]
+struct CapableService {
+    vfs: MemoryVfs,
+    workspace: WorkspaceManager,
+    capabilities: Value,
+}
+
+impl LanguageService for CapableService {
+    type Lang = Universal;
+    type Vfs = MemoryVfs;
+
+    fn vfs(&self) -> &MemoryVfs {
+        &self.vfs
+    }
+
+    fn workspace(&self) -> &WorkspaceManager {
+        &self.workspace
+    }
+
+    fn capabilities(&self) -> Value {
+        self.capabilities.clone()
+    }
+}
+
+fn locations(locations: Vec<LocationRange>) -> Vec<(String, usize, usize)> {
+    let mut locations: Vec<_> = locations.into_iter().map(|l| (l.uri.rsplit('/').next().unwrap_or_default().to_string(), l.range.start, l.range.end)).collect();
+    locations.sort();
+    locations
+}
+
+#[tokio::test]
+async fn test_cross_file_references() {
+    let uri = |name: &str| format!("file:///project/{}", name);
+    let (lib, main, other, reexport) = (uri("lib.x"), uri("main.x"), uri("other.x"), uri("reexport.x"));
+
+    // lib.x: `fn parse(){}; parse` and a local `fn helper(){let parse}`
+    let mut graph = ScopeGraph::new((0..60).into());
+    let root = graph.root();
+    graph.add_item(root, "parse", (3..8).into(), (0..12).into());
+    graph.add_reference(root, "parse", (14..19).into());
+    let body = graph.add_scope(root, (30..60).into());
+    graph.add_local(body, "parse", (40..45).into(), 45);
+    // main.x: `use lib.x::parse; parse`
+    let mut main_graph = ScopeGraph::new((0..30).into());
+    main_graph.add_import(main_graph.root(), "parse", (11..16).into(), "lib.x");
+    main_graph.add_reference(main_graph.root(), "parse", (18..23).into());
+    main_graph.add_import(main_graph.root(), "lib", (24..27).into(), "lib.x");
+    // other.x: `use reexport.x::parse; parse`, where reexport.x: `use lib.x::parse`
+    let mut other_graph = ScopeGraph::new((0..40).into());
+    other_graph.add_import(other_graph.root(), "parse", (16..21).into(), "reexport.x");
+    other_graph.add_reference(other_graph.root(), "parse", (23..28).into());
+    let mut reexport_graph = ScopeGraph::new((0..20).into());
+    reexport_graph.add_import(reexport_graph.root(), "parse", (11..16).into(), "lib.x");
+
+    let service = TestService::default().with_graph(&lib, graph).with_graph(&main, main_graph).with_graph(&other, other_graph).with_graph(&reexport, reexport_graph);
+    // Imports are resolved on the VFS of the service, not on the disk.
+    for file in [&lib, &main, &other, &reexport] {
+        service.vfs().write_file(file, "")
+    }
+
+    // Imports lead to the definition they import, through re-exports, or to the module they name.
+    for (file, offset) in [(&main, 12), (&main, 20), (&other, 25)] {
+        assert_eq!(locations(service.definition(file, Range { start: offset, end: offset }).await), [("lib.x".to_string(), 3, 8)], "{file}:{offset}");
+    }
+    assert_eq!(locations(service.definition(&main, Range { start: 25, end: 25 }).await), [("lib.x".to_string(), 0, 0)]);
+
+    // A top-level definition is found through the imports of it, from the definition or any use.
+    let expected = [("lib.x", 3, 8), ("lib.x", 14, 19), ("main.x", 11, 16), ("main.x", 18, 23), ("reexport.x", 11, 16)].map(|(file, start, end)| (file.to_string(), start, end));
+    service.builds.store(0, Ordering::SeqCst);
+    assert_eq!(locations(service.references(&lib, Range { start: 4, end: 4 }).await), expected);
+    // Every file's scope graph is built once per request.
+    assert_eq!(service.builds.load(Ordering::SeqCst), 4);
+    assert_eq!(locations(service.references(&main, Range { start: 20, end: 20 }).await), expected);
+    // A local is only found in its file.
+    assert_eq!(locations(service.references(&lib, Range { start: 41, end: 41 }).await), [("lib.x".to_string(), 40, 45)]);
+}
+
+#[tokio::test]
+async fn test_hierarchy_and_code_lens() {
+    let uri = "file:///calls.x";
+    let call = |callee: &'static str| Node(E::Call, vec![name(callee), punct("("), punct(")")]);
+    let a = Node(E::Definition, vec![kw("fn"), ws(" "), name("a"), punct("("), punct(")"), ws(" "), punct("{"), ws(" "), call("b"), ws(" "), punct("}")]);
+    let b = Node(E::Definition, vec![kw("fn"), ws(" "), name("b"), punct("("), punct(")"), ws(" "), punct("{"), punct("}")]);
+    let (text, root) = Node(E::Root, vec![a, ws("\n"), b, ws("\n"), call("b")]).build();
+    assert_eq!(text, "fn a() { b() }\nfn b() {}\nb()");
+    let mut graph = ScopeGraph::new((0..28).into());
+    let scope = graph.root();
+    graph.add_item(scope, "a", (3..4).into(), (0..14).into());
+    graph.add_item(scope, "b", (18..19).into(), (15..24).into());
+    graph.add_reference(scope, "b", (9..10).into());
+    graph.add_reference(scope, "b", (25..26).into());
+    let service = Arc::new(TestService::default().with_file(uri, root).with_graph(uri, graph));
+    service.vfs().write_file(uri, text.as_str());
+    let symbol = |name: &str, start: usize, end: usize| SymbolInformation { name: name.to_string(), role: E::Definition, uri: uri.into(), range: Range { start, end }, container_name: None };
+    service.workspace().symbols.update_file_symbols(uri.to_string(), vec![symbol("a", 0, 14), symbol("b", 15, 24)]);
+    let item = |item: &CallHierarchyItem| (item.name.clone(), item.kind, item.range.start, item.range.end, item.selection_range.start, item.selection_range.end);
+
+    // The enclosing definition is the item, and its calls are resolved in the file.
+    let items = service.prepare_call_hierarchy(uri, Range { start: 10, end: 10 }).await;
+    assert_eq!(items.iter().map(item).collect::<Vec<_>>(), [("a".to_string(), SymbolKind::Function, 0, 14, 3, 4)]);
+    let outgoing = service.outgoing_calls(items[0].clone()).await;
+    assert_eq!(outgoing.iter().map(|call| (item(&call.to), call.from_ranges.clone())).collect::<Vec<_>>(), [(("b".to_string(), SymbolKind::Function, 15, 24, 18, 19), vec![Range { start: 9, end: 10 }])]);
+    // Calls outside of definitions come from the file.
+    let incoming = service.incoming_calls(outgoing[0].to.clone()).await;
+    let expected = [(("a".to_string(), SymbolKind::Function, 0, 14, 3, 4), vec![Range { start: 9, end: 10 }]), ((uri.to_string(), SymbolKind::File, 0, 28, 0, 0), vec![Range { start: 25, end: 26 }])];
+    assert_eq!(incoming.iter().map(|call| (item(&call.from), call.from_ranges.clone())).collect::<Vec<_>>(), expected);
+    let types = service.prepare_type_hierarchy(uri, Range { start: 20, end: 20 }).await;
+    assert_eq!(types.iter().map(|t| (t.name.as_str(), t.range.start, t.range.end)).collect::<Vec<_>>(), [("b", 15, 24)]);
+    assert!(service.supertypes(types[0].clone()).await.is_empty() && service.subtypes(types[0].clone()).await.is_empty());
+
+    // Every indexed symbol gets a lens counting the references other than its name.
+    service.builds.store(0, Ordering::SeqCst);
+    let lenses = service.code_lens(uri).await;
+    assert_eq!(service.builds.load(Ordering::SeqCst), 1);
+    let titles: Vec<_> = lenses.iter().map(|lens| (lens.range.start, lens.range.end, lens.command.as_ref().map(|c| c.title.clone()).unwrap_or_default())).collect();
+    assert_eq!(titles, [(3, 4, "0 references".to_string()), (18, 19, "2 references".to_string())]);
+
+    // The server passes a position and locations to `editor.action.showReferences`.
+    let server = LspServer::new(service.clone());
+    let input = frame(&[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/codeLens", "params": { "textDocument": { "uri": uri } } }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]);
+    let (mut client, server_input) = tokio::io::duplex(1 << 16);
+    let (server_output, mut client_output) = tokio::io::duplex(1 << 16);
+    client.write_all(&input).await.unwrap();
+    server.run(server_input, server_output).await.unwrap();
+    let mut output = Vec::new();
+    client_output.read_to_end(&mut output).await.unwrap();
+    let messages = unframe(&output);
+    let lenses = &messages.iter().find(|m| m["id"] == 2).unwrap()["result"];
+    let position = |line: u32, character: u32| json!({ "line": line, "character": character });
+    let location = |line: u32, start: u32, end: u32| json!({ "uri": uri, "range": { "start": position(line, start), "end": position(line, end) } });
+    assert_eq!(lenses[1]["command"]["command"], "editor.action.showReferences");
+    assert_eq!(lenses[1]["command"]["arguments"], json!([uri, position(1, 3), [location(0, 9, 10), location(2, 0, 1)]]));
+}
+
+#[tokio::test]
+async fn test_multi_language_routing() {
+    let link = |target: &str| DocumentLink { range: Range { start: 0, end: 1 }, target: Some(target.to_string()), tooltip: None };
+    let (a, b, c) = ("file:///src/main.a", "file:///src/main.b.a", "file:///src/main.c");
+    let service =
+        MultiLanguageService::new(MemoryVfs::new()).with_language("a", &["a"], TestService::default().with_link(a, link("a")).with_link(b, link("a"))).with_language("b", &[".B.A"], TestService::default().with_link(a, link("b")).with_link(b, link("b")));
+    let targets = async |uri: &str| service.document_links(uri).await.into_iter().filter_map(|link| link.target).collect::<Vec<_>>();
+
+    // Files go to the language with the longest matching extension, and others to none.
+    assert_eq!((service.language_id(a), targets(a).await), ("a", vec!["a".to_string()]));
+    assert_eq!((service.language_id(b), targets(b).await), ("b", vec!["b".to_string()]));
+    assert_eq!((service.language_id(c), targets(c).await), ("plaintext", vec![]));
+    assert!(service.hover(c, Range { start: 0, end: 0 }).await.is_none());
+}
+
+#[test]
+fn test_multi_language_capabilities() {
+    let legend = |types: &[&str]| json!({ "legend": { "tokenTypes": types, "tokenModifiers": [] }, "full": true });
+    let first = CapableService { capabilities: json!({ "hoverProvider": true, "codeActionProvider": true, "semanticTokensProvider": legend(&["keyword", "string"]) }), ..CapableService::default() };
+    let second = CapableService { capabilities: json!({ "renameProvider": true, "codeActionProvider": { "codeActionKinds": ["refactor"] } }), ..CapableService::default() };
+    let third = CapableService { capabilities: json!({ "hoverProvider": false, "codeActionProvider": { "codeActionKinds": ["quickfix", "refactor"] } }), ..CapableService::default() };
+    let service = MultiLanguageService::new(MemoryVfs::new()).with_language("first", &["1"], first).with_language("second", &["2"], second).with_language("third", &["3"], third);
+
+    // What any language offers is advertised, with the combined legend.
+    let legend = service.semantic_tokens_legend();
+    let expected = json!({
+        "hoverProvider": true,
+        "renameProvider": true,
+        "codeActionProvider": { "codeActionKinds": ["refactor", "quickfix"] },
+        "semanticTokensProvider": { "legend": { "tokenTypes": legend.token_types, "tokenModifiers": legend.token_modifiers }, "full": true },
+    });
+    assert_eq!(service.capabilities(), expected);
+    // Without languages, nothing is advertised.
+    assert_eq!(MultiLanguageService::new(MemoryVfs::new()).capabilities(), json!({}));
+}
+
+/// Frames messages with `Content-Length` headers.
+fn frame(messages: &[Value]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for message in messages {
+        let body = serde_json::to_vec(message).unwrap();
+        bytes.extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+        bytes.extend_from_slice(&body)
+    }
+    bytes
+}
+
+/// Splits `Content-Length` framed messages.
+fn unframe(mut bytes: &[u8]) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while let Some(end) = bytes.windows(4).position(|window| window == b"\r\n\r\n") {
+        let header = std::str::from_utf8(&bytes[..end]).unwrap();
+        let length: usize = header.strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+        messages.push(serde_json::from_slice(&bytes[end + 4..end + 4 + length]).unwrap());
+        bytes = &bytes[end + 4 + length..]
+    }
+    messages
+}
+
+#[tokio::test]
+async fn test_json_rpc_round_trip() {
+    let uri = "file:///main.x";
+    let text = "let \u{e9} = 1;\n\u{e9}";
+    let statement = Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("\u{e9}")]), ws(" "), punct("="), ws(" "), Leaf(T::Literal, "1"), punct(";")]);
+    let (tree_text, root) = Node(E::Root, vec![statement, ws("\n"), name("\u{e9}")]).build();
+    assert_eq!(tree_text, text);
+    let link = DocumentLink { range: Range { start: 9, end: 10 }, target: Some("file:///one".to_string()), tooltip: None };
+    let service = Arc::new(TestService::default().with_file(uri, root).with_link(uri, link));
+    let server = LspServer::new(service.clone());
+
+    let position = |line: u32, character: u32| json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+    let input = frame(&[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": { "general": { "positionEncodings": ["utf-16", "utf-8"] } } } }),
+        json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": { "textDocument": { "uri": uri, "languageId": "universal", "version": 1, "text": text } } }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/documentHighlight", "params": position(1, 0) }),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "textDocument/documentLink", "params": { "textDocument": { "uri": uri } } }),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/linkedEditingRange", "params": position(0, 4) }),
+        json!({ "jsonrpc": "2.0", "id": 6, "method": "textDocument/hover", "params": { "textDocument": { "uri": "file:///missing.x" }, "position": { "line": 0, "character": 0 } } }),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didClose", "params": { "textDocument": { "uri": uri } } }),
+        json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]);
+    let (mut client, server_input) = tokio::io::duplex(1 << 16);
+    let (server_output, mut client_output) = tokio::io::duplex(1 << 16);
+    client.write_all(&input).await.unwrap();
+    server.run(server_input, server_output).await.unwrap();
+    let mut output = Vec::new();
+    client_output.read_to_end(&mut output).await.unwrap();
+    let messages = unframe(&output);
+
+    let response = |id: u64| messages.iter().find(|m| m["id"] == id).unwrap_or_else(|| panic!("no response to {id}"))["result"].clone();
+    let range = |start: (u32, u32), end: (u32, u32)| json!({ "start": { "line": start.0, "character": start.1 }, "end": { "line": end.0, "character": end.1 } });
+    // The client prefers UTF-16, so `é` is one column wide.
+    assert_eq!(response(1)["capabilities"]["positionEncoding"], "utf-16");
+    assert_eq!(response(1)["capabilities"]["linkedEditingRangeProvider"], true);
+    assert_eq!(response(2), json!([{ "range": range((0, 4), (0, 5)), "kind": null }, { "range": range((1, 0), (1, 1)), "kind": null }]));
+    assert_eq!(response(3), json!([{ "range": range((0, 8), (0, 9)), "target": "file:///one", "tooltip": null }]));
+    assert_eq!(response(4), json!({ "ranges": [range((0, 4), (0, 5)), range((1, 0), (1, 1))], "wordPattern": null }));
+    assert_eq!(response(5), Value::Null);
+    // Positions in a document that cannot be read are not guessed.
+    let missing = messages.iter().find(|m| m["id"] == 6).unwrap();
+    assert_eq!(missing["error"]["code"], -32803);
+    assert!(missing.get("result").is_none());
+
+    let published: Vec<_> = messages.iter().filter(|m| m["method"] == "textDocument/publishDiagnostics").collect();
+    assert_eq!(published.len(), 2);
+    assert_eq!(published[1]["params"]["diagnostics"], json!([]));
+    // Closing the document drops its buffer.
+    assert!(service.vfs().get_source(uri).is_none())
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn test_tcp_round_trip() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(oak_lsp::transport::serve_tcp(listener, Arc::new(TestService::default())));
+
+    let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+    let input = frame(&[json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }), json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }), json!({ "jsonrpc": "2.0", "method": "exit" })]);
+    stream.write_all(&input).await.unwrap();
+    // The server closes the connection after `exit`.
+    let mut output = Vec::new();
+    stream.read_to_end(&mut output).await.unwrap();
+    let messages = unframe(&output);
+    assert_eq!(messages.iter().map(|m| m["id"].clone()).collect::<Vec<_>>(), [json!(1), json!(2)]);
+    assert!(messages[0]["result"]["capabilities"].is_object());
+}
+
+/// A client of a TCP language server that waits for the response to each of its requests.
+#[cfg(feature = "axum")]
+struct TcpClient {
+    reader: tokio::io::BufReader<tokio::net::tcp::OwnedReadHalf>,
+    writer: tokio::net::tcp::OwnedWriteHalf,
+    next_id: u64,
+}
+
+#[cfg(feature = "axum")]
+impl TcpClient {
+    async fn connect(address: std::net::SocketAddr) -> Self {
+        let (read, writer) = tokio::net::TcpStream::connect(address).await.unwrap().into_split();
+        Self { reader: tokio::io::BufReader::new(read), writer, next_id: 1 }
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) {
+        self.writer.write_all(&frame(&[json!({ "jsonrpc": "2.0", "method": method, "params": params })])).await.unwrap()
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> Value {
+        use tokio::io::AsyncBufReadExt;
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.writer.write_all(&frame(&[json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })])).await.unwrap();
+        loop {
+            let mut header = String::new();
+            self.reader.read_line(&mut header).await.unwrap();
+            let length: usize = header.trim().strip_prefix("Content-Length: ").unwrap().parse().unwrap();
+            self.reader.read_line(&mut String::new()).await.unwrap();
+            let mut body = vec![0; length];
+            self.reader.read_exact(&mut body).await.unwrap();
+            let message: Value = serde_json::from_slice(&body).unwrap();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn test_tcp_sessions() {
+    let uri = "file:///main.x";
+    let statement = Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("\u{e9}")]), ws(" "), punct("="), ws(" "), Leaf(T::Literal, "1"), punct(";")]);
+    let (text, root) = Node(E::Root, vec![statement, ws("\n"), name("\u{e9}")]).build();
+    let service = Arc::new(TestService::default().with_file(uri, root));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(oak_lsp::transport::serve_tcp(listener, service.clone()));
+
+    // Both clients open the same document, one measuring columns in UTF-8 and the other in UTF-16.
+    let mut utf8 = TcpClient::connect(address).await;
+    let mut utf16 = TcpClient::connect(address).await;
+    for (client, encoding) in [(&mut utf8, "utf-8"), (&mut utf16, "utf-16")] {
+        let result = client.request("initialize", json!({ "capabilities": { "general": { "positionEncodings": [encoding] } } })).await;
+        assert_eq!(result["capabilities"]["positionEncoding"], encoding);
+        client.notify("initialized", json!({})).await;
+        client.notify("textDocument/didOpen", json!({ "textDocument": { "uri": uri, "languageId": "universal", "version": 1, "text": text } })).await;
+    }
+
+    let document = json!({ "textDocument": { "uri": uri } });
+    let highlight = json!({ "textDocument": { "uri": uri }, "position": { "line": 1, "character": 0 } });
+    let ends = |highlights: Value| highlights.as_array().unwrap().iter().map(|h| h["range"]["end"]["character"].as_u64().unwrap()).collect::<Vec<_>>();
+    assert_eq!(ends(utf8.request("textDocument/documentHighlight", highlight.clone()).await), [6, 2]);
+    assert_eq!(ends(utf16.request("textDocument/documentHighlight", highlight.clone()).await), [5, 1]);
+
+    // Result IDs belong to the session that received them.
+    let tokens = utf8.request("textDocument/semanticTokens/full", document.clone()).await;
+    let previous = json!({ "textDocument": { "uri": uri }, "previousResultId": tokens["resultId"] });
+    assert!(utf8.request("textDocument/semanticTokens/full/delta", previous.clone()).await["edits"].is_array());
+    assert!(utf16.request("textDocument/semanticTokens/full/delta", previous).await["data"].is_array());
+
+    // The buffer stays while the other session still has the document open.
+    utf8.notify("textDocument/didClose", document.clone()).await;
+    assert_eq!(utf8.request("shutdown", Value::Null).await, Value::Null);
+    assert!(service.vfs().get_source(uri).is_some());
+    assert_eq!(ends(utf16.request("textDocument/documentHighlight", highlight).await), [5, 1]);
+    utf16.notify("textDocument/didClose", document).await;
+    assert_eq!(utf16.request("shutdown", Value::Null).await, Value::Null);
+    assert!(service.vfs().get_source(uri).is_none())
+}
@@ -0,0 +1,343 @@
+use crate::{JsonRpcError, JsonRpcResponse, McpServer, SemanticSearch};
+use axum::{
+    Json, Router,
+    extract::{Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{get, post},
+};
+use oak_lsp::service::LanguageService;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    hash::{BuildHasher, RandomState},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpListener, sync::mpsc};
+
+/// The header that carries the session ID of the streamable HTTP transport.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Options of the HTTP transports.
+#[derive(Debug, Clone, Copy)]
+pub struct HttpOptions {
+    /// How long a session of the streamable HTTP transport lives without requests.
+    pub idle_timeout: Duration,
+    /// How many sessions of the streamable HTTP transport are kept; the least recently used one
+    /// ends when a new one would exceed it.
+    pub max_sessions: usize,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self { idle_timeout: Duration::from_secs(60 * 60), max_sessions: 1024 }
+    }
+}
+
+impl HttpOptions {
+    /// Sets how long a session lives without requests.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets how many sessions are kept.
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+}
+
+/// Sessions of the streamable HTTP transport, with the time of their last request.
+struct Sessions {
+    options: HttpOptions,
+    last_used: HashMap<String, Instant>,
+}
+
+impl Sessions {
+    /// Ends the sessions that were idle for too long.
+    fn expire(&mut self, now: Instant) {
+        let idle_timeout = self.options.idle_timeout;
+        self.last_used.retain(|_, last_used| now.duration_since(*last_used) < idle_timeout)
+    }
+
+    /// Starts a session, ending the least recently used one if there are too many.
+    fn create(&mut self) -> String {
+        let now = Instant::now();
+        self.expire(now);
+        while self.last_used.len() >= self.options.max_sessions {
+            let Some(oldest) = self.last_used.iter().min_by_key(|(_, last_used)| **last_used).map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            self.last_used.remove(&oldest);
+        }
+        let id = new_session_id();
+        self.last_used.insert(id.clone(), now);
+        id
+    }
+
+    /// Checks whether a session is alive, and marks it as used.
+    fn touch(&mut self, id: &str) -> bool {
+        let now = Instant::now();
+        self.expire(now);
+        self.last_used.get_mut(id).map(|last_used| *last_used = now).is_some()
+    }
+
+    /// Ends a session, returning whether it was alive.
+    fn remove(&mut self, id: &str) -> bool {
+        self.last_used.remove(id).is_some()
+    }
+}
+
+/// Shared state of the HTTP transports.
+struct HttpState<S: LanguageService, E: SemanticSearch> {
+    server: Arc<McpServer<S, E>>,
+    /// Sessions created by `initialize` requests on the streamable HTTP endpoint.
+    sessions: Mutex<Sessions>,
+    /// Open event streams of the legacy HTTP+SSE transport, keyed by session ID.
+    streams: Mutex<HashMap<String, mpsc::UnboundedSender<JsonRpcResponse>>>,
+}
+
+impl<S: LanguageService + 'static, E: SemanticSearch + 'static> McpServer<S, E>
+where
+    S::Vfs: oak_vfs::WritableVfs,
+{
+    /// Creates a router that serves this server over HTTP.
+    ///
+    /// - `/mcp` implements the streamable HTTP transport: clients `POST` JSON-RPC messages
+    ///   (single or batched) and receive the responses as JSON or, if they only accept
+    ///   `text/event-stream`, as server-sent events. `DELETE` ends a session, and so do idle
+    ///   timeouts and the session limit of the [`HttpOptions`].
+    /// - `/sse` and `/messages` implement the legacy HTTP+SSE transport of protocol
+    ///   version 2024-11-05 for older clients.
+    ///
+    /// Requests from browsers on other origins than the server itself or localhost are
+    /// rejected, so that web pages cannot reach a local server through DNS rebinding.
+    pub fn router(self: Arc<Self>) -> Router {
+        self.router_with(HttpOptions::default())
+    }
+
+    /// Creates a router that serves this server over HTTP with the given options, see [`router`](Self::router).
+    pub fn router_with(self: Arc<Self>, options: HttpOptions) -> Router {
+        let state = Arc::new(HttpState { server: self, sessions: Mutex::new(Sessions { options, last_used: HashMap::new() }), streams: Mutex::new(HashMap::new()) });
+        Router::new().route("/mcp", post(streamable_post::<S, E>).get(streamable_get).delete(streamable_delete::<S, E>)).route("/sse", get(legacy_sse::<S, E>)).route("/messages", post(legacy_post::<S, E>)).layer(middleware::from_fn(validate_origin)).with_state(state)
+    }
+
+    /// Serves this server over HTTP on the given listener.
+    ///
+    /// See [`router`](Self::router) for the available endpoints.
+    pub async fn serve_http(self, listener: TcpListener) -> Result<(), String> {
+        self.serve_http_with(listener, HttpOptions::default()).await
+    }
+
+    /// Serves this server over HTTP on the given listener with the given options.
+    pub async fn serve_http_with(self, listener: TcpListener, options: HttpOptions) -> Result<(), String> {
+        axum::serve(listener, Arc::new(self).router_with(options)).await.map_err(|e| e.to_string())
+    }
+}
+
+/// Creates a new session ID that is hard to guess.
+fn new_session_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:016x}{:016x}", RandomState::new().hash_one(count), RandomState::new().hash_one(count.rotate_left(32)))
+}
+
+/// Checks whether the `Origin` header, if any, is the origin of the server or a loopback address.
+fn is_allowed_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN)
+    else {
+        return true;
+    };
+    let Some((_, authority)) = origin.to_str().ok().and_then(|origin| origin.split_once("://"))
+    else {
+        return false;
+    };
+    let host = match authority.find(']') {
+        Some(end) => &authority[..=end],
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]") || headers.get(header::HOST).and_then(|v| v.to_str().ok()) == Some(authority)
+}
+
+async fn validate_origin(request: Request, next: Next) -> Response {
+    if !is_allowed_origin(request.headers()) {
+        return (StatusCode::FORBIDDEN, "origin not allowed").into_response();
+    }
+    next.run(request).await
+}
+
+/// An error status and its message.
+type HttpError = (StatusCode, &'static str);
+
+/// Locks shared state, answering with an internal server error if a handler panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, HttpError> {
+    mutex.lock().map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "poisoned session state"))
+}
+
+fn accepts_only_event_stream(headers: &HeaderMap) -> bool {
+    let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    accept.contains("text/event-stream") && !accept.contains("application/json")
+}
+
+fn parse_error(message: String) -> Response {
+    let response = JsonRpcResponse { jsonrpc: "2.0".to_string(), id: JsonValue::Null, result: None, error: Some(JsonRpcError { code: -32700, message, data: None }) };
+    (StatusCode::BAD_REQUEST, Json(response)).into_response()
+}
+
+async fn streamable_post<S, E>(State(state): State<Arc<HttpState<S, E>>>, headers: HeaderMap, body: String) -> Result<Response, HttpError>
+where
+    S: LanguageService + 'static,
+    E: SemanticSearch + 'static,
+    S::Vfs: oak_vfs::WritableVfs,
+{
+    let (messages, batch) = match serde_json::from_str::<JsonValue>(&body) {
+        Ok(JsonValue::Array(messages)) => (messages, true),
+        Ok(message) => (vec![message], false),
+        Err(e) => return Ok(parse_error(e.to_string())),
+    };
+
+    let session = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()).map(String::from);
+    let mut created = None;
+    let session = if messages.iter().any(|m| m.get("method").and_then(|v| v.as_str()) == Some("initialize")) {
+        let id = lock(&state.sessions)?.create();
+        created = Some(PendingSession { state: state.clone(), id: id.clone(), handled: false });
+        id
+    }
+    else {
+        match session {
+            Some(id) if lock(&state.sessions)?.touch(&id) => id,
+            Some(_) => return Err((StatusCode::NOT_FOUND, "unknown session")),
+            None => return Err((StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header")),
+        }
+    };
+
+    let mut responses = Vec::new();
+    for message in messages {
+        if let Some(response) = state.server.handle_message(message).await {
+            responses.push(response)
+        }
+    }
+    // The client is still connected, so it gets the new session.
+    if let Some(created) = &mut created {
+        created.handled = true
+    }
+
+    let mut response = if responses.is_empty() {
+        StatusCode::ACCEPTED.into_response()
+    }
+    else if accepts_only_event_stream(&headers) {
+        let events = responses.into_iter().map(|r| Ok::<_, Infallible>(Event::default().event("message").data(r.to_json())));
+        Sse::new(futures::stream::iter(events)).into_response()
+    }
+    else if batch {
+        Json(responses).into_response()
+    }
+    else {
+        Json(responses.remove(0)).into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(&session) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// This server never sends unsolicited messages, so it does not offer a standalone event stream.
+async fn streamable_get() -> StatusCode {
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+async fn streamable_delete<S: LanguageService, E: SemanticSearch>(State(state): State<Arc<HttpState<S, E>>>, headers: HeaderMap) -> Result<StatusCode, HttpError> {
+    let session = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok());
+    Ok(match session {
+        Some(id) if lock(&state.sessions)?.remove(id) => StatusCode::OK,
+        Some(_) => StatusCode::NOT_FOUND,
+        None => StatusCode::BAD_REQUEST,
+    })
+}
+
+/// Removes a session created by an `initialize` request when the client disconnects before the
+/// request is handled and the handler is dropped.
+struct PendingSession<S: LanguageService, E: SemanticSearch> {
+    state: Arc<HttpState<S, E>>,
+    id: String,
+    handled: bool,
+}
+
+impl<S: LanguageService, E: SemanticSearch> Drop for PendingSession<S, E> {
+    fn drop(&mut self) {
+        if !self.handled
+            && let Ok(mut sessions) = self.state.sessions.lock()
+        {
+            sessions.remove(&self.id);
+        }
+    }
+}
+
+/// Removes the session of a legacy event stream when the client disconnects and the stream is dropped.
+struct StreamSession<S: LanguageService, E: SemanticSearch> {
+    state: Arc<HttpState<S, E>>,
+    id: String,
+}
+
+impl<S: LanguageService, E: SemanticSearch> Drop for StreamSession<S, E> {
+    fn drop(&mut self) {
+        if let Ok(mut streams) = self.state.streams.lock() {
+            streams.remove(&self.id);
+        }
+    }
+}
+
+async fn legacy_sse<S: LanguageService + 'static, E: SemanticSearch + 'static>(State(state): State<Arc<HttpState<S, E>>>) -> Result<impl IntoResponse, HttpError> {
+    let id = new_session_id();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    lock(&state.streams)?.insert(id.clone(), sender);
+
+    let endpoint = futures::stream::once(std::future::ready(Ok::<_, Infallible>(Event::default().event("endpoint").data(format!("/messages?session_id={}", id)))));
+    let session = StreamSession { state, id };
+    let messages = futures::stream::unfold((receiver, session), |(mut receiver, session)| async move {
+        let response: JsonRpcResponse = receiver.recv().await?;
+        Some((Ok(Event::default().event("message").data(response.to_json())), (receiver, session)))
+    });
+    Ok(Sse::new(futures::StreamExt::chain(endpoint, messages)).keep_alive(KeepAlive::default()))
+}
+
+/// Query parameters of the legacy message endpoint.
+#[derive(Deserialize)]
+struct SessionQuery {
+    session_id: String,
+}
+
+async fn legacy_post<S, E>(State(state): State<Arc<HttpState<S, E>>>, Query(query): Query<SessionQuery>, body: String) -> Result<Response, HttpError>
+where
+    S: LanguageService + 'static,
+    E: SemanticSearch + 'static,
+    S::Vfs: oak_vfs::WritableVfs,
+{
+    let Some(sender) = lock(&state.streams)?.get(&query.session_id).cloned()
+    else {
+        return Err((StatusCode::NOT_FOUND, "unknown session"));
+    };
+    let message = match serde_json::from_str::<JsonValue>(&body) {
+        Ok(message) => message,
+        Err(e) => return Ok(parse_error(e.to_string())),
+    };
+    if let Some(response) = state.server.handle_message(message).await
+        && sender.send(response).is_err()
+    {
+        // The client closed its event stream.
+        return Err((StatusCode::GONE, "event stream closed"));
+    }
+    Ok(StatusCode::ACCEPTED.into_response())
+}
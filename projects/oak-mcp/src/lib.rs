@@ -6,14 +6,32 @@
 //! This crate provides an implementation of the MCP server, allowing
 //! Oak-based language services to be used as tools by LLMs.
 
-use oak_core::Range;
 use oak_lsp::service::LanguageService;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value as JsonValue, json};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
 
-pub use oak_semantic_search::{NoSemanticSearch, SemanticSearch};
+/// Streamable HTTP and legacy HTTP+SSE transports.
+#[cfg(feature = "axum")]
+pub mod http;
+mod prompts;
+mod resources;
+mod tools;
+
+pub use resources::OUTLINE_SCHEME;
+pub use tools::ToolError;
+
+pub use oak_semantic_search::{NoSemanticSearch, SearchHit, SemanticSearch};
+
+/// The MCP protocol versions this server understands, oldest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// Picks the protocol version requested by the client if it is supported, otherwise the latest one.
+fn negotiate_protocol_version(params: Option<&JsonValue>) -> &'static str {
+    let requested = params.and_then(|p| p.get("protocolVersion")).and_then(|v| v.as_str());
+    SUPPORTED_PROTOCOL_VERSIONS.iter().copied().find(|v| Some(*v) == requested).unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[SUPPORTED_PROTOCOL_VERSIONS.len() - 1])
+}
 
 /// Represents a JSON-RPC 2.0 request.
 #[derive(Debug, Serialize, Deserialize)]
@@ -129,16 +147,14 @@ where
                 continue;
             }
 
-            if let Ok(request) = JsonRpcRequest::from_json(input) {
-                let response = self.handle_request(request).await;
+            if let Ok(message) = serde_json::from_str::<JsonValue>(input)
+                && let Some(response) = self.handle_message(message).await
+            {
                 let response_json = response.to_json();
                 writer.write_all(response_json.as_bytes()).await.map_err(|e| e.to_string())?;
                 writer.write_all(b"\n").await.map_err(|e| e.to_string())?;
                 writer.flush().await.map_err(|e| e.to_string())?;
             }
-            else if let Ok(notification) = JsonRpcNotification::from_json(input) {
-                self.handle_notification(notification).await
-            }
 
             line.clear()
         }
@@ -146,62 +162,85 @@ where
         Ok(())
     }
 
-    /// Handles an incoming JSON-RPC request and returns a response.
+    /// Handles a single JSON-RPC message of any kind.
+    ///
+    /// Requests produce a response; notifications and responses to server requests produce `None`.
+    pub async fn handle_message(&self, message: JsonValue) -> Option<JsonRpcResponse> {
+        if message.get("id").is_some() && message.get("method").is_some() {
+            match serde_json::from_value::<JsonRpcRequest>(message) {
+                Ok(request) => Some(self.handle_request(request).await),
+                Err(e) => Some(JsonRpcResponse { jsonrpc: "2.0".to_string(), id: JsonValue::Null, result: None, error: Some(JsonRpcError { code: -32600, message: e.to_string(), data: None }) }),
+            }
+        }
+        else {
+            if let Ok(notification) = serde_json::from_value::<JsonRpcNotification>(message) {
+                self.handle_notification(notification).await
+            }
+            None
+        }
+    }
+
     /// Handles a single JSON-RPC request and returns a response.
     pub async fn handle_request(&self, request: JsonRpcRequest) -> JsonRpcResponse {
-        match request.method.as_str() {
-            "initialize" => JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id: request.id,
-                result: Some(json!({
-                    "protocolVersion": "2024-11-05",
-                    "capabilities": {
-                        "tools": {
-                            "listChanged": false
-                        }
+        let params = request.params.unwrap_or(json!(null));
+        let result = match request.method.as_str() {
+            "initialize" => Ok(json!({
+                "protocolVersion": negotiate_protocol_version(Some(&params)),
+                "capabilities": {
+                    "tools": {
+                        "listChanged": false
                     },
-                    "serverInfo": {
-                        "name": "oak-mcp",
-                        "version": "0.0.1"
+                    "resources": {
+                        "subscribe": false,
+                        "listChanged": false
+                    },
+                    "prompts": {
+                        "listChanged": false
                     }
-                })),
-                error: None,
-            },
-            "tools/list" => {
-                let tools_str = include_str!("tools.json");
-                let tools: JsonValue = serde_json::from_str(tools_str).unwrap();
-                JsonRpcResponse {
-                    jsonrpc: "2.0".to_string(),
-                    id: request.id,
-                    result: Some(json!({
-                        "tools": tools
-                    })),
-                    error: None,
+                },
+                "serverInfo": {
+                    "name": "oak-mcp",
+                    "version": "0.0.1"
                 }
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => {
+                let tools: JsonValue = serde_json::from_str(include_str!("tools.json")).unwrap();
+                Ok(json!({ "tools": tools }))
             }
             "tools/call" => {
-                let params = request.params.unwrap_or(json!(null));
                 let name = params.get("name").and_then(|v| v.as_str()).unwrap_or_default();
-                let args = params.get("arguments").cloned().unwrap_or(json!(null));
-
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
                 match self.handle_tool_call(name, args).await {
-                    Ok(result) => JsonRpcResponse {
-                        jsonrpc: "2.0".to_string(),
-                        id: request.id,
-                        result: Some(json!({
-                            "content": [
-                                {
-                                    "type": "text",
-                                    "text": result.to_string()
-                                }
-                            ]
-                        })),
-                        error: None,
-                    },
-                    Err(e) => JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: None, error: Some(JsonRpcError { code: -32000, message: e, data: None }) },
+                    Ok(result) => {
+                        let text = match result {
+                            JsonValue::String(text) => text,
+                            other => other.to_string(),
+                        };
+                        Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": false }))
+                    }
+                    // Unknown tools are protocol errors, failures of known tools are reported to the model.
+                    Err(e @ ToolError::UnknownTool(_)) => Err(JsonRpcError { code: -32602, message: e.to_string(), data: None }),
+                    Err(ToolError::ToolFailed(e)) => Ok(json!({ "content": [{ "type": "text", "text": e }], "isError": true })),
                 }
             }
-            _ => JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: None, error: Some(JsonRpcError { code: -32601, message: format!("Method not found: {}", request.method), data: None }) },
+            "resources/list" => Ok(self.list_resources().await),
+            "resources/templates/list" => Ok(self.list_resource_templates()),
+            "resources/read" => match params.get("uri").and_then(|v| v.as_str()) {
+                Some(uri) => self.read_resource(uri).await.map_err(|e| JsonRpcError { code: -32002, message: e, data: Some(json!({ "uri": uri })) }),
+                None => Err(JsonRpcError { code: -32602, message: "Missing uri".to_string(), data: None }),
+            },
+            "prompts/list" => Ok(self.list_prompts()),
+            "prompts/get" => {
+                let name = params.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                self.get_prompt(name, &args).await.map_err(|e| JsonRpcError { code: -32602, message: e, data: None })
+            }
+            _ => Err(JsonRpcError { code: -32601, message: format!("Method not found: {}", request.method), data: None }),
+        };
+        match result {
+            Ok(result) => JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: Some(result), error: None },
+            Err(error) => JsonRpcResponse { jsonrpc: "2.0".to_string(), id: request.id, result: None, error: Some(error) },
         }
     }
 
@@ -209,63 +248,4 @@ where
     pub async fn handle_notification(&self, _notification: JsonRpcNotification) {
         // Handle notifications (like initialized)
     }
-
-    /// Handles a tool call from the MCP client.
-    ///
-    /// Currently supports:
-    /// - `hover`: Get hover information for a position.
-    /// - `definitions`: Get definitions for a symbol at a position.
-    /// - `search`: Perform semantic search (if searcher is available).
-    pub async fn handle_tool_call(&self, name: &str, args: JsonValue) -> Result<JsonValue, String> {
-        match name {
-            "hover" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let offset = args.get("offset").and_then(|v| v.as_u64()).ok_or("Missing offset")? as usize;
-
-                let hover = self.service.hover(uri, Range { start: offset, end: offset }).await;
-                Ok(json!(hover.map(|h| h.contents).unwrap_or_default()))
-            }
-            "symbols" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let _symbols = self.service.document_symbols(uri).await;
-                // symbols is Vec<DocumentSymbol>, need proper conversion.
-                // For now just returning empty array as placeholder if conversion is complex
-                Ok(json!([]))
-            }
-            "definition" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let offset = args.get("offset").and_then(|v| v.as_u64()).ok_or("Missing offset")? as usize;
-
-                let _locs = self.service.definition(uri, Range { start: offset, end: offset }).await;
-                Ok(json!([]))
-            }
-            "references" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let offset = args.get("offset").and_then(|v| v.as_u64()).ok_or("Missing offset")? as usize;
-
-                let _locs = self.service.references(uri, Range { start: offset, end: offset }).await;
-                Ok(json!([]))
-            }
-            "diagnostics" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let _diagnostics = self.service.diagnostics(uri).await;
-                Ok(json!([]))
-            }
-            "completion" => {
-                let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
-                let offset = args.get("offset").and_then(|v| v.as_u64()).ok_or("Missing offset")? as usize;
-                let _items = self.service.completion(uri, offset).await;
-                Ok(json!([]))
-            }
-            "semantic_search" => {
-                let searcher = self.searcher.as_ref().ok_or("Semantic search is not enabled")?;
-                let query = args.get("query").and_then(|v| v.as_str()).ok_or("Missing query")?;
-                let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
-
-                let results = searcher.search(query, limit).await.map_err(|e| e.to_string())?;
-                Ok(json!(results))
-            }
-            _ => Err(format!("Unknown tool: {}", name)),
-        }
-    }
 }
@@ -0,0 +1,63 @@
+use crate::{McpServer, SemanticSearch, tools::Locator};
+use oak_core::{Range, source::Source};
+use oak_lsp::service::LanguageService;
+use oak_vfs::WritableVfs;
+use serde_json::{Value as JsonValue, json};
+use std::fmt::Write;
+
+impl<S: LanguageService + 'static, E: SemanticSearch + 'static> McpServer<S, E>
+where
+    S::Vfs: WritableVfs,
+{
+    /// Lists the prompts this server offers.
+    pub fn list_prompts(&self) -> JsonValue {
+        let prompts: JsonValue = serde_json::from_str(include_str!("prompts.json")).unwrap();
+        json!({ "prompts": prompts })
+    }
+
+    /// Renders a prompt with the given arguments, embedding the file content and diagnostics it needs.
+    pub async fn get_prompt(&self, name: &str, args: &JsonValue) -> Result<JsonValue, String> {
+        let uri = args.get("uri").and_then(|v| v.as_str()).ok_or("Missing uri")?;
+        let source = self.service.get_source(uri).ok_or_else(|| format!("File not found: {}", uri))?;
+        let text = source.get_text_in(Range { start: 0, end: source.length() }).into_owned();
+        let language = self.service.language_id(uri);
+
+        let (description, prompt) = match name {
+            "review_file" => ("Review a file", format!("Review the following {language} file `{uri}` for bugs, readability and style issues. Point to specific lines and suggest concrete changes.\n\n{}{}", fenced(language, &text), self.diagnostics_section(uri).await)),
+            "explain_code" => {
+                let line = |name: &str| args.get(name).and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or_else(|| v.as_u64().map(|v| v as usize)));
+                let lines: Vec<&str> = text.lines().collect();
+                let start = line("start_line").unwrap_or(1).clamp(1, lines.len().max(1));
+                let end = line("end_line").unwrap_or(lines.len()).clamp(start, lines.len().max(start));
+                let excerpt = lines.get(start - 1..end).map(|l| l.join("\n")).unwrap_or_default();
+                ("Explain code", format!("Explain what lines {start}-{end} of the {language} file `{uri}` do, including the role of each important symbol.\n\n{}", fenced(language, &excerpt)))
+            }
+            "fix_diagnostics" => ("Fix diagnostics", format!("Propose minimal fixes for the problems reported in the {language} file `{uri}`. Explain each fix.\n\n{}{}", fenced(language, &text), self.diagnostics_section(uri).await)),
+            "refactor" => {
+                let goal = args.get("goal").and_then(|v| v.as_str()).ok_or("Missing goal")?;
+                ("Refactor code", format!("Refactor the {language} file `{uri}` to achieve the following goal without changing its behavior: {goal}\n\n{}", fenced(language, &text)))
+            }
+            _ => return Err(format!("Unknown prompt: {}", name)),
+        };
+        Ok(json!({ "description": description, "messages": [{ "role": "user", "content": { "type": "text", "text": prompt } }] }))
+    }
+
+    async fn diagnostics_section(&self, uri: &str) -> String {
+        let diagnostics = self.service.diagnostics(uri).await;
+        if diagnostics.is_empty() {
+            return String::new();
+        }
+        let mut locator = Locator::new(self.service.vfs());
+        let mut section = String::from("\nReported problems:\n");
+        for diagnostic in diagnostics {
+            let (line, column) = locator.line_column(uri, diagnostic.range.start);
+            let severity = diagnostic.severity.map(|s| format!("{:?}", s)).unwrap_or_else(|| "Problem".to_string());
+            let _ = writeln!(section, "- {}:{}: {}: {}", line, column, severity, diagnostic.message);
+        }
+        section
+    }
+}
+
+fn fenced(language: &str, text: &str) -> String {
+    format!("```{}\n{}\n```\n", language, text)
+}
@@ -0,0 +1,65 @@
+use crate::{McpServer, SemanticSearch, tools::Locator};
+use oak_core::{Range, source::Source};
+use oak_lsp::service::LanguageService;
+use oak_vfs::{Vfs, WritableVfs};
+use serde_json::{Value as JsonValue, json};
+use std::collections::BTreeSet;
+
+/// The URI prefix of the resources that expose the parsed outline of a file.
+///
+/// `oak-outline:file:///src/main.rs` is the outline of `file:///src/main.rs`.
+pub const OUTLINE_SCHEME: &str = "oak-outline:";
+
+impl<S: LanguageService + 'static, E: SemanticSearch + 'static> McpServer<S, E>
+where
+    S::Vfs: WritableVfs,
+{
+    /// Collects the files known to the workspace: those below the workspace folders,
+    /// those with indexed symbols and those the VFS lists at its root.
+    pub async fn workspace_files(&self) -> Vec<String> {
+        let mut files = BTreeSet::new();
+        for (folder, _) in self.service.workspace().list_folders() {
+            files.extend(self.service.list_all_files(&folder).await)
+        }
+        files.extend(self.service.workspace().symbols.files());
+        let vfs = self.service.vfs();
+        files.extend(vfs.read_dir("").unwrap_or_default().iter().filter(|uri| vfs.is_file(uri)).map(|uri| uri.to_string()));
+        files.into_iter().collect()
+    }
+
+    /// Lists the workspace files and their outlines as MCP resources.
+    pub async fn list_resources(&self) -> JsonValue {
+        let mut resources = Vec::new();
+        for uri in self.workspace_files().await {
+            let name = uri.rsplit('/').next().unwrap_or(&uri).to_string();
+            let size = self.service.vfs().metadata(&uri).map(|m| m.len);
+            resources.push(json!({ "uri": uri, "name": name, "mimeType": "text/plain", "size": size }));
+            resources.push(json!({ "uri": format!("{}{}", OUTLINE_SCHEME, uri), "name": format!("{} (outline)", name), "description": format!("Parsed {} outline of {}", self.service.language_id(&uri), name), "mimeType": "application/json" }));
+        }
+        json!({ "resources": resources })
+    }
+
+    /// Lists the URI templates of the resources this server can read.
+    pub fn list_resource_templates(&self) -> JsonValue {
+        json!({ "resourceTemplates": [
+            { "uriTemplate": "{uri}", "name": "File", "description": "The content of a workspace file", "mimeType": "text/plain" },
+            { "uriTemplate": format!("{}{{uri}}", OUTLINE_SCHEME), "name": "Outline", "description": "The parsed outline of a workspace file", "mimeType": "application/json" },
+        ] })
+    }
+
+    /// Reads a file or outline resource.
+    pub async fn read_resource(&self, uri: &str) -> Result<JsonValue, String> {
+        if let Some(file) = uri.strip_prefix(OUTLINE_SCHEME) {
+            if self.service.get_source(file).is_none() {
+                return Err(format!("Resource not found: {}", uri));
+            }
+            let mut locator = Locator::new(self.service.vfs());
+            let items: Vec<_> = self.service.document_symbols(file).await.into_iter().map(|item| crate::tools::outline_item(&mut locator, file, item)).collect();
+            let text = serde_json::to_string_pretty(&items).map_err(|e| e.to_string())?;
+            return Ok(json!({ "contents": [{ "uri": uri, "mimeType": "application/json", "text": text }] }));
+        }
+        let source = self.service.get_source(uri).ok_or_else(|| format!("Resource not found: {}", uri))?;
+        let text = source.get_text_in(Range { start: 0, end: source.length() }).into_owned();
+        Ok(json!({ "contents": [{ "uri": uri, "mimeType": "text/plain", "text": text }] }))
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{McpServer, SemanticSearch};
+use oak_core::{Range, language::UniversalElementRole, source::Source};
+use oak_lsp::{
+    service::LanguageService,
+    types::{StructureItem, TextEdit},
+};
+use oak_resolver::{SymbolQuery, fuzzy_match};
+use oak_vfs::{LineMap, PositionEncoding, Vfs, WritableVfs};
+use serde_json::{Value as JsonValue, json};
+use std::{collections::HashMap, fmt};
+
+/// Why a tool call failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolError {
+    /// No tool has the requested name, which is a protocol error.
+    UnknownTool(String),
+    /// The tool failed, which is reported to the model as a tool result.
+    ToolFailed(String),
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolError::UnknownTool(name) => write!(f, "Unknown tool: {}", name),
+            ToolError::ToolFailed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+impl From<String> for ToolError {
+    fn from(message: String) -> Self {
+        ToolError::ToolFailed(message)
+    }
+}
+
+impl From<&str> for ToolError {
+    fn from(message: &str) -> Self {
+        ToolError::ToolFailed(message.to_string())
+    }
+}
+
+/// Resolves byte ranges to 1-based line/column positions, reusing the line map of each file.
+///
+/// Columns are counted in Unicode characters, which is what models and humans expect.
+pub(crate) struct Locator<'a, V: Vfs> {
+    vfs: &'a V,
+    line_maps: HashMap<String, Option<LineMap>>,
+}
+
+impl<'a, V: Vfs> Locator<'a, V> {
+    pub(crate) fn new(vfs: &'a V) -> Self {
+        Self { vfs, line_maps: HashMap::new() }
+    }
+
+    fn line_map(&mut self, uri: &str) -> Option<&LineMap> {
+        let vfs = self.vfs;
+        self.line_maps.entry(uri.to_string()).or_insert_with(|| vfs.line_map(uri)).as_ref()
+    }
+
+    /// Returns the 1-based line and column of a byte offset.
+    pub(crate) fn line_column(&mut self, uri: &str, offset: usize) -> (u32, u32) {
+        let (line, column) = self.line_map(uri).map(|m| m.offset_to_position(offset, PositionEncoding::Utf32)).unwrap_or_default();
+        (line + 1, column + 1)
+    }
+
+    /// Converts a 1-based line and column to a byte offset.
+    pub(crate) fn offset(&mut self, uri: &str, line: u32, column: u32) -> Option<usize> {
+        self.line_map(uri).map(|m| m.position_to_offset(line.saturating_sub(1), column.saturating_sub(1), PositionEncoding::Utf32))
+    }
+
+    pub(crate) fn position(&mut self, uri: &str, offset: usize) -> JsonValue {
+        let (line, column) = self.line_column(uri, offset);
+        json!({ "offset": offset, "line": line, "column": column })
+    }
+
+    pub(crate) fn range(&mut self, uri: &str, range: Range<usize>) -> JsonValue {
+        json!({ "start": self.position(uri, range.start), "end": self.position(uri, range.end) })
+    }
+
+    pub(crate) fn location(&mut self, uri: &str, range: Range<usize>) -> JsonValue {
+        json!({ "uri": uri, "range": self.range(uri, range) })
+    }
+}
+
+fn str_arg<'a>(args: &'a JsonValue, name: &str) -> Result<&'a str, String> {
+    args.get(name).and_then(|v| v.as_str()).ok_or_else(|| format!("Missing {}", name))
+}
+
+/// Reads the optional `roles` argument, a list of universal element roles like `definition`.
+fn roles_arg(args: &JsonValue) -> Result<Vec<UniversalElementRole>, String> {
+    match args.get("roles") {
+        None | Some(JsonValue::Null) => Ok(Vec::new()),
+        Some(roles) => serde_json::from_value(roles.clone()).map_err(|e| format!("Invalid roles: {}", e)),
+    }
+}
+
+fn u32_arg(args: &JsonValue, name: &str) -> Option<u32> {
+    args.get(name).and_then(|v| v.as_u64()).map(|v| v as u32)
+}
+
+impl<S: LanguageService + 'static, E: SemanticSearch + 'static> McpServer<S, E>
+where
+    S::Vfs: WritableVfs,
+{
+    /// Reads a position from tool arguments, given either as a byte `offset` or as a 1-based `line` and `column`.
+    fn offset_arg(&self, uri: &str, args: &JsonValue) -> Result<usize, String> {
+        if let Some(offset) = args.get("offset").and_then(|v| v.as_u64()) {
+            return Ok(offset as usize);
+        }
+        match (u32_arg(args, "line"), u32_arg(args, "column")) {
+            (Some(line), column) => Locator::new(self.service.vfs()).offset(uri, line, column.unwrap_or(1)).ok_or_else(|| format!("File not found: {}", uri)),
+            _ => Err("Missing offset or line/column".to_string()),
+        }
+    }
+
+    /// Handles a tool call from the MCP client.
+    ///
+    /// Positions can be given either as a byte `offset` or as a 1-based `line` and `column`,
+    /// and every reported range carries both forms.
+    pub async fn handle_tool_call(&self, name: &str, args: JsonValue) -> Result<JsonValue, ToolError> {
+        let service = &self.service;
+        let mut locator = Locator::new(service.vfs());
+        match name {
+            "hover" => {
+                let uri = str_arg(&args, "uri")?;
+                let offset = self.offset_arg(uri, &args)?;
+                let hover = service.hover(uri, Range { start: offset, end: offset }).await;
+                Ok(json!(hover.map(|h| h.contents).unwrap_or_default()))
+            }
+            "symbols" => {
+                let uri = str_arg(&args, "uri")?;
+                let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
+                let roles = roles_arg(&args)?;
+                let mut items = Vec::new();
+                flatten_items(service.document_symbols(uri).await, None, &mut items);
+                let mut matches: Vec<_> = items.into_iter().filter(|(item, _)| roles.is_empty() || roles.contains(&item.role)).filter_map(|(item, container)| Some((fuzzy_match(query, &item.name)?.score, item, container))).collect();
+                if !query.is_empty() {
+                    matches.sort_by_key(|(score, ..)| core::cmp::Reverse(*score))
+                }
+                Ok(JsonValue::Array(matches.into_iter().map(|(_, item, container)| json!({ "name": item.name, "kind": item.kind, "role": item.role, "detail": item.detail, "container": container, "range": locator.range(uri, item.range) })).collect()))
+            }
+            "outline" => {
+                let uri = str_arg(&args, "uri")?;
+                let items = service.document_symbols(uri).await;
+                Ok(JsonValue::Array(items.into_iter().map(|item| outline_item(&mut locator, uri, item)).collect()))
+            }
+            "definition" | "references" => {
+                let uri = str_arg(&args, "uri")?;
+                let offset = self.offset_arg(uri, &args)?;
+                let range = Range { start: offset, end: offset };
+                let locations = if name == "definition" { service.definition(uri, range).await } else { service.references(uri, range).await };
+                Ok(JsonValue::Array(locations.into_iter().map(|l| locator.location(&l.uri, l.range)).collect()))
+            }
+            "diagnostics" => {
+                let uri = str_arg(&args, "uri")?;
+                let diagnostics = service.diagnostics(uri).await;
+                Ok(JsonValue::Array(diagnostics.into_iter().map(|d| json!({ "severity": d.severity, "code": d.code, "source": d.source, "message": d.message, "range": locator.range(uri, d.range) })).collect()))
+            }
+            "completion" => {
+                let uri = str_arg(&args, "uri")?;
+                let offset = self.offset_arg(uri, &args)?;
+                Ok(json!(service.completion(uri, offset).await))
+            }
+            "folding_ranges" => {
+                let uri = str_arg(&args, "uri")?;
+                let ranges = service.folding_ranges(uri).await;
+                Ok(JsonValue::Array(ranges.into_iter().map(|f| json!({ "start_line": locator.line_column(uri, f.range.start).0, "end_line": locator.line_column(uri, f.range.end).0, "kind": f.kind })).collect()))
+            }
+            "format" => {
+                let uri = str_arg(&args, "uri")?;
+                let apply = args.get("apply").and_then(|v| v.as_bool()).unwrap_or(false);
+                let source = service.get_source(uri).ok_or_else(|| format!("File not found: {}", uri))?;
+                let text = source.get_text_in(Range { start: 0, end: source.length() }).into_owned();
+                let edits = service.formatting(uri).await;
+                let formatted = apply_edits(&text, &edits)?;
+                let edits: Vec<_> = edits.into_iter().map(|e| json!({ "range": locator.range(uri, e.range), "new_text": e.new_text })).collect();
+                if apply && formatted != text {
+                    service.vfs().write_file(uri, formatted.as_str().into())
+                }
+                Ok(json!({ "edits": edits, "formatted": formatted, "applied": apply }))
+            }
+            "rename" => {
+                let uri = str_arg(&args, "uri")?;
+                let offset = self.offset_arg(uri, &args)?;
+                let new_name = str_arg(&args, "new_name")?.to_string();
+                let edit = service.rename(uri, Range { start: offset, end: offset }, new_name).await.ok_or("Nothing to rename at this position")?;
+                let changes: serde_json::Map<String, JsonValue> =
+                    edit.changes.into_iter().map(|(uri, edits)| (uri.clone(), JsonValue::Array(edits.into_iter().map(|e| json!({ "range": locator.range(&uri, e.range), "new_text": e.new_text })).collect()))).collect();
+                Ok(json!({ "changes": changes }))
+            }
+            "syntax_tree" => {
+                let uri = str_arg(&args, "uri")?;
+                let max_depth = args.get("max_depth").and_then(|v| v.as_u64()).map(|v| v as usize).unwrap_or(usize::MAX);
+                service.get_source(uri).ok_or_else(|| format!("File not found: {}", uri))?;
+                let dump = service.syntax_tree(uri, max_depth).await.ok_or_else(|| format!("Failed to parse {}", uri))?;
+                Ok(JsonValue::String(dump))
+            }
+            "set_file_content" => {
+                let uri = str_arg(&args, "uri")?;
+                let content = str_arg(&args, "content")?;
+                service.vfs().write_file(uri, content.into());
+                Ok(json!(format!("Updated {} ({} bytes)", uri, content.len())))
+            }
+            "search" => {
+                let query = str_arg(&args, "query")?;
+                let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(20) as usize;
+                let matches = service.search_symbols(SymbolQuery::new(query).with_roles(roles_arg(&args)?).with_limit(limit)).await;
+                Ok(JsonValue::Array(
+                    matches.into_iter().map(|m| json!({ "name": m.symbol.name, "role": m.symbol.role, "score": m.score, "matched": m.positions, "container": m.symbol.container_name, "location": locator.location(&m.symbol.uri, m.symbol.range) })).collect(),
+                ))
+            }
+            "semantic_search" => {
+                let searcher = self.searcher.as_ref().ok_or("Semantic search is not enabled")?;
+                let query = str_arg(&args, "query")?;
+                let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
+
+                let hits = searcher.search(query, limit).await.map_err(|e| e.to_string())?;
+                Ok(JsonValue::Array(hits.into_iter().map(|hit| json!({ "role": hit.role, "score": hit.score, "snippet": hit.snippet, "location": locator.location(&hit.uri, hit.range) })).collect()))
+            }
+            _ => Err(ToolError::UnknownTool(name.to_string())),
+        }
+    }
+}
+
+/// Flattens a symbol tree into items paired with the name of their container.
+fn flatten_items(items: Vec<StructureItem>, container: Option<&str>, out: &mut Vec<(StructureItem, Option<String>)>) {
+    for mut item in items {
+        let children = std::mem::take(&mut item.children);
+        let name = item.name.clone();
+        out.push((item, container.map(String::from)));
+        flatten_items(children, Some(&name), out)
+    }
+}
+
+pub(crate) fn outline_item<V: Vfs>(locator: &mut Locator<V>, uri: &str, item: StructureItem) -> JsonValue {
+    let children: Vec<_> = item.children.into_iter().map(|child| outline_item(locator, uri, child)).collect();
+    json!({ "name": item.name, "kind": item.kind, "detail": item.detail, "deprecated": item.deprecated, "range": locator.range(uri, item.range), "selection_range": locator.range(uri, item.selection_range), "children": children })
+}
+
+/// Applies non-overlapping edits to a text.
+///
+/// Fails if an edit is out of bounds, splits a character or overlaps another edit.
+fn apply_edits(text: &str, edits: &[TextEdit]) -> Result<String, String> {
+    let mut edits: Vec<_> = edits.iter().collect();
+    edits.sort_by_key(|e| core::cmp::Reverse((e.range.start, e.range.end)));
+    let mut result = text.to_string();
+    let mut limit = text.len();
+    for edit in edits {
+        let Range { start, end } = edit.range;
+        if start > end || end > limit || !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return Err(format!("Invalid edit range {}..{}", start, end));
+        }
+        result.replace_range(start..end, &edit.new_text);
+        limit = start
+    }
+    Ok(result)
+}
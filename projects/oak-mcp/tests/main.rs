@@ -0,0 +1,223 @@
+#![feature(new_range_api)]
+
+use core::range::Range;
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    source::Source,
+    tree::{GreenNode, RedNode},
+};
+use oak_lsp::{LanguageService, MemoryVfs, TextEdit, WorkspaceManager};
+use oak_mcp::{McpServer, ToolError};
+use oak_symbols::SymbolInformation;
+use oak_testing::building::{
+    Spec::{Leaf, Node},
+    Universal, kw, name, punct, ws,
+};
+use serde_json::{Value, json};
+use std::{future::Future, sync::Mutex};
+
+/// A service over a hand-written tree whose formatting edits are set by the test.
+#[derive(Default)]
+struct TestService {
+    vfs: MemoryVfs,
+    workspace: WorkspaceManager,
+    root: Option<&'static GreenNode<'static, Universal>>,
+    edits: Mutex<Vec<TextEdit>>,
+}
+
+impl LanguageService for TestService {
+    type Lang = Universal;
+    type Vfs = MemoryVfs;
+
+    fn vfs(&self) -> &MemoryVfs {
+        &self.vfs
+    }
+
+    fn workspace(&self) -> &WorkspaceManager {
+        &self.workspace
+    }
+
+    fn get_root(&self, _uri: &str) -> impl Future<Output = Option<RedNode<'_, Universal>>> + Send + '_ {
+        let root = self.root;
+        async move { root.map(|green| RedNode::new(green, 0)) }
+    }
+
+    async fn formatting(&self, _uri: &str) -> Vec<TextEdit> {
+        self.edits.lock().unwrap().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_tools() {
+    let uri = "file:///main.x";
+    let statement = Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("\u{e9}")]), ws(" "), punct("="), ws(" "), Leaf(T::Literal, "1"), punct(";")]);
+    let function = Node(E::Definition, vec![kw("fn"), ws(" "), name("f"), punct("("), punct(")"), ws(" "), Node(E::Container, vec![punct("{"), ws("\n"), punct("}")])]);
+    let (text, root) = Node(E::Root, vec![statement, ws("\n"), name("\u{e9}"), ws("\n"), function]).build();
+    let service = TestService { root: Some(root), ..TestService::default() };
+    service.vfs.write_file(uri, text.as_str());
+    service.workspace.symbols.update_file_symbols(uri.to_string(), vec![SymbolInformation { name: "f".to_string(), role: E::Definition, uri: uri.into(), range: Range { start: 15, end: 25 }, container_name: None }]);
+    let server = McpServer::new(service);
+    let call = async |name: &str, args: Value| server.handle_tool_call(name, args).await;
+
+    let position = |offset: usize, line: u32, column: u32| json!({ "offset": offset, "line": line, "column": column });
+    let range = |start: Value, end: Value| json!({ "start": start, "end": end });
+    let (binding, use_site) = (range(position(4, 1, 5), position(6, 1, 6)), range(position(12, 2, 1), position(14, 2, 2)));
+    let function = range(position(15, 3, 1), position(25, 4, 2));
+    let at_use = json!({ "uri": uri, "line": 2, "column": 1 });
+
+    // Positions are given as byte offsets or 1-based lines and character columns, and reported as both.
+    assert!(call("hover", json!({ "uri": uri, "offset": 12 })).await.is_ok());
+    assert_eq!(call("definition", at_use.clone()).await, Ok(json!([{ "uri": uri, "range": binding }])));
+    assert_eq!(call("references", json!({ "uri": uri, "offset": 4 })).await, Ok(json!([{ "uri": uri, "range": binding }, { "uri": uri, "range": use_site }])));
+    let symbols = call("symbols", json!({ "uri": uri, "query": "f", "roles": ["definition"] })).await.unwrap();
+    assert_eq!(symbols, json!([{ "name": "f", "kind": "Function", "role": "definition", "detail": null, "container": null, "range": function }]));
+    let outline = call("outline", json!({ "uri": uri })).await.unwrap();
+    assert_eq!((&outline[0]["name"], &outline[0]["range"], &outline[0]["selection_range"]), (&json!("f"), &function, &range(position(18, 3, 4), position(19, 3, 5))));
+    let search = call("search", json!({ "query": "f" })).await.unwrap();
+    assert_eq!((&search[0]["name"], &search[0]["location"]), (&json!("f"), &json!({ "uri": uri, "range": function })));
+    let completion = call("completion", at_use.clone()).await.unwrap();
+    assert!(completion.as_array().unwrap().iter().any(|item| item["label"] == "\u{e9}"));
+    assert_eq!(call("diagnostics", json!({ "uri": uri })).await, Ok(json!([])));
+    assert!(call("folding_ranges", json!({ "uri": uri })).await.unwrap().is_array());
+    assert!(call("syntax_tree", json!({ "uri": uri, "max_depth": 1 })).await.unwrap().as_str().unwrap().starts_with("Root [Root] 0..25\n  Statement [Statement] 0..11\n"));
+
+    // Rename reports its edits with positions.
+    let rename = call("rename", json!({ "uri": uri, "line": 1, "column": 5, "new_name": "x" })).await.unwrap();
+    assert_eq!(rename, json!({ "changes": { uri: [{ "range": binding, "new_text": "x" }, { "range": use_site, "new_text": "x" }] } }));
+
+    // Formatting edits are applied, and broken edits are a tool error instead of a panic.
+    *server.service.edits.lock().unwrap() = vec![TextEdit { range: Range { start: 6, end: 7 }, new_text: "  ".to_string() }, TextEdit { range: Range { start: 3, end: 4 }, new_text: String::new() }];
+    let format = call("format", json!({ "uri": uri, "apply": true })).await.unwrap();
+    assert_eq!(format["formatted"], "let\u{e9}  = 1;\n\u{e9}\nfn f() {\n}");
+    assert_eq!(format["edits"][1]["range"], range(position(3, 1, 4), position(4, 1, 5)));
+    assert_eq!(server.service.get_source(uri).map(|source| source.length()), Some(text.len()));
+    for (start, end) in [(4, 5), (20, 40), (4, 3)] {
+        *server.service.edits.lock().unwrap() = vec![TextEdit { range: Range { start, end }, new_text: String::new() }];
+        assert_eq!(call("format", json!({ "uri": uri })).await, Err(ToolError::ToolFailed(format!("Invalid edit range {start}..{end}"))))
+    }
+    *server.service.edits.lock().unwrap() = vec![TextEdit { range: Range { start: 0, end: 4 }, new_text: String::new() }, TextEdit { range: Range { start: 2, end: 3 }, new_text: String::new() }];
+    assert!(call("format", json!({ "uri": uri })).await.is_err());
+
+    assert_eq!(call("set_file_content", json!({ "uri": uri, "content": "x" })).await, Ok(json!("Updated file:///main.x (1 bytes)")));
+    assert_eq!(server.service.get_source(uri).map(|source| source.length()), Some(1));
+    assert_eq!(call("semantic_search", json!({ "query": "f" })).await, Err(ToolError::ToolFailed("Semantic search is not enabled".to_string())));
+    assert_eq!(call("definition", json!({ "uri": uri })).await, Err(ToolError::ToolFailed("Missing offset or line/column".to_string())));
+    assert_eq!(call("unknown", json!({})).await, Err(ToolError::UnknownTool("unknown".to_string())));
+
+    // Unknown tools are protocol errors, failures of known tools are tool results.
+    let tools_call = async |name: &str| server.handle_message(json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": { "name": name, "arguments": {} } })).await.unwrap();
+    let unknown = tools_call("unknown").await;
+    assert_eq!(unknown.error.map(|error| (error.code, error.message)), Some((-32602, "Unknown tool: unknown".to_string())));
+    let failed = tools_call("definition").await.result.unwrap();
+    assert_eq!((&failed["isError"], &failed["content"][0]["text"]), (&json!(true), &json!("Missing uri")));
+}
+#[cfg(feature = "axum")]
+mod http {
+    use oak_mcp::{
+        McpServer,
+        http::{HttpOptions, SESSION_HEADER},
+    };
+    use oak_rust::RustLanguageService;
+    use oak_vfs::MemoryVfs;
+    use serde_json::{Value, json};
+    use std::{net::SocketAddr, time::Duration};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    /// Serves a server over HTTP on a free local port.
+    async fn serve() -> SocketAddr {
+        serve_with(HttpOptions::default()).await
+    }
+
+    async fn serve_with(options: HttpOptions) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(McpServer::new(RustLanguageService::new(MemoryVfs::new())).serve_http_with(listener, options));
+        address
+    }
+
+    /// Starts a session and returns its ID.
+    async fn initialize(address: SocketAddr) -> String {
+        let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }).to_string();
+        let (_, head, _) = request(address, "POST", "/mcp", &[], &initialize).await;
+        head.lines().find_map(|line| line.strip_prefix(&format!("{SESSION_HEADER}: "))).unwrap().to_string()
+    }
+
+    /// Sends a request and returns the status, the headers and the body of the response.
+    async fn request(address: SocketAddr, method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let mut request = format!("{method} {path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n", body.len());
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"))
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, head.to_ascii_lowercase(), body.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_streamable_post() {
+        let address = serve().await;
+        let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "protocolVersion": "2025-03-26" } }).to_string();
+        let (status, head, body) = request(address, "POST", "/mcp", &[("Accept", "application/json, text/event-stream")], &initialize).await;
+        assert_eq!(status, 200);
+        let response: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["id"], 1);
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+        let session = head.lines().find_map(|line| line.strip_prefix(&format!("{SESSION_HEADER}: "))).unwrap().to_string();
+
+        // Later requests need the session, and batches are answered with a batch.
+        let batch = json!([{ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }, { "jsonrpc": "2.0", "method": "notifications/initialized" }]).to_string();
+        let (status, _, body) = request(address, "POST", "/mcp", &[(SESSION_HEADER, &session)], &batch).await;
+        assert_eq!(status, 200);
+        let responses: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(responses[0]["id"], 2);
+        assert!(responses[0]["result"]["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+        assert_eq!(request(address, "POST", "/mcp", &[], &batch).await.0, 400);
+        assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, "unknown")], &batch).await.0, 404);
+
+        assert_eq!(request(address, "DELETE", "/mcp", &[(SESSION_HEADER, &session)], "").await.0, 200);
+        assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, &session)], &batch).await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_session_expiry() {
+        let ping = json!({ "jsonrpc": "2.0", "id": 2, "method": "ping" }).to_string();
+
+        // The least recently used session ends when there are too many.
+        let address = serve_with(HttpOptions::default().with_max_sessions(2)).await;
+        let (first, second) = (initialize(address).await, initialize(address).await);
+        assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, &first)], &ping).await.0, 200);
+        let third = initialize(address).await;
+        assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, &second)], &ping).await.0, 404);
+        for session in [&first, &third] {
+            assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, session)], &ping).await.0, 200);
+        }
+
+        // Sessions end after being idle for too long, but requests keep them alive.
+        let address = serve_with(HttpOptions::default().with_idle_timeout(Duration::from_millis(300))).await;
+        let (idle, active) = (initialize(address).await, initialize(address).await);
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, &active)], &ping).await.0, 200);
+        }
+        assert_eq!(request(address, "POST", "/mcp", &[(SESSION_HEADER, &idle)], &ping).await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_origin_validation() {
+        let address = serve().await;
+        let initialize = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }).to_string();
+        let own = format!("http://{address}");
+        for (origin, status) in [(own.as_str(), 200), ("http://localhost:3000", 200), ("http://[::1]:8080", 200), ("https://example.com", 403), ("http://localhost.example.com", 403), ("null", 403)] {
+            assert_eq!(request(address, "POST", "/mcp", &[("Origin", origin)], &initialize).await.0, status, "{origin}");
+        }
+    }
+}
@@ -6,6 +6,7 @@
 //! like "Go to Definition" and "Find All References".
 use core::range::Range;
 use oak_core::{Language, TokenType, language::UniversalTokenRole, tree::RedNode, visitor::Visitor};
+use oak_resolver::{ScopeProvider, UniversalScopeProvider};
 
 /// Represents a location in a source file.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
@@ -31,6 +32,9 @@ pub trait ReferencesProvider<L: Language> {
 }
 
 /// A helper to find all references of a name in a tree.
+///
+/// This matches every name token with the same text, whatever scope it is in; use
+/// [`ScopedReferenceFinder`] to find the references of one particular binding.
 pub struct SimpleReferenceFinder<'a, L: Language> {
     /// The name of the symbol to find references for.
     name: &'a str,
@@ -73,3 +77,44 @@ impl<'a, L: Language> Visitor<'a, L> for SimpleReferenceFinder<'a, L> {
         }
     }
 }
+
+/// The references of a name found by a [`ScopedReferenceFinder`].
+#[derive(Debug, Clone)]
+pub struct ScopedReferences {
+    /// The referenced name.
+    pub name: String,
+    /// The occurrences of the name in the file, including its declaration.
+    pub locations: Vec<Location>,
+    /// Whether the name is bound in an inner scope of the file, so that other files cannot refer to it.
+    pub local: bool,
+}
+
+/// A helper to find the references of a name through the scope graph of its file.
+pub struct ScopedReferenceFinder;
+
+impl ScopedReferenceFinder {
+    /// Finds the references of the name at `offset`, with the scope graph built from universal roles.
+    ///
+    /// Returns `None` if there is no name at `offset`. See [`find_with`](Self::find_with).
+    pub fn find<L: Language>(root: &RedNode<L>, offset: usize, source: &str, uri: impl Into<oak_core::Arc<str>>) -> Option<ScopedReferences> {
+        Self::find_with(&UniversalScopeProvider, root, offset, source, uri)
+    }
+
+    /// Finds the references of the name at `offset`, with the scope graph built by `provider`.
+    ///
+    /// If the name resolves to a binding, only the occurrences of that binding are returned, so
+    /// that two local variables of the same name in different functions are told apart. Otherwise
+    /// every name token with the same text is returned, as with [`SimpleReferenceFinder`].
+    pub fn find_with<L: Language>(provider: &impl ScopeProvider<L>, root: &RedNode<L>, offset: usize, source: &str, uri: impl Into<oak_core::Arc<str>>) -> Option<ScopedReferences> {
+        let uri = uri.into();
+        let graph = provider.scope_graph(root, &source);
+        if let Some(id) = graph.definition_at(offset) {
+            let binding = graph.binding(id);
+            let locations = graph.occurrences(id, true).into_iter().map(|range| Location { uri: uri.clone(), range }).collect();
+            return Some(ScopedReferences { name: binding.name.clone(), locations, local: binding.scope != graph.root() });
+        }
+        let leaf = root.leaf_at_offset(offset).filter(|leaf| leaf.kind.is_universal(UniversalTokenRole::Name))?;
+        let name = &source[leaf.span.start..leaf.span.end];
+        Some(ScopedReferences { name: name.to_string(), locations: SimpleReferenceFinder::find(root, name, source, uri), local: false })
+    }
+}
@@ -0,0 +1,62 @@
+use oak_highlight::LanguageRegistry;
+
+/// Returns a registry with the languages enabled by the cargo features of this crate.
+///
+/// Every language is behind a feature of its name (e.g. `rust`), so a REPL only pulls in the
+/// lexers it highlights. Input is highlighted by its tokens alone, which is fast and unaffected by
+/// incomplete input.
+#[allow(unused_mut)]
+pub fn languages() -> LanguageRegistry {
+    let mut registry = LanguageRegistry::new();
+    #[cfg(feature = "css")]
+    registry.register("css", &[], &["css"], |highlighter, source, theme| {
+        let language = oak_css::language::CssLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_css::lexer::CssLexer::new(&language))
+    });
+    #[cfg(feature = "html")]
+    registry.register("html", &["xhtml"], &["html", "htm", "xhtml"], |highlighter, source, theme| {
+        let language = oak_html::HtmlLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_html::HtmlLexer::new(&language))
+    });
+    #[cfg(feature = "javascript")]
+    registry.register("javascript", &["js", "ecmascript"], &["js", "mjs", "cjs", "jsx"], |highlighter, source, theme| {
+        let language = oak_javascript::language::JavaScriptLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_javascript::lexer::JavaScriptLexer::new(&language))
+    });
+    #[cfg(feature = "json")]
+    registry.register("json", &["jsonc", "json5"], &["json", "jsonc", "json5"], |highlighter, source, theme| {
+        let language = oak_json::language::JsonLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_json::lexer::JsonLexer::new(&language))
+    });
+    #[cfg(feature = "markdown")]
+    registry.register("markdown", &["md"], &["md", "markdown"], |highlighter, source, theme| {
+        let language = oak_markdown::MarkdownLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_markdown::MarkdownLexer::new(&language))
+    });
+    #[cfg(feature = "python")]
+    registry.register("python", &["py", "python3"], &["py", "pyi"], |highlighter, source, theme| {
+        let language = oak_python::PythonLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_python::PythonLexer::new(&language))
+    });
+    #[cfg(feature = "rust")]
+    registry.register("rust", &["rs"], &["rs"], |highlighter, source, theme| {
+        let language = oak_rust::RustLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_rust::RustLexer::new(&language))
+    });
+    #[cfg(feature = "sql")]
+    registry.register("sql", &[], &["sql"], |highlighter, source, theme| {
+        let language = oak_sql::language::SqlLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_sql::lexer::SqlLexer::new(&language))
+    });
+    #[cfg(feature = "toml")]
+    registry.register("toml", &[], &["toml", "Cargo.lock"], |highlighter, source, theme| {
+        let language = oak_toml::language::TomlLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_toml::lexer::TomlLexer::new(&language))
+    });
+    #[cfg(feature = "yaml")]
+    registry.register("yaml", &["yml"], &["yaml", "yml"], |highlighter, source, theme| {
+        let language = oak_yaml::YamlLanguage::default();
+        highlighter.highlight_with_lexer(source, theme, &oak_yaml::YamlLexer::new(&language))
+    });
+    registry
+}
@@ -10,9 +10,13 @@ use crossterm::{
     execute,
     terminal::{self, Clear, ClearType},
 };
-use oak_highlight::{AnsiExporter, Exporter, HighlightResult, OakHighlighter};
+use oak_highlight::{AnsiExporter, Exporter, HighlightResult, OakHighlighter, Theme};
 use std::io::{self, Write};
 
+mod languages;
+
+pub use crate::languages::languages;
+
 use std::{
     error::Error,
     fmt::{Display, Formatter},
@@ -67,10 +71,18 @@ pub enum HandleResult {
 /// Interface for language integration in the REPL.
 pub trait ReplHandler {
     /// Get syntax highlighting for the given code.
+    ///
+    /// Returns `None` by default, so the code is highlighted as the [`language`](Self::language).
     fn highlight<'a>(&self, _code: &'a str) -> Option<HighlightResult<'a>> {
         None
     }
 
+    /// The name, an alias or an extension of the language, to highlight the code through the
+    /// registry of the REPL's highlighter when [`highlight`](Self::highlight) returns `None`.
+    fn language(&self) -> Option<&str> {
+        None
+    }
+
     /// The prompt to display. `is_continuation` is true for multi-line input.
     fn prompt(&self, is_continuation: bool) -> &str;
 
@@ -152,12 +164,34 @@ impl LineBuffer {
 pub struct OakRepl<H: ReplHandler> {
     /// The handler that implements language-specific logic.
     handler: H,
+    /// Highlights the input as the language of the handler.
+    highlighter: OakHighlighter,
+    /// The theme of the highlighted input.
+    theme: Theme,
 }
 
 impl<H: ReplHandler> OakRepl<H> {
-    /// Create a new Oak REPL with the given handler.
+    /// Create a new Oak REPL with the given handler, highlighting the [`languages`] enabled by
+    /// the cargo features of this crate.
     pub fn new(handler: H) -> Self {
-        Self { handler }
+        Self { handler, highlighter: OakHighlighter::new().with_registry(languages()), theme: Theme::OneDarkPro }
+    }
+
+    /// Set the highlighter, and with it the languages that can be highlighted.
+    pub fn with_highlighter(mut self, highlighter: OakHighlighter) -> Self {
+        self.highlighter = highlighter;
+        self
+    }
+
+    /// Set the theme of the highlighted input.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Highlight a line, by the handler or else as the handler's language.
+    fn highlight<'a>(&self, line: &'a str) -> Option<HighlightResult<'a>> {
+        self.handler.highlight(line).or_else(|| self.highlighter.highlight(line, self.handler.language()?, self.theme).ok())
     }
 
     /// Run the REPL loop.
@@ -165,7 +199,6 @@ impl<H: ReplHandler> OakRepl<H> {
         let mut stdout = io::stdout();
         let mut line_buf = LineBuffer::new();
         let mut is_continuation = false;
-        let _highlighter = OakHighlighter::new();
         let exporter = AnsiExporter;
 
         terminal::enable_raw_mode()?;
@@ -178,7 +211,7 @@ impl<H: ReplHandler> OakRepl<H> {
             let current_line_text = &line_buf.lines[line_buf.current_line];
 
             // Syntax highlighting
-            let displayed_text = if let Some(highlighted) = self.handler.highlight(current_line_text) { exporter.export(&highlighted) } else { current_line_text.clone() };
+            let displayed_text = if let Some(highlighted) = self.highlight(current_line_text) { exporter.export(&highlighted) } else { current_line_text.clone() };
 
             write!(stdout, "{}{}", prompt, displayed_text)?;
 
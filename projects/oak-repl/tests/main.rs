@@ -0,0 +1,37 @@
+use oak_highlight::{OakHighlighter, Theme};
+use oak_repl::languages;
+
+const LANGUAGES: [(&str, bool, &str, &str); 10] = [
+    ("css", cfg!(feature = "css"), "style.css", "a { color: red; }"),
+    ("html", cfg!(feature = "html"), "index.htm", "<p class=\"a\">text</p>"),
+    ("javascript", cfg!(feature = "javascript"), "main.mjs", "const a = 1;"),
+    ("json", cfg!(feature = "json"), "package.json", "{\"a\": [1, true]}"),
+    ("markdown", cfg!(feature = "markdown"), "readme.md", "# Title\n\ntext"),
+    ("python", cfg!(feature = "python"), "main.py", "def f(x):\n    return x\n"),
+    ("rust", cfg!(feature = "rust"), "src/main.rs", "fn main() { let x = 1; }"),
+    ("sql", cfg!(feature = "sql"), "query.sql", "SELECT a FROM t;"),
+    ("toml", cfg!(feature = "toml"), "Cargo.lock", "name = \"oak\"\n"),
+    ("yaml", cfg!(feature = "yaml"), "config.yml", "a: 1\n"),
+];
+
+#[test]
+fn test_enabled_languages() {
+    let registry = languages();
+    let enabled: Vec<_> = LANGUAGES.iter().filter(|(_, enabled, _, _)| *enabled).map(|(name, _, _, _)| *name).collect();
+    assert_eq!(registry.languages().map(|language| language.name.as_str()).collect::<Vec<_>>(), enabled);
+
+    for (name, enabled, path, _) in LANGUAGES {
+        let resolved = registry.resolve(name).map(|language| language.name.as_str());
+        assert_eq!(resolved, enabled.then_some(name), "{name}");
+        assert_eq!(registry.resolve(path).map(|language| language.name.as_str()), enabled.then_some(name), "{path}")
+    }
+}
+
+#[test]
+fn test_highlight_languages() {
+    let highlighter = OakHighlighter::new().with_registry(languages());
+    for (name, _, _, code) in LANGUAGES {
+        let result = highlighter.highlight(code, name, Theme::OneDarkPro).unwrap_or_else(|error| panic!("{name}: {error}"));
+        assert_eq!(result.segments.iter().map(|segment| segment.text.as_ref()).collect::<String>(), code, "{name}")
+    }
+}
@@ -0,0 +1,154 @@
+/// A fuzzy match of a query in a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// The score of the match; higher is better.
+    pub score: i32,
+    /// The character indices of the name that the query characters matched.
+    pub positions: Vec<usize>,
+}
+
+/// The score of every matched character.
+const MATCH: i32 = 16;
+/// The bonus for matching the first character of the name.
+const FIRST: i32 = 24;
+/// The bonus for matching the first character of a word, after `_`, `-`, `.`, `:`, `/` or a space.
+const WORD_START: i32 = 20;
+/// The bonus for matching a camel hump, an upper case letter after a lower case one or a digit after a letter.
+const CAMEL_HUMP: i32 = 18;
+/// The bonus for matching right after the previous query character.
+const CONSECUTIVE: i32 = 12;
+/// The bonus for matching with the same case.
+const SAME_CASE: i32 = 1;
+/// The penalty for every skipped character between two matches.
+const GAP: i32 = 2;
+/// The penalty for every skipped character before the first match, up to [`MAX_LEADING`].
+const LEADING: i32 = 1;
+/// The maximum number of leading characters that are penalized.
+const MAX_LEADING: i32 = 8;
+/// The bonus for a name that equals the query, ignoring case.
+const EXACT: i32 = 64;
+
+/// Returns the bonus for a match at index `index` of a name.
+fn boundary_bonus(name: &[char], index: usize) -> i32 {
+    if index == 0 {
+        return FIRST;
+    }
+    let (previous, current) = (name[index - 1], name[index]);
+    if matches!(previous, '_' | '-' | '.' | ':' | '/' | ' ' | '$' | '@') && current.is_alphanumeric() {
+        WORD_START
+    }
+    else if (previous.is_lowercase() && current.is_uppercase()) || (previous.is_alphabetic() && current.is_ascii_digit()) {
+        CAMEL_HUMP
+    }
+    else {
+        0
+    }
+}
+
+/// Picks the higher scoring of two alignments, preferring the first on ties.
+fn better(a: Option<(i32, usize)>, b: Option<(i32, usize)>) -> Option<(i32, usize)> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Folds a character to lower case for case-insensitive comparison.
+fn fold(c: char) -> char {
+    if c.is_ascii() { c.to_ascii_lowercase() } else { c.to_lowercase().next().unwrap_or(c) }
+}
+
+/// Matches a query as a case-insensitive subsequence of a name.
+///
+/// Among all ways to match, the one with the highest score is chosen: matches at the start of the
+/// name, at the start of words and on camel humps score higher, as do runs of consecutive matches,
+/// while skipped characters cost a little. So `HLV` matches `HighlightVisitor` on its humps and ranks
+/// it above names where the letters are merely scattered. Returns `None` if the query is not a
+/// subsequence of the name; an empty query matches every name with a score of zero.
+pub fn fuzzy_match(query: &str, name: &str) -> Option<FuzzyMatch> {
+    FuzzyMatcher::new(query).matches(name)
+}
+
+/// A query prepared for [`fuzzy_match`]ing many names, reusing its buffers between them.
+pub(crate) struct FuzzyMatcher {
+    query: Vec<char>,
+    query_folded: Vec<char>,
+    name: Vec<char>,
+    folded: Vec<char>,
+    /// `scores[i * n + j]` is the best score of matching the first `i + 1` query characters with the last one at `j`.
+    scores: Vec<Option<i32>>,
+    /// `from[i * n + j]` is where the previous query character matched in that best alignment.
+    from: Vec<usize>,
+}
+
+impl FuzzyMatcher {
+    /// Prepares a query, ignoring whitespace.
+    pub(crate) fn new(query: &str) -> Self {
+        let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+        let query_folded = query.iter().copied().map(fold).collect();
+        Self { query, query_folded, name: Vec::new(), folded: Vec::new(), scores: Vec::new(), from: Vec::new() }
+    }
+
+    /// Matches the query against a name.
+    pub(crate) fn matches(&mut self, name: &str) -> Option<FuzzyMatch> {
+        let (query, query_folded) = (&self.query, &self.query_folded);
+        if query.is_empty() {
+            return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+        }
+        self.name.clear();
+        self.name.extend(name.chars());
+        self.folded.clear();
+        self.folded.extend(self.name.iter().copied().map(fold));
+        let (name, folded) = (&self.name, &self.folded);
+        let (m, n) = (query.len(), name.len());
+        // Reject names that do not contain the query as a subsequence before scoring.
+        let mut rest = folded.iter();
+        if !query_folded.iter().all(|q| rest.any(|c| c == q)) {
+            return None;
+        }
+
+        self.scores.clear();
+        self.scores.resize(m * n, None);
+        self.from.clear();
+        self.from.resize(m * n, 0);
+        let (scores, from) = (&mut self.scores, &mut self.from);
+        for i in 0..m {
+            // The best score of the previous row at or before `j - 2`, with the gap to `j` already deducted.
+            let mut best: Option<(i32, usize)> = None;
+            for j in i..n {
+                if i > 0 && j >= 2 {
+                    let decayed = best.map(|(score, k)| (score - GAP, k));
+                    let skipped_one = scores[(i - 1) * n + j - 2].map(|score| (score - GAP, j - 2));
+                    best = better(decayed, skipped_one)
+                }
+                if folded[j] != query_folded[i] {
+                    continue;
+                }
+                let bonus = MATCH + boundary_bonus(name, j) + if name[j] == query[i] { SAME_CASE } else { 0 };
+                if i == 0 {
+                    scores[j] = Some(bonus - LEADING * (j as i32).min(MAX_LEADING));
+                    continue;
+                }
+                let consecutive = scores[(i - 1) * n + j - 1].map(|score| (score + CONSECUTIVE, j - 1));
+                if let Some((score, k)) = better(consecutive, best) {
+                    scores[i * n + j] = Some(score + bonus);
+                    from[i * n + j] = k;
+                }
+            }
+        }
+
+        let (mut score, end) = (m - 1..n).filter_map(|j| scores[(m - 1) * n + j].map(|score| (score, j))).max_by_key(|(score, j)| (*score, std::cmp::Reverse(*j)))?;
+        let mut positions = vec![0; m];
+        let mut j = end;
+        for i in (0..m).rev() {
+            positions[i] = j;
+            if i > 0 {
+                j = from[i * n + j]
+            }
+        }
+        if m == n {
+            score += EXACT
+        }
+        Some(FuzzyMatch { score, positions })
+    }
+}
@@ -0,0 +1,120 @@
+use super::{ImportProbe, ImportStrategy, uri};
+use oak_vfs::Vfs;
+
+/// Resolves Go import paths to package directories.
+///
+/// The nearest `go.mod` gives the module path: imports below it resolve inside the module, imports
+/// matching a `replace` directive with a local path resolve there, and other imports are looked up
+/// in the module's `vendor` directory and then below each configured root, like `$GOROOT/src`.
+#[derive(Debug, Clone, Default)]
+pub struct GoStrategy {
+    roots: Vec<String>,
+}
+
+/// The parts of a `go.mod` file the strategy needs.
+struct GoModule<'a> {
+    path: &'a str,
+    replaces: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> GoModule<'a> {
+    fn parse(text: &'a str) -> Option<Self> {
+        let mut path = None;
+        let mut replaces = Vec::new();
+        let mut in_replace_block = false;
+        for line in text.lines() {
+            let line = line.split("//").next().unwrap_or("").trim();
+            if in_replace_block {
+                if line == ")" {
+                    in_replace_block = false
+                }
+                else if let Some(replace) = Self::replace(line) {
+                    replaces.push(replace)
+                }
+            }
+            else if let Some(module) = line.strip_prefix("module ") {
+                path = Some(module.trim().trim_matches('"'))
+            }
+            else if let Some(rest) = line.strip_prefix("replace") {
+                let rest = rest.trim();
+                if rest == "(" {
+                    in_replace_block = true
+                }
+                else if let Some(replace) = Self::replace(rest) {
+                    replaces.push(replace)
+                }
+            }
+        }
+        Some(Self { path: path?, replaces })
+    }
+
+    /// Parses `old [version] => new [version]`, keeping only replacements with a local path.
+    fn replace(line: &'a str) -> Option<(&'a str, &'a str)> {
+        let (old, new) = line.split_once("=>")?;
+        let old = old.split_whitespace().next()?;
+        let new = new.split_whitespace().next()?;
+        (new.starts_with("./") || new.starts_with("../") || new.starts_with('/')).then_some((old, new))
+    }
+}
+
+/// Returns the rest of an import path below a module or package path, if it is one.
+fn below<'p>(import_path: &'p str, prefix: &str) -> Option<&'p str> {
+    match import_path.strip_prefix(prefix)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+impl GoStrategy {
+    /// Creates a strategy without roots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory URI containing packages by import path, like `$GOROOT/src` or `$GOPATH/src`.
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.roots.push(root.into());
+        self
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for GoStrategy {
+    fn name(&self) -> &str {
+        "go"
+    }
+
+    fn accepts(&self, base_uri: &str) -> bool {
+        uri::has_extension(base_uri, &["go"])
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let directory = uri::parent(base_uri);
+        if import_path.starts_with("./") || import_path.starts_with("../") {
+            let target = uri::join(directory, import_path);
+            return if probe.dir(&target) { Some(target) } else { None };
+        }
+        for ancestor in uri::ancestors(directory) {
+            let Some(text) = probe.read(&uri::join(ancestor, "go.mod"))
+            else {
+                continue;
+            };
+            if let Some(module) = GoModule::parse(&text) {
+                let mut targets = Vec::new();
+                if let Some(rest) = below(import_path, module.path) {
+                    targets.push(uri::join(ancestor, rest))
+                }
+                for (old, new) in &module.replaces {
+                    if let Some(rest) = below(import_path, old) {
+                        targets.push(uri::join(&uri::join(ancestor, new), rest))
+                    }
+                }
+                targets.push(uri::join(ancestor, &format!("vendor/{}", import_path)));
+                if let Some(target) = targets.into_iter().find(|target| probe.dir(target)) {
+                    return Some(target);
+                }
+            }
+            break;
+        }
+        self.roots.iter().map(|root| uri::join(root, import_path)).find(|target| probe.dir(target))
+    }
+}
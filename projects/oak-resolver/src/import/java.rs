@@ -0,0 +1,66 @@
+use super::{ImportProbe, ImportStrategy, read_text, uri};
+use oak_vfs::Vfs;
+
+/// Resolves Java imports against source roots, like a classpath of source directories.
+///
+/// The source root of the importing file is found by stripping the directories of its `package`
+/// declaration. `a.b.C` resolves to `a/b/C.java`, a static or nested import like `a.b.C.d` to the
+/// file of its outermost class, and an on-demand import like `a.b.*` to the package directory.
+#[derive(Debug, Clone, Default)]
+pub struct JavaStrategy {
+    classpath: Vec<String>,
+}
+
+impl JavaStrategy {
+    /// Creates a strategy without additional source roots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a source root directory URI to search after the root of the importing file.
+    pub fn with_classpath(mut self, root: impl Into<String>) -> Self {
+        self.classpath.push(root.into());
+        self
+    }
+
+    /// Returns the source root of a file from its `package` declaration.
+    fn source_root<V: Vfs>(vfs: &V, base_uri: &str) -> Option<String> {
+        let text = read_text(vfs, base_uri)?;
+        let package = text.lines().map(str::trim).find_map(|line| line.strip_prefix("package "))?.trim_end_matches(';').trim();
+        let depth = package.split('.').count();
+        uri::ancestors(uri::parent(base_uri)).nth(depth).map(String::from)
+    }
+
+    fn class<V: Vfs>(probe: &mut ImportProbe<'_, V>, root: &str, segments: &[&str]) -> Option<String> {
+        if segments.last() == Some(&"*") {
+            let target = uri::join(root, &segments[..segments.len() - 1].join("/"));
+            return if probe.dir(&target) { Some(target) } else { None };
+        }
+        // Drop trailing segments naming members or nested classes until a class file is found.
+        (1..=segments.len()).rev().map(|len| format!("{}.java", uri::join(root, &segments[..len].join("/")))).find(|candidate| probe.file(candidate))
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for JavaStrategy {
+    fn name(&self) -> &str {
+        "java"
+    }
+
+    fn accepts(&self, base_uri: &str) -> bool {
+        uri::has_extension(base_uri, &["java"])
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let import_path = import_path.trim().trim_start_matches("static ").trim_end_matches(';').trim();
+        let segments: Vec<&str> = import_path.split('.').map(str::trim).collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return None;
+        }
+        // Without a package declaration, the file is in the unnamed package at its own directory.
+        let root = Self::source_root(probe.vfs(), base_uri).unwrap_or_else(|| uri::parent(base_uri).to_string());
+        if let Some(class) = Self::class(probe, &root, &segments) {
+            return Some(class);
+        }
+        self.classpath.iter().find_map(|root| Self::class(probe, root, &segments))
+    }
+}
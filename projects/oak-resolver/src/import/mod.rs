@@ -0,0 +1,201 @@
+use crate::ModuleResolver;
+use oak_core::Source;
+use oak_vfs::Vfs;
+
+mod go;
+mod java;
+mod node;
+mod python;
+mod rust;
+mod uri;
+
+pub use go::GoStrategy;
+pub use java::JavaStrategy;
+pub use node::NodeStrategy;
+pub use python::PythonStrategy;
+pub use rust::RustStrategy;
+
+/// The outcome of resolving an import with an [`ImportResolver`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportResolution {
+    /// The URI of the resolved file, or of the directory for package based languages like Go.
+    pub uri: Option<String>,
+    /// The name of the strategy that resolved the import.
+    pub strategy: Option<String>,
+    /// The URIs that were looked at, in order, including manifests like `package.json`.
+    pub candidates: Vec<String>,
+}
+
+impl ImportResolution {
+    /// Checks whether the import was resolved.
+    pub fn is_resolved(&self) -> bool {
+        self.uri.is_some()
+    }
+
+    /// Describes why an import could not be resolved, for an "unresolved import" diagnostic.
+    pub fn explain(&self, import_path: &str) -> String {
+        match &self.uri {
+            Some(uri) => format!("`{}` resolves to {}", import_path, uri),
+            None if self.candidates.is_empty() => format!("cannot resolve import `{}`: no resolution strategy applies", import_path),
+            None => {
+                let mut message = format!("cannot resolve import `{}`, tried:", import_path);
+                for candidate in &self.candidates {
+                    message.push_str("\n  - ");
+                    message.push_str(candidate)
+                }
+                message
+            }
+        }
+    }
+}
+
+/// A view of a [`Vfs`] that records every URI an [`ImportStrategy`] looks at.
+pub struct ImportProbe<'a, V> {
+    vfs: &'a V,
+    candidates: Vec<String>,
+}
+
+impl<'a, V: Vfs> ImportProbe<'a, V> {
+    /// Creates a probe without candidates.
+    pub fn new(vfs: &'a V) -> Self {
+        Self { vfs, candidates: Vec::new() }
+    }
+
+    /// Returns the underlying VFS, for lookups that should not be reported as candidates.
+    pub fn vfs(&self) -> &'a V {
+        self.vfs
+    }
+
+    /// Checks whether a file exists at a URI and records it as a candidate.
+    pub fn file(&mut self, uri: &str) -> bool {
+        self.candidates.push(uri.to_string());
+        self.vfs.is_file(uri)
+    }
+
+    /// Checks whether a directory exists at a URI and records it as a candidate.
+    pub fn dir(&mut self, uri: &str) -> bool {
+        self.candidates.push(uri.to_string());
+        self.vfs.is_dir(uri)
+    }
+
+    /// Reads the file at a URI, e.g. a manifest, and records it as a candidate.
+    pub fn read(&mut self, uri: &str) -> Option<String> {
+        self.candidates.push(uri.to_string());
+        read_text(self.vfs, uri)
+    }
+
+    /// Returns the candidates recorded so far.
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// Returns the recorded candidates.
+    pub fn into_candidates(self) -> Vec<String> {
+        self.candidates
+    }
+}
+
+/// A language specific way to find the file an import refers to.
+///
+/// Strategies only look at the [`Vfs`] through an [`ImportProbe`], so they work the same on disk,
+/// in memory and in archives, and every candidate they try ends up in the [`ImportResolution`].
+pub trait ImportStrategy<V: Vfs>: Send + Sync {
+    /// Returns a short name of the strategy, e.g. `node`.
+    fn name(&self) -> &str;
+
+    /// Checks whether this strategy handles imports written in the file at `base_uri`.
+    fn accepts(&self, base_uri: &str) -> bool {
+        let _ = base_uri;
+        true
+    }
+
+    /// Resolves an import written in the file at `base_uri`.
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String>;
+}
+
+/// The language agnostic strategy of [`StandardResolver`](crate::StandardResolver): the import path
+/// is joined onto the directory of the importing file, then onto each root.
+#[derive(Debug, Clone, Default)]
+pub struct PathStrategy {
+    roots: Vec<String>,
+}
+
+impl PathStrategy {
+    /// Creates a strategy without roots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a root directory URI to search after the directory of the importing file.
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.roots.push(root.into());
+        self
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for PathStrategy {
+    fn name(&self) -> &str {
+        "path"
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let relative = uri::join(uri::parent(base_uri), import_path);
+        if probe.file(&relative) {
+            return Some(relative);
+        }
+        self.roots.iter().map(|root| uri::join(root, import_path)).find(|candidate| probe.file(candidate))
+    }
+}
+
+/// A [`ModuleResolver`] that tries a list of [`ImportStrategy`]s against a [`Vfs`].
+pub struct ImportResolver<V: Vfs> {
+    vfs: V,
+    strategies: Vec<Box<dyn ImportStrategy<V>>>,
+}
+
+impl<V: Vfs> ImportResolver<V> {
+    /// Creates a resolver without strategies.
+    pub fn new(vfs: V) -> Self {
+        Self { vfs, strategies: Vec::new() }
+    }
+
+    /// Creates a resolver with the strategies for Node, Python, Rust, Go and Java, falling back to a [`PathStrategy`].
+    pub fn with_defaults(vfs: V) -> Self {
+        Self::new(vfs).with_strategy(NodeStrategy::new()).with_strategy(PythonStrategy::new()).with_strategy(RustStrategy::new()).with_strategy(GoStrategy::new()).with_strategy(JavaStrategy::new()).with_strategy(PathStrategy::new())
+    }
+
+    /// Adds a strategy, tried after the ones added before.
+    pub fn with_strategy(mut self, strategy: impl ImportStrategy<V> + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    /// Returns the VFS imports are resolved against.
+    pub fn vfs(&self) -> &V {
+        &self.vfs
+    }
+
+    /// Resolves an import written in the file at `base_uri` with the first strategy that accepts the file and finds it.
+    pub fn resolve_import(&self, base_uri: &str, import_path: &str) -> ImportResolution {
+        let mut probe = ImportProbe::new(&self.vfs);
+        for strategy in self.strategies.iter().filter(|strategy| strategy.accepts(base_uri)) {
+            if let Some(uri) = strategy.resolve(&mut probe, base_uri, import_path) {
+                tracing::trace!("resolved `{}` from {} to {} with the {} strategy", import_path, base_uri, uri, strategy.name());
+                return ImportResolution { uri: Some(uri), strategy: Some(strategy.name().to_string()), candidates: probe.into_candidates() };
+            }
+        }
+        ImportResolution { uri: None, strategy: None, candidates: probe.into_candidates() }
+    }
+}
+
+impl<V: Vfs> ModuleResolver for ImportResolver<V> {
+    fn resolve(&self, base_uri: &str, import_path: &str) -> Option<String> {
+        self.resolve_import(base_uri, import_path).uri
+    }
+}
+
+/// Reads the whole text of the file at a URI.
+fn read_text<V: Vfs>(vfs: &V, uri: &str) -> Option<String> {
+    let source = vfs.get_source(uri)?;
+    Some(source.get_text_in((0..source.length()).into()).into_owned())
+}
@@ -0,0 +1,144 @@
+use super::{ImportProbe, ImportStrategy, uri};
+use oak_vfs::Vfs;
+use serde_json::Value;
+
+/// Resolves imports like Node.js and TypeScript do.
+///
+/// Relative imports are tried as a file, with each extension appended, and as a directory with a
+/// `package.json` `main` field or an `index` file. Bare imports are looked up in the `node_modules`
+/// directories of all ancestors; a package with `exports` in its `package.json` only exposes the
+/// subpaths listed there, picking conditions in the configured order.
+#[derive(Debug, Clone)]
+pub struct NodeStrategy {
+    extensions: Vec<String>,
+    conditions: Vec<String>,
+}
+
+impl Default for NodeStrategy {
+    fn default() -> Self {
+        Self { extensions: [".ts", ".tsx", ".d.ts", ".js", ".jsx", ".mjs", ".cjs", ".json"].into_iter().map(String::from).collect(), conditions: ["types", "import", "require", "node", "default"].into_iter().map(String::from).collect() }
+    }
+}
+
+impl NodeStrategy {
+    /// Creates a strategy for TypeScript and JavaScript files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the extensions appended to extensionless imports, e.g. `.js`, in order of preference.
+    pub fn with_extensions(mut self, extensions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the `exports` conditions to match, e.g. `import` or `require`, in order of preference.
+    pub fn with_conditions(mut self, conditions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.conditions = conditions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn file_or_directory<V: Vfs>(&self, probe: &mut ImportProbe<'_, V>, target: &str) -> Option<String> {
+        if let Some(file) = self.file(probe, target) {
+            return Some(file);
+        }
+        if let Some(manifest) = probe.read(&uri::join(target, "package.json"))
+            && let Ok(manifest) = serde_json::from_str::<Value>(&manifest)
+            && let Some(main) = manifest.get("main").and_then(Value::as_str)
+        {
+            let main = uri::join(target, main);
+            if let Some(file) = self.file(probe, &main).or_else(|| self.index(probe, &main)) {
+                return Some(file);
+            }
+        }
+        self.index(probe, target)
+    }
+
+    fn file<V: Vfs>(&self, probe: &mut ImportProbe<'_, V>, target: &str) -> Option<String> {
+        if probe.file(target) {
+            return Some(target.to_string());
+        }
+        self.extensions.iter().map(|extension| format!("{}{}", target, extension)).find(|candidate| probe.file(candidate))
+    }
+
+    fn index<V: Vfs>(&self, probe: &mut ImportProbe<'_, V>, directory: &str) -> Option<String> {
+        self.extensions.iter().map(|extension| format!("{}/index{}", directory.trim_end_matches('/'), extension)).find(|candidate| probe.file(candidate))
+    }
+
+    /// Resolves a subpath like `.` or `./utils` through the `exports` of a package, returning the target relative to the package.
+    fn exports<'v>(&self, exports: &'v Value, subpath: &str) -> Option<(&'v str, Option<String>)> {
+        let is_subpath_map = exports.as_object().is_some_and(|map| map.keys().any(|key| key.starts_with('.')));
+        if !is_subpath_map {
+            return if subpath == "." { self.target(exports).map(|target| (target, None)) } else { None };
+        }
+        let map = exports.as_object()?;
+        if let Some(value) = map.get(subpath) {
+            return self.target(value).map(|target| (target, None));
+        }
+        // The pattern with the longest prefix before its `*` wins.
+        let (key, star) = map
+            .keys()
+            .filter_map(|key| {
+                let (prefix, suffix) = key.split_once('*')?;
+                let star = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                Some((key, star))
+            })
+            .max_by_key(|(key, _)| key.find('*'))?;
+        self.target(&map[key]).map(|target| (target, Some(star.to_string())))
+    }
+
+    /// Picks the target of an `exports` entry, following conditions and fallback arrays.
+    fn target<'v>(&self, value: &'v Value) -> Option<&'v str> {
+        match value {
+            Value::String(target) => Some(target),
+            Value::Array(targets) => targets.iter().find_map(|target| self.target(target)),
+            Value::Object(map) => self.conditions.iter().find_map(|condition| map.get(condition).and_then(|target| self.target(target))),
+            _ => None,
+        }
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for NodeStrategy {
+    fn name(&self) -> &str {
+        "node"
+    }
+
+    fn accepts(&self, base_uri: &str) -> bool {
+        uri::has_extension(base_uri, &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"])
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let directory = uri::parent(base_uri);
+        if import_path.starts_with("./") || import_path.starts_with("../") || import_path.starts_with('/') || import_path == "." || import_path == ".." {
+            return self.file_or_directory(probe, &uri::join(directory, import_path));
+        }
+        // `@scope/name/sub/path` and `name/sub/path` split into the package name and the subpath.
+        let split = if import_path.starts_with('@') { import_path.match_indices('/').nth(1) } else { import_path.match_indices('/').next() };
+        let (name, subpath) = match split {
+            Some((index, _)) => (&import_path[..index], &import_path[index + 1..]),
+            None => (import_path, ""),
+        };
+        for ancestor in uri::ancestors(directory) {
+            if uri::file_name(ancestor) == "node_modules" {
+                continue;
+            }
+            let package = uri::join(ancestor, &format!("node_modules/{}", name));
+            let manifest = probe.read(&uri::join(&package, "package.json")).and_then(|manifest| serde_json::from_str::<Value>(&manifest).ok());
+            if let Some(exports) = manifest.as_ref().and_then(|manifest| manifest.get("exports")) {
+                let subpath = if subpath.is_empty() { ".".to_string() } else { format!("./{}", subpath) };
+                let (target, star) = self.exports(exports, &subpath)?;
+                let target = match star {
+                    Some(star) => target.replace('*', &star),
+                    None => target.to_string(),
+                };
+                let target = uri::join(&package, &target);
+                return if probe.file(&target) { Some(target) } else { None };
+            }
+            let target = if subpath.is_empty() { package } else { uri::join(&package, subpath) };
+            if let Some(file) = self.file_or_directory(probe, &target) {
+                return Some(file);
+            }
+        }
+        None
+    }
+}
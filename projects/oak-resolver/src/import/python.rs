@@ -0,0 +1,75 @@
+use super::{ImportProbe, ImportStrategy, uri};
+use oak_vfs::Vfs;
+
+/// Resolves imports like Python's import system does.
+///
+/// `a.b` is looked up as the package `a/b/__init__.py`, the module `a/b.py`, the stub `a/b.pyi`
+/// and finally the namespace package directory `a/b`, below the root of the package containing the
+/// importing file and then below each configured search path, like `sys.path`. Relative imports like
+/// `..a` start at the package of the importing file, going up one package per additional dot.
+#[derive(Debug, Clone, Default)]
+pub struct PythonStrategy {
+    search_paths: Vec<String>,
+}
+
+impl PythonStrategy {
+    /// Creates a strategy without search paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory URI to search, like an entry of `sys.path` or `site-packages`.
+    pub fn with_search_path(mut self, path: impl Into<String>) -> Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    fn module<V: Vfs>(probe: &mut ImportProbe<'_, V>, root: &str, segments: &[&str]) -> Option<String> {
+        let target = uri::join(root, &segments.join("/"));
+        if segments.is_empty() {
+            let init = uri::join(&target, "__init__.py");
+            return if probe.file(&init) { Some(init) } else { None };
+        }
+        let init = uri::join(&target, "__init__.py");
+        if probe.file(&init) {
+            return Some(init);
+        }
+        for extension in [".py", ".pyi"] {
+            let module = format!("{}{}", target, extension);
+            if probe.file(&module) {
+                return Some(module);
+            }
+        }
+        if probe.dir(&target) { Some(target) } else { None }
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for PythonStrategy {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn accepts(&self, base_uri: &str) -> bool {
+        uri::has_extension(base_uri, &["py", "pyi"])
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let dots = import_path.len() - import_path.trim_start_matches('.').len();
+        let segments: Vec<&str> = import_path[dots..].split('.').filter(|segment| !segment.is_empty()).collect();
+        let directory = uri::parent(base_uri);
+        if dots > 0 {
+            let package = uri::ancestors(directory).nth(dots - 1)?;
+            return Self::module(probe, package, &segments);
+        }
+        if segments.is_empty() {
+            return None;
+        }
+        // The directory above the outermost package containing the importing file.
+        let vfs = probe.vfs();
+        let root = uri::ancestors(directory).find(|ancestor| !vfs.is_file(&uri::join(ancestor, "__init__.py"))).unwrap_or(directory);
+        if let Some(module) = Self::module(probe, root, &segments) {
+            return Some(module);
+        }
+        self.search_paths.iter().find_map(|path| Self::module(probe, path, &segments))
+    }
+}
@@ -0,0 +1,88 @@
+use super::{ImportProbe, ImportStrategy, uri};
+use oak_vfs::Vfs;
+
+/// Resolves Rust modules following the `mod` file layout.
+///
+/// `mod a;` in `src/lib.rs`, `src/main.rs` or a `mod.rs` refers to `a.rs` or `a/mod.rs` next to
+/// it, while in `src/b.rs` it refers to `src/b/a.rs` or `src/b/a/mod.rs`. Paths like `crate::a::b`,
+/// `self::a` and `super::a` are followed module by module and resolve to the file of the innermost
+/// module they name, starting at the crate root found through the nearest `Cargo.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct RustStrategy;
+
+impl RustStrategy {
+    /// Creates a strategy for Rust files.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Checks whether a file is a crate root or a `mod.rs`, whose child modules live next to it.
+    fn owns_directory(file: &str) -> bool {
+        matches!(uri::file_name(file), "lib.rs" | "main.rs" | "mod.rs")
+    }
+
+    /// Returns the directory containing the files of the child modules of a module.
+    fn module_directory(file: &str) -> String {
+        if Self::owns_directory(file) { uri::parent(file).to_string() } else { file.trim_end_matches(".rs").to_string() }
+    }
+
+    fn child<V: Vfs>(probe: &mut ImportProbe<'_, V>, module: &str, name: &str) -> Option<String> {
+        let directory = Self::module_directory(module);
+        [format!("{}.rs", name), format!("{}/mod.rs", name)].into_iter().map(|path| uri::join(&directory, &path)).find(|candidate| probe.file(candidate))
+    }
+
+    fn parent<V: Vfs>(probe: &mut ImportProbe<'_, V>, module: &str) -> Option<String> {
+        let directory = if uri::file_name(module) == "mod.rs" { uri::parent(uri::parent(module)) } else { uri::parent(module) };
+        if Self::owns_directory(module) && uri::file_name(module) != "mod.rs" {
+            return None;
+        }
+        let name = uri::file_name(directory);
+        let up = uri::parent(directory);
+        [uri::join(up, &format!("{}.rs", name)), uri::join(directory, "mod.rs"), uri::join(directory, "lib.rs"), uri::join(directory, "main.rs")].into_iter().find(|candidate| probe.file(candidate))
+    }
+
+    fn crate_root<V: Vfs>(probe: &mut ImportProbe<'_, V>, base_uri: &str) -> Option<String> {
+        let vfs = probe.vfs();
+        let manifest = uri::ancestors(uri::parent(base_uri)).find(|ancestor| vfs.is_file(&uri::join(ancestor, "Cargo.toml")))?;
+        ["src/lib.rs", "src/main.rs"].into_iter().map(|path| uri::join(manifest, path)).find(|candidate| probe.file(candidate))
+    }
+}
+
+impl<V: Vfs> ImportStrategy<V> for RustStrategy {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn accepts(&self, base_uri: &str) -> bool {
+        uri::has_extension(base_uri, &["rs"])
+    }
+
+    fn resolve(&self, probe: &mut ImportProbe<'_, V>, base_uri: &str, import_path: &str) -> Option<String> {
+        let mut segments = import_path.trim_start_matches("::").split("::").map(str::trim).filter(|segment| !segment.is_empty()).peekable();
+        let mut module = match *segments.peek()? {
+            "crate" => Self::crate_root(probe, base_uri)?,
+            "self" => base_uri.to_string(),
+            "super" => base_uri.to_string(),
+            // `mod a;` or a path starting at a child module.
+            name => {
+                segments.next();
+                Self::child(probe, base_uri, name)?
+            }
+        };
+        if segments.peek() == Some(&"crate") || segments.peek() == Some(&"self") {
+            segments.next();
+        }
+        for segment in segments {
+            if segment == "super" {
+                module = Self::parent(probe, &module)?;
+                continue;
+            }
+            // The remaining segments name items inside the module.
+            match Self::child(probe, &module, segment) {
+                Some(child) => module = child,
+                None => break,
+            }
+        }
+        Some(module)
+    }
+}
@@ -0,0 +1,62 @@
+//! Path manipulation on URIs, for any scheme the VFS understands.
+
+/// Returns the length of the part of a URI that `..` cannot leave, e.g. `file:///`,
+/// `snapshot://<hash>/` or `zip:file:///a.jar!/`.
+fn root_len(uri: &str) -> usize {
+    if let Some(index) = uri.rfind("!/") {
+        return index + 2;
+    }
+    if let Some(index) = uri.find("://") {
+        let rest = index + 3;
+        return rest + uri[rest..].find('/').map(|i| i + 1).unwrap_or(uri.len() - rest);
+    }
+    if uri.starts_with('/') { 1 } else { 0 }
+}
+
+/// Returns the directory containing a file, without a trailing `/`, or the root if there is none.
+pub fn parent(uri: &str) -> &str {
+    let root = root_len(uri);
+    let path = uri[root..].trim_end_matches('/');
+    match path.rfind('/') {
+        Some(index) => &uri[..root + index],
+        None => &uri[..root],
+    }
+}
+
+/// Returns the last segment of a URI.
+pub fn file_name(uri: &str) -> &str {
+    let path = uri.trim_end_matches('/');
+    &path[path.rfind('/').map(|i| i + 1).unwrap_or(0)..]
+}
+
+/// Joins a relative path onto a directory, resolving `.` and `..`; a path starting with `/` replaces the path of the directory.
+pub fn join(directory: &str, path: &str) -> String {
+    let root = root_len(directory);
+    let mut segments: Vec<&str> = if path.starts_with('/') { Vec::new() } else { directory[root..].split('/').filter(|s| !s.is_empty()).collect() };
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("{}{}", &directory[..root], segments.join("/"))
+}
+
+/// Returns a directory followed by its ancestors up to the root.
+pub fn ancestors(directory: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(directory);
+    std::iter::from_fn(move || {
+        let current = next?;
+        let up = parent(current);
+        next = if up.len() < current.len() { Some(up) } else { None };
+        Some(current)
+    })
+}
+
+/// Checks whether a URI ends with one of the given extensions, without the dot.
+pub fn has_extension(uri: &str, extensions: &[&str]) -> bool {
+    file_name(uri).rsplit_once('.').is_some_and(|(_, extension)| extensions.contains(&extension))
+}
@@ -0,0 +1,268 @@
+use crate::fuzzy::FuzzyMatcher;
+use oak_core::language::UniversalElementRole;
+use oak_symbols::SymbolInformation;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeSet, BinaryHeap, HashMap},
+};
+
+/// A query for a [`SymbolIndex`].
+#[derive(Debug, Clone)]
+pub struct SymbolQuery {
+    /// The text to match fuzzily against symbol names.
+    pub text: String,
+    /// The roles to search for; empty means all roles.
+    pub roles: Vec<UniversalElementRole>,
+    /// The maximum number of results.
+    pub limit: usize,
+}
+
+impl SymbolQuery {
+    /// The number of results returned unless [`with_limit`](Self::with_limit) says otherwise.
+    pub const DEFAULT_LIMIT: usize = 128;
+
+    /// Creates a query for symbols of every role.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), roles: Vec::new(), limit: Self::DEFAULT_LIMIT }
+    }
+
+    /// Restricts the results to symbols with one of the given roles.
+    pub fn with_roles(mut self, roles: impl IntoIterator<Item = UniversalElementRole>) -> Self {
+        self.roles = roles.into_iter().collect();
+        self
+    }
+
+    /// Sets the maximum number of results.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn accepts(&self, role: UniversalElementRole) -> bool {
+        self.roles.is_empty() || self.roles.contains(&role)
+    }
+}
+
+/// A symbol found by a [`SymbolQuery`].
+#[derive(Debug, Clone)]
+pub struct SymbolMatch {
+    /// The symbol.
+    pub symbol: SymbolInformation,
+    /// The fuzzy match score; higher is better.
+    pub score: i32,
+    /// The character indices of the symbol name that matched the query.
+    pub positions: Vec<usize>,
+}
+
+struct IndexEntry {
+    symbol: SymbolInformation,
+    /// The lower case name, the key of the entry in the prefix index.
+    folded: String,
+    /// A bit for every kind of character in the name, see [`char_mask`].
+    mask: u64,
+}
+
+/// A fuzzy, ranked search index over the symbols of a workspace.
+///
+/// Symbols are added and replaced per file. A query matches symbol names as case-insensitive
+/// subsequences and ranks them with [`fuzzy_match`](crate::fuzzy_match), so `hlv` finds `HighlightVisitor`. To stay
+/// fast on hundreds of thousands of symbols, the symbols are indexed by the characters of their
+/// names: a query only visits the symbols listed under its rarest character, only scores those whose
+/// bit mask of characters contains all characters of the query, and only keeps the best `limit`
+/// results. Names are also kept sorted in a prefix index, which answers empty queries and
+/// [`prefix`](Self::prefix) lookups without scanning.
+#[derive(Default)]
+pub struct SymbolIndex {
+    entries: Vec<Option<IndexEntry>>,
+    /// Slots of removed entries, reused by new ones.
+    free: Vec<usize>,
+    /// Entry slots by file URI.
+    files: HashMap<String, Vec<usize>>,
+    /// Lower case names and entry slots, sorted.
+    prefixes: BTreeSet<(String, usize)>,
+    /// Entry slots by the bits of their [`char_mask`].
+    postings: Vec<BTreeSet<usize>>,
+}
+
+impl SymbolIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of symbols in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    /// Checks whether the index contains no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replaces the symbols of a file.
+    pub fn update_file(&mut self, uri: &str, symbols: Vec<SymbolInformation>) {
+        self.remove_file(uri);
+        let mut slots = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            let folded = symbol.name.to_lowercase();
+            let mask = char_mask(&folded);
+            let entry = IndexEntry { mask, folded: folded.clone(), symbol };
+            let slot = match self.free.pop() {
+                Some(slot) => {
+                    self.entries[slot] = Some(entry);
+                    slot
+                }
+                None => {
+                    self.entries.push(Some(entry));
+                    self.entries.len() - 1
+                }
+            };
+            self.postings.resize_with(MASK_BITS, BTreeSet::new);
+            for bit in mask_bits(mask) {
+                self.postings[bit].insert(slot);
+            }
+            self.prefixes.insert((folded, slot));
+            slots.push(slot)
+        }
+        if !slots.is_empty() {
+            self.files.insert(uri.to_string(), slots);
+        }
+    }
+
+    /// Removes the symbols of a file.
+    pub fn remove_file(&mut self, uri: &str) {
+        for slot in self.files.remove(uri).unwrap_or_default() {
+            if let Some(entry) = self.entries[slot].take() {
+                for bit in mask_bits(entry.mask) {
+                    self.postings[bit].remove(&slot);
+                }
+                self.prefixes.remove(&(entry.folded, slot));
+                self.free.push(slot)
+            }
+        }
+    }
+
+    /// Returns the symbols of a file.
+    pub fn file_symbols(&self, uri: &str) -> Vec<SymbolInformation> {
+        self.files.get(uri).map(|slots| slots.iter().filter_map(|slot| self.entries[*slot].as_ref()).map(|entry| entry.symbol.clone()).collect()).unwrap_or_default()
+    }
+
+    /// Returns the URIs of all files with symbols.
+    pub fn files(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    /// Returns the symbols whose name starts with a prefix, ignoring case, in alphabetical order.
+    pub fn prefix(&self, prefix: &str, query: &SymbolQuery) -> Vec<SymbolInformation> {
+        let prefix = prefix.to_lowercase();
+        self.prefixes
+            .range((prefix.clone(), 0)..)
+            .take_while(|(name, _)| name.starts_with(&prefix))
+            .filter_map(|(_, slot)| self.entries[*slot].as_ref())
+            .filter(|entry| query.accepts(entry.symbol.role))
+            .take(query.limit)
+            .map(|entry| entry.symbol.clone())
+            .collect()
+    }
+
+    /// Finds the symbols matching a query, best first.
+    ///
+    /// Ties are broken by shorter names, then alphabetically.
+    pub fn search(&self, query: &SymbolQuery) -> Vec<SymbolMatch> {
+        if query.limit == 0 {
+            return Vec::new();
+        }
+        let text: String = query.text.chars().filter(|c| !c.is_whitespace()).collect();
+        if text.is_empty() {
+            return self.prefix("", query).into_iter().map(|symbol| SymbolMatch { symbol, score: 0, positions: Vec::new() }).collect();
+        }
+        let mask = char_mask(&text.to_lowercase());
+        // Every match contains the rarest character of the query.
+        let Some(candidates) = mask_bits(mask).map(|bit| self.postings.get(bit)).min_by_key(|postings| postings.map_or(0, BTreeSet::len)).flatten()
+        else {
+            return Vec::new();
+        };
+        let mut matcher = FuzzyMatcher::new(&text);
+        // A min-heap of the best `limit` candidates, so the worst one is dropped first.
+        let mut best = BinaryHeap::with_capacity(query.limit + 1);
+        for &slot in candidates {
+            let Some(entry) = &self.entries[slot]
+            else {
+                continue;
+            };
+            if entry.mask & mask != mask || !query.accepts(entry.symbol.role) {
+                continue;
+            }
+            if let Some(matched) = matcher.matches(&entry.symbol.name) {
+                let ranked = Ranked { score: matched.score, len: entry.folded.len(), name: &entry.folded, slot, positions: matched.positions };
+                if best.len() == query.limit && best.peek().is_some_and(|Reverse(worst)| &ranked <= worst) {
+                    continue;
+                }
+                best.push(Reverse(ranked));
+                if best.len() > query.limit {
+                    best.pop();
+                }
+            }
+        }
+        let mut ranked: Vec<_> = best.into_iter().map(|Reverse(ranked)| ranked).collect();
+        ranked.sort_by(|a, b| b.cmp(a));
+        ranked.into_iter().filter_map(|ranked| Some(SymbolMatch { symbol: self.entries[ranked.slot].as_ref()?.symbol.clone(), score: ranked.score, positions: ranked.positions })).collect()
+    }
+}
+
+/// A scored candidate, ordered so that greater is better.
+struct Ranked<'a> {
+    score: i32,
+    len: usize,
+    name: &'a str,
+    slot: usize,
+    positions: Vec<usize>,
+}
+
+impl Ranked<'_> {
+    fn key(&self) -> (i32, Reverse<usize>, Reverse<&str>, Reverse<usize>) {
+        (self.score, Reverse(self.len), Reverse(self.name), Reverse(self.slot))
+    }
+}
+
+impl PartialEq for Ranked<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Ranked<'_> {}
+
+impl PartialOrd for Ranked<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The number of bits of a [`char_mask`].
+const MASK_BITS: usize = 64;
+
+/// Returns the indices of the set bits of a [`char_mask`].
+fn mask_bits(mask: u64) -> impl Iterator<Item = usize> {
+    (0..MASK_BITS).filter(move |bit| mask & 1 << bit != 0)
+}
+
+/// Returns a bit for every letter, digit and `_` in a lower case name, and a shared bit per bucket of other characters.
+fn char_mask(folded: &str) -> u64 {
+    folded.chars().filter(|c| !c.is_whitespace()).fold(0, |mask, c| {
+        let bit = match c {
+            'a'..='z' => c as u32 - 'a' as u32,
+            '0'..='9' => 26 + c as u32 - '0' as u32,
+            '_' => 36,
+            c => 37 + c as u32 % 27,
+        };
+        mask | 1 << bit
+    })
+}
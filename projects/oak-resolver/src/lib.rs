@@ -1,8 +1,11 @@
+#![feature(new_range_api)]
 #![warn(missing_docs)]
 //! Symbol and module resolution for the Oak language framework.
 //!
-//! This crate provides traits and implementations for resolving module imports
-//! and managing global symbol tables across a workspace.
+//! This crate provides traits and implementations for resolving module imports,
+//! managing global symbol tables across a workspace and resolving local names
+//! through scope graphs. Imports are resolved with language specific strategies
+//! on top of the [`Vfs`](oak_vfs::Vfs).
 
 use dashmap::DashMap;
 use oak_symbols::SymbolInformation;
@@ -11,6 +14,22 @@ use url::Url;
 
 use std::sync::RwLock;
 
+/// Scope graphs for resolving names within a file.
+pub mod scope;
+pub use scope::{Binding, BindingId, BindingKind, Reference, ReferenceId, Scope, ScopeGraph, ScopeId, ScopeProvider, UniversalScopeProvider};
+
+/// Language specific import resolution strategies.
+pub mod import;
+pub use import::{GoStrategy, ImportProbe, ImportResolution, ImportResolver, ImportStrategy, JavaStrategy, NodeStrategy, PathStrategy, PythonStrategy, RustStrategy};
+
+/// Fuzzy matching of symbol names.
+pub mod fuzzy;
+pub use fuzzy::{FuzzyMatch, fuzzy_match};
+
+/// A fuzzy, ranked index of workspace symbols.
+pub mod index;
+pub use index::{SymbolIndex, SymbolMatch, SymbolQuery};
+
 /// Trait for resolving module imports to file URIs.
 pub trait ModuleResolver: Send + Sync {
     /// Resolve an import path relative to a base URI.
@@ -18,6 +37,8 @@ pub trait ModuleResolver: Send + Sync {
 }
 
 /// A standard resolver that handles relative and absolute file paths.
+///
+/// It only looks at the file system; see [`ImportResolver`] for language aware resolution on a [`Vfs`](oak_vfs::Vfs).
 pub struct StandardResolver {
     /// The list of root directories to search for modules.
     root_dirs: RwLock<Vec<PathBuf>>,
@@ -70,12 +91,14 @@ pub struct GlobalSymbolTable {
     file_symbols: DashMap<String, Vec<SymbolInformation>>,
     /// Map of fully qualified name to symbol information.
     qualified_symbols: DashMap<String, SymbolInformation>,
+    /// The search index over all symbols.
+    index: RwLock<SymbolIndex>,
 }
 
 impl GlobalSymbolTable {
     /// Creates a new, empty `GlobalSymbolTable`.
     pub fn new() -> Self {
-        Self { file_symbols: DashMap::new(), qualified_symbols: DashMap::new() }
+        Self { file_symbols: DashMap::new(), qualified_symbols: DashMap::new(), index: RwLock::new(SymbolIndex::new()) }
     }
 
     /// Add or update symbols for a file.
@@ -93,6 +116,9 @@ impl GlobalSymbolTable {
             let fqn = self.make_qualified_name(sym);
             self.qualified_symbols.insert(fqn, sym.clone());
         }
+        if let Ok(mut index) = self.index.write() {
+            index.update_file(&uri, symbols.clone())
+        }
         self.file_symbols.insert(uri, symbols);
     }
 
@@ -113,9 +139,21 @@ impl GlobalSymbolTable {
         self.file_symbols.get(uri).map(|r| r.value().clone()).unwrap_or_default()
     }
 
-    /// Find all symbols matching a query (for workspace/symbol).
+    /// Get the URIs of all files that currently have indexed symbols.
+    pub fn files(&self) -> Vec<String> {
+        self.file_symbols.iter().map(|r| r.key().clone()).collect()
+    }
+
+    /// Find the symbols whose name fuzzily matches a query, best first (for workspace/symbol).
+    ///
+    /// At most [`SymbolQuery::DEFAULT_LIMIT`] symbols are returned; see [`search`](Self::search).
     pub fn query(&self, query: &str) -> Vec<SymbolInformation> {
-        self.qualified_symbols.iter().filter(|r| r.key().contains(query)).map(|r| r.value().clone()).collect()
+        self.search(&SymbolQuery::new(query)).into_iter().map(|m| m.symbol).collect()
+    }
+
+    /// Find the symbols matching a query, best first, with their scores.
+    pub fn search(&self, query: &SymbolQuery) -> Vec<SymbolMatch> {
+        self.index.read().map(|index| index.search(query)).unwrap_or_default()
     }
 }
 
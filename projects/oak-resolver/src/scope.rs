@@ -0,0 +1,372 @@
+use core::range::Range;
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+
+/// The index of a scope in a [`ScopeGraph`].
+pub type ScopeId = usize;
+
+/// The index of a binding in a [`ScopeGraph`].
+pub type BindingId = usize;
+
+/// The index of a reference in a [`ScopeGraph`].
+pub type ReferenceId = usize;
+
+/// How a binding becomes visible in its scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingKind {
+    /// A local variable, visible from [`Binding::visible_from`] to the end of its scope.
+    ///
+    /// A later local of the same name in the same scope shadows it.
+    Local,
+    /// An item like a function or a type, visible in its whole scope.
+    Item,
+    /// A name imported from another module, visible in its whole scope.
+    Import {
+        /// The module path as written in the import.
+        path: String,
+    },
+}
+
+/// A region of the source in which names are bound.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    /// The enclosing scope, or `None` for the root scope.
+    pub parent: Option<ScopeId>,
+    /// The byte range the scope covers.
+    pub range: Range<usize>,
+    /// The bindings declared directly in this scope, in source order.
+    pub bindings: Vec<BindingId>,
+}
+
+/// A name introduced into a scope.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    /// The bound name.
+    pub name: String,
+    /// The range of the name.
+    pub range: Range<usize>,
+    /// The range of the whole declaration, e.g. a function including its body.
+    pub definition_range: Range<usize>,
+    /// The scope the name is bound in.
+    pub scope: ScopeId,
+    /// How the binding becomes visible.
+    pub kind: BindingKind,
+    /// The offset from which a [`BindingKind::Local`] is visible, usually the end of its declaration.
+    pub visible_from: usize,
+}
+
+/// A use of a name.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// The referenced name.
+    pub name: String,
+    /// The range of the name.
+    pub range: Range<usize>,
+    /// The scope the name is used in.
+    pub scope: ScopeId,
+}
+
+/// The scopes, bindings and references of a file.
+///
+/// Names are resolved by walking from the scope of a reference outwards, where the innermost
+/// visible binding of the name wins. Within a scope, items and imports are visible everywhere,
+/// while a local is only visible after its declaration and is shadowed by later locals of the
+/// same name.
+#[derive(Debug, Clone)]
+pub struct ScopeGraph {
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding>,
+    references: Vec<Reference>,
+}
+
+impl ScopeGraph {
+    /// Creates a graph with a root scope covering `range`.
+    pub fn new(range: Range<usize>) -> Self {
+        Self { scopes: vec![Scope { parent: None, range, bindings: Vec::new() }], bindings: Vec::new(), references: Vec::new() }
+    }
+
+    /// Returns the root scope.
+    pub fn root(&self) -> ScopeId {
+        0
+    }
+
+    /// Adds a scope nested in `parent`.
+    pub fn add_scope(&mut self, parent: ScopeId, range: Range<usize>) -> ScopeId {
+        self.scopes.push(Scope { parent: Some(parent), range, bindings: Vec::new() });
+        self.scopes.len() - 1
+    }
+
+    /// Adds a binding to a scope.
+    pub fn add_binding(&mut self, scope: ScopeId, binding: Binding) -> BindingId {
+        let id = self.bindings.len();
+        self.scopes[scope].bindings.push(id);
+        self.bindings.push(Binding { scope, ..binding });
+        id
+    }
+
+    /// Adds a local variable, visible from `visible_from` to the end of the scope.
+    pub fn add_local(&mut self, scope: ScopeId, name: impl Into<String>, range: Range<usize>, visible_from: usize) -> BindingId {
+        self.add_binding(scope, Binding { name: name.into(), range, definition_range: range, scope, kind: BindingKind::Local, visible_from })
+    }
+
+    /// Adds an item declared by `definition_range`, visible in the whole scope.
+    pub fn add_item(&mut self, scope: ScopeId, name: impl Into<String>, range: Range<usize>, definition_range: Range<usize>) -> BindingId {
+        self.add_binding(scope, Binding { name: name.into(), range, definition_range, scope, kind: BindingKind::Item, visible_from: 0 })
+    }
+
+    /// Adds a name imported from the module `path`, visible in the whole scope.
+    pub fn add_import(&mut self, scope: ScopeId, name: impl Into<String>, range: Range<usize>, path: impl Into<String>) -> BindingId {
+        self.add_binding(scope, Binding { name: name.into(), range, definition_range: range, scope, kind: BindingKind::Import { path: path.into() }, visible_from: 0 })
+    }
+
+    /// Adds a use of `name` in a scope.
+    pub fn add_reference(&mut self, scope: ScopeId, name: impl Into<String>, range: Range<usize>) -> ReferenceId {
+        self.references.push(Reference { name: name.into(), range, scope });
+        self.references.len() - 1
+    }
+
+    /// Returns a scope.
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id]
+    }
+
+    /// Returns a binding.
+    pub fn binding(&self, id: BindingId) -> &Binding {
+        &self.bindings[id]
+    }
+
+    /// Returns a reference.
+    pub fn reference(&self, id: ReferenceId) -> &Reference {
+        &self.references[id]
+    }
+
+    /// Returns all bindings.
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Returns all references.
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    /// Returns the innermost scope containing an offset.
+    pub fn scope_at(&self, offset: usize) -> ScopeId {
+        let mut scope = self.root();
+        for (id, candidate) in self.scopes.iter().enumerate().skip(1) {
+            let range = candidate.range;
+            let current = self.scopes[scope].range;
+            if range.start <= offset && offset <= range.end && range.end - range.start <= current.end - current.start {
+                scope = id
+            }
+        }
+        scope
+    }
+
+    /// Finds the binding of `name` that is visible at `offset` in a scope or its parents.
+    pub fn lookup(&self, scope: ScopeId, name: &str, offset: usize) -> Option<BindingId> {
+        let mut current = Some(scope);
+        while let Some(scope) = current {
+            let mut item = None;
+            let mut local: Option<BindingId> = None;
+            for &id in &self.scopes[scope].bindings {
+                let binding = &self.bindings[id];
+                if binding.name != name {
+                    continue;
+                }
+                match binding.kind {
+                    BindingKind::Local if binding.visible_from <= offset => {
+                        if local.is_none_or(|local| self.bindings[local].visible_from <= binding.visible_from) {
+                            local = Some(id)
+                        }
+                    }
+                    BindingKind::Local => {}
+                    _ => item = item.or(Some(id)),
+                }
+            }
+            if let Some(id) = local.or(item) {
+                return Some(id);
+            }
+            current = self.scopes[scope].parent;
+        }
+        None
+    }
+
+    /// Checks whether `name` refers to a binding at `offset`.
+    pub fn is_visible(&self, name: &str, offset: usize) -> bool {
+        self.lookup(self.scope_at(offset), name, offset).is_some()
+    }
+
+    /// Returns the bindings visible at `offset`, innermost first, without shadowed ones.
+    pub fn visible_bindings(&self, offset: usize) -> Vec<BindingId> {
+        let mut visible: Vec<BindingId> = Vec::new();
+        let mut current = Some(self.scope_at(offset));
+        while let Some(scope) = current {
+            for &id in &self.scopes[scope].bindings {
+                let name = &self.bindings[id].name;
+                if !visible.iter().any(|&v| self.bindings[v].name == *name) && self.lookup(scope, name, offset) == Some(id) {
+                    visible.push(id)
+                }
+            }
+            current = self.scopes[scope].parent;
+        }
+        visible
+    }
+
+    /// Resolves a reference to its binding.
+    pub fn resolve(&self, reference: ReferenceId) -> Option<BindingId> {
+        let reference = &self.references[reference];
+        self.lookup(reference.scope, &reference.name, reference.range.start)
+    }
+
+    /// Returns the binding whose name is at `offset`.
+    pub fn binding_at(&self, offset: usize) -> Option<BindingId> {
+        self.bindings.iter().position(|binding| binding.range.start <= offset && offset <= binding.range.end)
+    }
+
+    /// Returns the reference whose name is at `offset`.
+    pub fn reference_at(&self, offset: usize) -> Option<ReferenceId> {
+        self.references.iter().position(|reference| reference.range.start <= offset && offset <= reference.range.end)
+    }
+
+    /// Returns the binding of the name at `offset`, which is either a binding or a resolved reference.
+    pub fn definition_at(&self, offset: usize) -> Option<BindingId> {
+        self.binding_at(offset).or_else(|| self.resolve(self.reference_at(offset)?))
+    }
+
+    /// Returns the references that resolve to a binding.
+    pub fn references_to(&self, binding: BindingId) -> Vec<ReferenceId> {
+        (0..self.references.len()).filter(|&reference| self.references[reference].name == self.bindings[binding].name && self.resolve(reference) == Some(binding)).collect()
+    }
+
+    /// Returns the ranges of all occurrences of a binding, optionally including the binding itself.
+    pub fn occurrences(&self, binding: BindingId, include_declaration: bool) -> Vec<Range<usize>> {
+        let mut ranges: Vec<_> = self.references_to(binding).into_iter().map(|reference| self.references[reference].range).collect();
+        if include_declaration {
+            ranges.push(self.bindings[binding].range)
+        }
+        ranges.sort_by_key(|range| range.start);
+        ranges
+    }
+}
+
+/// Builds the [`ScopeGraph`] of a syntax tree.
+pub trait ScopeProvider<L: Language> {
+    /// Builds the scope graph of the tree rooted at `root`.
+    fn scope_graph(&self, root: &RedNode<L>, source: &dyn Source) -> ScopeGraph;
+}
+
+/// A [`ScopeProvider`] that works for every language from the universal roles of its tree.
+///
+/// - A `Container` node opens a scope.
+/// - A `Definition` node binds its name as an item in the enclosing scope and opens a scope for
+///   the rest of the node, e.g. the parameters and body of a function. Its name is the first
+///   `Binding` or `Name` node inside it, or else its first name token.
+/// - A `Binding` node binds its first name token as a local, visible after the enclosing
+///   `Statement`, or after the binding itself if it is not in a statement.
+/// - Every other name token, typically inside a `Reference` node, is a use of the name.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniversalScopeProvider;
+
+impl<L: Language> ScopeProvider<L> for UniversalScopeProvider {
+    fn scope_graph(&self, root: &RedNode<L>, source: &dyn Source) -> ScopeGraph {
+        let mut builder = Builder { graph: ScopeGraph::new(root.span()), source };
+        builder.visit(*root, 0, None, None);
+        builder.graph
+    }
+}
+
+struct Builder<'s> {
+    graph: ScopeGraph,
+    source: &'s dyn Source,
+}
+
+impl Builder<'_> {
+    /// Visits a node in `scope`, skipping the name leaf at `skip` and with `statement_end` the
+    /// end of the innermost enclosing statement.
+    fn visit<L: Language>(&mut self, node: RedNode<L>, scope: ScopeId, skip: Option<usize>, statement_end: Option<usize>) {
+        let role = node.green.kind.role().universal();
+        let mut span = node.span();
+        if matches!(role, UniversalElementRole::Container | UniversalElementRole::Statement | UniversalElementRole::Definition | UniversalElementRole::Binding) {
+            span.end = content_end(&node).unwrap_or(span.end)
+        }
+        let (scope, skip, statement_end) = match role {
+            UniversalElementRole::Container => (self.graph.add_scope(scope, span), skip, statement_end),
+            UniversalElementRole::Statement => (scope, skip, Some(span.end)),
+            UniversalElementRole::Definition => match definition_name(node) {
+                Some(name) => {
+                    self.graph.add_item(scope, self.text(name.span), name.span, span);
+                    (self.graph.add_scope(scope, span), Some(name.span.start), statement_end)
+                }
+                None => (self.graph.add_scope(scope, span), skip, statement_end),
+            },
+            UniversalElementRole::Binding => match first_name(node) {
+                Some(name) if Some(name.span.start) != skip => {
+                    self.graph.add_local(scope, self.text(name.span), name.span, statement_end.unwrap_or(span.end));
+                    (scope, Some(name.span.start), statement_end)
+                }
+                _ => (scope, skip, statement_end),
+            },
+            _ => (scope, skip, statement_end),
+        };
+        for child in node.children() {
+            match child {
+                RedTree::Node(child) => self.visit(child, scope, skip, statement_end),
+                RedTree::Leaf(leaf) => {
+                    if leaf.kind.role().universal() == UniversalTokenRole::Name && Some(leaf.span.start) != skip {
+                        self.graph.add_reference(scope, self.text(leaf.span), leaf.span);
+                    }
+                }
+            }
+        }
+    }
+
+    fn text(&self, range: Range<usize>) -> String {
+        self.source.get_text_in(range).into_owned()
+    }
+}
+
+/// Returns the name token of a definition.
+fn definition_name<L: Language>(node: RedNode<L>) -> Option<RedLeaf<L>> {
+    for child in node.children() {
+        match child {
+            RedTree::Node(child) if matches!(child.green.kind.role().universal(), UniversalElementRole::Binding | UniversalElementRole::Name) => return first_name(child),
+            RedTree::Leaf(leaf) if leaf.kind.role().universal() == UniversalTokenRole::Name => return Some(leaf),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the end of the last token in a node that is not whitespace or a comment, so trailing
+/// trivia the parser attached to a node does not widen its scope or delay its locals.
+fn content_end<L: Language>(node: &RedNode<L>) -> Option<usize> {
+    let mut end = None;
+    for child in node.children() {
+        match child {
+            RedTree::Leaf(leaf) if !leaf.kind.is_whitespace() && !leaf.kind.is_comment() => end = Some(leaf.span.end),
+            RedTree::Node(child) => end = content_end(&child).or(end),
+            _ => {}
+        }
+    }
+    end
+}
+
+/// Returns the first name token in a node.
+fn first_name<L: Language>(node: RedNode<L>) -> Option<RedLeaf<L>> {
+    for child in node.children() {
+        match child {
+            RedTree::Leaf(leaf) if leaf.kind.role().universal() == UniversalTokenRole::Name => return Some(leaf),
+            RedTree::Node(child) => {
+                if let Some(leaf) = first_name(child) {
+                    return Some(leaf);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
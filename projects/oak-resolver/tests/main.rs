@@ -0,0 +1,196 @@
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    tree::RedNode,
+};
+use oak_resolver::{BindingKind, ScopeGraph, ScopeProvider, UniversalScopeProvider};
+use oak_testing::building::{
+    Spec::{self, Leaf, Node},
+    name, punct,
+};
+
+fn binding(text: &'static str) -> Spec {
+    Node(E::Binding, vec![name(text)])
+}
+
+/// `let <name>=<value>;`
+fn let_statement(binding_name: &'static str, value: Spec) -> Spec {
+    Node(E::Statement, vec![Leaf(T::Keyword, "let "), binding(binding_name), punct("="), value, punct(";")])
+}
+
+/// Builds the scope graph of `fn f(a){let x=a;{let x=1;x}x}let x=x;let y=f;x`.
+fn example() -> (String, ScopeGraph) {
+    let body = Node(E::Container, vec![punct("{"), let_statement("x", name("a")), Node(E::Container, vec![punct("{"), let_statement("x", Leaf(T::Literal, "1")), name("x"), punct("}")]), name("x"), punct("}")]);
+    let function = Node(E::Definition, vec![Leaf(T::Keyword, "fn "), name("f"), punct("("), binding("a"), punct(")"), body]);
+    let root = Node(E::Root, vec![function, let_statement("x", name("x")), let_statement("y", name("f")), name("x")]);
+    let (text, green) = root.build();
+    let graph = UniversalScopeProvider.scope_graph(&RedNode::new(green, 0), &text.as_str());
+    (text, graph)
+}
+
+#[test]
+fn test_universal_scopes_and_shadowing() {
+    let (text, graph) = example();
+    assert_eq!(text, "fn f(a){let x=a;{let x=1;x}x}let x=x;let y=f;x");
+    let at = |pattern: &str| text.find(pattern).unwrap();
+    let resolve = |offset: usize| graph.definition_at(offset).map(|binding| graph.binding(binding).range.start);
+
+    // The inner block shadows `x`, the outer use sees the first `x` again.
+    assert_eq!(resolve(at("x}")), Some(at("x=1")));
+    assert_eq!(resolve(at("x}let")), Some(at("x=a")));
+    assert_eq!(resolve(at("a;")), Some(at("a)")));
+    // A local is not visible in its own initializer, an item is visible everywhere.
+    assert_eq!(resolve(at("x;let y")), None);
+    assert_eq!(resolve(at("f;")), Some(at("f(")));
+    assert_eq!(graph.binding(graph.definition_at(at("f;")).unwrap()).kind, BindingKind::Item);
+    assert_eq!(resolve(text.len() - 1), Some(at("x=x")));
+
+    let outer = graph.definition_at(at("x=a")).unwrap();
+    let occurrences: Vec<_> = graph.occurrences(outer, true).into_iter().map(|range| range.start).collect();
+    assert_eq!(occurrences, [at("x=a"), at("x}let")]);
+
+    assert!(graph.is_visible("a", at("x}")) && !graph.is_visible("a", text.len() - 1));
+    let visible: Vec<_> = graph.visible_bindings(at("x}")).into_iter().map(|binding| (graph.binding(binding).name.as_str(), graph.binding(binding).range.start)).collect();
+    assert_eq!(visible, [("x", at("x=1")), ("a", at("a)")), ("f", at("f("))]);
+}
+
+#[test]
+fn test_scope_graph_api() {
+    let mut graph = ScopeGraph::new((0..100).into());
+    let root = graph.root();
+    let json = graph.add_import(root, "json", (0..4).into(), "serde_json");
+    let block = graph.add_scope(root, (10..50).into());
+    let local = graph.add_local(block, "json", (12..16).into(), 20);
+    let before = graph.add_reference(block, "json", (17..21).into());
+    let after = graph.add_reference(block, "json", (30..34).into());
+    let outside = graph.add_reference(root, "json", (60..64).into());
+
+    assert_eq!(graph.scope_at(30), block);
+    assert_eq!(graph.scope(block).parent, Some(root));
+    assert_eq!(graph.resolve(before), Some(json));
+    assert_eq!(graph.resolve(after), Some(local));
+    assert_eq!(graph.resolve(outside), Some(json));
+    assert_eq!(graph.references_to(json), [before, outside]);
+    assert_eq!(graph.binding(json).kind, BindingKind::Import { path: "serde_json".to_string() });
+    assert_eq!(graph.lookup(root, "missing", 0), None);
+}
+
+#[test]
+fn test_import_strategies() {
+    use oak_resolver::{GoStrategy, ImportResolver, JavaStrategy, ModuleResolver, NodeStrategy, PythonStrategy, RustStrategy};
+    use oak_vfs::SnapshotVfs;
+
+    let vfs = SnapshotVfs::from_files([
+        // Node
+        ("web/src/app.ts", ""),
+        ("web/src/util/index.ts", ""),
+        ("web/node_modules/lodash/package.json", r#"{ "main": "lodash.js" }"#),
+        ("web/node_modules/lodash/lodash.js", ""),
+        ("web/node_modules/@acme/ui/package.json", r#"{ "exports": { ".": { "import": "./esm/index.mjs", "require": "./cjs/index.cjs" }, "./icons/*": "./dist/icons/*.js" } }"#),
+        ("web/node_modules/@acme/ui/esm/index.mjs", ""),
+        ("web/node_modules/@acme/ui/dist/icons/star.js", ""),
+        ("web/node_modules/@acme/ui/internal.js", ""),
+        // Python
+        ("py/pkg/__init__.py", ""),
+        ("py/pkg/core.py", ""),
+        ("py/pkg/sub/__init__.py", ""),
+        ("py/pkg/sub/leaf.py", ""),
+        ("site/requests/__init__.py", ""),
+        // Rust
+        ("rs/Cargo.toml", ""),
+        ("rs/src/lib.rs", ""),
+        ("rs/src/parser.rs", ""),
+        ("rs/src/parser/lexer.rs", ""),
+        ("rs/src/ast/mod.rs", ""),
+        // Go
+        ("go/go.mod", "module example.com/app\n\nreplace example.com/lib => ../golib\n"),
+        ("go/main.go", ""),
+        ("go/internal/db/db.go", ""),
+        ("golib/util/util.go", ""),
+        // Java
+        ("java/src/com/acme/App.java", "package com.acme;\n\nimport com.acme.model.User;\n"),
+        ("java/src/com/acme/model/User.java", "package com.acme.model;\n"),
+    ]);
+    let root = vfs.root().to_string();
+    let uri = |path: &str| format!("{}{}", root, path);
+    let resolver = ImportResolver::new(vfs).with_strategy(NodeStrategy::new()).with_strategy(PythonStrategy::new().with_search_path(uri("site"))).with_strategy(RustStrategy::new()).with_strategy(GoStrategy::new()).with_strategy(JavaStrategy::new());
+    let resolve = |base: &str, import: &str| resolver.resolve_import(&uri(base), import).uri;
+
+    assert_eq!(resolve("web/src/app.ts", "./util"), Some(uri("web/src/util/index.ts")));
+    assert_eq!(resolve("web/src/app.ts", "lodash"), Some(uri("web/node_modules/lodash/lodash.js")));
+    assert_eq!(resolve("web/src/app.ts", "@acme/ui"), Some(uri("web/node_modules/@acme/ui/esm/index.mjs")));
+    assert_eq!(resolve("web/src/app.ts", "@acme/ui/icons/star"), Some(uri("web/node_modules/@acme/ui/dist/icons/star.js")));
+    // Subpaths not listed in `exports` are encapsulated.
+    assert_eq!(resolve("web/src/app.ts", "@acme/ui/internal.js"), None);
+
+    assert_eq!(resolve("py/pkg/sub/leaf.py", "pkg.core"), Some(uri("py/pkg/core.py")));
+    assert_eq!(resolve("py/pkg/sub/leaf.py", "..core"), Some(uri("py/pkg/core.py")));
+    assert_eq!(resolve("py/pkg/core.py", ".sub"), Some(uri("py/pkg/sub/__init__.py")));
+    assert_eq!(resolve("py/pkg/core.py", "requests"), Some(uri("site/requests/__init__.py")));
+
+    assert_eq!(resolve("rs/src/lib.rs", "parser"), Some(uri("rs/src/parser.rs")));
+    assert_eq!(resolve("rs/src/parser.rs", "lexer"), Some(uri("rs/src/parser/lexer.rs")));
+    assert_eq!(resolve("rs/src/parser/lexer.rs", "crate::ast::Node"), Some(uri("rs/src/ast/mod.rs")));
+    assert_eq!(resolve("rs/src/parser/lexer.rs", "super::super::ast"), Some(uri("rs/src/ast/mod.rs")));
+
+    assert_eq!(resolve("go/main.go", "example.com/app/internal/db"), Some(uri("go/internal/db")));
+    assert_eq!(resolve("go/main.go", "example.com/lib/util"), Some(uri("golib/util")));
+
+    assert_eq!(resolve("java/src/com/acme/App.java", "com.acme.model.User"), Some(uri("java/src/com/acme/model/User.java")));
+    assert_eq!(resolve("java/src/com/acme/App.java", "static com.acme.model.User.create"), Some(uri("java/src/com/acme/model/User.java")));
+    assert_eq!(resolve("java/src/com/acme/App.java", "com.acme.model.*"), Some(uri("java/src/com/acme/model")));
+
+    // Unresolved imports report every candidate that was tried.
+    let resolution = resolver.resolve_import(&uri("py/pkg/core.py"), "missing");
+    assert!(!resolution.is_resolved());
+    assert!(resolution.candidates.contains(&uri("py/missing.py")));
+    assert!(resolution.candidates.contains(&uri("site/missing/__init__.py")));
+    assert!(resolution.explain("missing").starts_with("cannot resolve import `missing`, tried:"));
+    assert_eq!(resolver.resolve(&uri("py/pkg/core.py"), "pkg.sub.leaf"), Some(uri("py/pkg/sub/leaf.py")));
+}
+
+#[test]
+fn test_fuzzy_symbol_index() {
+    use oak_core::language::UniversalElementRole;
+    use oak_resolver::{GlobalSymbolTable, SymbolIndex, SymbolQuery, fuzzy_match};
+    use oak_symbols::SymbolInformation;
+
+    let symbol = |name: &str, role: UniversalElementRole| SymbolInformation { name: name.to_string(), role, uri: "file:///a.rs".into(), range: (0..0).into(), container_name: None };
+
+    // Camel humps, word starts and prefixes rank above scattered matches.
+    let humps = fuzzy_match("HLV", "HighlightVisitor").unwrap();
+    assert_eq!(humps.positions, vec![0, 4, 9]);
+    assert!(fuzzy_match("hlv", "HighlightVisitor").is_some());
+    assert!(fuzzy_match("hlv", "shallow_valve").unwrap().score < humps.score);
+    assert!(fuzzy_match("parse", "parse_file").unwrap().score > fuzzy_match("parse", "reparse").unwrap().score);
+    assert!(fuzzy_match("vh", "HighlightVisitor").is_none());
+    assert_eq!(fuzzy_match("sv", "snake_visitor").unwrap().positions, vec![0, 6]);
+
+    let mut index = SymbolIndex::new();
+    index.update_file("file:///a.rs", vec![symbol("HighlightVisitor", UniversalElementRole::Definition), symbol("highlight", UniversalElementRole::Definition), symbol("shallow_valve", UniversalElementRole::Binding)]);
+    index.update_file("file:///b.rs", vec![symbol("Highlighter", UniversalElementRole::Definition), symbol("parse_file", UniversalElementRole::Definition)]);
+    assert_eq!(index.len(), 5);
+
+    let names = |index: &SymbolIndex, query: &SymbolQuery| index.search(query).into_iter().map(|m| m.symbol.name).collect::<Vec<_>>();
+    assert_eq!(names(&index, &SymbolQuery::new("hlv")), ["HighlightVisitor", "shallow_valve"]);
+    assert_eq!(names(&index, &SymbolQuery::new("hlv").with_roles([UniversalElementRole::Binding])), ["shallow_valve"]);
+    assert_eq!(names(&index, &SymbolQuery::new("highlight")), ["highlight", "Highlighter", "HighlightVisitor"]);
+    assert_eq!(names(&index, &SymbolQuery::new("highlight").with_limit(1)), ["highlight"]);
+    assert_eq!(names(&index, &SymbolQuery::new("")), ["highlight", "Highlighter", "HighlightVisitor", "parse_file", "shallow_valve"]);
+    assert_eq!(index.prefix("HIGH", &SymbolQuery::new("")).len(), 3);
+    // Candidates come from the symbols containing the rarest query character, here `f` and `z`.
+    assert_eq!(names(&index, &SymbolQuery::new("pf")), ["parse_file"]);
+    assert_eq!(names(&index, &SymbolQuery::new("hz")), Vec::<String>::new());
+
+    // Replacing a file drops its old symbols and reuses their slots.
+    index.update_file("file:///a.rs", vec![symbol("render", UniversalElementRole::Definition)]);
+    assert_eq!(index.len(), 3);
+    assert_eq!(names(&index, &SymbolQuery::new("hlv")), Vec::<String>::new());
+    assert_eq!(names(&index, &SymbolQuery::new("rnd")), ["render"]);
+    index.remove_file("file:///b.rs");
+    assert_eq!(index.files(), ["file:///a.rs"]);
+    assert_eq!(names(&index, &SymbolQuery::new("pf")), Vec::<String>::new());
+
+    let table = GlobalSymbolTable::new();
+    table.update_file_symbols("file:///a.rs".to_string(), vec![symbol("HighlightVisitor", UniversalElementRole::Definition), symbol("Visitor", UniversalElementRole::Definition)]);
+    assert_eq!(table.query("visitor").into_iter().map(|s| s.name).collect::<Vec<_>>(), ["Visitor", "HighlightVisitor"]);
+}
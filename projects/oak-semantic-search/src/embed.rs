@@ -0,0 +1,91 @@
+use crate::tokenize::{stable_hash, tokenize};
+
+/// Turns texts into vectors whose cosine similarity reflects how related the texts are.
+///
+/// Implement it to plug a model into [`SemanticSearcher`](crate::SemanticSearcher); the
+/// [`HashingEmbedder`] is a deterministic reference that needs no model at all.
+pub trait Embedder: Send + Sync {
+    /// Returns a name identifying the model and its settings.
+    ///
+    /// Stored vectors are only reused by an embedder of the same name, so it must change whenever
+    /// the vectors would.
+    fn name(&self) -> String;
+
+    /// Returns the number of dimensions of the vectors.
+    fn dimensions(&self) -> usize;
+
+    /// Embeds a text.
+    fn embed(&self, text: &str) -> Vec<f32>;
+
+    /// Embeds several texts; the default embeds them one by one.
+    fn embed_batch(&self, texts: &[&str]) -> Vec<Vec<f32>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+/// A deterministic [`Embedder`] hashing the terms of a text and their character trigrams into a
+/// fixed number of dimensions.
+///
+/// Texts sharing identifiers, words of identifiers or parts of words end up close, so it finds
+/// `tokenizeIdentifier` for `tokenizer` without any model or network access. It is meant for
+/// tests and as a baseline, not to capture meaning beyond spelling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl HashingEmbedder {
+    /// Creates an embedder with vectors of `dimensions` dimensions, at least one.
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions: dimensions.max(1) }
+    }
+
+    /// Adds a feature to a vector, with a sign from the hash so collisions tend to cancel out.
+    fn add(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = stable_hash(feature);
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % self.dimensions as u64) as usize] += sign * weight
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn name(&self) -> String {
+        format!("hashing-{}", self.dimensions)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        for term in tokenize(text) {
+            self.add(&mut vector, &term, 1.0);
+            let chars: Vec<char> = format!("^{}$", term).chars().collect();
+            for trigram in chars.windows(3) {
+                self.add(&mut vector, &trigram.iter().collect::<String>(), 0.5)
+            }
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+/// Scales a vector to unit length, leaving a zero vector as is.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm)
+    }
+}
+
+/// Returns the dot product of two vectors, their cosine similarity if both have unit length.
+pub(crate) fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
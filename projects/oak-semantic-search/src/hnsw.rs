@@ -0,0 +1,259 @@
+use crate::embed::{dot, normalize};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+/// A node found by a search with its similarity to the query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scored {
+    similarity: f32,
+    node: usize,
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity.total_cmp(&other.similarity).then(other.node.cmp(&self.node))
+    }
+}
+
+/// A vector in the graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    /// The key of the vector, `None` once it is removed.
+    key: Option<usize>,
+    /// The vector, scaled to unit length.
+    vector: Vec<f32>,
+    /// The neighbours of the node on every layer it is on, from the bottom one.
+    layers: Vec<Vec<usize>>,
+}
+
+/// An approximate nearest neighbour index of vectors by cosine similarity, a hierarchical
+/// navigable small world graph.
+///
+/// Every vector is a node on the bottom layer, and on each layer above with decreasing
+/// probability; a search walks greedily from the sparse top layer down to the dense bottom one.
+/// Removed vectors stay in the graph to keep it connected until they outnumber the others, when
+/// the graph is rebuilt. Levels come from a fixed seed, so the same insertions build the same graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    nodes: Vec<Node>,
+    /// The node of every key.
+    keys: HashMap<usize, usize>,
+    /// The node on the top layer where searches start.
+    entry: Option<usize>,
+    /// The number of removed nodes still in the graph.
+    removed: usize,
+    /// The state of the random number generator picking levels.
+    seed: u64,
+    /// The number of neighbours of a node on the layers above the bottom one, twice that on the bottom one.
+    connections: usize,
+    /// The number of candidates kept while inserting.
+    ef_construction: usize,
+    /// The number of candidates kept while searching.
+    ef_search: usize,
+}
+
+impl Default for HnswIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HnswIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), keys: HashMap::new(), entry: None, removed: 0, seed: 0x9e37_79b9_7f4a_7c15, connections: 16, ef_construction: 100, ef_search: 64 }
+    }
+
+    /// Sets the number of neighbours of every node, at least 2; more is slower but more accurate.
+    pub fn with_connections(mut self, connections: usize) -> Self {
+        self.connections = connections.max(2);
+        self
+    }
+
+    /// Sets the number of candidates kept while searching; more is slower but more accurate.
+    pub fn with_ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = ef_search.max(1);
+        self
+    }
+
+    /// Returns the number of vectors.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Checks whether there are no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the vector of a key, scaled to unit length.
+    pub fn vector(&self, key: usize) -> Option<&[f32]> {
+        self.keys.get(&key).map(|&node| self.nodes[node].vector.as_slice())
+    }
+
+    /// Inserts the vector of a key, replacing its previous one.
+    pub fn insert(&mut self, key: usize, mut vector: Vec<f32>) {
+        self.remove(key);
+        normalize(&mut vector);
+        let level = self.random_level();
+        let id = self.nodes.len();
+        self.nodes.push(Node { key: Some(key), vector, layers: vec![Vec::new(); level + 1] });
+        self.keys.insert(key, id);
+        let Some(entry) = self.entry
+        else {
+            self.entry = Some(id);
+            return;
+        };
+
+        let query = self.nodes[id].vector.clone();
+        let top = self.nodes[entry].layers.len() - 1;
+        let mut nearest = entry;
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy(&query, nearest, layer)
+        }
+        let mut entries = vec![nearest];
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entries, self.ef_construction, layer);
+            let neighbors: Vec<usize> = found.iter().take(self.connections).map(|scored| scored.node).collect();
+            for &neighbor in &neighbors {
+                self.nodes[neighbor].layers[layer].push(id);
+                self.prune(neighbor, layer)
+            }
+            self.nodes[id].layers[layer] = neighbors;
+            entries = found.into_iter().map(|scored| scored.node).collect();
+        }
+        if level > top {
+            self.entry = Some(id)
+        }
+    }
+
+    /// Removes the vector of a key, returning whether there was one.
+    pub fn remove(&mut self, key: usize) -> bool {
+        let Some(node) = self.keys.remove(&key)
+        else {
+            return false;
+        };
+        self.nodes[node].key = None;
+        self.removed += 1;
+        if self.removed > self.keys.len() {
+            self.rebuild()
+        }
+        true
+    }
+
+    /// Returns the keys of the vectors most similar to `query`, with their cosine similarity, most similar first.
+    pub fn search(&self, query: &[f32], limit: usize) -> Vec<(usize, f32)> {
+        let Some(entry) = self.entry
+        else {
+            return vec![];
+        };
+        let mut query = query.to_vec();
+        normalize(&mut query);
+        let mut nearest = entry;
+        for layer in (1..self.nodes[entry].layers.len()).rev() {
+            nearest = self.greedy(&query, nearest, layer)
+        }
+        // Removed nodes are found too, so look for more candidates while there are any.
+        let ef = self.ef_search.max(limit) + self.removed.min(self.ef_search);
+        self.search_layer(&query, &[nearest], ef, 0).into_iter().filter_map(|scored| Some((self.nodes[scored.node].key?, scored.similarity))).take(limit).collect()
+    }
+
+    /// Picks the top layer of a new node, each layer with `1 / connections` the probability of the one below.
+    fn random_level(&mut self) -> usize {
+        // xorshift64
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let uniform = ((self.seed >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (self.connections as f64).ln()) as usize
+    }
+
+    /// Walks from `current` to neighbours more similar to `query` on a layer while there are any.
+    fn greedy(&self, query: &[f32], mut current: usize, layer: usize) -> usize {
+        let mut best = dot(query, &self.nodes[current].vector);
+        loop {
+            let mut improved = false;
+            for &neighbor in &self.nodes[current].layers[layer] {
+                let similarity = dot(query, &self.nodes[neighbor].vector);
+                if similarity > best {
+                    (best, current, improved) = (similarity, neighbor, true)
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    /// Returns the `ef` nodes most similar to `query` on a layer reachable from `entries`, most similar first.
+    fn search_layer(&self, query: &[f32], entries: &[usize], ef: usize, layer: usize) -> Vec<Scored> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &node in entries {
+            let scored = Scored { similarity: dot(query, &self.nodes[node].vector), node };
+            candidates.push(scored);
+            found.push(Reverse(scored));
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+        while let Some(candidate) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|Reverse(worst)| candidate.similarity < worst.similarity) {
+                break;
+            }
+            for &neighbor in &self.nodes[candidate.node].layers[layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let scored = Scored { similarity: dot(query, &self.nodes[neighbor].vector), node: neighbor };
+                if found.len() < ef || found.peek().is_some_and(|Reverse(worst)| scored.similarity > worst.similarity) {
+                    candidates.push(scored);
+                    found.push(Reverse(scored));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        let mut found: Vec<Scored> = found.into_iter().map(|Reverse(scored)| scored).collect();
+        found.sort_by(|a, b| b.cmp(a));
+        found
+    }
+
+    /// Keeps only the most similar neighbours of a node on a layer if it has too many.
+    fn prune(&mut self, node: usize, layer: usize) {
+        let max = if layer == 0 { self.connections * 2 } else { self.connections };
+        if self.nodes[node].layers[layer].len() <= max {
+            return;
+        }
+        let vector = &self.nodes[node].vector;
+        let mut neighbors: Vec<Scored> = self.nodes[node].layers[layer].iter().map(|&neighbor| Scored { similarity: dot(vector, &self.nodes[neighbor].vector), node: neighbor }).collect();
+        neighbors.sort_by(|a, b| b.cmp(a));
+        self.nodes[node].layers[layer] = neighbors.into_iter().take(max).map(|scored| scored.node).collect()
+    }
+
+    /// Rebuilds the graph without the removed nodes.
+    fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.keys.clear();
+        self.entry = None;
+        self.removed = 0;
+        for node in nodes {
+            if let Some(key) = node.key {
+                self.insert(key, node.vector)
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    LexicalIndex, SearchHit,
+    embed::{dot, normalize},
+    hnsw::HnswIndex,
+};
+use std::collections::HashMap;
+
+/// How the lexical and vector scores of a chunk are mixed into the score of a hit.
+///
+/// The lexical score is the BM25 score divided by the best one among the candidates, the vector
+/// score the cosine similarity of the query and chunk vectors, ignoring negative ones; both are
+/// thus between 0 and 1, and the score of a hit is their weighted sum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HybridRanking {
+    /// The weight of the lexical score.
+    pub lexical_weight: f32,
+    /// The weight of the vector score.
+    pub vector_weight: f32,
+    /// How many candidates each index contributes for every requested hit.
+    pub candidates: usize,
+}
+
+impl Default for HybridRanking {
+    fn default() -> Self {
+        Self::new(0.5, 0.5)
+    }
+}
+
+impl HybridRanking {
+    /// Creates a ranking with the given weights of the lexical and vector scores.
+    pub fn new(lexical_weight: f32, vector_weight: f32) -> Self {
+        Self { lexical_weight, vector_weight, candidates: 4 }
+    }
+
+    /// Creates a ranking by the lexical score only.
+    pub fn lexical() -> Self {
+        Self::new(1.0, 0.0)
+    }
+
+    /// Creates a ranking by the vector score only.
+    pub fn vector() -> Self {
+        Self::new(0.0, 1.0)
+    }
+
+    /// Sets how many candidates each index contributes for every requested hit, at least one.
+    pub fn with_candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates.max(1);
+        self
+    }
+
+    /// Ranks the chunks for a query and its vector, best first.
+    pub(crate) fn rank(&self, lexical: &LexicalIndex, vectors: &HnswIndex, query: &str, query_vector: &[f32], limit: usize) -> Vec<SearchHit> {
+        let pool = limit.saturating_mul(self.candidates);
+        let mut query_vector = query_vector.to_vec();
+        normalize(&mut query_vector);
+
+        let mut candidates: HashMap<usize, f32> = HashMap::new();
+        if self.lexical_weight != 0.0 {
+            let top = lexical.top(lexical.scores(query), pool);
+            let best = top.first().map_or(0.0, |&(_, score)| score);
+            for (id, score) in top {
+                candidates.insert(id, if best > 0.0 { self.lexical_weight * score / best } else { 0.0 });
+            }
+        }
+        if self.vector_weight != 0.0 {
+            for (id, _) in vectors.search(&query_vector, pool) {
+                candidates.entry(id).or_default();
+            }
+        }
+        let scores = candidates.into_iter().map(|(id, score)| {
+            let similarity = vectors.vector(id).map_or(0.0, |vector| dot(vector, &query_vector)).max(0.0);
+            (id, score + self.vector_weight * similarity)
+        });
+        lexical.top(scores.filter(|&(_, score)| score > 0.0), limit).into_iter().filter_map(|(id, score)| lexical.hit(id, score)).collect()
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{CodeChunk, SearchHit, tokenize::tokenize};
+use core::range::Range;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The term frequency saturation of BM25.
+const K1: f32 = 1.2;
+/// How strongly BM25 normalizes term frequencies by the length of a chunk.
+const B: f32 = 0.75;
+
+/// A chunk in the index with its terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// The URI of the file the chunk is in.
+    uri: String,
+    /// The chunk.
+    chunk: CodeChunk,
+    /// The distinct terms of the chunk, see [`tokenize`].
+    terms: Vec<String>,
+    /// The number of terms of the chunk, counting repetitions.
+    length: u32,
+}
+
+/// An inverted index of code chunks, ranked with BM25.
+///
+/// Chunks are indexed per file: [`replace_file`](Self::replace_file) swaps all chunks of a file
+/// at once, so re-indexing an edited file does not touch the others.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LexicalIndex {
+    /// The chunks by id, `None` for removed ones whose ids are in `free`.
+    entries: Vec<Option<Entry>>,
+    /// The ids of removed chunks, reused for new ones.
+    free: Vec<usize>,
+    /// The chunk ids of every file.
+    files: HashMap<String, Vec<usize>>,
+    /// The ids of the chunks every term occurs in, with the number of occurrences.
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    /// The sum of the lengths of all chunks.
+    total_length: u64,
+}
+
+impl LexicalIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of indexed chunks.
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    /// Checks whether no chunk is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the URIs of the indexed files, in no particular order.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Returns the indexed chunks of a file.
+    pub fn chunks(&self, uri: &str) -> Vec<&CodeChunk> {
+        self.files.get(uri).into_iter().flatten().filter_map(|&id| self.entries[id].as_ref()).map(|entry| &entry.chunk).collect()
+    }
+
+    /// Replaces the chunks of a file, removing the file if there are none.
+    pub fn replace_file(&mut self, uri: &str, chunks: Vec<CodeChunk>) {
+        self.remove_file(uri);
+        if chunks.is_empty() {
+            return;
+        }
+        let mut ids = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for term in tokenize(&chunk.text) {
+                *counts.entry(term).or_default() += 1
+            }
+            let id = self.free.pop().unwrap_or(self.entries.len());
+            let length = counts.values().sum();
+            for (term, &count) in &counts {
+                self.postings.entry(term.clone()).or_default().push((id, count))
+            }
+            self.total_length += u64::from(length);
+            let entry = Entry { uri: uri.to_string(), chunk, terms: counts.into_keys().collect(), length };
+            if id == self.entries.len() {
+                self.entries.push(Some(entry))
+            }
+            else {
+                self.entries[id] = Some(entry)
+            }
+            ids.push(id)
+        }
+        self.files.insert(uri.to_string(), ids);
+    }
+
+    /// Removes the chunks of a file, returning whether it was indexed.
+    pub fn remove_file(&mut self, uri: &str) -> bool {
+        let Some(ids) = self.files.remove(uri)
+        else {
+            return false;
+        };
+        for id in ids {
+            let Some(entry) = self.entries[id].take()
+            else {
+                continue;
+            };
+            for term in entry.terms {
+                if let Some(postings) = self.postings.get_mut(&term) {
+                    postings.retain(|&(posting, _)| posting != id);
+                    if postings.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+            self.total_length -= u64::from(entry.length);
+            self.free.push(id)
+        }
+        true
+    }
+
+    /// Returns the ids of the chunks of a file, in the order they were given.
+    pub(crate) fn chunk_ids(&self, uri: &str) -> &[usize] {
+        self.files.get(uri).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the chunks matching `query` best, highest BM25 score first.
+    ///
+    /// The query is split into terms like the chunks, and a chunk scores for every distinct term
+    /// it contains: more for rare terms and for terms it repeats, less the longer it is.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        self.top(self.scores(query), limit).into_iter().filter_map(|(id, score)| self.hit(id, score)).collect()
+    }
+
+    /// Returns the BM25 scores of the chunks containing any term of `query` by id, in no particular order.
+    pub(crate) fn scores(&self, query: &str) -> Vec<(usize, f32)> {
+        let count = self.len();
+        if count == 0 {
+            return vec![];
+        }
+        let average_length = (self.total_length as f32 / count as f32).max(1.0);
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term)
+            else {
+                continue;
+            };
+            let matching = postings.len() as f32;
+            let idf = (1.0 + (count as f32 - matching + 0.5) / (matching + 0.5)).ln();
+            for &(id, frequency) in postings {
+                let Some(entry) = &self.entries[id]
+                else {
+                    continue;
+                };
+                let frequency = frequency as f32;
+                let norm = K1 * (1.0 - B + B * entry.length as f32 / average_length);
+                *scores.entry(id).or_default() += idf * frequency * (K1 + 1.0) / (frequency + norm)
+            }
+        }
+        scores.into_iter().collect()
+    }
+
+    /// Returns the `limit` highest scores of chunks by id, ties in the order of the files and positions.
+    pub(crate) fn top(&self, scores: impl IntoIterator<Item = (usize, f32)>, limit: usize) -> Vec<(usize, f32)> {
+        let mut ranked: Vec<(&Entry, usize, f32)> = scores.into_iter().filter_map(|(id, score)| Some((self.entries.get(id)?.as_ref()?, id, score))).collect();
+        ranked.sort_by(|(a, _, a_score), (b, _, b_score)| b_score.total_cmp(a_score).then_with(|| (&a.uri, a.chunk.range_start).cmp(&(&b.uri, b.chunk.range_start))));
+        ranked.into_iter().take(limit).map(|(_, id, score)| (id, score)).collect()
+    }
+
+    /// Returns the hit for a chunk by id with a score.
+    pub(crate) fn hit(&self, id: usize, score: f32) -> Option<SearchHit> {
+        let entry = self.entries.get(id)?.as_ref()?;
+        let chunk = &entry.chunk;
+        Some(SearchHit { uri: entry.uri.clone(), range: Range { start: chunk.range_start, end: chunk.range_end }, role: chunk.role.clone(), score, snippet: chunk.snippet() })
+    }
+}
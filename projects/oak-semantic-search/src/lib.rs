@@ -5,6 +5,22 @@
 //!
 //! This crate provides traits and structures for performing semantic search
 //! on source code, including code chunking and indexing.
+mod embed;
+mod hnsw;
+mod hybrid;
+mod index;
+mod store;
+mod tokenize;
+
+use crate::store::Store;
+pub use crate::{
+    embed::{Embedder, HashingEmbedder, normalize},
+    hnsw::HnswIndex,
+    hybrid::HybridRanking,
+    index::LexicalIndex,
+    tokenize::tokenize,
+};
+use core::range::Range;
 use oak_core::{
     errors::OakError,
     language::{ElementRole, ElementType, Language, UniversalElementRole},
@@ -12,6 +28,11 @@ use oak_core::{
     visitor::Visitor,
 };
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::RwLock,
+};
 
 /// Trait for semantic search implementations.
 pub trait SemanticSearch: Send + Sync {
@@ -20,7 +41,7 @@ pub trait SemanticSearch: Send + Sync {
     /// # Arguments
     /// * `query` - The search query string.
     /// * `limit` - The maximum number of results to return.
-    async fn search(&self, query: &str, limit: usize) -> Result<Vec<String>, OakError>;
+    fn search<'a>(&'a self, query: &'a str, limit: usize) -> impl Future<Output = Result<Vec<SearchHit>, OakError>> + Send + 'a;
 }
 
 /// A default implementation of SemanticSearch that does nothing.
@@ -28,13 +49,13 @@ pub struct NoSemanticSearch;
 
 impl SemanticSearch for NoSemanticSearch {
     /// Always returns an error indicating semantic search is disabled.
-    async fn search(&self, _query: &str, _limit: usize) -> Result<Vec<String>, OakError> {
+    async fn search(&self, _query: &str, _limit: usize) -> Result<Vec<SearchHit>, OakError> {
         Err(OakError::semantic_error("Semantic search is not enabled on this server"))
     }
 }
 
 /// Represents a chunk of code extracted for semantic indexing.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CodeChunk {
     /// The text content of the chunk.
     pub text: String,
@@ -46,8 +67,79 @@ pub struct CodeChunk {
     pub role: String,
 }
 
-/// A searcher that performs semantic search on code.
-pub struct SemanticSearcher {}
+impl CodeChunk {
+    /// Returns the first non-empty line of the chunk, trimmed.
+    pub fn snippet(&self) -> String {
+        self.text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default().to_string()
+    }
+}
+
+/// A chunk of code found by a search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// The URI of the file the chunk is in.
+    pub uri: String,
+    /// The byte range of the chunk in the file.
+    #[serde(with = "oak_core::serde_range")]
+    pub range: Range<usize>,
+    /// The role of the code element (e.g., "Definition", "Statement").
+    pub role: String,
+    /// How well the chunk matches the query; higher is better.
+    pub score: f32,
+    /// The first line of the chunk.
+    pub snippet: String,
+}
+
+/// A searcher that indexes code chunks of files and ranks them for queries.
+///
+/// Chunks are ranked with BM25, and with an [`Embedder`] also by the similarity of their vectors
+/// to the one of the query, mixed by a [`HybridRanking`]. The index is fully local: the chunks of
+/// every file and their vectors are persisted under the database path, with a snapshot of the
+/// indexes written by [`persist`](Self::persist) and when the searcher is dropped, and loaded again
+/// when the searcher is created. Nothing needs network access unless the embedder does.
+pub struct SemanticSearcher {
+    store: Store,
+    indexes: RwLock<Indexes>,
+    embedder: Option<Box<dyn Embedder>>,
+    ranking: HybridRanking,
+}
+
+/// The indexes of a [`SemanticSearcher`], the vectors keyed by the ids of the lexical chunks.
+#[derive(Default)]
+struct Indexes {
+    lexical: LexicalIndex,
+    vectors: HnswIndex,
+    /// The hash of the segment every indexed file was indexed from.
+    segments: HashMap<String, u64>,
+    /// Whether the indexes changed since their snapshot was written.
+    dirty: bool,
+}
+
+impl Indexes {
+    /// Replaces the chunks of a file and their vectors, if they were embedded, from the segment with
+    /// the given hash, or removes the file without one.
+    fn replace_file(&mut self, uri: &str, chunks: Vec<CodeChunk>, vectors: Vec<Vec<f32>>, segment: Option<u64>) {
+        for &id in self.lexical.chunk_ids(uri) {
+            self.vectors.remove(id);
+        }
+        self.lexical.replace_file(uri, chunks);
+        for (&id, vector) in self.lexical.chunk_ids(uri).iter().zip(vectors) {
+            self.vectors.insert(id, vector)
+        }
+        match segment {
+            Some(hash) => self.segments.insert(uri.to_string(), hash),
+            None => self.segments.remove(uri),
+        };
+        self.dirty = true
+    }
+
+    /// Returns the indexed chunks of a file and their vectors, if they were embedded.
+    fn file(&self, uri: &str) -> (Vec<CodeChunk>, Vec<Vec<f32>>) {
+        let chunks = self.lexical.chunks(uri).into_iter().cloned().collect();
+        let vectors = self.lexical.chunk_ids(uri).iter().filter_map(|&id| self.vectors.vector(id)).map(<[f32]>::to_vec).collect();
+        (chunks, vectors)
+    }
+}
 
 /// A visitor that collects code chunks from the syntax tree.
 struct ChunkCollector<'a, L: Language> {
@@ -73,7 +165,9 @@ impl<'a, L: Language> Visitor<'a, L> for ChunkCollector<'a, L> {
         match role {
             UniversalElementRole::Definition | UniversalElementRole::Statement | UniversalElementRole::Documentation => {
                 let range = node.span();
-                let text = self.source[range.start..range.end].to_string();
+                // Leave out the trailing whitespace and comments the parser attached to the node.
+                let text = self.source[range.start..range.end].trim_end().to_string();
+                let range = Range { start: range.start, end: range.start + text.len() };
 
                 // Only index chunks that are meaningful in length
                 if text.len() > 20 {
@@ -89,30 +183,145 @@ impl<'a, L: Language> Visitor<'a, L> for ChunkCollector<'a, L> {
 }
 
 impl SemanticSearcher {
-    /// Creates a new semantic searcher with the given database path.
-    pub async fn new(_db_path: &str) -> Result<Self, OakError> {
-        Ok(Self {})
+    /// Creates a lexical semantic searcher storing its index in the directory `db_path`, loading
+    /// the files indexed there before.
+    pub async fn new(db_path: impl AsRef<Path>) -> Result<Self, OakError> {
+        Self::open(db_path.as_ref(), None)
     }
 
-    /// Chunks code using oak-core's AST and indexes it
-    pub async fn index_code<'a, L: Language>(&self, root: RedNode<'a, L>, source: &'a str, _table_name: &str) -> Result<(), OakError> {
+    /// Creates a semantic searcher that also ranks chunks by their vectors from `embedder`,
+    /// embedding the files indexed before unless their vectors by the same embedder are stored.
+    pub async fn with_embedder(db_path: impl AsRef<Path>, embedder: impl Embedder + 'static) -> Result<Self, OakError> {
+        Self::open(db_path.as_ref(), Some(Box::new(embedder)))
+    }
+
+    /// Opens the store, loading the snapshot of the indexes and indexing the segments written
+    /// since, or all of them if the snapshot is missing or from another embedder.
+    ///
+    /// A corrupt segment is skipped. It is written again from the snapshot if that has its file,
+    /// and deleted otherwise, so the file is only found again once it is indexed again.
+    fn open(db_path: &Path, embedder: Option<Box<dyn Embedder>>) -> Result<Self, OakError> {
+        let store = Store::open(db_path)?;
+        let name = embedder.as_ref().map(|embedder| embedder.name());
+        let mut indexes = match store.load_snapshot() {
+            Some(snapshot) if snapshot.embedder == name => Indexes { lexical: snapshot.lexical, vectors: snapshot.vectors, segments: snapshot.segments, dirty: false },
+            _ => Indexes::default(),
+        };
+        let (segments, corrupt) = store.load()?;
+        let mut missing: HashSet<String> = indexes.segments.keys().cloned().collect();
+        for segment in segments {
+            missing.remove(&segment.uri);
+            if indexes.segments.get(&segment.uri) == Some(&segment.hash) {
+                continue;
+            }
+            let mut vectors = segment.vectors;
+            let mut hash = segment.hash;
+            if let Some(embedder) = &embedder
+                && (segment.embedder != name || vectors.len() != segment.chunks.len())
+            {
+                vectors = embedder.embed_batch(&segment.chunks.iter().map(|chunk| chunk.text.as_str()).collect::<Vec<_>>());
+                hash = store.save(&segment.uri, &segment.chunks, name.as_deref(), &vectors)?;
+            }
+            indexes.replace_file(&segment.uri, segment.chunks, if embedder.is_some() { vectors } else { Vec::new() }, Some(hash))
+        }
+        for path in corrupt {
+            match missing.iter().find(|uri| store.segment_path(uri) == path).cloned() {
+                Some(uri) => {
+                    missing.remove(&uri);
+                    let (chunks, vectors) = indexes.file(&uri);
+                    let hash = store.save(&uri, &chunks, name.as_deref(), &vectors)?;
+                    indexes.segments.insert(uri, hash);
+                    indexes.dirty = true
+                }
+                None => store.remove_corrupt(&path)?,
+            }
+        }
+        for uri in missing {
+            indexes.replace_file(&uri, Vec::new(), Vec::new(), None)
+        }
+        let searcher = Self { store, indexes: RwLock::new(indexes), embedder, ranking: HybridRanking::default() };
+        searcher.persist()?;
+        Ok(searcher)
+    }
+
+    /// Writes the snapshot of the indexes if they changed since the last one, so they are loaded
+    /// instead of being built again when the searcher is created.
+    pub fn persist(&self) -> Result<(), OakError> {
+        let mut indexes = self.indexes.write().unwrap();
+        if indexes.dirty {
+            self.store.save_snapshot(self.embedder.as_ref().map(|embedder| embedder.name()).as_deref(), &indexes.segments, &indexes.lexical, &indexes.vectors)?;
+            indexes.dirty = false
+        }
+        Ok(())
+    }
+
+    /// Sets how lexical and vector scores are mixed, which only matters with an embedder.
+    pub fn with_ranking(mut self, ranking: HybridRanking) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    /// Chunks the code of a file using oak-core's AST and indexes it, replacing the chunks
+    /// indexed for the file before.
+    pub async fn index_code<'a, L: Language>(&self, root: RedNode<'a, L>, source: &'a str, uri: &str) -> Result<(), OakError> {
         let mut collector = ChunkCollector::<L>::new(source);
         collector.visit_node(root);
 
-        if collector.chunks.is_empty() {
-            return Ok(());
+        let chunks = collector.chunks;
+        let vectors = match &self.embedder {
+            Some(embedder) => embedder.embed_batch(&chunks.iter().map(|chunk| chunk.text.as_str()).collect::<Vec<_>>()),
+            None => Vec::new(),
+        };
+        let segment = if chunks.is_empty() {
+            self.store.remove(uri)?;
+            None
         }
+        else {
+            Some(self.store.save(uri, &chunks, self.embedder.as_ref().map(|embedder| embedder.name()).as_deref(), &vectors)?)
+        };
+        self.indexes.write().unwrap().replace_file(uri, chunks, vectors, segment);
+        Ok(())
+    }
 
-        // Concrete implementation (embedding and vector storage) has been removed.
-        // This is now a placeholder that only performs AST-based chunking.
+    /// Removes a file from the index, returning whether it was indexed.
+    pub async fn remove_file(&self, uri: &str) -> Result<bool, OakError> {
+        self.store.remove(uri)?;
+        let mut indexes = self.indexes.write().unwrap();
+        let removed = !indexes.lexical.chunk_ids(uri).is_empty();
+        indexes.replace_file(uri, Vec::new(), Vec::new(), None);
+        Ok(removed)
+    }
 
-        Ok(())
+    /// Returns the chunks matching `query` best, highest score first.
+    pub fn search_hits(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let indexes = self.indexes.read().unwrap();
+        match &self.embedder {
+            Some(embedder) => self.ranking.rank(&indexes.lexical, &indexes.vectors, query, &embedder.embed(query), limit),
+            None => indexes.lexical.search(query, limit),
+        }
+    }
+
+    /// Returns the number of indexed chunks.
+    pub fn len(&self) -> usize {
+        self.indexes.read().unwrap().lexical.len()
+    }
+
+    /// Checks whether no chunk is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for SemanticSearcher {
+    fn drop(&mut self) {
+        if let Err(e) = self.persist() {
+            tracing::warn!("cannot write the index snapshot: {}", e)
+        }
     }
 }
 
 impl SemanticSearch for SemanticSearcher {
-    async fn search(&self, _query: &str, _limit: usize) -> Result<Vec<String>, OakError> {
-        // Concrete implementation (query embedding and vector search) has been removed.
-        Ok(vec![])
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, OakError> {
+        Ok(self.search_hits(query, limit))
     }
 }
@@ -0,0 +1,167 @@
+use crate::{CodeChunk, HnswIndex, LexicalIndex, tokenize::stable_hash};
+use oak_core::errors::OakError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file holding the [`Snapshot`] of the indexes.
+const SNAPSHOT: &str = "index.snapshot";
+
+/// The chunks of a file as written to disk.
+#[derive(Serialize)]
+struct SegmentRef<'a> {
+    uri: &'a str,
+    chunks: &'a [CodeChunk],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embedder: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    vectors: &'a [Vec<f32>],
+}
+
+/// The chunks of a file as read from disk.
+#[derive(Deserialize)]
+pub(crate) struct Segment {
+    /// The URI of the file.
+    pub uri: String,
+    /// The chunks of the file.
+    pub chunks: Vec<CodeChunk>,
+    /// The name of the [`Embedder`](crate::Embedder) of the vectors.
+    #[serde(default)]
+    pub embedder: Option<String>,
+    /// The vectors of the chunks, if they were embedded.
+    #[serde(default)]
+    pub vectors: Vec<Vec<f32>>,
+    /// The stable hash of the segment as written, see [`Snapshot::segments`].
+    #[serde(skip)]
+    pub hash: u64,
+}
+
+/// The indexes as written to disk.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    embedder: Option<&'a str>,
+    segments: &'a HashMap<String, u64>,
+    lexical: &'a LexicalIndex,
+    vectors: &'a HnswIndex,
+}
+
+/// The indexes as read from disk, with the segments they were built from.
+#[derive(Deserialize)]
+pub(crate) struct Snapshot {
+    /// The name of the [`Embedder`](crate::Embedder) of the vectors.
+    pub embedder: Option<String>,
+    /// The stable hash of the segment of every indexed file by URI, to find the segments written since.
+    pub segments: HashMap<String, u64>,
+    /// The lexical index.
+    pub lexical: LexicalIndex,
+    /// The vector index.
+    pub vectors: HnswIndex,
+}
+
+/// A directory holding one JSON segment with the chunks of every indexed file, and their vectors
+/// if they were embedded, and a snapshot of the indexes built from them.
+///
+/// Re-indexing a file only rewrites its own segment, and the vectors are only computed again for
+/// another embedder. The indexes are loaded from the snapshot when the store is opened, and only
+/// the segments written since are indexed again.
+#[derive(Debug, Clone)]
+pub(crate) struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    /// Opens the store in `dir`, creating the directory if needed.
+    pub(crate) fn open(dir: &Path) -> Result<Self, OakError> {
+        fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    /// Returns the path of the segment of a file, named by a stable hash of its URI.
+    pub(crate) fn segment_path(&self, uri: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", stable_hash(uri)))
+    }
+
+    /// Writes the segment of a file with the vectors of its chunks by an embedder, replacing the
+    /// previous one atomically, and returns its hash.
+    pub(crate) fn save(&self, uri: &str, chunks: &[CodeChunk], embedder: Option<&str>, vectors: &[Vec<f32>]) -> Result<u64, OakError> {
+        let json = serde_json::to_vec(&SegmentRef { uri, chunks, embedder, vectors }).map_err(|e| OakError::serde_error(e.to_string()))?;
+        write_atomically(&self.segment_path(uri), &json)?;
+        Ok(stable_hash(&json))
+    }
+
+    /// Deletes the segment of a file, if any.
+    pub(crate) fn remove(&self, uri: &str) -> Result<(), OakError> {
+        remove_if_exists(&self.segment_path(uri))
+    }
+
+    /// Reads all segments, ordered by the URIs of their files, and the paths of the corrupt ones.
+    pub(crate) fn load(&self) -> Result<(Vec<Segment>, Vec<PathBuf>), OakError> {
+        let mut segments = Vec::new();
+        let mut corrupt = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let bytes = fs::read(&path)?;
+            match serde_json::from_slice::<Segment>(&bytes) {
+                Ok(segment) => segments.push(Segment { hash: stable_hash(&bytes), ..segment }),
+                Err(e) => {
+                    tracing::warn!("skipping corrupt segment {}: {}", path.display(), e);
+                    corrupt.push(path)
+                }
+            }
+        }
+        segments.sort_by(|a, b| a.uri.cmp(&b.uri));
+        corrupt.sort();
+        Ok((segments, corrupt))
+    }
+
+    /// Deletes a corrupt segment.
+    pub(crate) fn remove_corrupt(&self, path: &Path) -> Result<(), OakError> {
+        remove_if_exists(path)
+    }
+
+    /// Reads the snapshot of the indexes, if there is a readable one.
+    pub(crate) fn load_snapshot(&self) -> Option<Snapshot> {
+        let path = self.dir.join(SNAPSHOT);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    tracing::warn!("cannot read the index snapshot {}: {}", path.display(), e)
+                }
+                return None;
+            }
+        };
+        serde_json::from_slice(&bytes).inspect_err(|e| tracing::warn!("ignoring corrupt index snapshot {}: {}", path.display(), e)).ok()
+    }
+
+    /// Writes the snapshot of the indexes built from the segments with the given hashes, replacing
+    /// the previous one atomically.
+    pub(crate) fn save_snapshot(&self, embedder: Option<&str>, segments: &HashMap<String, u64>, lexical: &LexicalIndex, vectors: &HnswIndex) -> Result<(), OakError> {
+        let json = serde_json::to_vec(&SnapshotRef { embedder, segments, lexical, vectors }).map_err(|e| OakError::serde_error(e.to_string()))?;
+        write_atomically(&self.dir.join(SNAPSHOT), &json)
+    }
+}
+
+/// Writes a file through a temporary one, so readers never see it half written.
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), OakError> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, bytes)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Deletes a file, if it exists.
+fn remove_if_exists(path: &Path) -> Result<(), OakError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,42 @@
+/// Splits text into lower case search terms.
+///
+/// Every identifier, a run of alphanumeric characters and `_`, yields its words, split on `_` and
+/// on camel humps, and also the whole identifier if it has more than one word. So `parseWithLexer`
+/// yields `parse`, `with`, `lexer` and `parsewithlexer`, and `HTTPServer` yields `http`, `server`
+/// and `httpserver`. Words of a single character are dropped.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for identifier in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        let words = words(identifier);
+        if words.len() > 1 {
+            terms.push(identifier.trim_matches('_').to_lowercase())
+        }
+        terms.extend(words.into_iter().filter(|word| word.chars().nth(1).is_some()).map(str::to_lowercase))
+    }
+    terms
+}
+
+/// Splits an identifier into its words.
+fn words(identifier: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in identifier.split('_').filter(|part| !part.is_empty()) {
+        let chars: Vec<(usize, char)> = part.char_indices().collect();
+        let mut start = 0;
+        for (i, window) in chars.windows(2).enumerate() {
+            let ((_, previous), (index, current)) = (window[0], window[1]);
+            let next_is_lower = chars.get(i + 2).is_some_and(|(_, next)| next.is_lowercase());
+            // `parseWith` splits before `W`, `HTTPServer` splits before `S`.
+            if current.is_uppercase() && (previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower)) {
+                words.push(&part[start..index]);
+                start = index
+            }
+        }
+        words.push(&part[start..])
+    }
+    words
+}
+
+/// Hashes bytes with FNV-1a, which unlike the standard hasher does not change between releases.
+pub(crate) fn stable_hash(bytes: impl AsRef<[u8]>) -> u64 {
+    bytes.as_ref().iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
@@ -0,0 +1,223 @@
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    tree::{GreenLeaf, GreenNode, GreenTree, RedNode},
+};
+use oak_semantic_search::{CodeChunk, Embedder, HashingEmbedder, HnswIndex, HybridRanking, LexicalIndex, SemanticSearch, SemanticSearcher, normalize, tokenize};
+use oak_testing::building::{Universal, leak_node};
+
+/// Builds a root with a definition of a single token for every line of `lines`.
+fn tree(lines: &[&str]) -> (String, &'static GreenNode<'static, Universal>) {
+    let mut text = String::new();
+    let mut children = Vec::new();
+    for line in lines {
+        text.push_str(line);
+        text.push('\n');
+        let leaves = vec![GreenTree::Leaf(GreenLeaf::new(T::Name, line.len() as u32)), GreenTree::Leaf(GreenLeaf::new(T::Whitespace, 1))];
+        children.push(GreenTree::Node(leak_node(E::Definition, leaves)));
+    }
+    (text, leak_node(E::Root, children))
+}
+
+fn chunk(text: &str, start: usize) -> CodeChunk {
+    CodeChunk { text: text.to_string(), range_start: start, range_end: start + text.len(), role: "Definition".to_string() }
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("oak-semantic-search-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_tokenize() {
+    assert_eq!(tokenize("parseWithLexer(HTTPServer, max_depth, x)"), ["parsewithlexer", "parse", "with", "lexer", "httpserver", "http", "server", "max_depth", "max", "depth"]);
+    assert_eq!(tokenize("utf8Decoder __init__"), ["utf8decoder", "utf8", "decoder", "init"]);
+}
+
+#[test]
+fn test_lexical_index() {
+    let mut index = LexicalIndex::new();
+    index.replace_file("file:///a.rs", vec![chunk("fn parse_with_lexer(lexer: &Lexer) -> Tree", 0), chunk("fn format(tree: &Tree) -> String", 50)]);
+    index.replace_file("file:///b.rs", vec![chunk("fn lex(text: &str) -> Tokens", 0), chunk("fn highlight(tree: &Tree, theme: &Theme)", 40)]);
+    assert_eq!(index.len(), 4);
+
+    // Rare terms and repeated terms weigh more.
+    let hits = index.search("lexer tree", 10);
+    assert_eq!(hits[0].uri, "file:///a.rs");
+    assert_eq!(hits[0].range.start, 0);
+    assert_eq!(hits[0].snippet, "fn parse_with_lexer(lexer: &Lexer) -> Tree");
+    assert_eq!(hits.len(), 3);
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert_eq!(index.search("LEXER", 10).len(), 1);
+    assert!(index.search("missing", 10).is_empty());
+    assert_eq!(index.search("tree", 1).len(), 1);
+
+    // Re-indexing a file replaces its chunks only.
+    index.replace_file("file:///a.rs", vec![chunk("fn render(doc: &Document)", 0)]);
+    assert_eq!(index.len(), 3);
+    assert!(index.search("lexer", 10).is_empty());
+    assert_eq!(index.search("render", 10)[0].uri, "file:///a.rs");
+    assert_eq!(index.search("highlight", 10)[0].uri, "file:///b.rs");
+
+    assert!(index.remove_file("file:///b.rs"));
+    assert!(!index.remove_file("file:///b.rs"));
+    assert_eq!(index.files().collect::<Vec<_>>(), ["file:///a.rs"]);
+    assert!(index.search("tree", 10).is_empty());
+}
+
+#[tokio::test]
+async fn test_semantic_searcher() {
+    let dir = temp_dir("searcher");
+    let searcher = SemanticSearcher::new(&dir).await.unwrap();
+    assert!(searcher.is_empty());
+
+    let (text, green) = tree(&["fn parse_document(source: &str) -> Document", "fn render_document(document: &Document) -> String", "short"]);
+    searcher.index_code(RedNode::new(green, 0), &text, "file:///doc.rs").await.unwrap();
+    let (other, green) = tree(&["fn tokenize_identifier(word: &str) -> Vec<String>"]);
+    searcher.index_code(RedNode::new(green, 0), &other, "file:///tokens.rs").await.unwrap();
+    // Chunks of 20 bytes or less are not indexed.
+    assert_eq!(searcher.len(), 3);
+
+    let hits = searcher.search_hits("render document", 5);
+    assert_eq!(hits[0].uri, "file:///doc.rs");
+    assert_eq!(&text[hits[0].range.start..hits[0].range.end], "fn render_document(document: &Document) -> String");
+    assert_eq!(hits[0].role, "Definition");
+    let results = searcher.search("tokenize", 5).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!((results[0].uri.as_str(), results[0].range.start, results[0].range.end), ("file:///tokens.rs", 0, 49));
+    assert_eq!(results[0].snippet, "fn tokenize_identifier(word: &str) -> Vec<String>");
+
+    // The index survives a restart, and re-indexing or removing a file updates it.
+    let reopened = SemanticSearcher::new(&dir).await.unwrap();
+    assert_eq!(reopened.search_hits("render document", 5), hits);
+    let (text, green) = tree(&["fn print_document(document: &Document)"]);
+    reopened.index_code(RedNode::new(green, 0), &text, "file:///doc.rs").await.unwrap();
+    assert!(reopened.remove_file("file:///tokens.rs").await.unwrap());
+    let reopened = SemanticSearcher::new(&dir).await.unwrap();
+    assert_eq!(reopened.len(), 1);
+    assert!(reopened.search_hits("render", 5).iter().all(|hit| hit.uri == "file:///parse.rs"));
+    assert_eq!(reopened.search_hits("print", 5)[0].snippet, "fn print_document(document: &Document)");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_hashing_embedder() {
+    let embedder = HashingEmbedder::new(64);
+    assert_eq!(embedder.name(), "hashing-64");
+    let vector = embedder.embed("fn tokenize_identifier(word: &str)");
+    assert_eq!(vector.len(), 64);
+    assert_eq!(vector, embedder.embed("fn tokenize_identifier(word: &str)"));
+    assert!((vector.iter().map(|x| x * x).sum::<f32>() - 1.0).abs() < 1e-5);
+
+    // Sharing words and parts of words makes texts closer.
+    let similarity = |a: &str, b: &str| embedder.embed(a).iter().zip(embedder.embed(b)).map(|(a, b)| a * b).sum::<f32>();
+    assert!(similarity("tokenizer", "tokenizeIdentifier") > similarity("tokenizer", "render_document"));
+    assert_eq!(embedder.embed(""), vec![0.0; 64]);
+}
+
+#[test]
+fn test_hnsw_index() {
+    // Points on a circle, so the nearest neighbours of an angle are known.
+    let point = |angle: f32| vec![angle.cos(), angle.sin(), 0.0];
+    let mut index = HnswIndex::new().with_connections(4);
+    for key in 0..200 {
+        index.insert(key, point(key as f32 * 0.0314))
+    }
+    assert_eq!(index.len(), 200);
+    let mut found: Vec<usize> = index.search(&point(100.0 * 0.0314), 3).into_iter().map(|(key, _)| key).collect();
+    assert_eq!(found[0], 100);
+    found.sort();
+    assert_eq!(found, [99, 100, 101]);
+    let (_, similarity) = index.search(&[2.0, 0.0, 0.0], 1)[0];
+    assert!((similarity - 1.0).abs() < 1e-5);
+
+    // Removed vectors are not found, and replaced ones are found at their new place.
+    for key in 0..150 {
+        assert!(index.remove(key));
+    }
+    assert!(!index.remove(0));
+    index.insert(199, point(180.0 * 0.0314));
+    assert_eq!(index.len(), 50);
+    assert_eq!(index.search(&point(140.0 * 0.0314), 1)[0].0, 150);
+    let mut found: Vec<usize> = index.search(&point(180.0 * 0.0314), 2).into_iter().map(|(key, _)| key).collect();
+    found.sort();
+    assert_eq!(found, [180, 199]);
+    assert!(index.vector(10).is_none());
+    let mut expected = point(180.0 * 0.0314);
+    normalize(&mut expected);
+    assert_eq!(index.vector(199), Some(expected.as_slice()));
+}
+
+#[tokio::test]
+async fn test_hybrid_search() {
+    let dir = temp_dir("hybrid");
+    let lines = ["fn tokenize_identifier(word: &str) -> Vec<String>", "fn render_document(document: &Document) -> String", "fn parse_document(source: &str) -> Document"];
+    let (text, green) = tree(&lines);
+    let searcher = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap();
+    searcher.index_code(RedNode::new(green, 0), &text, "file:///lib.rs").await.unwrap();
+
+    // `tokenizer` is no term of any chunk, but shares trigrams with `tokenize_identifier`.
+    assert!(searcher.search_hits("tokenizer", 5).first().is_some_and(|hit| hit.snippet == lines[0]));
+    let hits = searcher.search_hits("render document", 5);
+    assert_eq!(hits[0].snippet, lines[1]);
+    assert!(hits.iter().all(|hit| hit.score > 0.0 && hit.score <= 1.0 + 1e-5));
+
+    // The weights decide which score counts.
+    let lexical = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap().with_ranking(HybridRanking::lexical());
+    assert!(lexical.search_hits("tokenizer", 5).is_empty());
+    assert_eq!(lexical.search_hits("document", 5).len(), 2);
+    let vector = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap().with_ranking(HybridRanking::vector().with_candidates(2));
+    assert_eq!(vector.search_hits("tokenizer", 1)[0].snippet, lines[0]);
+
+    // Stored vectors are reused by the same embedder and replaced for another one.
+    let segment = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).find(|path| path.extension().is_some_and(|extension| extension == "json")).unwrap();
+    assert!(std::fs::read_to_string(&segment).unwrap().contains("\"embedder\":\"hashing-256\""));
+    let other = SemanticSearcher::with_embedder(&dir, HashingEmbedder::new(32)).await.unwrap();
+    assert!(std::fs::read_to_string(&segment).unwrap().contains("\"embedder\":\"hashing-32\""));
+    assert_eq!(other.search_hits("tokenizer", 1)[0].snippet, lines[0]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_searcher_snapshot() {
+    let dir = temp_dir("snapshot");
+    let segments = || {
+        let mut paths: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).filter(|path| path.extension().is_some_and(|extension| extension == "json")).collect();
+        paths.sort();
+        paths
+    };
+    let searcher = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap();
+    let (text, green) = tree(&["fn parse_document(source: &str) -> Document"]);
+    searcher.index_code(RedNode::new(green, 0), &text, "file:///parse.rs").await.unwrap();
+    let (text, green) = tree(&["fn render_document(document: &Document) -> String"]);
+    searcher.index_code(RedNode::new(green, 0), &text, "file:///render.rs").await.unwrap();
+    let hits = searcher.search_hits("document", 5);
+    drop(searcher);
+    assert!(dir.join("index.snapshot").exists());
+
+    // A corrupt segment is written again from the snapshot.
+    let corrupt = segments().into_iter().find(|path| std::fs::read_to_string(path).unwrap().contains("render.rs")).unwrap();
+    std::fs::write(&corrupt, "{\"uri\":").unwrap();
+    let reopened = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap();
+    assert_eq!(reopened.search_hits("document", 5), hits);
+    assert!(std::fs::read_to_string(&corrupt).unwrap().contains("fn render_document"));
+
+    // Segments written since the snapshot are indexed again, even by another searcher.
+    let (text, green) = tree(&["fn print_document(document: &Document)"]);
+    SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap().index_code(RedNode::new(green, 0), &text, "file:///parse.rs").await.unwrap();
+    drop(reopened);
+    let reopened = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap();
+    assert_eq!(reopened.search_hits("print", 5)[0].uri, "file:///parse.rs");
+    assert!(reopened.search_hits("parse", 5).is_empty());
+    drop(reopened);
+
+    // Without the snapshot, a corrupt segment is skipped and deleted.
+    std::fs::write(dir.join("index.snapshot"), "corrupt").unwrap();
+    std::fs::write(&corrupt, "corrupt").unwrap();
+    let reopened = SemanticSearcher::with_embedder(&dir, HashingEmbedder::default()).await.unwrap();
+    assert_eq!(reopened.len(), 1);
+    assert!(reopened.search_hits("render", 5).iter().all(|hit| hit.uri == "file:///parse.rs"));
+    assert!(!corrupt.exists());
+    drop(reopened);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,48 @@
+use crate::SemanticToken;
+use std::{
+    collections::HashMap,
+    sync::{
+        RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Remembers the last token array reported for each document, keyed by result ID.
+///
+/// This is the state needed to answer `semanticTokens/full/delta` requests.
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    /// Map of URI to the last result ID and token array.
+    entries: RwLock<HashMap<String, (String, Vec<SemanticToken>)>>,
+    /// The next result ID to assign.
+    next_id: AtomicU64,
+}
+
+impl SemanticTokensCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the tokens reported for a document and returns their new result ID.
+    pub fn store(&self, uri: &str, tokens: Vec<SemanticToken>) -> String {
+        let result_id = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(uri.to_string(), (result_id.clone(), tokens));
+        }
+        result_id
+    }
+
+    /// Returns the tokens previously reported for a document under the given result ID.
+    pub fn previous(&self, uri: &str, result_id: &str) -> Option<Vec<SemanticToken>> {
+        let entries = self.entries.read().ok()?;
+        entries.get(uri).filter(|(id, _)| id == result_id).map(|(_, tokens)| tokens.clone())
+    }
+
+    /// Forgets the tokens of a document, e.g. when it is closed.
+    pub fn remove(&self, uri: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(uri);
+        }
+    }
+}
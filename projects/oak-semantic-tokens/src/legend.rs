@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+
+/// The standard semantic token types.
+///
+/// The discriminant of each variant is its index in [`SemanticTokensLegend::standard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SemanticTokenType {
+    /// A namespace, module or package.
+    Namespace,
+    /// A type that is not covered by a more specific variant.
+    Type,
+    /// A class type.
+    Class,
+    /// An enumeration type.
+    Enum,
+    /// An interface type.
+    Interface,
+    /// A struct type.
+    Struct,
+    /// A type parameter.
+    TypeParameter,
+    /// A function or method parameter.
+    Parameter,
+    /// A local or global variable.
+    Variable,
+    /// A member property, field or attribute key.
+    Property,
+    /// An enumeration member.
+    EnumMember,
+    /// An event property.
+    Event,
+    /// A function.
+    Function,
+    /// A member function.
+    Method,
+    /// A macro.
+    Macro,
+    /// A language keyword.
+    Keyword,
+    /// A modifier keyword.
+    Modifier,
+    /// A comment.
+    Comment,
+    /// A string literal.
+    String,
+    /// A number literal.
+    Number,
+    /// A regular expression literal.
+    Regexp,
+    /// An operator.
+    Operator,
+    /// A decorator or annotation.
+    Decorator,
+}
+
+impl SemanticTokenType {
+    /// All token types, in legend order.
+    pub const ALL: [SemanticTokenType; 23] = [
+        Self::Namespace,
+        Self::Type,
+        Self::Class,
+        Self::Enum,
+        Self::Interface,
+        Self::Struct,
+        Self::TypeParameter,
+        Self::Parameter,
+        Self::Variable,
+        Self::Property,
+        Self::EnumMember,
+        Self::Event,
+        Self::Function,
+        Self::Method,
+        Self::Macro,
+        Self::Keyword,
+        Self::Modifier,
+        Self::Comment,
+        Self::String,
+        Self::Number,
+        Self::Regexp,
+        Self::Operator,
+        Self::Decorator,
+    ];
+
+    /// Returns the LSP name of this token type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Namespace => "namespace",
+            Self::Type => "type",
+            Self::Class => "class",
+            Self::Enum => "enum",
+            Self::Interface => "interface",
+            Self::Struct => "struct",
+            Self::TypeParameter => "typeParameter",
+            Self::Parameter => "parameter",
+            Self::Variable => "variable",
+            Self::Property => "property",
+            Self::EnumMember => "enumMember",
+            Self::Event => "event",
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Macro => "macro",
+            Self::Keyword => "keyword",
+            Self::Modifier => "modifier",
+            Self::Comment => "comment",
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Regexp => "regexp",
+            Self::Operator => "operator",
+            Self::Decorator => "decorator",
+        }
+    }
+
+    /// Returns the index of this token type in the standard legend.
+    pub fn index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// The standard semantic token modifiers.
+///
+/// The discriminant of each variant is its bit position in the modifier bitset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SemanticTokenModifier {
+    /// The declaration of a symbol.
+    Declaration,
+    /// The definition of a symbol, e.g. in header files.
+    Definition,
+    /// A read-only variable or member.
+    Readonly,
+    /// A static member.
+    Static,
+    /// A symbol that should no longer be used.
+    Deprecated,
+    /// An abstract type or member.
+    Abstract,
+    /// An async function.
+    Async,
+    /// A variable reference where the variable is assigned to.
+    Modification,
+    /// An occurrence in documentation.
+    Documentation,
+    /// A symbol that is part of the standard library.
+    DefaultLibrary,
+}
+
+impl SemanticTokenModifier {
+    /// All token modifiers, in legend order.
+    pub const ALL: [SemanticTokenModifier; 10] = [Self::Declaration, Self::Definition, Self::Readonly, Self::Static, Self::Deprecated, Self::Abstract, Self::Async, Self::Modification, Self::Documentation, Self::DefaultLibrary];
+
+    /// Returns the LSP name of this modifier.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Declaration => "declaration",
+            Self::Definition => "definition",
+            Self::Readonly => "readonly",
+            Self::Static => "static",
+            Self::Deprecated => "deprecated",
+            Self::Abstract => "abstract",
+            Self::Async => "async",
+            Self::Modification => "modification",
+            Self::Documentation => "documentation",
+            Self::DefaultLibrary => "defaultLibrary",
+        }
+    }
+
+    /// Returns the bit of this modifier in the modifier bitset.
+    pub fn bit(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
+/// The legend that maps token type indices and modifier bits to names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SemanticTokensLegend {
+    /// The token types a server uses.
+    pub token_types: Vec<String>,
+    /// The token modifiers a server uses.
+    pub token_modifiers: Vec<String>,
+}
+
+impl SemanticTokensLegend {
+    /// Returns the standard legend used by [`UniversalSemanticTokensProvider`](crate::UniversalSemanticTokensProvider).
+    pub fn standard() -> Self {
+        Self { token_types: SemanticTokenType::ALL.iter().map(|t| t.as_str().to_string()).collect(), token_modifiers: SemanticTokenModifier::ALL.iter().map(|m| m.as_str().to_string()).collect() }
+    }
+}
+
+impl Default for SemanticTokensLegend {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
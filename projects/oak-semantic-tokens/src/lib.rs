@@ -1,16 +1,26 @@
+#![feature(new_range_api)]
 #![warn(missing_docs)]
 //! Semantic tokens support for the Oak language framework.
 //!
 //! This crate provides traits and structures for semantic syntax highlighting,
 //! compatible with the LSP Semantic Tokens specification.
+use core::range::Range;
 use oak_core::{language::Language, source::Source, tree::RedNode};
-use oak_vfs::LineMap;
+use oak_vfs::{LineMap, PositionEncoding};
 use serde::{Deserialize, Serialize};
 
+mod cache;
+mod legend;
+mod universal;
+
+pub use cache::SemanticTokensCache;
+pub use legend::{SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend};
+pub use universal::UniversalSemanticTokensProvider;
+
 /// Represents a semantic token for syntax highlighting.
 ///
 /// Benchmarked against LSP's Semantic Tokens.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SemanticToken {
     /// The delta line from the previous token.
     pub delta_line: u32,
@@ -24,8 +34,111 @@ pub struct SemanticToken {
     pub token_modifiers_bitset: u32,
 }
 
+/// A semantic token with an absolute position, before relative encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AbsoluteSemanticToken {
+    /// The line of the token (0-indexed).
+    pub line: u32,
+    /// The start character of the token in the provider's position encoding (0-indexed).
+    pub start: u32,
+    /// The length of the token in the provider's position encoding.
+    pub length: u32,
+    /// The type of the token (index into the legend).
+    pub token_type: u32,
+    /// The modifiers of the token (bitset).
+    pub token_modifiers_bitset: u32,
+}
+
+/// Describes a replacement of a part of a previously reported token array.
+///
+/// `start` and `delete_count` index into the flattened `u32` array (five integers per token),
+/// as required by `semanticTokens/full/delta`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SemanticTokensEdit {
+    /// The start offset of the edit in the flattened token array.
+    pub start: u32,
+    /// The number of integers to delete.
+    pub delete_count: u32,
+    /// The integers to insert.
+    pub data: Option<Vec<u32>>,
+}
+
+/// Encodes absolute tokens into the relative LSP representation.
+///
+/// The tokens must be sorted by position.
+pub fn encode(tokens: &[AbsoluteSemanticToken]) -> Vec<SemanticToken> {
+    let mut last_line = 0;
+    let mut last_start = 0;
+    tokens
+        .iter()
+        .map(|t| {
+            let delta_line = t.line - last_line;
+            let delta_start = if delta_line == 0 { t.start - last_start } else { t.start };
+            last_line = t.line;
+            last_start = t.start;
+            SemanticToken { delta_line, delta_start, length: t.length, token_type: t.token_type, token_modifiers_bitset: t.token_modifiers_bitset }
+        })
+        .collect()
+}
+
+/// Decodes relative LSP tokens into absolute positions.
+pub fn decode(tokens: &[SemanticToken]) -> Vec<AbsoluteSemanticToken> {
+    let mut line = 0;
+    let mut start = 0;
+    tokens
+        .iter()
+        .map(|t| {
+            if t.delta_line == 0 {
+                start += t.delta_start
+            }
+            else {
+                line += t.delta_line;
+                start = t.delta_start
+            }
+            AbsoluteSemanticToken { line, start, length: t.length, token_type: t.token_type, token_modifiers_bitset: t.token_modifiers_bitset }
+        })
+        .collect()
+}
+
+/// Flattens tokens into the `u32` array used on the wire.
+pub fn flatten(tokens: &[SemanticToken]) -> Vec<u32> {
+    tokens.iter().flat_map(|t| [t.delta_line, t.delta_start, t.length, t.token_type, t.token_modifiers_bitset]).collect()
+}
+
+/// Computes the edits that turn `previous` into `current`.
+///
+/// The common prefix and suffix are kept and everything in between is replaced by a
+/// single edit, which is what editors expect for typing-sized changes.
+pub fn diff(previous: &[SemanticToken], current: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = previous.iter().zip(current).take_while(|(a, b)| a == b).count();
+    let max_suffix = previous.len().min(current.len()) - prefix;
+    let suffix = previous.iter().rev().zip(current.iter().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+    let data = if inserted.is_empty() { None } else { Some(flatten(inserted)) };
+    vec![SemanticTokensEdit { start: (prefix * 5) as u32, delete_count: (deleted * 5) as u32, data }]
+}
+
 /// Trait for languages that support semantic highlighting.
 pub trait SemanticTokensProvider<L: Language> {
     /// Returns the semantic tokens for the given document.
     fn semantic_tokens<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap) -> Vec<SemanticToken>;
+
+    /// Returns the unit in which this provider measures token columns and lengths.
+    fn position_encoding(&self) -> PositionEncoding {
+        PositionEncoding::Utf16
+    }
+
+    /// Returns the semantic tokens that start within the given byte range.
+    fn semantic_tokens_range<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap, range: Range<usize>) -> Vec<SemanticToken> {
+        let start = line_map.offset_to_position(range.start, self.position_encoding());
+        let end = line_map.offset_to_position(range.end, self.position_encoding());
+        let tokens = decode(&self.semantic_tokens(root, source, line_map));
+        let visible: Vec<_> = tokens.into_iter().filter(|t| (t.line, t.start) >= start && (t.line, t.start) < end).collect();
+        encode(&visible)
+    }
 }
@@ -0,0 +1,246 @@
+use crate::{AbsoluteSemanticToken, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensProvider, encode};
+use core::range::Range;
+use oak_core::{
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedLeaf, RedNode, RedTree},
+};
+use oak_vfs::{LineMap, PositionEncoding};
+
+/// A semantic tokens provider that works for any language whose token and element types implement `role()`.
+///
+/// Tokens are classified by their [`UniversalTokenRole`]; `Name` tokens are further refined by
+/// the [`UniversalElementRole`] of their enclosing nodes. For example, a `Name` directly inside a
+/// `Definition` is a declaration, a `Name` inside a `Typing` node is a type, and a `Name` in the
+/// callee position of a `Call` is a function.
+///
+/// Tokens are reported against [`SemanticTokensLegend::standard`](crate::SemanticTokensLegend::standard).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniversalSemanticTokensProvider {
+    /// The unit in which token columns and lengths are measured.
+    encoding: PositionEncoding,
+}
+
+impl UniversalSemanticTokensProvider {
+    /// Creates a new universal semantic tokens provider that measures columns in UTF-16.
+    pub const fn new() -> Self {
+        Self { encoding: PositionEncoding::Utf16 }
+    }
+
+    /// Creates a new universal semantic tokens provider that measures columns in the given encoding.
+    pub const fn with_encoding(encoding: PositionEncoding) -> Self {
+        Self { encoding }
+    }
+
+    /// Returns the tokens of the document with absolute positions.
+    pub fn absolute_tokens<L: Language, S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap) -> Vec<AbsoluteSemanticToken> {
+        let mut collector = Collector { source, line_map, encoding: self.encoding, tokens: Vec::new(), ancestors: Vec::new() };
+        collector.visit(*root, Context::default());
+        collector.tokens
+    }
+}
+
+impl<L: Language> SemanticTokensProvider<L> for UniversalSemanticTokensProvider {
+    fn semantic_tokens<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, line_map: &LineMap) -> Vec<SemanticToken> {
+        encode(&self.absolute_tokens(root, source, line_map))
+    }
+
+    fn position_encoding(&self) -> PositionEncoding {
+        self.encoding
+    }
+}
+
+/// Inherited classification state while walking the tree.
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    /// Modifiers that apply to the names declared by the innermost declaration.
+    declaration_modifiers: u32,
+    /// The span of the callee name of the innermost call.
+    callee: Option<Range<usize>>,
+    /// Whether we are inside a documentation node.
+    documentation: bool,
+    /// Whether the innermost definition contains nested definitions.
+    type_like: bool,
+}
+
+struct Collector<'s, S: ?Sized> {
+    source: &'s S,
+    line_map: &'s LineMap,
+    encoding: PositionEncoding,
+    tokens: Vec<AbsoluteSemanticToken>,
+    ancestors: Vec<UniversalElementRole>,
+}
+
+impl<'s, S: Source + ?Sized> Collector<'s, S> {
+    fn visit<L: Language>(&mut self, node: RedNode<L>, context: Context) {
+        let mut pending_deprecated = false;
+        for child in node.children() {
+            match child {
+                RedTree::Leaf(leaf) => self.leaf(leaf, context),
+                RedTree::Node(child) => {
+                    let role = child.green.kind.role().universal();
+                    let mut inner = context;
+                    match role {
+                        UniversalElementRole::Definition => {
+                            inner.declaration_modifiers = self.keyword_modifiers(child);
+                            if pending_deprecated || self.has_deprecation_marker(child) {
+                                inner.declaration_modifiers |= SemanticTokenModifier::Deprecated.bit()
+                            }
+                            inner.type_like = has_nested_definition(child);
+                            pending_deprecated = false
+                        }
+                        UniversalElementRole::Statement => inner.declaration_modifiers |= self.keyword_modifiers(child),
+                        UniversalElementRole::Call => inner.callee = self.callee(child),
+                        UniversalElementRole::Documentation => inner.documentation = true,
+                        UniversalElementRole::Metadata | UniversalElementRole::Attribute => pending_deprecated |= self.text(child.span()).to_lowercase().contains("deprecated"),
+                        _ => {}
+                    }
+                    self.ancestors.push(role);
+                    self.visit(child, inner);
+                    self.ancestors.pop();
+                }
+            }
+        }
+    }
+
+    fn leaf<L: Language>(&mut self, leaf: RedLeaf<L>, context: Context) {
+        let role = leaf.kind.role().universal();
+        let (token_type, mut modifiers) = match role {
+            UniversalTokenRole::Keyword => (SemanticTokenType::Keyword, 0),
+            UniversalTokenRole::Operator => (SemanticTokenType::Operator, 0),
+            UniversalTokenRole::Literal | UniversalTokenRole::Escape => (self.literal_type(leaf.span), 0),
+            UniversalTokenRole::Comment => {
+                let text = self.text(leaf.span);
+                let doc = ["///", "//!", "/**", "/*!"].iter().any(|p| text.starts_with(p));
+                (SemanticTokenType::Comment, if doc { SemanticTokenModifier::Documentation.bit() } else { 0 })
+            }
+            UniversalTokenRole::Name => self.name_type(leaf.span, context),
+            _ => return,
+        };
+        if context.documentation {
+            modifiers |= SemanticTokenModifier::Documentation.bit();
+            if role != UniversalTokenRole::Comment && role != UniversalTokenRole::Keyword {
+                return self.push(leaf.span, SemanticTokenType::Comment, modifiers);
+            }
+        }
+        self.push(leaf.span, token_type, modifiers)
+    }
+
+    /// Classifies a `Name` token by its enclosing element roles.
+    fn name_type(&self, span: Range<usize>, context: Context) -> (SemanticTokenType, u32) {
+        let declaration = SemanticTokenModifier::Declaration.bit() | context.declaration_modifiers;
+        let parent = self.ancestors.last().copied().unwrap_or(UniversalElementRole::None);
+        let grandparent = self.ancestors.iter().rev().nth(1).copied().unwrap_or(UniversalElementRole::None);
+
+        if context.callee == Some(span) {
+            return (SemanticTokenType::Function, 0);
+        }
+        if self.ancestors.iter().any(|r| matches!(r, UniversalElementRole::Metadata)) {
+            return (SemanticTokenType::Decorator, 0);
+        }
+        if self.ancestors.iter().any(|r| matches!(r, UniversalElementRole::Typing)) {
+            return (SemanticTokenType::Type, 0);
+        }
+        match (grandparent, parent) {
+            (_, UniversalElementRole::Definition) | (UniversalElementRole::Definition, UniversalElementRole::Name) => {
+                let ty = if context.type_like { SemanticTokenType::Class } else { SemanticTokenType::Function };
+                (ty, declaration | SemanticTokenModifier::Definition.bit())
+            }
+            (UniversalElementRole::Container | UniversalElementRole::Detail, UniversalElementRole::Binding) if self.ancestors.iter().rev().nth(2) == Some(&UniversalElementRole::Definition) => (SemanticTokenType::Parameter, declaration),
+            (_, UniversalElementRole::Binding) => (SemanticTokenType::Variable, declaration),
+            (_, UniversalElementRole::Attribute | UniversalElementRole::AttributeKey) => (SemanticTokenType::Property, 0),
+            _ => (SemanticTokenType::Variable, 0),
+        }
+    }
+
+    fn literal_type(&self, span: Range<usize>) -> SemanticTokenType {
+        let text = self.text(span);
+        let mut chars = text.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_digit() => SemanticTokenType::Number,
+            Some('-' | '+' | '.') if chars.next().is_some_and(|c| c.is_ascii_digit()) => SemanticTokenType::Number,
+            Some('/') if text.len() > 1 => SemanticTokenType::Regexp,
+            _ if matches!(text.as_str(), "true" | "false" | "null" | "nil" | "None" | "undefined") => SemanticTokenType::Keyword,
+            _ => SemanticTokenType::String,
+        }
+    }
+
+    /// Derives modifiers from the keywords that directly belong to a declaration.
+    fn keyword_modifiers<L: Language>(&self, node: RedNode<L>) -> u32 {
+        let mut modifiers = 0;
+        for child in node.children() {
+            let RedTree::Leaf(leaf) = child
+            else {
+                continue;
+            };
+            if !leaf.kind.is_universal(UniversalTokenRole::Keyword) {
+                continue;
+            }
+            modifiers |= match self.text(leaf.span).as_str() {
+                "const" | "final" | "val" | "readonly" | "constexpr" => SemanticTokenModifier::Readonly.bit(),
+                "static" => SemanticTokenModifier::Static.bit(),
+                "async" => SemanticTokenModifier::Async.bit(),
+                "abstract" => SemanticTokenModifier::Abstract.bit(),
+                _ => 0,
+            }
+        }
+        modifiers
+    }
+
+    /// Checks whether a definition carries a deprecation attribute or documentation tag.
+    fn has_deprecation_marker<L: Language>(&self, node: RedNode<L>) -> bool {
+        node.children().any(|child| match child {
+            RedTree::Node(n) => matches!(n.green.kind.role().universal(), UniversalElementRole::Metadata | UniversalElementRole::Attribute | UniversalElementRole::Documentation) && self.text(n.span()).to_lowercase().contains("deprecated"),
+            RedTree::Leaf(_) => false,
+        })
+    }
+
+    /// Finds the callee name of a call: the last `Name` token before the argument list.
+    fn callee<L: Language>(&self, call: RedNode<L>) -> Option<Range<usize>> {
+        let mut first = None;
+        let mut last = None;
+        for element in oak_core::visitor::PreOrder::new(RedTree::Node(call)) {
+            let RedTree::Leaf(leaf) = element
+            else {
+                continue;
+            };
+            if leaf.kind.is_universal(UniversalTokenRole::Name) {
+                first.get_or_insert(leaf.span);
+                last = Some(leaf.span)
+            }
+            else if leaf.kind.is_universal(UniversalTokenRole::Punctuation) && self.text(leaf.span) == "(" {
+                break;
+            }
+        }
+        last.or(first)
+    }
+
+    /// Pushes a token, splitting it at line breaks since LSP tokens cannot span lines.
+    fn push(&mut self, span: Range<usize>, token_type: SemanticTokenType, modifiers: u32) {
+        let start_line = self.line_map.line_of(span.start);
+        let end_line = self.line_map.line_of(span.end);
+        for line in start_line..=end_line {
+            let line_start = self.line_map.line_start(line).unwrap_or(span.start).max(span.start);
+            let line_end = self.line_map.line_content_end(line).unwrap_or(span.end).min(span.end);
+            if line_end <= line_start {
+                continue;
+            }
+            let (_, start) = self.line_map.offset_to_position(line_start, self.encoding);
+            let (_, end) = self.line_map.offset_to_position(line_end, self.encoding);
+            let length = end - start;
+            self.tokens.push(AbsoluteSemanticToken { line, start, length, token_type: token_type.index(), token_modifiers_bitset: modifiers })
+        }
+    }
+
+    fn text(&self, span: Range<usize>) -> String {
+        self.source.get_text_in(span).into_owned()
+    }
+}
+
+/// Whether a definition declares further definitions in its body (e.g. a class with methods).
+fn has_nested_definition<L: Language>(node: RedNode<L>) -> bool {
+    node.children().any(|child| match child {
+        RedTree::Node(n) => n.green.kind.is_universal(UniversalElementRole::Definition) || (!n.green.kind.is_universal(UniversalElementRole::Call) && has_nested_definition(n)),
+        RedTree::Leaf(_) => false,
+    })
+}
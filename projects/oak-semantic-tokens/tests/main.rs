@@ -0,0 +1,111 @@
+use oak_core::{
+    language::{UniversalElementRole as E, UniversalTokenRole as T},
+    source::SourceText,
+    tree::RedNode,
+};
+use oak_semantic_tokens::{AbsoluteSemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit, SemanticTokensLegend, SemanticTokensProvider, UniversalSemanticTokensProvider, decode, diff, encode, flatten};
+use oak_testing::building::{
+    Spec::{Leaf, Node},
+    Universal, comment, kw, name, punct, ws,
+};
+use oak_vfs::{LineMap, PositionEncoding};
+
+fn token(line: u32, start: u32, length: u32, token_type: SemanticTokenType) -> AbsoluteSemanticToken {
+    AbsoluteSemanticToken { line, start, length, token_type: token_type.index(), token_modifiers_bitset: 0 }
+}
+
+#[test]
+fn test_encode_roundtrip() {
+    let tokens = vec![token(0, 0, 5, SemanticTokenType::Keyword), token(0, 6, 3, SemanticTokenType::Function), token(2, 4, 1, SemanticTokenType::Variable), token(2, 8, 2, SemanticTokenType::Number)];
+    let encoded = encode(&tokens);
+    assert_eq!(flatten(&encoded), vec![0, 0, 5, 15, 0, 0, 6, 3, 12, 0, 2, 4, 1, 8, 0, 0, 4, 2, 19, 0]);
+    assert_eq!(decode(&encoded), tokens)
+}
+
+#[test]
+fn test_diff_replaces_middle() {
+    let previous = encode(&[token(0, 0, 5, SemanticTokenType::Keyword), token(1, 0, 3, SemanticTokenType::Variable), token(2, 0, 3, SemanticTokenType::Comment)]);
+    let current = encode(&[token(0, 0, 5, SemanticTokenType::Keyword), token(1, 0, 3, SemanticTokenType::Function), token(1, 4, 1, SemanticTokenType::Number), token(2, 0, 3, SemanticTokenType::Comment)]);
+    let edits = diff(&previous, &current);
+    assert_eq!(edits, vec![SemanticTokensEdit { start: 5, delete_count: 5, data: Some(flatten(&current[1..3])) }]);
+    assert!(diff(&current, &current).is_empty())
+}
+
+#[test]
+fn test_standard_legend() {
+    let legend = SemanticTokensLegend::standard();
+    assert_eq!(legend.token_types[SemanticTokenType::Parameter.index() as usize], "parameter");
+    assert_eq!(legend.token_modifiers[SemanticTokenModifier::Documentation as usize], "documentation");
+    assert_eq!(SemanticTokenModifier::Readonly.bit(), 0b100)
+}
+
+#[test]
+fn test_universal_provider() {
+    let root = Node(
+        E::Root,
+        vec![
+            Node(E::Definition, vec![Node(E::Metadata, vec![punct("#[deprecated]")]), ws("\n"), kw("const"), ws(" "), Node(E::Binding, vec![name("N")]), ws(" "), punct("="), ws(" "), Leaf(T::Literal, "1.5")]),
+            ws("\n"),
+            Node(
+                E::Definition,
+                vec![
+                    kw("fn"),
+                    ws(" "),
+                    Node(E::Name, vec![name("add")]),
+                    Node(E::Detail, vec![punct("("), Node(E::Binding, vec![name("a")]), punct(")")]),
+                    ws(" "),
+                    Node(
+                        E::Container,
+                        vec![
+                            punct("{"),
+                            ws("\n    "),
+                            Node(E::Statement, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("x")]), ws(" "), punct("="), ws(" "), Node(E::Call, vec![name("f"), punct("("), Leaf(T::Literal, "\"\u{e9}\""), punct(")")])]),
+                            ws("\n"),
+                            punct("}"),
+                        ],
+                    ),
+                ],
+            ),
+            ws("\n\n"),
+            comment("/// done"),
+        ],
+    );
+    let (text, green) = root.build();
+    assert_eq!(text, "#[deprecated]\nconst N = 1.5\nfn add(a) {\n    let x = f(\"\u{e9}\")\n}\n\n/// done");
+    let source = SourceText::new(text.as_str());
+    let line_map = LineMap::from_source(&source);
+    let root = RedNode::new(green, 0);
+    let provider = UniversalSemanticTokensProvider::new();
+
+    let bits = |modifiers: &[SemanticTokenModifier]| modifiers.iter().fold(0, |bits, modifier| bits | modifier.bit());
+    let declaration = SemanticTokenModifier::Declaration;
+    let tokens: Vec<_> = provider.absolute_tokens(&root, &source, &line_map).into_iter().map(|token| (token.line, token.start, token.length, token.token_type, token.token_modifiers_bitset)).collect();
+    assert_eq!(
+        tokens,
+        [
+            (1, 0, 5, SemanticTokenType::Keyword.index(), 0),
+            // `const` and the deprecation attribute of the definition apply to the name it declares.
+            (1, 6, 1, SemanticTokenType::Variable.index(), bits(&[declaration, SemanticTokenModifier::Readonly, SemanticTokenModifier::Deprecated])),
+            (1, 10, 3, SemanticTokenType::Number.index(), 0),
+            (2, 0, 2, SemanticTokenType::Keyword.index(), 0),
+            (2, 3, 3, SemanticTokenType::Function.index(), bits(&[declaration, SemanticTokenModifier::Definition])),
+            (2, 7, 1, SemanticTokenType::Parameter.index(), bits(&[declaration])),
+            (3, 4, 3, SemanticTokenType::Keyword.index(), 0),
+            (3, 8, 1, SemanticTokenType::Variable.index(), bits(&[declaration])),
+            (3, 12, 1, SemanticTokenType::Function.index(), 0),
+            // Columns and lengths are in UTF-16 code units.
+            (3, 14, 3, SemanticTokenType::String.index(), 0),
+            (6, 0, 8, SemanticTokenType::Comment.index(), bits(&[SemanticTokenModifier::Documentation])),
+        ]
+    );
+
+    // Lines and starts are relative to the previous token.
+    let encoded = provider.semantic_tokens(&root, &source, &line_map);
+    let deltas: Vec<_> = encoded.iter().map(|token| (token.delta_line, token.delta_start, token.length)).collect();
+    assert_eq!(deltas, [(1, 0, 5), (0, 6, 1), (0, 4, 3), (1, 0, 2), (0, 3, 3), (0, 4, 1), (1, 4, 3), (0, 4, 1), (0, 4, 1), (0, 2, 3), (3, 0, 8)]);
+    assert_eq!(decode(&encoded), provider.absolute_tokens(&root, &source, &line_map));
+
+    let utf8 = UniversalSemanticTokensProvider::with_encoding(PositionEncoding::Utf8);
+    assert_eq!(SemanticTokensProvider::<Universal>::position_encoding(&utf8), PositionEncoding::Utf8);
+    assert_eq!(utf8.absolute_tokens(&root, &source, &line_map)[9].length, 4);
+}
@@ -3,11 +3,13 @@
 //! Document structural view (结构化视图) support for the Oak language framework.
 //!
 //! This crate provides traits and structures for representing the hierarchical
-//! structural view of a document, such as for an outline view or breadcrumbs.
+//! structural view of a document, such as for an outline view or breadcrumbs,
+//! and a [`UniversalStructureProvider`] that builds it from element roles.
 use core::range::Range;
 use oak_core::{
-    language::{Language, UniversalElementRole},
-    tree::RedNode,
+    language::{ElementRole, ElementType, Language, TokenRole, TokenType, UniversalElementRole, UniversalTokenRole},
+    source::Source,
+    tree::{RedNode, RedTree},
 };
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +22,8 @@ pub struct StructureItem {
     pub detail: Option<String>,
     /// The universal role of this element.
     pub role: UniversalElementRole,
+    /// What this element defines, from the specific name of its role.
+    pub kind: StructureKind,
     /// The range of the entire element in the source code.
     #[serde(with = "oak_core::serde_range", bound(serialize = "", deserialize = ""))]
     pub range: Range<usize>,
@@ -33,10 +37,249 @@ pub struct StructureItem {
     pub children: Vec<StructureItem>,
 }
 
+/// What a structure item defines, which editors show as its icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StructureKind {
+    /// A module, namespace or package.
+    Module,
+    /// A class or another nominal type.
+    Class,
+    /// A struct or union.
+    Struct,
+    /// An enumeration.
+    Enum,
+    /// An interface, trait or protocol.
+    Interface,
+    /// A free function or lambda.
+    Function,
+    /// A method.
+    Method,
+    /// A constructor.
+    Constructor,
+    /// A field or property.
+    Field,
+    /// A constant.
+    Constant,
+    /// A variable or parameter.
+    Variable,
+}
+
+impl From<UniversalElementRole> for StructureKind {
+    fn from(role: UniversalElementRole) -> Self {
+        match role {
+            UniversalElementRole::Container | UniversalElementRole::Root => StructureKind::Module,
+            UniversalElementRole::Typing => StructureKind::Class,
+            UniversalElementRole::Binding | UniversalElementRole::Reference | UniversalElementRole::Expression => StructureKind::Variable,
+            UniversalElementRole::Attribute | UniversalElementRole::AttributeKey | UniversalElementRole::Metadata => StructureKind::Field,
+            UniversalElementRole::Value => StructureKind::Constant,
+            _ => StructureKind::Function,
+        }
+    }
+}
+
+impl StructureKind {
+    /// Returns the kind of a definition from the specific name of its role, like `entity.name.type.struct`,
+    /// falling back to its universal role. Later segments of the name are more specific, so they win.
+    pub fn of_role<R: ElementRole>(role: R) -> Self {
+        for segment in role.name().rsplit(['.', '-', '_']) {
+            match segment {
+                "function" | "procedure" | "lambda" => return StructureKind::Function,
+                "method" => return StructureKind::Method,
+                "constructor" => return StructureKind::Constructor,
+                "class" | "type" | "record" => return StructureKind::Class,
+                "struct" | "union" => return StructureKind::Struct,
+                "enum" => return StructureKind::Enum,
+                "interface" | "trait" | "protocol" => return StructureKind::Interface,
+                "module" | "namespace" | "package" => return StructureKind::Module,
+                "field" | "property" | "member" => return StructureKind::Field,
+                "constant" => return StructureKind::Constant,
+                "variable" | "parameter" => return StructureKind::Variable,
+                _ => {}
+            }
+        }
+        role.universal().into()
+    }
+}
+
+/// An item of a hierarchical outline that [`breadcrumbs`] can walk.
+pub trait OutlineItem: Sized {
+    /// The range of the entire item.
+    fn range(&self) -> Range<usize>;
+
+    /// The items nested in this one.
+    fn children(&self) -> &[Self];
+}
+
+impl OutlineItem for StructureItem {
+    fn range(&self) -> Range<usize> {
+        self.range
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.children
+    }
+}
+
 /// Trait for languages that support structure view and navigation.
 ///
 /// Benchmarked against IntelliJ's Structure View and LSP's `textDocument/documentSymbol`.
 pub trait StructureProvider<L: Language> {
     /// Returns the hierarchical structure of the document.
     fn structure(&self, root: &RedNode<L>) -> Vec<StructureItem>;
+
+    /// Returns the hierarchical structure of the document, with access to its text.
+    ///
+    /// The default ignores the text and calls [`structure`](Self::structure).
+    fn structure_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S) -> Vec<StructureItem> {
+        let _ = source;
+        self.structure(root)
+    }
+
+    /// Returns the chain of structure items enclosing an offset, outermost first.
+    ///
+    /// The returned items have no children. See [`breadcrumbs`].
+    fn breadcrumbs<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S, offset: usize) -> Vec<StructureItem> {
+        breadcrumbs(&self.structure_in(root, source), offset).into_iter().map(|item| StructureItem { children: Vec::new(), ..item.clone() }).collect()
+    }
+}
+
+/// Returns the chain of items enclosing an offset, outermost first.
+///
+/// An item encloses the offsets from the start of its range up to and including its end, so the
+/// cursor right after a closing brace is still inside. When an offset is both at the end of one
+/// item and at the start of the next, the next one wins.
+pub fn breadcrumbs<T: OutlineItem>(items: &[T], offset: usize) -> Vec<&T> {
+    let mut chain = Vec::new();
+    let mut items = items;
+    while let Some(item) = items.iter().find(|item| item.range().start <= offset && offset < item.range().end).or_else(|| items.iter().find(|item| item.range().end == offset)) {
+        chain.push(item);
+        items = item.children()
+    }
+    chain
+}
+
+/// A structure provider that works for any language whose kinds implement `role()`.
+///
+/// Every [`Definition`](UniversalElementRole::Definition) node becomes an item, nested under the
+/// closest enclosing definition. An item is
+/// - named by the first [`Name`](UniversalTokenRole::Name) token or [`Name`](UniversalElementRole::Name)
+///   element of the definition, looking through nodes other than nested definitions, containers,
+///   types and details, with a [`Binding`](UniversalElementRole::Binding) element as a fallback;
+///   its `selection_range` is the range of that name,
+/// - detailed by the text of its [`Typing`](UniversalElementRole::Typing) and
+///   [`Detail`](UniversalElementRole::Detail) children, with whitespace collapsed, like `(a: i32) -> u32`,
+/// - deprecated if one of its [`Metadata`](UniversalElementRole::Metadata) or
+///   [`Attribute`](UniversalElementRole::Attribute) children mentions `deprecated`.
+///
+/// Its kind comes from the specific name of the definition's role, see [`StructureKind::of_role`].
+/// Ranges exclude surrounding whitespace. Without the source text, as in
+/// [`structure`](StructureProvider::structure), items are named after their kind and have no detail.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniversalStructureProvider;
+
+impl UniversalStructureProvider {
+    /// Creates a new universal structure provider.
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl<L: Language> StructureProvider<L> for UniversalStructureProvider {
+    fn structure(&self, root: &RedNode<L>) -> Vec<StructureItem> {
+        let mut items = Vec::new();
+        collect(root, None::<&str>, &mut items);
+        items
+    }
+
+    fn structure_in<S: Source + ?Sized>(&self, root: &RedNode<L>, source: &S) -> Vec<StructureItem> {
+        let mut items = Vec::new();
+        collect(root, Some(source), &mut items);
+        items
+    }
+}
+
+/// Collects the definitions below a node.
+fn collect<L: Language, S: Source + ?Sized>(node: &RedNode<L>, source: Option<&S>, items: &mut Vec<StructureItem>) {
+    for child in node.children() {
+        if let RedTree::Node(child) = child {
+            if child.green.kind.role().universal() == UniversalElementRole::Definition {
+                items.push(item(&child, source))
+            }
+            else {
+                collect(&child, source, items)
+            }
+        }
+    }
+}
+
+/// Builds the item of a definition node.
+fn item<L: Language, S: Source + ?Sized>(node: &RedNode<L>, source: Option<&S>) -> StructureItem {
+    let range = source.map_or(node.span(), |source| trim(source, node.span()));
+    let (name, selection_range) = match (source, find_name(node).or_else(|| find_binding(node))) {
+        (Some(source), Some(span)) => {
+            let span = trim(source, span);
+            (source.get_text_in(span).into_owned(), span)
+        }
+        _ => (format!("<{:?}>", node.green.kind), range),
+    };
+
+    let mut detail: Option<Range<usize>> = None;
+    let mut deprecated = false;
+    for child in node.children() {
+        let RedTree::Node(child) = child
+        else {
+            continue;
+        };
+        match child.green.kind.role().universal() {
+            UniversalElementRole::Typing | UniversalElementRole::Detail => {
+                let span = child.span();
+                detail = Some(detail.map_or(span, |detail| (detail.start..span.end).into()))
+            }
+            UniversalElementRole::Metadata | UniversalElementRole::Attribute => deprecated |= source.is_some_and(|source| source.get_text_in(child.span()).to_ascii_lowercase().contains("deprecated")),
+            _ => {}
+        }
+    }
+    let detail = source.zip(detail).map(|(source, span)| source.get_text_in(span).split_whitespace().collect::<Vec<_>>().join(" ")).filter(|detail| !detail.is_empty());
+
+    let mut children = Vec::new();
+    collect(node, source, &mut children);
+    let role = node.green.kind.role();
+    StructureItem { name, detail, role: role.universal(), kind: StructureKind::of_role(role), range, selection_range, deprecated, children }
+}
+
+/// Finds the first name token or name element of a definition, outside nested definitions, containers, types and details.
+fn find_name<L: Language>(node: &RedNode<L>) -> Option<Range<usize>> {
+    for child in node.children() {
+        match child {
+            RedTree::Leaf(leaf) if leaf.kind.role().universal() == UniversalTokenRole::Name => return Some(leaf.span),
+            RedTree::Leaf(_) => {}
+            RedTree::Node(child) => match child.green.kind.role().universal() {
+                UniversalElementRole::Name => return Some(child.span()),
+                UniversalElementRole::Definition | UniversalElementRole::Container | UniversalElementRole::Typing | UniversalElementRole::Detail | UniversalElementRole::Documentation | UniversalElementRole::Metadata | UniversalElementRole::Attribute => {}
+                _ => {
+                    if let Some(span) = find_name(&child) {
+                        return Some(span);
+                    }
+                }
+            },
+        }
+    }
+    None
+}
+
+/// Finds the first binding element of a definition, outside nested definitions and containers.
+fn find_binding<L: Language>(node: &RedNode<L>) -> Option<Range<usize>> {
+    node.children().filter_map(|child| child.as_node()).find_map(|child| match child.green.kind.role().universal() {
+        UniversalElementRole::Binding => Some(child.span()),
+        UniversalElementRole::Definition | UniversalElementRole::Container => None,
+        _ => find_binding(&child),
+    })
+}
+
+/// Shrinks a range to exclude leading and trailing whitespace.
+fn trim<S: Source + ?Sized>(source: &S, range: Range<usize>) -> Range<usize> {
+    let text = source.get_text_in(range);
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.start + text.trim_end().len();
+    (start..end.max(start)).into()
 }
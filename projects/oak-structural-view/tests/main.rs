@@ -0,0 +1,108 @@
+use oak_core::{
+    language::{ElementRole, UniversalElementRole as E},
+    source::SourceText,
+    tree::RedNode,
+};
+use oak_structural_view::{StructureKind, StructureProvider, UniversalStructureProvider, breadcrumbs};
+use oak_testing::building::{Spec::Node, Universal, kw, name, punct, ws};
+
+#[test]
+fn test_universal_structure() {
+    let root = Node(
+        E::Root,
+        vec![
+            Node(
+                E::Definition,
+                vec![
+                    Node(E::Metadata, vec![punct("#[deprecated]")]),
+                    ws("\n"),
+                    kw("impl"),
+                    ws(" "),
+                    Node(E::Name, vec![name("Point")]),
+                    ws(" "),
+                    Node(
+                        E::Container,
+                        vec![
+                            punct("{"),
+                            ws("\n    "),
+                            Node(
+                                E::Definition,
+                                vec![
+                                    kw("fn"),
+                                    ws(" "),
+                                    name("len"),
+                                    Node(E::Detail, vec![punct("("), name("self"), punct(")")]),
+                                    ws(" "),
+                                    punct("->"),
+                                    ws("\n        "),
+                                    Node(E::Typing, vec![name("f64")]),
+                                    ws(" "),
+                                    Node(E::Container, vec![punct("{"), ws(" "), Node(E::Definition, vec![kw("let"), ws(" "), Node(E::Binding, vec![name("x")])]), ws(" "), punct("}")]),
+                                ],
+                            ),
+                            ws("\n"),
+                            punct("}"),
+                        ],
+                    ),
+                ],
+            ),
+            ws("\n"),
+            Node(E::Statement, vec![Node(E::Definition, vec![kw("const"), ws(" "), Node(E::Typing, vec![name("u8")]), ws(" "), name("N"), punct(";")])]),
+            ws("\n"),
+        ],
+    );
+    let (text, green) = root.build();
+    assert_eq!(text, "#[deprecated]\nimpl Point {\n    fn len(self) ->\n        f64 { let x }\n}\nconst u8 N;\n");
+    let source = SourceText::new(text.as_str());
+    let root = RedNode::new(green, 0);
+    let items = UniversalStructureProvider.structure_in(&root, &source);
+
+    let outline: Vec<_> = items.iter().map(|item| (item.name.as_str(), item.detail.as_deref(), item.deprecated, item.children.len())).collect();
+    assert_eq!(outline, [("Point", None, true, 1), ("N", Some("u8"), false, 0)]);
+    let point = &items[0];
+    assert_eq!(&text[point.range.start..point.range.end], "#[deprecated]\nimpl Point {\n    fn len(self) ->\n        f64 { let x }\n}");
+    assert_eq!(&text[point.selection_range.start..point.selection_range.end], "Point");
+
+    let len = &point.children[0];
+    assert_eq!((len.name.as_str(), len.detail.as_deref()), ("len", Some("(self) -> f64")));
+    assert_eq!(&text[len.selection_range.start..len.selection_range.end], "len");
+    // Names are looked up outside nested containers, and bindings are a fallback.
+    assert_eq!(len.children[0].name, "x");
+    assert_eq!((len.role, len.kind), (E::Definition, StructureKind::Function));
+
+    let offset = text.find("let").unwrap();
+    let chain: Vec<_> = breadcrumbs(&items, offset).into_iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(chain, ["Point", "len", "x"]);
+    // The end of an item is still inside it.
+    let chain: Vec<_> = breadcrumbs(&items, point.range.end).into_iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(chain, ["Point"]);
+    let chain = UniversalStructureProvider.breadcrumbs(&root, &source, text.find("{ let").unwrap());
+    assert_eq!(chain.iter().map(|item| item.name.as_str()).collect::<Vec<_>>(), ["Point", "len"]);
+    assert!(chain.iter().all(|item| item.children.is_empty()));
+
+    // Without the source, items are named after their kind.
+    assert_eq!(StructureProvider::<Universal>::structure(&UniversalStructureProvider, &root)[0].name, "<Definition>");
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Role(&'static str);
+
+impl ElementRole for Role {
+    fn universal(&self) -> E {
+        E::Definition
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+#[test]
+fn test_structure_kind_of_role() {
+    assert_eq!(StructureKind::of_role(Role("entity.name.type.struct")), StructureKind::Struct);
+    assert_eq!(StructureKind::of_role(Role("definition.method")), StructureKind::Method);
+    assert_eq!(StructureKind::of_role(Role("trait_item")), StructureKind::Interface);
+    assert_eq!(StructureKind::of_role(Role("definition")), StructureKind::Function);
+    assert_eq!(StructureKind::of_role(E::Typing), StructureKind::Class);
+    assert_eq!(StructureKind::from(E::Container), StructureKind::Module);
+}
@@ -2,10 +2,16 @@
 //!
 //! This module provides comprehensive testing infrastructure for builders,
 //! including file-based testing, expected output comparison, timeout handling,
-//! and test result serialization for typed root structures.
+//! and test result serialization for typed root structures, as well as the
+//! [`Universal`] language to build syntax trees by hand.
 
 use crate::{create_file, json_from_path, source_from_path};
-use oak_core::{Builder, Language, errors::OakError};
+use oak_core::{
+    Builder, Language,
+    errors::OakError,
+    language::{UniversalElementRole, UniversalTokenRole},
+    tree::{GreenLeaf, GreenNode, GreenTree},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -202,3 +208,82 @@ impl BuilderTester {
         })
     }
 }
+
+/// A language whose kinds are the universal roles, so trees can be written by hand.
+///
+/// Tests of the language-agnostic providers use it to check them against the roles alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Universal;
+
+impl Language for Universal {
+    const NAME: &'static str = "universal";
+    type TokenType = UniversalTokenRole;
+    type ElementType = UniversalElementRole;
+    type TypedRoot = ();
+}
+
+/// A syntax tree of the [`Universal`] language written by hand.
+#[derive(Debug, Clone)]
+pub enum Spec {
+    /// A token and its text.
+    Leaf(UniversalTokenRole, &'static str),
+    /// A node and its children.
+    Node(UniversalElementRole, Vec<Spec>),
+}
+
+impl Spec {
+    /// Builds the green tree, appending the text of its tokens to `text`.
+    pub fn green(&self, text: &mut String) -> GreenTree<'static, Universal> {
+        match self {
+            Spec::Leaf(kind, token) => {
+                text.push_str(token);
+                GreenTree::Leaf(GreenLeaf::new(*kind, token.len() as u32))
+            }
+            Spec::Node(kind, children) => GreenTree::Node(leak_node(*kind, children.iter().map(|child| child.green(text)).collect())),
+        }
+    }
+
+    /// Builds the green tree of a node and returns it with its text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spec is a leaf.
+    pub fn build(&self) -> (String, &'static GreenNode<'static, Universal>) {
+        let mut text = String::new();
+        let GreenTree::Node(green) = self.green(&mut text)
+        else {
+            panic!("the root of a tree must be a node")
+        };
+        (text, green)
+    }
+}
+
+/// Creates a green node that lives for the rest of the test, so red nodes can borrow it freely.
+pub fn leak_node<L: Language>(kind: L::ElementType, children: Vec<GreenTree<'static, L>>) -> &'static GreenNode<'static, L> {
+    Box::leak(Box::new(GreenNode::new(kind, Box::leak(children.into_boxed_slice()))))
+}
+
+/// A whitespace token.
+pub fn ws(text: &'static str) -> Spec {
+    Spec::Leaf(UniversalTokenRole::Whitespace, text)
+}
+
+/// A keyword token.
+pub fn kw(text: &'static str) -> Spec {
+    Spec::Leaf(UniversalTokenRole::Keyword, text)
+}
+
+/// A name token.
+pub fn name(text: &'static str) -> Spec {
+    Spec::Leaf(UniversalTokenRole::Name, text)
+}
+
+/// A punctuation token.
+pub fn punct(text: &'static str) -> Spec {
+    Spec::Leaf(UniversalTokenRole::Punctuation, text)
+}
+
+/// A comment token.
+pub fn comment(text: &'static str) -> Spec {
+    Spec::Leaf(UniversalTokenRole::Comment, text)
+}
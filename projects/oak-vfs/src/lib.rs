@@ -15,7 +15,7 @@ use oak_core::{
 use serde::{Deserialize, Serialize};
 
 mod line_map;
-pub use line_map::LineMap;
+pub use line_map::{LineMap, PositionEncoding};
 
 /// Type of a file in the VFS.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,9 +43,15 @@ pub struct FileMetadata {
 
 /// Virtual File System implementation and utilities.
 pub mod vfs;
-pub use vfs::MemoryVfs;
+pub use vfs::{IgnoreRules, MemoryVfs};
 #[cfg(feature = "disk")]
-pub use vfs::{DiskVfs, DiskWatcher, VfsEvent, VfsWatcher};
+pub use vfs::{Debouncer, DiskVfs, DiskWatcher, OverlayVfs, VfsEvent, VfsWatcher, WatchOptions};
+#[cfg(feature = "snapshot")]
+pub use vfs::{ContentHash, SnapshotVfs};
+#[cfg(feature = "tar")]
+pub use vfs::TarVfs;
+#[cfg(feature = "zip")]
+pub use vfs::ZipVfs;
 
 /// A trait for a Virtual File System that can provide source content and location mapping.
 pub trait Vfs: Send + Sync {
@@ -110,6 +116,47 @@ pub trait Vfs: Send + Sync {
     }
 }
 
+/// A borrowed VFS, e.g. to hand the VFS of a service to a resolver for the duration of a request.
+impl<V: Vfs + ?Sized> Vfs for &V {
+    type Source = V::Source;
+
+    fn get_source(&self, uri: &str) -> Option<Self::Source> {
+        (**self).get_source(uri)
+    }
+
+    fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+        (**self).get_uri(id)
+    }
+
+    fn get_id(&self, uri: &str) -> Option<SourceId> {
+        (**self).get_id(uri)
+    }
+
+    fn exists(&self, uri: &str) -> bool {
+        (**self).exists(uri)
+    }
+
+    fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+        (**self).metadata(uri)
+    }
+
+    fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+        (**self).read_dir(uri)
+    }
+
+    fn is_file(&self, uri: &str) -> bool {
+        (**self).is_file(uri)
+    }
+
+    fn is_dir(&self, uri: &str) -> bool {
+        (**self).is_dir(uri)
+    }
+
+    fn line_map(&self, uri: &str) -> Option<LineMap> {
+        (**self).line_map(uri)
+    }
+}
+
 /// A trait for a Virtual File System that supports writing.
 pub trait WritableVfs: Vfs {
     /// Update or create a file with the given content.
@@ -1,13 +1,117 @@
 use oak_core::source::Source;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The unit in which the column of a position is measured.
+///
+/// This corresponds to the `PositionEncodingKind` negotiated between an LSP client and server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PositionEncoding {
+    /// Columns are counted in UTF-8 code units (bytes).
+    #[cfg_attr(feature = "serde", serde(rename = "utf-8"))]
+    Utf8,
+    /// Columns are counted in UTF-16 code units. This is the LSP default.
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "utf-16"))]
+    Utf16,
+    /// Columns are counted in Unicode scalar values.
+    #[cfg_attr(feature = "serde", serde(rename = "utf-32"))]
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Returns the LSP name of this encoding.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16 => "utf-16",
+            Self::Utf32 => "utf-32",
+        }
+    }
+
+    /// Parses an LSP encoding name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "utf-8" => Some(Self::Utf8),
+            "utf-16" => Some(Self::Utf16),
+            "utf-32" => Some(Self::Utf32),
+            _ => None,
+        }
+    }
+
+    /// Picks the encoding to use from the encodings a client supports, in its order of preference.
+    ///
+    /// Every encoding is supported, so this is the first one the client offers, and UTF-16 when
+    /// it offers none, as required by the LSP.
+    pub fn negotiate(client: &[PositionEncoding]) -> Self {
+        client.first().copied().unwrap_or(Self::Utf16)
+    }
+}
+
+/// A character outside the ASCII range, recorded so that columns can be converted without rescanning the line.
+#[derive(Debug, Clone, Copy)]
+struct WideChar {
+    /// The byte offset of the character relative to the start of its line.
+    start: u32,
+    /// The length of the character in UTF-8 code units.
+    len_utf8: u8,
+    /// The length of the character in UTF-16 code units.
+    len_utf16: u8,
+    /// The difference between byte and UTF-16 columns accumulated by the preceding characters of the line.
+    utf16_shrink: u32,
+    /// The difference between byte and UTF-32 columns accumulated by the preceding characters of the line.
+    utf32_shrink: u32,
+}
+
+impl WideChar {
+    /// Returns the length of the character in the given encoding.
+    fn len(&self, encoding: PositionEncoding) -> u32 {
+        match encoding {
+            PositionEncoding::Utf8 => self.len_utf8 as u32,
+            PositionEncoding::Utf16 => self.len_utf16 as u32,
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+
+    /// Returns the byte-to-column difference accumulated before this character.
+    fn shrink(&self, encoding: PositionEncoding) -> u32 {
+        match encoding {
+            PositionEncoding::Utf8 => 0,
+            PositionEncoding::Utf16 => self.utf16_shrink,
+            PositionEncoding::Utf32 => self.utf32_shrink,
+        }
+    }
+
+    /// Returns the column at which this character starts in the given encoding.
+    fn column(&self, encoding: PositionEncoding) -> u32 {
+        self.start - self.shrink(encoding)
+    }
+
+    /// Returns the byte-to-column difference accumulated up to and including this character.
+    fn shrink_after(&self, encoding: PositionEncoding) -> u32 {
+        self.shrink(encoding) + self.len_utf8 as u32 - self.len(encoding)
+    }
+}
 
 /// A map that tracks line starts in a source file for efficient coordinate conversion.
 ///
 /// `LineMap` provides methods to convert between byte offsets and (line, column) coordinates.
-/// It is optimized for cases where multiple conversions are needed for the same source.
+/// It is optimized for cases where multiple conversions are needed for the same source:
+/// the non-ASCII characters of each line are recorded once, so every conversion is a pair of
+/// binary searches and never rescans the text.
+///
+/// Lines are terminated by `\n`; a preceding `\r` is treated as part of the terminator.
 #[derive(Debug, Clone)]
 pub struct LineMap {
     /// The byte offsets of the start of each line.
     line_starts: Vec<usize>,
+    /// The byte offsets of the end of each line's content, excluding `\n` or `\r\n`.
+    content_ends: Vec<usize>,
+    /// For each line, the index of its first entry in `wide_chars`, plus a final sentinel.
+    wide_starts: Vec<u32>,
+    /// The non-ASCII characters of all lines, in source order.
+    wide_chars: Vec<WideChar>,
     /// The total length of the source in bytes.
     len: usize,
 }
@@ -28,25 +132,41 @@ impl LineMap {
     /// ```
     pub fn from_source<S: Source + ?Sized>(source: &S) -> Self {
         let len = source.length();
-        let mut line_starts = Vec::new();
-        line_starts.push(0);
+        let mut map = Self { line_starts: vec![0], content_ends: Vec::new(), wide_starts: vec![0], wide_chars: Vec::new(), len };
+        let mut line_start = 0usize;
+        let mut previous = 0u8;
+        let mut utf16_shrink = 0u32;
+        let mut utf32_shrink = 0u32;
 
         let mut offset = 0usize;
         while offset < len {
             let chunk = source.chunk_at(offset);
             let text = chunk.slice_from(offset);
-            for (i, b) in text.as_bytes().iter().enumerate() {
-                if *b == b'\n' {
-                    let next = offset + i + 1;
-                    if next <= len {
-                        line_starts.push(next)
-                    }
+            for (i, b) in text.as_bytes().iter().copied().enumerate() {
+                let position = offset + i;
+                if b == b'\n' {
+                    map.content_ends.push(if previous == b'\r' { position - 1 } else { position });
+                    line_start = position + 1;
+                    map.line_starts.push(line_start);
+                    map.wide_starts.push(map.wide_chars.len() as u32);
+                    utf16_shrink = 0;
+                    utf32_shrink = 0
+                }
+                else if b >= 0xC0 {
+                    // A leading byte of a multi-byte UTF-8 sequence.
+                    let len_utf8: u8 = if b >= 0xF0 { 4 } else if b >= 0xE0 { 3 } else { 2 };
+                    let len_utf16: u8 = if len_utf8 == 4 { 2 } else { 1 };
+                    map.wide_chars.push(WideChar { start: (position - line_start) as u32, len_utf8, len_utf16, utf16_shrink, utf32_shrink });
+                    utf16_shrink += (len_utf8 - len_utf16) as u32;
+                    utf32_shrink += (len_utf8 - 1) as u32
                 }
+                previous = b
             }
             offset = chunk.end()
         }
-
-        Self { line_starts, len }
+        map.content_ends.push(if previous == b'\r' && len > line_start { len - 1 } else { len });
+        map.wide_starts.push(map.wide_chars.len() as u32);
+        map
     }
 
     /// Returns the total number of lines in the source.
@@ -54,6 +174,16 @@ impl LineMap {
         self.line_starts.len()
     }
 
+    /// Returns the total length of the source in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the source is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Returns the byte offset of the start of the given line (0-indexed).
     pub fn line_start(&self, line: u32) -> Option<usize> {
         self.line_starts.get(line as usize).copied()
@@ -70,9 +200,90 @@ impl LineMap {
         Some(next.max(start))
     }
 
+    /// Returns the byte offset of the end of the given line's content (0-indexed),
+    /// excluding the `\n` or `\r\n` terminator.
+    pub fn line_content_end(&self, line: u32) -> Option<usize> {
+        self.content_ends.get(line as usize).copied()
+    }
+
+    /// Returns the line (0-indexed) containing the given byte offset.
+    pub fn line_of(&self, offset: usize) -> u32 {
+        let offset = offset.min(self.len);
+        (self.line_starts.partition_point(|&start| start <= offset) - 1) as u32
+    }
+
+    fn wide_chars_of(&self, line: usize) -> &[WideChar] {
+        &self.wide_chars[self.wide_starts[line] as usize..self.wide_starts[line + 1] as usize]
+    }
+
+    /// Converts a byte offset to a (line, column) position, with the column measured in the given encoding.
+    ///
+    /// Offsets inside a multi-byte character are snapped to the start of that character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oak_core::source::SourceText;
+    /// # use oak_vfs::{LineMap, PositionEncoding};
+    /// let source = SourceText::new("a😀b\nc");
+    /// let line_map = LineMap::from_source(&source);
+    /// assert_eq!(line_map.offset_to_position(5, PositionEncoding::Utf8), (0, 5));
+    /// assert_eq!(line_map.offset_to_position(5, PositionEncoding::Utf16), (0, 3));
+    /// assert_eq!(line_map.offset_to_position(5, PositionEncoding::Utf32), (0, 2));
+    /// ```
+    pub fn offset_to_position(&self, offset: usize, encoding: PositionEncoding) -> (u32, u32) {
+        let offset = offset.min(self.len);
+        let line = self.line_of(offset);
+        let mut column = (offset - self.line_starts[line as usize]) as u32;
+        let chars = self.wide_chars_of(line as usize);
+        let before = chars.partition_point(|c| c.start < column);
+        let shrink = match before.checked_sub(1).map(|i| chars[i]) {
+            Some(c) if column < c.start + c.len_utf8 as u32 => {
+                column = c.start;
+                c.shrink(encoding)
+            }
+            Some(c) => c.shrink_after(encoding),
+            None => 0,
+        };
+        (line, column - shrink)
+    }
+
+    /// Converts a (line, column) position, with the column measured in the given encoding, to a byte offset.
+    ///
+    /// As required by the LSP, columns past the end of a line resolve to the end of that line's
+    /// content and lines past the end of the source resolve to the end of the source.
+    /// Columns inside a multi-byte character resolve to the start of that character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use oak_core::source::SourceText;
+    /// # use oak_vfs::{LineMap, PositionEncoding};
+    /// let source = SourceText::new("a😀b\nc");
+    /// let line_map = LineMap::from_source(&source);
+    /// assert_eq!(line_map.position_to_offset(0, 3, PositionEncoding::Utf16), 5);
+    /// assert_eq!(line_map.position_to_offset(0, 2, PositionEncoding::Utf32), 5);
+    /// assert_eq!(line_map.position_to_offset(0, 99, PositionEncoding::Utf8), 6);
+    /// ```
+    pub fn position_to_offset(&self, line: u32, column: u32, encoding: PositionEncoding) -> usize {
+        let Some(line_start) = self.line_start(line)
+        else {
+            return self.len;
+        };
+        let chars = self.wide_chars_of(line as usize);
+        let before = chars.partition_point(|c| c.column(encoding) < column);
+        let byte_column = match before.checked_sub(1).map(|i| chars[i]) {
+            Some(c) if column < c.column(encoding) + c.len(encoding) => c.start,
+            Some(c) => column + c.shrink_after(encoding),
+            None => column,
+        };
+        (line_start + byte_column as usize).min(self.content_ends[line as usize])
+    }
+
     /// Converts a byte offset to (line, column) coordinates using UTF-16 for the column.
     ///
     /// This is useful for LSP integration where positions are typically specified in UTF-16.
+    /// The source is not needed for the conversion and is only kept for compatibility.
     ///
     /// # Examples
     ///
@@ -85,21 +296,14 @@ impl LineMap {
     /// assert_eq!(line, 1);
     /// assert_eq!(col, 1);
     /// ```
-    pub fn offset_to_line_col_utf16<S: Source + ?Sized>(&self, source: &S, offset: usize) -> (u32, u32) {
-        let offset = offset.min(self.len);
-        let line_idx = match self.line_starts.binary_search(&offset) {
-            Ok(i) => i,
-            Err(0) => 0,
-            Err(i) => i - 1,
-        };
-        let line_start = self.line_starts[line_idx];
-        let slice = source.get_text_in(core::range::Range { start: line_start, end: offset });
-        let col = slice.as_ref().encode_utf16().count() as u32;
-        (line_idx as u32, col)
+    pub fn offset_to_line_col_utf16<S: Source + ?Sized>(&self, _source: &S, offset: usize) -> (u32, u32) {
+        self.offset_to_position(offset, PositionEncoding::Utf16)
     }
 
     /// Converts (line, column) coordinates (in UTF-16) to a byte offset.
     ///
+    /// The source is not needed for the conversion and is only kept for compatibility.
+    ///
     /// # Examples
     ///
     /// ```
@@ -110,23 +314,7 @@ impl LineMap {
     /// let offset = line_map.line_col_utf16_to_offset(&source, 1, 1);
     /// assert_eq!(offset, 7);
     /// ```
-    pub fn line_col_utf16_to_offset<S: Source + ?Sized>(&self, source: &S, line: u32, col_utf16: u32) -> usize {
-        let Some(line_start) = self.line_start(line)
-        else {
-            return self.len;
-        };
-        let line_end = self.line_end(line).unwrap_or(self.len);
-        let slice = source.get_text_in(core::range::Range { start: line_start, end: line_end });
-        let text = slice.as_ref();
-        let target = col_utf16 as usize;
-
-        let mut utf16 = 0usize;
-        for (byte_idx, ch) in text.char_indices() {
-            if utf16 >= target {
-                return (line_start + byte_idx).min(self.len);
-            }
-            utf16 += ch.len_utf16()
-        }
-        line_end.min(self.len)
+    pub fn line_col_utf16_to_offset<S: Source + ?Sized>(&self, _source: &S, line: u32, col_utf16: u32) -> usize {
+        self.position_to_offset(line, col_utf16, PositionEncoding::Utf16)
     }
 }
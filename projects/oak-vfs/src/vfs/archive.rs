@@ -0,0 +1,252 @@
+use crate::{FileMetadata, Vfs, vfs::tree::FileTree};
+use oak_core::{
+    Arc, OakError,
+    source::{SourceId, SourceText},
+};
+use std::path::Path;
+
+/// Returns the URI of the root directory of an archive file, e.g. `zip:file:///lib.jar!/`.
+fn archive_prefix(scheme: &str, path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match url::Url::from_file_path(&path) {
+        Ok(url) => format!("{}:{}!/", scheme, url),
+        Err(_) => format!("{}:{}!/", scheme, path.display()),
+    }
+}
+
+#[cfg(feature = "zip")]
+pub use self::zip_vfs::ZipVfs;
+
+#[cfg(feature = "zip")]
+mod zip_vfs {
+    use super::*;
+    use std::{
+        fs::File,
+        io::{BufReader, Read, Seek},
+        sync::{Mutex, OnceLock},
+    };
+    use zip::{ZipArchive, result::ZipError};
+
+    /// A reader an archive can be read from.
+    trait ReadSeek: Read + Seek + Send {}
+
+    impl<T: Read + Seek + Send> ReadSeek for T {}
+
+    /// A file in a zip archive, decompressed on first access.
+    struct ZipEntry {
+        /// The index of the file in the archive.
+        index: usize,
+        /// The content of the file, or `None` if it is not UTF-8 text.
+        content: OnceLock<Option<Arc<str>>>,
+    }
+
+    /// A read-only virtual file system over the entries of a zip archive, such as a jar or a wheel.
+    ///
+    /// Entries are addressed by URIs of the form `zip:file:///path/to/lib.jar!/com/example/Main.java`,
+    /// or by their path inside the archive. Files are decompressed when they are first read and
+    /// files that are not UTF-8 text have metadata but no source.
+    pub struct ZipVfs {
+        tree: FileTree<ZipEntry>,
+        archive: Mutex<ZipArchive<Box<dyn ReadSeek>>>,
+    }
+
+    impl ZipVfs {
+        /// Opens the zip archive at a path.
+        pub fn open(path: &Path) -> Result<Self, OakError> {
+            let file = File::open(path)?;
+            Self::from_reader(BufReader::new(file), &archive_prefix("zip", path))
+        }
+
+        /// Reads a zip archive, addressing its entries below the URI `prefix`.
+        pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R, prefix: &str) -> Result<Self, OakError> {
+            let mut archive = ZipArchive::new(Box::new(reader) as Box<dyn ReadSeek>).map_err(zip_error)?;
+            let mut tree = FileTree::new(prefix);
+            for index in 0..archive.len() {
+                let file = archive.by_index_raw(index).map_err(zip_error)?;
+                if file.is_dir() {
+                    tree.insert_dir(file.name());
+                }
+                else {
+                    let modified = file.last_modified().map(unix_time);
+                    tree.insert(file.name(), file.size(), modified, ZipEntry { index, content: OnceLock::new() });
+                }
+            }
+            Ok(Self { tree, archive: Mutex::new(archive) })
+        }
+
+        /// Returns the URI of the root directory of the archive.
+        pub fn root(&self) -> &str {
+            self.tree.prefix()
+        }
+
+        /// Returns the number of files in the archive.
+        pub fn len(&self) -> usize {
+            self.tree.len()
+        }
+
+        /// Checks whether the archive contains no files.
+        pub fn is_empty(&self) -> bool {
+            self.tree.len() == 0
+        }
+
+        fn read(&self, index: usize) -> Option<Arc<str>> {
+            let mut archive = self.archive.lock().ok()?;
+            let mut file = archive.by_index(index).ok()?;
+            let mut text = String::new();
+            file.read_to_string(&mut text).ok()?;
+            Some(Arc::from(text.as_str()))
+        }
+    }
+
+    impl Vfs for ZipVfs {
+        type Source = SourceText;
+
+        fn get_source(&self, uri: &str) -> Option<Self::Source> {
+            let file = self.tree.file(uri)?;
+            let content = file.data.content.get_or_init(|| self.read(file.data.index)).clone()?;
+            Some(SourceText::new_with_id(content, file.id))
+        }
+
+        fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+            self.tree.get_uri(id)
+        }
+
+        fn get_id(&self, uri: &str) -> Option<SourceId> {
+            self.tree.get_id(uri)
+        }
+
+        fn exists(&self, uri: &str) -> bool {
+            self.tree.exists(uri)
+        }
+
+        fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+            self.tree.metadata(uri)
+        }
+
+        fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+            self.tree.read_dir(uri)
+        }
+    }
+
+    fn zip_error(error: ZipError) -> OakError {
+        match error {
+            ZipError::Io(error) => error.into(),
+            error => OakError::zip_error(error.to_string()),
+        }
+    }
+
+    /// Converts the MS-DOS timestamp of a zip entry to a Unix timestamp.
+    fn unix_time(time: zip::DateTime) -> u64 {
+        // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+        let (year, month, day) = (time.year() as i64, time.month() as i64, time.day() as i64);
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146097 + day_of_era - 719468;
+        (days * 86400 + time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64).max(0) as u64
+    }
+}
+
+#[cfg(feature = "tar")]
+pub use self::tar_vfs::TarVfs;
+
+#[cfg(feature = "tar")]
+mod tar_vfs {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::{
+        fs::File,
+        io::{BufRead, BufReader, Read},
+    };
+    use tar::{Archive, EntryType};
+
+    /// A read-only virtual file system over the entries of a tar archive, such as a crate or an sdist.
+    ///
+    /// Gzip-compressed archives are detected and decompressed. Entries are addressed by URIs of the
+    /// form `tar:file:///path/to/serde-1.0.0.crate!/serde-1.0.0/src/lib.rs`, or by their path inside
+    /// the archive. Tar archives cannot be read out of order, so all files are read up front; files
+    /// that are not UTF-8 text have metadata but no source.
+    pub struct TarVfs {
+        tree: FileTree<Option<Arc<str>>>,
+    }
+
+    impl TarVfs {
+        /// Opens the tar archive at a path.
+        pub fn open(path: &Path) -> Result<Self, OakError> {
+            let file = File::open(path)?;
+            Self::from_reader(file, &archive_prefix("tar", path))
+        }
+
+        /// Reads a tar archive, addressing its entries below the URI `prefix`.
+        pub fn from_reader<R: Read>(reader: R, prefix: &str) -> Result<Self, OakError> {
+            let mut reader = BufReader::new(reader);
+            let gzip = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+            let reader: Box<dyn Read> = if gzip { Box::new(GzDecoder::new(reader)) } else { Box::new(reader) };
+
+            let mut tree = FileTree::new(prefix);
+            for entry in Archive::new(reader).entries()? {
+                let mut entry = entry?;
+                let path = entry.path()?.to_string_lossy().into_owned();
+                let modified = entry.header().mtime().ok();
+                match entry.header().entry_type() {
+                    EntryType::Directory => tree.insert_dir(&path),
+                    EntryType::Regular | EntryType::Continuous => {
+                        let mut bytes = Vec::new();
+                        entry.read_to_end(&mut bytes)?;
+                        let len = bytes.len() as u64;
+                        let content = String::from_utf8(bytes).ok().map(|text| Arc::from(text.as_str()));
+                        tree.insert(&path, len, modified, content)
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Self { tree })
+        }
+
+        /// Returns the URI of the root directory of the archive.
+        pub fn root(&self) -> &str {
+            self.tree.prefix()
+        }
+
+        /// Returns the number of files in the archive.
+        pub fn len(&self) -> usize {
+            self.tree.len()
+        }
+
+        /// Checks whether the archive contains no files.
+        pub fn is_empty(&self) -> bool {
+            self.tree.len() == 0
+        }
+    }
+
+    impl Vfs for TarVfs {
+        type Source = SourceText;
+
+        fn get_source(&self, uri: &str) -> Option<Self::Source> {
+            let file = self.tree.file(uri)?;
+            Some(SourceText::new_with_id(file.data.clone()?, file.id))
+        }
+
+        fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+            self.tree.get_uri(id)
+        }
+
+        fn get_id(&self, uri: &str) -> Option<SourceId> {
+            self.tree.get_id(uri)
+        }
+
+        fn exists(&self, uri: &str) -> bool {
+            self.tree.exists(uri)
+        }
+
+        fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+            self.tree.metadata(uri)
+        }
+
+        fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+            self.tree.read_dir(uri)
+        }
+    }
+}
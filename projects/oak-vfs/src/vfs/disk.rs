@@ -3,14 +3,44 @@ use oak_core::{
     Arc,
     source::{SourceId, SourceText},
 };
-use std::{collections::HashMap, fs, path::PathBuf, sync::RwLock};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        RwLock,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::SystemTime,
+};
+use url::Url;
+
+/// The content of a file as it was last read from disk.
+struct CachedFile {
+    content: Arc<str>,
+    /// The modification time of the file when it was read.
+    modified: Option<SystemTime>,
+    /// The length of the file when it was read.
+    len: u64,
+}
 
 /// A virtual file system that reads from and writes to the physical disk.
+///
+/// Files are addressed by `file://` URIs. Plain paths are accepted too, and relative ones are
+/// resolved against the root path; both forms are normalized to the `file://` URI of the file,
+/// so the same file always has the same URI and [`SourceId`].
+///
+/// Contents are cached and only read again when the modification time or length of the file
+/// changes, or when the cache entry is [invalidated](Self::invalidate).
 pub struct DiskVfs {
     /// Mapping from file URIs to internal source IDs.
     uri_to_id: RwLock<HashMap<Arc<str>, SourceId>>,
     /// Mapping from source IDs back to file URIs.
     id_to_uri: RwLock<HashMap<SourceId, Arc<str>>>,
+    /// The next source ID to assign.
+    next_id: AtomicU32,
+    /// File contents keyed by URI.
+    cache: RwLock<HashMap<Arc<str>, CachedFile>>,
     /// Base path for relative URIs.
     root_path: PathBuf,
 }
@@ -18,30 +48,87 @@ pub struct DiskVfs {
 impl DiskVfs {
     /// Creates a new DiskVfs instance with the given root path.
     pub fn new(root_path: PathBuf) -> Self {
-        Self { uri_to_id: RwLock::new(HashMap::new()), id_to_uri: RwLock::new(HashMap::new()), root_path }
+        Self { uri_to_id: RwLock::new(HashMap::new()), id_to_uri: RwLock::new(HashMap::new()), next_id: AtomicU32::new(0), cache: RwLock::new(HashMap::new()), root_path }
     }
 
     /// Converts a URI to a physical file path.
-    fn uri_to_path(&self, uri: &str) -> Option<PathBuf> {
+    ///
+    /// `file://` URIs are decoded, other URIs are treated as paths relative to the root path.
+    pub fn uri_to_path(&self, uri: &str) -> Option<PathBuf> {
+        if uri.starts_with("file:") {
+            return Url::parse(uri).ok()?.to_file_path().ok();
+        }
+        if uri.contains("://") {
+            return None;
+        }
         let path = PathBuf::from(uri);
         if path.is_absolute() { Some(path) } else { Some(self.root_path.join(path)) }
     }
 
-    /// Converts a physical file path to a URI.
-    fn path_to_uri(&self, path: PathBuf) -> Arc<str> {
-        let uri = if path.is_absolute() { path.to_string_lossy().to_string() } else { path.strip_prefix(&self.root_path).unwrap_or(&path).to_string_lossy().to_string() };
-        Arc::from(uri)
+    /// Converts a physical file path to a `file://` URI.
+    pub fn path_to_uri(path: &Path) -> Arc<str> {
+        match Url::from_file_path(path) {
+            Ok(url) => Arc::from(url.as_str()),
+            Err(_) => Arc::from(path.to_string_lossy().as_ref()),
+        }
+    }
+
+    /// Returns the normalized `file://` URI of a URI or path.
+    pub fn normalize_uri(&self, uri: &str) -> Option<Arc<str>> {
+        let path = self.uri_to_path(uri)?;
+        Some(Self::path_to_uri(&path))
+    }
+
+    /// Returns the source ID of a URI, assigning a new one if it has none yet.
+    pub fn source_id(&self, uri: &str) -> SourceId {
+        let uri: Arc<str> = self.normalize_uri(uri).unwrap_or_else(|| Arc::from(uri));
+        if let Some(id) = self.uri_to_id.read().unwrap().get(&uri) {
+            return *id;
+        }
+        let mut uri_to_id = self.uri_to_id.write().unwrap();
+        *uri_to_id.entry(uri.clone()).or_insert_with(|| {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            self.id_to_uri.write().unwrap().insert(id, uri);
+            id
+        })
+    }
+
+    /// Drops the cached content of a file, so that it is read again on next access.
+    pub fn invalidate(&self, uri: &str) {
+        if let Some(uri) = self.normalize_uri(uri) {
+            self.cache.write().unwrap().remove(&uri);
+        }
+    }
+
+    /// Drops the cached content of all files.
+    pub fn invalidate_all(&self) {
+        self.cache.write().unwrap().clear()
     }
 }
 
 impl Vfs for DiskVfs {
     type Source = SourceText;
 
-    /// Reads the content of a file from disk given its URI.
+    /// Reads the content of a file given its URI, from the cache if the file is unchanged.
     fn get_source(&self, uri: &str) -> Option<Self::Source> {
         let path = self.uri_to_path(uri)?;
-        let content = fs::read_to_string(path).ok()?;
-        Some(SourceText::new(&*content))
+        let uri = Self::path_to_uri(&path);
+        let meta = fs::metadata(&path).ok()?;
+        if !meta.is_file() {
+            return None;
+        }
+        let modified = meta.modified().ok();
+
+        let cached = self.cache.read().unwrap().get(&uri).filter(|file| file.modified == modified && file.len == meta.len()).map(|file| file.content.clone());
+        let content = match cached {
+            Some(content) => content,
+            None => {
+                let content: Arc<str> = Arc::from(fs::read_to_string(&path).ok()?.as_str());
+                self.cache.write().unwrap().insert(uri.clone(), CachedFile { content: content.clone(), modified, len: meta.len() });
+                content
+            }
+        };
+        Some(SourceText::new_with_id(content, self.source_id(&uri)))
     }
 
     /// Returns the URI for a given source ID.
@@ -50,10 +137,11 @@ impl Vfs for DiskVfs {
         id_to_uri.get(&id).cloned()
     }
 
-    /// Returns the source ID for a given URI, if it exists.
+    /// Returns the source ID for a given URI, if it has been read before.
     fn get_id(&self, uri: &str) -> Option<SourceId> {
+        let uri = self.normalize_uri(uri)?;
         let uri_to_id = self.uri_to_id.read().unwrap();
-        uri_to_id.get(uri).cloned()
+        uri_to_id.get(&uri).cloned()
     }
 
     /// Checks if a file or directory exists at the given URI on disk.
@@ -81,7 +169,7 @@ impl Vfs for DiskVfs {
         Some(FileMetadata { file_type, len: meta.len(), modified })
     }
 
-    /// Reads the contents of a directory on disk and returns their URIs.
+    /// Reads the contents of a directory on disk and returns their `file://` URIs.
     fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
         let path = self.uri_to_path(uri)?;
         if !path.is_dir() {
@@ -91,7 +179,7 @@ impl Vfs for DiskVfs {
         let mut entries = Vec::new();
         for entry in fs::read_dir(path).ok()? {
             if let Ok(entry) = entry {
-                entries.push(self.path_to_uri(entry.path()));
+                entries.push(Self::path_to_uri(&entry.path()));
             }
         }
         Some(entries)
@@ -106,6 +194,7 @@ impl WritableVfs for DiskVfs {
                 let _ = fs::create_dir_all(parent);
             }
             let _ = fs::write(path, content.as_ref());
+            self.invalidate(uri)
         }
     }
 
@@ -113,6 +202,7 @@ impl WritableVfs for DiskVfs {
     fn remove_file(&self, uri: &str) {
         if let Some(path) = self.uri_to_path(uri) {
             let _ = fs::remove_file(path);
+            self.invalidate(uri)
         }
     }
 }
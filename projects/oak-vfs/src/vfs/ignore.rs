@@ -0,0 +1,157 @@
+/// A pattern of an ignore file.
+#[derive(Clone, Debug)]
+struct IgnoreRule {
+    /// The glob, without a leading or trailing `/`.
+    pattern: Vec<char>,
+    /// Whether the pattern started with `!` and re-includes what it matches.
+    negated: bool,
+    /// Whether the pattern ended with `/` and only matches directories.
+    directory_only: bool,
+    /// Whether the pattern contains a `/` and is matched against the whole relative path
+    /// instead of the file name.
+    anchored: bool,
+}
+
+/// A set of `.gitignore`-style ignore patterns.
+///
+/// Supported are comments, `!` negation, a trailing `/` for directories, a leading or inner `/`
+/// to anchor a pattern to the root, and the wildcards `*`, `?`, `**` and `[...]`. As in git, a
+/// path is ignored when one of its parent directories is, and later patterns take precedence.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Creates an empty set of ignore patterns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the content of a `.gitignore` file.
+    pub fn parse(content: &str) -> Self {
+        let mut rules = Self::new();
+        for line in content.lines() {
+            rules.add(line)
+        }
+        rules
+    }
+
+    /// Adds a pattern; empty lines and comments are skipped.
+    pub fn add(&mut self, line: &str) {
+        let mut line = line.trim_end_matches(['\r', ' ']);
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+        let negated = line.starts_with('!');
+        // `\!` and `\#` escape a leading `!` or `#`.
+        if negated || line.starts_with("\\!") || line.starts_with("\\#") {
+            line = &line[1..]
+        }
+        let directory_only = line.ends_with('/');
+        let line = line.trim_end_matches('/');
+        let anchored = line.contains('/');
+        let line = line.trim_start_matches('/');
+        if line.is_empty() {
+            return;
+        }
+        self.rules.push(IgnoreRule { pattern: line.chars().collect(), negated, directory_only, anchored })
+    }
+
+    /// Adds the patterns of another set after the patterns of this one.
+    pub fn extend(&mut self, other: &IgnoreRules) {
+        self.rules.extend(other.rules.iter().cloned())
+    }
+
+    /// Checks whether there are no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Checks whether a path, relative to the directory of the patterns and separated by `/`, is ignored.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let mut end = 0;
+        for (index, _) in path.match_indices('/') {
+            if index > end && self.matches(&path[..index], true) {
+                return true;
+            }
+            end = index + 1;
+        }
+        self.matches(path.trim_end_matches('/'), is_dir)
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        let name = match path.iter().rposition(|c| *c == '/') {
+            Some(index) => &path[index + 1..],
+            None => &path[..],
+        };
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.negated != ignored || (rule.directory_only && !is_dir) {
+                continue;
+            }
+            if glob_match(&rule.pattern, if rule.anchored { &path } else { name }) {
+                ignored = !rule.negated
+            }
+        }
+        ignored
+    }
+}
+
+/// Matches a glob against a path, where only `**` matches across `/`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            if let ['/', after @ ..] = rest
+                && glob_match(after, text)
+            {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        ['*', rest @ ..] => {
+            let segment = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob_match(rest, &text[i..]))
+        }
+        ['?', rest @ ..] => matches!(text, [c, ..] if *c != '/') && glob_match(rest, &text[1..]),
+        ['[', rest @ ..] => match (text.first(), match_class(rest, text.first().copied().unwrap_or('/'))) {
+            (Some(c), Some((matched, rest))) => *c != '/' && matched && glob_match(rest, &text[1..]),
+            (_, None) => matches!(text, ['[', ..]) && glob_match(rest, &text[1..]),
+            (None, Some(_)) => false,
+        },
+        ['\\', c, rest @ ..] => matches!(text, [t, ..] if t == c) && glob_match(rest, &text[1..]),
+        [c, rest @ ..] => matches!(text, [t, ..] if t == c) && glob_match(rest, &text[1..]),
+    }
+}
+
+/// Matches a character against a `[...]` class, given the pattern after the `[`.
+///
+/// Returns whether it matched and the pattern after the `]`, or `None` if the class is not closed.
+fn match_class(pattern: &[char], c: char) -> Option<(bool, &[char])> {
+    let (negated, mut pattern) = match pattern {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, pattern),
+    };
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match pattern {
+            [] => return None,
+            [']', rest @ ..] if !first => return Some((matched != negated, rest)),
+            [low, '-', high, rest @ ..] if *high != ']' => {
+                matched |= (*low..=*high).contains(&c);
+                pattern = rest
+            }
+            [x, rest @ ..] => {
+                matched |= *x == c;
+                pattern = rest
+            }
+        }
+        first = false
+    }
+}
@@ -1,19 +1,41 @@
 //! Virtual File System (VFS) implementations and management.
 //!
 //! This module provides different implementations of the [`Vfs`](crate::Vfs) trait,
-//! including in-memory and disk-based storage.
+//! including in-memory and disk-based storage and read-only archives and snapshots.
 
+/// Read-only VFS implementations over zip and tar archives.
+#[cfg(any(feature = "zip", feature = "tar"))]
+pub mod archive;
 /// Disk-based VFS implementation using the local file system.
 #[cfg(feature = "disk")]
 pub mod disk;
+/// `.gitignore`-style ignore patterns.
+pub mod ignore;
 /// In-memory VFS implementation.
 pub mod memory;
+/// Overlay VFS layering open documents over the disk.
+#[cfg(feature = "disk")]
+pub mod overlay;
+/// Read-only content-addressed snapshot VFS implementation.
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(any(feature = "zip", feature = "tar", feature = "snapshot"))]
+mod tree;
 /// File system watcher for tracking changes on disk.
 #[cfg(feature = "disk")]
 pub mod watch;
 
+#[cfg(feature = "tar")]
+pub use archive::TarVfs;
+#[cfg(feature = "zip")]
+pub use archive::ZipVfs;
 #[cfg(feature = "disk")]
 pub use disk::DiskVfs;
+pub use ignore::IgnoreRules;
 pub use memory::MemoryVfs;
 #[cfg(feature = "disk")]
-pub use watch::{DiskWatcher, VfsEvent, VfsWatcher};
+pub use overlay::OverlayVfs;
+#[cfg(feature = "snapshot")]
+pub use snapshot::{ContentHash, SnapshotVfs};
+#[cfg(feature = "disk")]
+pub use watch::{Debouncer, DiskWatcher, VfsEvent, VfsWatcher, WatchOptions};
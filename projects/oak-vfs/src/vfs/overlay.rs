@@ -0,0 +1,183 @@
+use crate::{DiskVfs, DiskWatcher, FileMetadata, FileType, Vfs, VfsEvent, WritableVfs};
+use oak_core::{
+    Arc,
+    source::{SourceId, SourceText},
+};
+use std::{collections::HashMap, path::PathBuf, sync::RwLock};
+
+/// The content of a document open in the editor.
+struct Document {
+    content: Arc<str>,
+    /// When the content was last set, as a Unix timestamp in seconds.
+    modified: u64,
+}
+
+/// A virtual file system that layers the unsaved buffers of open documents over the disk.
+///
+/// Reads see the buffer of an open document if there is one and the file on disk otherwise.
+/// Writes only change the buffers: [`write_file`](WritableVfs::write_file) opens or updates a
+/// document and [`remove_file`](WritableVfs::remove_file) closes it, after which the file on disk
+/// is visible again. This is what a language server needs for `didOpen`, `didChange` and `didClose`.
+///
+/// Both layers share the `file://` URI normalization and the [`SourceId`]s of the underlying
+/// [`DiskVfs`], so a file keeps its ID when it is opened or closed. Clones share their state.
+#[derive(Clone)]
+pub struct OverlayVfs {
+    disk: std::sync::Arc<DiskVfs>,
+    /// The open documents, keyed by normalized URI.
+    documents: std::sync::Arc<RwLock<HashMap<Arc<str>, Document>>>,
+}
+
+impl OverlayVfs {
+    /// Creates an overlay over the disk, resolving relative paths against `root_path`.
+    pub fn new(root_path: PathBuf) -> Self {
+        Self::with_disk(DiskVfs::new(root_path))
+    }
+
+    /// Creates an overlay over the given disk layer.
+    pub fn with_disk(disk: DiskVfs) -> Self {
+        Self { disk: std::sync::Arc::new(disk), documents: Default::default() }
+    }
+
+    /// Returns the disk layer.
+    pub fn disk(&self) -> &DiskVfs {
+        &self.disk
+    }
+
+    /// Normalizes `file://` URIs and paths; other URIs (e.g. `untitled:`) are kept as they are.
+    fn key(&self, uri: &str) -> Arc<str> {
+        self.disk.normalize_uri(uri).unwrap_or_else(|| Arc::from(uri))
+    }
+
+    /// Opens a document or replaces its buffer.
+    pub fn open(&self, uri: &str, content: impl Into<Arc<str>>) {
+        let key = self.key(uri);
+        self.disk.source_id(&key);
+        self.documents.write().unwrap().insert(key, Document { content: content.into(), modified: now() });
+    }
+
+    /// Closes a document, discarding its buffer.
+    pub fn close(&self, uri: &str) {
+        let key = self.key(uri);
+        self.documents.write().unwrap().remove(&key);
+    }
+
+    /// Checks whether a document is open.
+    pub fn is_open(&self, uri: &str) -> bool {
+        self.documents.read().unwrap().contains_key(&self.key(uri))
+    }
+
+    /// Returns the URIs of the open documents.
+    pub fn open_documents(&self) -> Vec<Arc<str>> {
+        self.documents.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Writes the buffer of an open document to disk. Returns `false` if the document is not open.
+    pub fn save(&self, uri: &str) -> bool {
+        let content = self.documents.read().unwrap().get(&self.key(uri)).map(|document| document.content.clone());
+        match content {
+            Some(content) => {
+                self.disk.write_file(uri, content);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Invalidates the cached disk content of the files a watcher event is about.
+    pub fn handle_event(&self, event: &VfsEvent) {
+        match event {
+            VfsEvent::Changed(uri) | VfsEvent::Created(uri) | VfsEvent::Removed(uri) => self.disk.invalidate(uri),
+            VfsEvent::Renamed { from, to } => {
+                self.disk.invalidate(from);
+                self.disk.invalidate(to)
+            }
+        }
+    }
+
+    /// Creates a [`DiskWatcher`] that keeps the disk cache of this overlay up to date.
+    ///
+    /// Events are forwarded to `callback` after the cache has been invalidated.
+    pub fn watcher<F>(&self, mut callback: F) -> notify::Result<DiskWatcher>
+    where
+        F: FnMut(VfsEvent) + Send + 'static,
+    {
+        let overlay = self.clone();
+        DiskWatcher::new(move |event| {
+            overlay.handle_event(&event);
+            callback(event)
+        })
+    }
+}
+
+impl Vfs for OverlayVfs {
+    type Source = SourceText;
+
+    /// Returns the buffer of an open document, or else the file on disk.
+    fn get_source(&self, uri: &str) -> Option<Self::Source> {
+        let key = self.key(uri);
+        let content = self.documents.read().unwrap().get(&key).map(|document| document.content.clone());
+        match content {
+            Some(content) => Some(SourceText::new_with_id(content, self.disk.source_id(&key))),
+            None => self.disk.get_source(uri),
+        }
+    }
+
+    fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+        self.disk.get_uri(id)
+    }
+
+    fn get_id(&self, uri: &str) -> Option<SourceId> {
+        if self.is_open(uri) {
+            return Some(self.disk.source_id(&self.key(uri)));
+        }
+        self.disk.get_id(uri)
+    }
+
+    fn exists(&self, uri: &str) -> bool {
+        self.is_open(uri) || self.disk.exists(uri)
+    }
+
+    fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+        match self.documents.read().unwrap().get(&self.key(uri)) {
+            Some(document) => Some(FileMetadata { file_type: FileType::File, len: document.content.len() as u64, modified: Some(document.modified) }),
+            None => self.disk.metadata(uri),
+        }
+    }
+
+    /// Lists the entries of a directory on disk together with the open documents in it
+    /// that do not exist on disk yet.
+    fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+        let mut entries = self.disk.read_dir(uri);
+        let directory = self.key(uri);
+        let prefix = if directory.ends_with('/') { directory.to_string() } else { format!("{}/", directory) };
+        for document in self.documents.read().unwrap().keys() {
+            if let Some(name) = document.strip_prefix(prefix.as_str())
+                && !name.is_empty()
+                && !name.contains('/')
+            {
+                let entries = entries.get_or_insert_with(Vec::new);
+                if !entries.contains(document) {
+                    entries.push(document.clone())
+                }
+            }
+        }
+        entries
+    }
+}
+
+impl WritableVfs for OverlayVfs {
+    /// Opens a document or replaces its buffer; the file on disk is not touched.
+    fn write_file(&self, uri: &str, content: Arc<str>) {
+        self.open(uri, content)
+    }
+
+    /// Closes a document; the file on disk is not touched.
+    fn remove_file(&self, uri: &str) {
+        self.close(uri)
+    }
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
@@ -0,0 +1,172 @@
+use crate::{FileMetadata, Vfs, vfs::tree::FileTree};
+use oak_core::{
+    Arc, OakError,
+    source::{SourceId, SourceText},
+};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+/// The SHA-256 hash of a file or of a whole snapshot.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentHash(pub [u8; 32]);
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ContentHash({})", self)
+    }
+}
+
+/// An immutable, content-addressed snapshot of a file tree.
+///
+/// Like a git tree object, every file is stored as a blob keyed by the hash of its content, so
+/// identical files share their content, and the snapshot itself is identified by a hash over the
+/// paths and blob hashes of its files. Two snapshots of the same content have the same hash, the
+/// same URIs and the same source IDs, which makes them usable as cache keys.
+///
+/// Entries are addressed by URIs of the form `snapshot://<hash>/src/lib.rs`, or by their relative
+/// path. Files that are not UTF-8 text have metadata but no source.
+pub struct SnapshotVfs {
+    tree: FileTree<ContentHash>,
+    blobs: HashMap<ContentHash, Option<Arc<str>>>,
+    hash: ContentHash,
+}
+
+impl SnapshotVfs {
+    /// Captures the files below a directory, skipping hidden files and directories like `.git`.
+    pub fn capture(root: &Path) -> Result<Self, OakError> {
+        Self::capture_filtered(root, |path| !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+    }
+
+    /// Captures the files below a directory for which `filter` returns `true`.
+    ///
+    /// Directories for which `filter` returns `false` are not entered. Symbolic links are not followed.
+    pub fn capture_filtered(root: &Path, mut filter: impl FnMut(&Path) -> bool) -> Result<Self, OakError> {
+        let mut files = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(directory) = pending.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                let file_type = entry.file_type()?;
+                if !filter(&path) {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    pending.push(path)
+                }
+                else if file_type.is_file() {
+                    let relative = path.strip_prefix(root).unwrap_or(&path).components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                    files.push((relative, fs::read(&path)?))
+                }
+            }
+        }
+        Ok(Self::from_files(files))
+    }
+
+    /// Creates a snapshot from relative paths and file contents.
+    pub fn from_files<P: AsRef<str>, C: AsRef<[u8]>>(files: impl IntoIterator<Item = (P, C)>) -> Self {
+        let mut blobs = HashMap::new();
+        let mut entries: Vec<(String, ContentHash, u64)> = files
+            .into_iter()
+            .map(|(path, content)| {
+                let content = content.as_ref();
+                let hash = blob_hash(content);
+                blobs.entry(hash).or_insert_with(|| std::str::from_utf8(content).ok().map(Arc::from));
+                (path.as_ref().trim_start_matches("./").trim_start_matches('/').to_string(), hash, content.len() as u64)
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|a, b| a.0 == b.0);
+
+        let mut hasher = Sha256::new();
+        for (path, hash, _) in &entries {
+            hasher.update(path.as_bytes());
+            hasher.update([0]);
+            hasher.update(hash.0);
+        }
+        let hash = ContentHash(hasher.finalize().into());
+
+        let mut tree = FileTree::new(&format!("snapshot://{}/", hash));
+        for (path, blob, len) in entries {
+            tree.insert(&path, len, None, blob)
+        }
+        Self { tree, blobs, hash }
+    }
+
+    /// Returns the hash identifying the content of this snapshot.
+    pub fn hash(&self) -> ContentHash {
+        self.hash
+    }
+
+    /// Returns the hash of the content of the file at a URI.
+    pub fn blob_hash(&self, uri: &str) -> Option<ContentHash> {
+        self.tree.file(uri).map(|file| file.data)
+    }
+
+    /// Returns the URI of the root directory of the snapshot.
+    pub fn root(&self) -> &str {
+        self.tree.prefix()
+    }
+
+    /// Returns the number of files in the snapshot.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Checks whether the snapshot contains no files.
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    /// Returns the number of distinct file contents in the snapshot.
+    pub fn blob_count(&self) -> usize {
+        self.blobs.len()
+    }
+}
+
+impl Vfs for SnapshotVfs {
+    type Source = SourceText;
+
+    fn get_source(&self, uri: &str) -> Option<Self::Source> {
+        let file = self.tree.file(uri)?;
+        let content = self.blobs.get(&file.data)?.clone()?;
+        Some(SourceText::new_with_id(content, file.id))
+    }
+
+    fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+        self.tree.get_uri(id)
+    }
+
+    fn get_id(&self, uri: &str) -> Option<SourceId> {
+        self.tree.get_id(uri)
+    }
+
+    fn exists(&self, uri: &str) -> bool {
+        self.tree.exists(uri)
+    }
+
+    fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+        self.tree.metadata(uri)
+    }
+
+    fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+        self.tree.read_dir(uri)
+    }
+}
+
+/// Hashes a file the way git hashes blobs, with SHA-256.
+fn blob_hash(content: &[u8]) -> ContentHash {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    ContentHash(hasher.finalize().into())
+}
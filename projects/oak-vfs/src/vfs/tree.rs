@@ -0,0 +1,161 @@
+use crate::{FileMetadata, FileType};
+use oak_core::{Arc, source::SourceId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Bound,
+};
+
+/// A file in a [`FileTree`].
+pub(crate) struct TreeFile<T> {
+    /// The source ID of the file, assigned in insertion order.
+    pub id: SourceId,
+    /// The length of the file in bytes.
+    pub len: u64,
+    /// The last modification time as a Unix timestamp in seconds.
+    pub modified: Option<u64>,
+    /// Backend specific data, e.g. the content or where to find it.
+    pub data: T,
+}
+
+/// An immutable tree of files and directories addressed by their path below a common URI prefix.
+///
+/// This is the shared index of the read-only VFS backends. A URI is either the prefix followed
+/// by the path of an entry, or a bare relative path without a scheme.
+pub(crate) struct FileTree<T> {
+    /// The URI of the root directory, ending with `/`.
+    prefix: Arc<str>,
+    /// Files keyed by path.
+    files: BTreeMap<Arc<str>, TreeFile<T>>,
+    /// Directory paths; the root directory is the empty path.
+    directories: BTreeSet<Arc<str>>,
+    /// File paths indexed by source ID.
+    paths: Vec<Arc<str>>,
+}
+
+impl<T> FileTree<T> {
+    /// Creates an empty tree whose root directory has the given URI.
+    pub fn new(prefix: &str) -> Self {
+        let prefix = if prefix.ends_with('/') { Arc::from(prefix) } else { Arc::from(format!("{}/", prefix).as_str()) };
+        let mut directories = BTreeSet::new();
+        directories.insert(Arc::from(""));
+        Self { prefix, files: BTreeMap::new(), directories, paths: Vec::new() }
+    }
+
+    /// Returns the URI of the root directory.
+    pub fn prefix(&self) -> &Arc<str> {
+        &self.prefix
+    }
+
+    /// Adds a directory and its parents.
+    pub fn insert_dir(&mut self, path: &str) {
+        let mut path = normalize(path);
+        while !path.is_empty() && self.directories.insert(Arc::from(path)) {
+            path = parent(path);
+        }
+    }
+
+    /// Adds a file and its parent directories. A file added twice keeps its ID but gets the new data.
+    pub fn insert(&mut self, path: &str, len: u64, modified: Option<u64>, data: T) {
+        let path = normalize(path);
+        if path.is_empty() {
+            return;
+        }
+        self.insert_dir(parent(path));
+        let id = match self.files.get(path) {
+            Some(file) => file.id,
+            None => {
+                self.paths.push(Arc::from(path));
+                (self.paths.len() - 1) as SourceId
+            }
+        };
+        self.files.insert(Arc::from(path), TreeFile { id, len, modified, data });
+    }
+
+    /// Returns the path of an entry given its URI, or `None` if the URI is outside this tree.
+    pub fn path<'a>(&self, uri: &'a str) -> Option<&'a str> {
+        if let Some(path) = uri.strip_prefix(self.prefix.as_ref()) {
+            return Some(normalize(path));
+        }
+        if uri == &self.prefix[..self.prefix.len() - 1] {
+            return Some("");
+        }
+        match uri.split_once(':') {
+            Some((scheme, _)) if !scheme.contains('/') => None,
+            _ => Some(normalize(uri)),
+        }
+    }
+
+    /// Returns the URI of the entry at a path.
+    pub fn uri(&self, path: &str) -> Arc<str> {
+        Arc::from(format!("{}{}", self.prefix, path).as_str())
+    }
+
+    /// Returns the file at a URI.
+    pub fn file(&self, uri: &str) -> Option<&TreeFile<T>> {
+        self.files.get(self.path(uri)?)
+    }
+
+    /// Returns the number of files in this tree.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns the URI of the file with a source ID.
+    pub fn get_uri(&self, id: SourceId) -> Option<Arc<str>> {
+        self.paths.get(id as usize).map(|path| self.uri(path))
+    }
+
+    /// Returns the source ID of the file at a URI.
+    pub fn get_id(&self, uri: &str) -> Option<SourceId> {
+        self.file(uri).map(|file| file.id)
+    }
+
+    /// Checks whether a file or directory exists at a URI.
+    pub fn exists(&self, uri: &str) -> bool {
+        self.path(uri).is_some_and(|path| self.files.contains_key(path) || self.directories.contains(path))
+    }
+
+    /// Returns the metadata of the file or directory at a URI.
+    pub fn metadata(&self, uri: &str) -> Option<FileMetadata> {
+        let path = self.path(uri)?;
+        match self.files.get(path) {
+            Some(file) => Some(FileMetadata { file_type: FileType::File, len: file.len, modified: file.modified }),
+            None if self.directories.contains(path) => Some(FileMetadata { file_type: FileType::Directory, len: 0, modified: None }),
+            None => None,
+        }
+    }
+
+    /// Returns the URIs of the files and directories directly inside the directory at a URI.
+    pub fn read_dir(&self, uri: &str) -> Option<Vec<Arc<str>>> {
+        let path = self.path(uri)?;
+        if !self.directories.contains(path) {
+            return None;
+        }
+        let start = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        let children = |entry: &&Arc<str>| entry.starts_with(start.as_str()) && entry.len() > start.len() && !entry[start.len()..].contains('/');
+        let directories = self.directories.range::<str, _>((Bound::Included(start.as_str()), Bound::Unbounded)).take_while(|entry| entry.starts_with(start.as_str())).filter(children);
+        let files = self.files.range::<str, _>((Bound::Included(start.as_str()), Bound::Unbounded)).map(|(entry, _)| entry).take_while(|entry| entry.starts_with(start.as_str())).filter(children);
+        Some(directories.chain(files).map(|entry| self.uri(entry)).collect())
+    }
+}
+
+/// Strips leading `./` and `/` and trailing `/` from a path.
+fn normalize(mut path: &str) -> &str {
+    loop {
+        if let Some(rest) = path.strip_prefix("./") {
+            path = rest
+        }
+        else if let Some(rest) = path.strip_prefix('/') {
+            path = rest
+        }
+        else {
+            break;
+        }
+    }
+    path.trim_end_matches('/')
+}
+
+/// Returns the parent directory of a normalized path.
+fn parent(path: &str) -> &str {
+    path.rfind('/').map(|i| &path[..i]).unwrap_or("")
+}
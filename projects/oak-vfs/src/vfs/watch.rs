@@ -1,7 +1,22 @@
-use notify::{Event, RecursiveMode, Result, Watcher};
-use std::path::PathBuf;
+use crate::{DiskVfs, vfs::ignore::IgnoreRules};
+use notify::{
+    Event, EventKind, RecursiveMode, Result, Watcher,
+    event::{MetadataKind, ModifyKind, RenameMode},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, RecvTimeoutError, channel},
+    },
+    time::{Duration, Instant},
+};
 
 /// Events emitted by a VFS watcher.
+///
+/// Paths are reported as `file://` URIs, the same as the URIs of [`DiskVfs`].
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum VfsEvent {
     /// A file or directory was modified.
     Changed(String),
@@ -9,6 +24,13 @@ pub enum VfsEvent {
     Created(String),
     /// A file or directory was removed.
     Removed(String),
+    /// A file or directory was moved.
+    Renamed {
+        /// The URI before the move.
+        from: String,
+        /// The URI after the move.
+        to: String,
+    },
 }
 
 /// A trait for watching file system changes.
@@ -21,47 +43,411 @@ pub trait VfsWatcher {
     fn unwatch_all(&mut self) -> Result<()>;
 }
 
+/// Options of a [`DiskWatcher`].
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// How long the file system has to be quiet before a burst of changes is reported.
+    pub debounce: Duration,
+    /// How long a burst of changes is held back at most, so that changes are reported while the
+    /// file system never gets quiet.
+    pub max_latency: Duration,
+    /// Patterns of paths to ignore, relative to each watched root.
+    pub ignore: IgnoreRules,
+    /// Whether to also honour the `.gitignore` file at the top of each watched root.
+    pub gitignore: bool,
+}
+
+impl Default for WatchOptions {
+    /// Debounces for 50ms up to 500ms and ignores `.git/`, `target/` and `node_modules/` and what `.gitignore` lists.
+    fn default() -> Self {
+        Self { debounce: Duration::from_millis(50), max_latency: Duration::from_millis(500), ignore: IgnoreRules::parse(".git/\ntarget/\nnode_modules/"), gitignore: true }
+    }
+}
+
+impl WatchOptions {
+    /// Sets how long the file system has to be quiet before a burst of changes is reported.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Sets how long a burst of changes is held back at most.
+    pub fn with_max_latency(mut self, max_latency: Duration) -> Self {
+        self.max_latency = max_latency;
+        self
+    }
+
+    /// Adds a `.gitignore`-style pattern of paths to ignore.
+    pub fn with_ignore(mut self, pattern: &str) -> Self {
+        self.ignore.add(pattern);
+        self
+    }
+
+    /// Sets whether to honour the `.gitignore` file at the top of each watched root.
+    pub fn with_gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+}
+
+/// A directory being watched.
+struct WatchedRoot {
+    path: PathBuf,
+    /// The patterns of the `.gitignore` file of the directory.
+    ignore: IgnoreRules,
+}
+
 /// A watcher implementation that watches files on disk.
+///
+/// Raw file system events of ignored paths are dropped as they arrive, so that e.g. a build
+/// writing to `target/` neither delays nor wakes the watcher. The others are collected on a
+/// background thread by a [`Debouncer`] until the file system has been quiet for the debounce
+/// duration, or for at most the maximum latency. The burst is then coalesced, so that e.g. a file
+/// that was created and modified is reported once as created and a temporary file that was
+/// created and removed is not reported at all.
 pub struct DiskWatcher {
     watcher: notify::RecommendedWatcher,
+    roots: Arc<Mutex<Vec<WatchedRoot>>>,
+    options: WatchOptions,
 }
 
 impl DiskWatcher {
-    /// Creates a new `DiskWatcher` with the given callback.
-    pub fn new<F>(mut callback: F) -> Result<Self>
+    /// Creates a new `DiskWatcher` with the given callback and the default options.
+    pub fn new<F>(callback: F) -> Result<Self>
     where
         F: FnMut(VfsEvent) + Send + 'static,
     {
+        Self::with_options(WatchOptions::default(), callback)
+    }
+
+    /// Creates a new `DiskWatcher` with the given options and callback.
+    pub fn with_options<F>(options: WatchOptions, callback: F) -> Result<Self>
+    where
+        F: FnMut(VfsEvent) + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let roots = Arc::new(Mutex::new(Vec::new()));
+        let filter = Filter { roots: roots.clone(), ignore: options.ignore.clone() };
         let watcher = notify::recommended_watcher(move |res: Result<Event>| {
-            if let Ok(event) = res {
-                for path in event.paths {
-                    let uri = path.to_string_lossy().to_string();
-                    if event.kind.is_modify() {
-                        callback(VfsEvent::Changed(uri))
-                    }
-                    else if event.kind.is_create() {
-                        callback(VfsEvent::Created(uri))
-                    }
-                    else if event.kind.is_remove() {
-                        callback(VfsEvent::Removed(uri))
-                    }
-                }
+            if let Ok(event) = res
+                && filter.is_relevant(&event)
+            {
+                let _ = sender.send(event);
             }
         })?;
-        Ok(Self { watcher })
+        let filter = Filter { roots: roots.clone(), ignore: options.ignore.clone() };
+        let mut thread = WatchThread { filter, burst: Debouncer::new(options.debounce, options.max_latency), trackers: HashSet::new(), callback };
+        std::thread::Builder::new().name("oak-vfs-watcher".to_string()).spawn(move || thread.run(receiver))?;
+        Ok(Self { watcher, roots, options })
+    }
+
+    /// Returns the URIs of the watched directories.
+    pub fn roots(&self) -> Vec<String> {
+        self.roots.lock().unwrap().iter().map(|root| DiskVfs::path_to_uri(&root.path).to_string()).collect()
     }
 }
 
 impl VfsWatcher for DiskWatcher {
+    /// Starts watching a directory or file, given as a `file://` URI or a path.
     fn watch(&mut self, uri: &str) -> Result<()> {
-        self.watcher.watch(PathBuf::from(uri).as_path(), RecursiveMode::Recursive)
+        let path = uri_to_path(uri);
+        self.watcher.watch(&path, RecursiveMode::Recursive)?;
+        let ignore = match self.options.gitignore {
+            true => std::fs::read_to_string(path.join(".gitignore")).map(|content| IgnoreRules::parse(&content)).unwrap_or_default(),
+            false => IgnoreRules::new(),
+        };
+        let mut roots = self.roots.lock().unwrap();
+        roots.retain(|root| root.path != path);
+        roots.push(WatchedRoot { path, ignore });
+        Ok(())
     }
 
     fn unwatch(&mut self, uri: &str) -> Result<()> {
-        self.watcher.unwatch(PathBuf::from(uri).as_path())
+        let path = uri_to_path(uri);
+        self.roots.lock().unwrap().retain(|root| root.path != path);
+        self.watcher.unwatch(&path)
     }
 
     fn unwatch_all(&mut self) -> Result<()> {
-        Ok(())
+        let roots = std::mem::take(&mut *self.roots.lock().unwrap());
+        let mut result = Ok(());
+        for root in roots {
+            if let Err(error) = self.watcher.unwatch(&root.path) {
+                result = Err(error)
+            }
+        }
+        result
+    }
+}
+
+/// Converts a `file://` URI or a path to an absolute path.
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = match url::Url::parse(uri).ok().filter(|url| url.scheme() == "file").and_then(|url| url.to_file_path().ok()) {
+        Some(path) => path,
+        None => PathBuf::from(uri),
+    };
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// A pending change of a path in a burst.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Created,
+    Changed,
+    Removed,
+}
+
+impl Change {
+    /// Combines two changes of the same path, or returns `None` if they cancel out.
+    fn then(self, next: Change) -> Option<Change> {
+        match (self, next) {
+            (Change::Created, Change::Removed) => None,
+            (Change::Created, _) => Some(Change::Created),
+            (Change::Removed, Change::Removed) => Some(Change::Removed),
+            (Change::Removed, _) => Some(Change::Changed),
+            (Change::Changed, next) => Some(next),
+        }
+    }
+}
+
+/// A coalesced change in a burst.
+enum Pending {
+    Path(PathBuf, Change),
+    Renamed(PathBuf, PathBuf),
+    /// A change that was cancelled out by a later one.
+    None,
+}
+
+/// Groups events into bursts.
+///
+/// A burst is ready once no event was pushed for the debounce duration, or once its first event
+/// is as old as the maximum latency, so that a steady stream of events is still reported. The
+/// time is passed in, so the watcher uses the system clock and tests can use their own.
+#[derive(Debug)]
+pub struct Debouncer<T> {
+    debounce: Duration,
+    max_latency: Duration,
+    events: Vec<T>,
+    /// When the first and the last event of the burst were pushed.
+    times: Option<(Instant, Instant)>,
+}
+
+impl<T> Debouncer<T> {
+    /// Creates a debouncer without events.
+    pub fn new(debounce: Duration, max_latency: Duration) -> Self {
+        Self { debounce, max_latency, events: Vec::new(), times: None }
+    }
+
+    /// Adds an event to the burst.
+    pub fn push(&mut self, event: T, now: Instant) {
+        self.events.push(event);
+        let first = self.times.map_or(now, |(first, _)| first);
+        self.times = Some((first, now))
+    }
+
+    /// Returns when the burst will be ready, or `None` if it is empty.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.times.map(|(first, last)| (last + self.debounce).min(first + self.max_latency))
+    }
+
+    /// Checks whether the burst is ready at `now`.
+    pub fn is_ready(&self, now: Instant) -> bool {
+        self.deadline().is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Takes the events of the burst, leaving the debouncer empty.
+    pub fn take(&mut self) -> Vec<T> {
+        self.times = None;
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// Decides which paths a [`DiskWatcher`] reports.
+struct Filter {
+    roots: Arc<Mutex<Vec<WatchedRoot>>>,
+    ignore: IgnoreRules,
+}
+
+impl Filter {
+    /// Checks whether an event touches a path that is below a watched root and not ignored.
+    fn is_relevant(&self, event: &Event) -> bool {
+        let roots = self.roots.lock().unwrap();
+        event.paths.iter().any(|path| self.is_visible(&roots, path))
+    }
+
+    /// Checks whether a path is below a watched root and not ignored.
+    fn is_visible(&self, roots: &[WatchedRoot], path: &Path) -> bool {
+        let Some((root, relative)) = roots.iter().filter_map(|root| Some((root, path.strip_prefix(&root.path).ok()?))).max_by_key(|(root, _)| root.path.as_os_str().len())
+        else {
+            return false;
+        };
+        let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        if relative.is_empty() {
+            return true;
+        }
+        let is_dir = path.is_dir();
+        !self.ignore.is_ignored(&relative, is_dir) && !root.ignore.is_ignored(&relative, is_dir)
+    }
+}
+
+/// The state of the background thread of a [`DiskWatcher`].
+struct WatchThread<F> {
+    filter: Filter,
+    burst: Debouncer<Event>,
+    /// Rename trackers already reported through a `From`/`To` pair, whose `Both` event must be skipped.
+    trackers: HashSet<usize>,
+    callback: F,
+}
+
+impl<F: FnMut(VfsEvent)> WatchThread<F> {
+    /// Reports bursts of events until the watcher is dropped.
+    fn run(&mut self, receiver: Receiver<Event>) {
+        while let Ok(event) = receiver.recv() {
+            self.burst.push(event, Instant::now());
+            while let Some(deadline) = self.burst.deadline() {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                match receiver.recv_timeout(deadline - now) {
+                    Ok(event) => self.burst.push(event, Instant::now()),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            let events = self.burst.take();
+            self.flush(events)
+        }
+    }
+
+    /// Coalesces a burst of events and reports the result.
+    fn flush(&mut self, events: Vec<Event>) {
+        let mut pending = Vec::new();
+        let mut index = HashMap::new();
+        let mut renamed_from = HashMap::new();
+        for event in events {
+            let tracker = event.tracker();
+            let mut paths = event.paths.into_iter();
+            let change = match event.kind {
+                EventKind::Create(_) => Change::Created,
+                EventKind::Remove(_) => Change::Removed,
+                EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    if let Some(path) = paths.next() {
+                        renamed_from.insert(tracker, path);
+                    }
+                    continue;
+                }
+                EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                    let Some(to) = paths.next()
+                    else {
+                        continue;
+                    };
+                    match renamed_from.remove(&tracker) {
+                        Some(from) => {
+                            if let Some(tracker) = tracker {
+                                self.trackers.insert(tracker);
+                            }
+                            record_rename(&mut pending, &mut index, from, to)
+                        }
+                        None => record(&mut pending, &mut index, to, Change::Created),
+                    }
+                    continue;
+                }
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                    if let (Some(from), Some(to)) = (paths.next(), paths.next())
+                        && !tracker.is_some_and(|tracker| self.trackers.remove(&tracker))
+                    {
+                        record_rename(&mut pending, &mut index, from, to)
+                    }
+                    continue;
+                }
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    for path in paths {
+                        let change_kind = if path.exists() { Change::Created } else { Change::Removed };
+                        record(&mut pending, &mut index, path, change_kind)
+                    }
+                    continue;
+                }
+                EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime | MetadataKind::Permissions | MetadataKind::Ownership | MetadataKind::Extended)) => continue,
+                EventKind::Modify(_) => Change::Changed,
+                _ => continue,
+            };
+            for path in paths {
+                record(&mut pending, &mut index, path, change)
+            }
+        }
+        for (_, from) in renamed_from {
+            record(&mut pending, &mut index, from, Change::Removed)
+        }
+        if self.trackers.len() > 1024 {
+            self.trackers.clear()
+        }
+
+        // Only one path of a relevant rename may be visible, so they are checked again.
+        let roots = self.filter.roots.lock().unwrap();
+        let visible = |path: &Path| self.filter.is_visible(&roots, path);
+        let events: Vec<_> = pending
+            .into_iter()
+            .filter_map(|pending| match pending {
+                Pending::Path(path, change) if visible(&path) => {
+                    let uri = DiskVfs::path_to_uri(&path).to_string();
+                    Some(match change {
+                        Change::Created => VfsEvent::Created(uri),
+                        Change::Changed => VfsEvent::Changed(uri),
+                        Change::Removed => VfsEvent::Removed(uri),
+                    })
+                }
+                Pending::Renamed(from, to) => match (visible(&from), visible(&to)) {
+                    (true, true) => Some(VfsEvent::Renamed { from: DiskVfs::path_to_uri(&from).to_string(), to: DiskVfs::path_to_uri(&to).to_string() }),
+                    (true, false) => Some(VfsEvent::Removed(DiskVfs::path_to_uri(&from).to_string())),
+                    (false, true) => Some(VfsEvent::Created(DiskVfs::path_to_uri(&to).to_string())),
+                    (false, false) => None,
+                },
+                _ => None,
+            })
+            .collect();
+        drop(roots);
+        for event in events {
+            (self.callback)(event)
+        }
+    }
+}
+
+/// Records a change of a path in a burst.
+fn record(pending: &mut Vec<Pending>, index: &mut HashMap<PathBuf, usize>, path: PathBuf, change: Change) {
+    match index.get(&path) {
+        Some(&i) => {
+            let Pending::Path(_, previous) = pending[i]
+            else {
+                return;
+            };
+            match previous.then(change) {
+                Some(change) => pending[i] = Pending::Path(path, change),
+                None => {
+                    pending[i] = Pending::None;
+                    index.remove(&path);
+                }
+            }
+        }
+        None => {
+            index.insert(path.clone(), pending.len());
+            pending.push(Pending::Path(path, change))
+        }
+    }
+}
+
+/// Records a move in a burst. Moving a file created in the same burst, like the temporary file
+/// of an atomic save, is reported as a creation or change of the target instead.
+fn record_rename(pending: &mut Vec<Pending>, index: &mut HashMap<PathBuf, usize>, from: PathBuf, to: PathBuf) {
+    if let Some(&i) = index.get(&from)
+        && matches!(pending[i], Pending::Path(_, Change::Created))
+    {
+        pending[i] = Pending::None;
+        index.remove(&from);
+        let target = if index.contains_key(&to) { Change::Changed } else { Change::Created };
+        return record(pending, index, to, target);
     }
+    index.remove(&from);
+    index.remove(&to);
+    pending.push(Pending::Renamed(from, to))
 }
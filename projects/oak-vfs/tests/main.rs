@@ -0,0 +1,348 @@
+use oak_core::source::SourceText;
+use oak_vfs::{LineMap, PositionEncoding};
+
+const ENCODINGS: [PositionEncoding; 3] = [PositionEncoding::Utf8, PositionEncoding::Utf16, PositionEncoding::Utf32];
+
+/// Computes a position by scanning the text, as a reference for the cached conversion.
+fn naive_position(text: &str, offset: usize, encoding: PositionEncoding) -> (u32, u32) {
+    let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = text[..offset].matches('\n').count() as u32;
+    let prefix = &text[line_start..offset];
+    let column = match encoding {
+        PositionEncoding::Utf8 => prefix.len(),
+        PositionEncoding::Utf16 => prefix.encode_utf16().count(),
+        PositionEncoding::Utf32 => prefix.chars().count(),
+    };
+    (line, column as u32)
+}
+
+#[test]
+fn test_astral_plane_characters() {
+    let text = "let 😀 = \"𝔘𝔫𝔦\";\nfn 中文() {}\n";
+    let source = SourceText::new(text);
+    let line_map = LineMap::from_source(&source);
+    assert_eq!(line_map.line_count(), 3);
+
+    for (offset, _) in text.char_indices() {
+        for encoding in ENCODINGS {
+            let (line, column) = line_map.offset_to_position(offset, encoding);
+            assert_eq!((line, column), naive_position(text, offset, encoding), "offset {offset} in {encoding:?}");
+            assert_eq!(line_map.position_to_offset(line, column, encoding), offset, "offset {offset} in {encoding:?}")
+        }
+    }
+
+    // The emoji occupies two UTF-16 code units but a single scalar value.
+    let after_emoji = text.find(" =").unwrap();
+    assert_eq!(line_map.offset_to_position(after_emoji, PositionEncoding::Utf16), (0, 6));
+    assert_eq!(line_map.offset_to_position(after_emoji, PositionEncoding::Utf32), (0, 5));
+}
+
+#[test]
+fn test_positions_inside_characters_snap_to_start() {
+    let source = SourceText::new("a😀b");
+    let line_map = LineMap::from_source(&source);
+    // Byte 2 is in the middle of the emoji.
+    assert_eq!(line_map.offset_to_position(2, PositionEncoding::Utf16), (0, 1));
+    // UTF-16 column 2 is between the two surrogates of the emoji.
+    assert_eq!(line_map.position_to_offset(0, 2, PositionEncoding::Utf16), 1);
+    assert_eq!(line_map.position_to_offset(0, 3, PositionEncoding::Utf16), 5)
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let text = "αβ\r\ngamma\r\n\r\nδ";
+    let source = SourceText::new(text);
+    let line_map = LineMap::from_source(&source);
+    assert_eq!(line_map.line_count(), 4);
+    assert_eq!(line_map.line_start(1), Some(6));
+    assert_eq!(line_map.line_content_end(0), Some(4));
+    assert_eq!(line_map.line_end(0), Some(6));
+    assert_eq!(line_map.line_content_end(2), Some(13));
+
+    for (offset, _) in text.char_indices() {
+        for encoding in ENCODINGS {
+            assert_eq!(line_map.offset_to_position(offset, encoding), naive_position(text, offset, encoding), "offset {offset} in {encoding:?}")
+        }
+    }
+
+    // Columns past the end of a line stop before the `\r\n` terminator.
+    assert_eq!(line_map.position_to_offset(0, 100, PositionEncoding::Utf16), 4);
+    assert_eq!(line_map.position_to_offset(0, 2, PositionEncoding::Utf32), 4);
+    assert_eq!(line_map.position_to_offset(2, 5, PositionEncoding::Utf8), 13);
+    // Lines past the end resolve to the end of the source.
+    assert_eq!(line_map.position_to_offset(9, 0, PositionEncoding::Utf16), text.len())
+}
+
+#[test]
+fn test_negotiate_position_encoding() {
+    assert_eq!(PositionEncoding::negotiate(&[]), PositionEncoding::Utf16);
+    assert_eq!(PositionEncoding::negotiate(&[PositionEncoding::Utf16, PositionEncoding::Utf8]), PositionEncoding::Utf16);
+    assert_eq!(PositionEncoding::negotiate(&[PositionEncoding::Utf8, PositionEncoding::Utf16]), PositionEncoding::Utf8);
+    assert_eq!(PositionEncoding::negotiate(&[PositionEncoding::Utf32, PositionEncoding::Utf16]), PositionEncoding::Utf32);
+    assert_eq!(PositionEncoding::from_name("utf-16"), Some(PositionEncoding::Utf16));
+    assert_eq!(PositionEncoding::Utf8.as_str(), "utf-8")
+}
+
+/// Creates an empty directory for a test under the system temp directory.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("oak-vfs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_disk_file_uris() {
+    use oak_vfs::{DiskVfs, Vfs};
+    let dir = temp_dir("uris");
+    std::fs::write(dir.join("a b.txt"), "hello").unwrap();
+    let vfs = DiskVfs::new(dir.clone());
+
+    let uri = DiskVfs::path_to_uri(&dir.join("a b.txt"));
+    assert!(uri.starts_with("file://") && uri.ends_with("a%20b.txt"));
+    assert_eq!(vfs.uri_to_path(&uri), Some(dir.join("a b.txt")));
+    assert_eq!(vfs.normalize_uri("a b.txt"), Some(uri.clone()));
+
+    let source = vfs.get_source(&uri).unwrap();
+    assert_eq!(source.text(), "hello");
+    let id = vfs.get_id(&uri).unwrap();
+    assert_eq!(vfs.get_source("a b.txt").unwrap().source_id(), Some(id));
+    assert_eq!(vfs.get_uri(id), Some(uri.clone()));
+    assert_eq!(vfs.read_dir(dir.to_str().unwrap()), Some(vec![uri]));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_disk_cache_invalidation() {
+    use oak_vfs::{DiskVfs, Vfs};
+    let dir = temp_dir("cache");
+    let path = dir.join("file.txt");
+    std::fs::write(&path, "one").unwrap();
+    let vfs = DiskVfs::new(dir.clone());
+    assert_eq!(vfs.get_source("file.txt").unwrap().text(), "one");
+
+    // A change of length is noticed even when the modification time has not moved.
+    std::fs::write(&path, "three").unwrap();
+    assert_eq!(vfs.get_source("file.txt").unwrap().text(), "three");
+
+    std::fs::write(&path, "four!").unwrap();
+    vfs.invalidate("file.txt");
+    assert_eq!(vfs.get_source("file.txt").unwrap().text(), "four!");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_overlay_precedence() {
+    use oak_vfs::{OverlayVfs, Vfs, WritableVfs};
+    let dir = temp_dir("overlay");
+    std::fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+    let vfs = OverlayVfs::new(dir.clone());
+    let uri = vfs.disk().normalize_uri("main.rs").unwrap();
+    let id = vfs.get_source(&uri).unwrap().source_id();
+
+    vfs.write_file(&uri, "fn main() { todo!() }".into());
+    assert!(vfs.is_open("main.rs"));
+    let source = vfs.get_source("main.rs").unwrap();
+    assert_eq!(source.text(), "fn main() { todo!() }");
+    assert_eq!(source.source_id(), id);
+    assert_eq!(std::fs::read_to_string(dir.join("main.rs")).unwrap(), "fn main() {}");
+
+    // Unsaved documents show up in their directory.
+    vfs.open("new.rs", "");
+    let new = vfs.disk().normalize_uri("new.rs").unwrap();
+    assert!(vfs.exists(&new) && !vfs.disk().exists(&new));
+    assert_eq!(vfs.read_dir(&vfs.disk().normalize_uri(dir.to_str().unwrap()).unwrap()).unwrap().len(), 2);
+
+    vfs.remove_file(&uri);
+    assert_eq!(vfs.get_source(&uri).unwrap().text(), "fn main() {}");
+    assert_eq!(vfs.get_id(&uri), id);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_archive() {
+    use oak_vfs::{FileType, Vfs, ZipVfs};
+    use std::io::Write;
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    writer.add_directory("com/example/", options).unwrap();
+    writer.start_file("com/example/Main.java", options).unwrap();
+    writer.write_all(b"class Main {}").unwrap();
+    writer.start_file("META-INF/MANIFEST.MF", options).unwrap();
+    writer.write_all(b"Manifest-Version: 1.0").unwrap();
+    writer.start_file("com/example/Main.class", options).unwrap();
+    writer.write_all(&[0xca, 0xfe, 0xba, 0xbe]).unwrap();
+    let archive = writer.finish().unwrap();
+
+    let vfs = ZipVfs::from_reader(archive, "zip:file:///lib.jar!/").unwrap();
+    assert_eq!(vfs.len(), 3);
+    let uri = "zip:file:///lib.jar!/com/example/Main.java";
+    let source = vfs.get_source(uri).unwrap();
+    assert_eq!(source.text(), "class Main {}");
+    assert_eq!(vfs.get_uri(source.source_id().unwrap()).as_deref(), Some(uri));
+    assert_eq!(vfs.get_source("com/example/Main.java").unwrap().source_id(), source.source_id());
+
+    // Binary entries have metadata but no source.
+    assert!(vfs.get_source("com/example/Main.class").is_none());
+    assert_eq!(vfs.metadata("com/example/Main.class").unwrap().len, 4);
+    assert_eq!(vfs.metadata("zip:file:///lib.jar!/com").unwrap().file_type, FileType::Directory);
+    assert!(vfs.is_dir("META-INF") && !vfs.exists("com/other"));
+
+    let mut root = vfs.read_dir(vfs.root()).unwrap();
+    root.sort();
+    assert_eq!(root.iter().map(|uri| uri.as_ref()).collect::<Vec<_>>(), ["zip:file:///lib.jar!/META-INF", "zip:file:///lib.jar!/com"]);
+    assert_eq!(vfs.read_dir("com/example").unwrap().len(), 2);
+    assert!(vfs.read_dir("file:///lib.jar").is_none());
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn test_tar_archive() {
+    use oak_vfs::{TarVfs, Vfs};
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, content) in [("serde-1.0.0/Cargo.toml", "[package]\n"), ("serde-1.0.0/src/lib.rs", "pub mod de;\n")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mtime(1_700_000_000);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+    }
+    let archive = builder.into_inner().unwrap();
+
+    let vfs = TarVfs::from_reader(archive.as_slice(), "tar:file:///serde-1.0.0.crate!/").unwrap();
+    assert_eq!(vfs.get_source("tar:file:///serde-1.0.0.crate!/serde-1.0.0/src/lib.rs").unwrap().text(), "pub mod de;\n");
+    assert_eq!(vfs.metadata("serde-1.0.0/Cargo.toml").unwrap().modified, Some(1_700_000_000));
+    assert_eq!(vfs.read_dir("serde-1.0.0").unwrap().len(), 2);
+    assert!(vfs.is_dir("serde-1.0.0/src"));
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_content_addressed_snapshot() {
+    use oak_vfs::{SnapshotVfs, Vfs};
+    let files = [("src/lib.rs", "mod a;"), ("src/a.rs", "pub fn f() {}"), ("tests/a.rs", "pub fn f() {}")];
+    let snapshot = SnapshotVfs::from_files(files);
+    assert_eq!(snapshot.len(), 3);
+    assert_eq!(snapshot.blob_count(), 2);
+    assert_eq!(snapshot.blob_hash("src/a.rs"), snapshot.blob_hash("tests/a.rs"));
+    assert!(snapshot.root().starts_with(&format!("snapshot://{}", snapshot.hash())));
+
+    // The same content in another order is the same snapshot.
+    let same = SnapshotVfs::from_files(files.iter().rev().copied());
+    assert_eq!(same.hash(), snapshot.hash());
+    assert_eq!(same.get_id("src/lib.rs"), snapshot.get_id("src/lib.rs"));
+    let changed = SnapshotVfs::from_files([("src/lib.rs", "mod b;"), ("src/a.rs", "pub fn f() {}"), ("tests/a.rs", "pub fn f() {}")]);
+    assert_ne!(changed.hash(), snapshot.hash());
+
+    let dir = temp_dir("snapshot");
+    for (path, content) in files {
+        std::fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+        std::fs::write(dir.join(path), content).unwrap();
+    }
+    std::fs::create_dir_all(dir.join(".git")).unwrap();
+    std::fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+    let captured = SnapshotVfs::capture(&dir).unwrap();
+    assert_eq!(captured.hash(), snapshot.hash());
+    let uri = format!("{}src/lib.rs", captured.root());
+    assert_eq!(captured.get_source(&uri).unwrap().text(), "mod a;");
+    assert!(!captured.exists(".git/HEAD"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_ignore_rules() {
+    use oak_vfs::IgnoreRules;
+    let rules = IgnoreRules::parse("# build output\ntarget/\n*.log\n!keep.log\n/dist\ndocs/**/*.png\nfile[0-9].txt\n");
+    assert!(rules.is_ignored("target", true));
+    assert!(rules.is_ignored("target/debug/oak", false));
+    assert!(rules.is_ignored("crates/a/target/x.rs", false));
+    assert!(!rules.is_ignored("target", false));
+    assert!(rules.is_ignored("logs/error.log", false));
+    assert!(!rules.is_ignored("logs/keep.log", false));
+    assert!(rules.is_ignored("dist/index.js", false));
+    assert!(!rules.is_ignored("src/dist/index.js", false));
+    assert!(rules.is_ignored("docs/a/b/c.png", false) && rules.is_ignored("docs/c.png", false));
+    assert!(!rules.is_ignored("docs/c.svg", false));
+    assert!(rules.is_ignored("file1.txt", false) && !rules.is_ignored("filex.txt", false));
+    assert!(!rules.is_ignored("src/main.rs", false));
+}
+
+#[test]
+fn test_disk_watcher() {
+    use oak_vfs::{DiskVfs, DiskWatcher, VfsEvent, VfsWatcher, WatchOptions};
+    use std::{sync::mpsc, time::Duration};
+
+    /// Collects events until none arrive for a while.
+    fn collect(receiver: &mpsc::Receiver<VfsEvent>) -> Vec<VfsEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.recv_timeout(Duration::from_millis(1000)) {
+            events.push(event)
+        }
+        events
+    }
+
+    let dir = temp_dir("watch");
+    std::fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let options = WatchOptions::default().with_debounce(Duration::from_millis(200)).with_ignore("*.tmp");
+    let mut watcher = DiskWatcher::with_options(options, move |event| sender.send(event).unwrap()).unwrap();
+    watcher.watch(DiskVfs::path_to_uri(&dir).as_ref()).unwrap();
+    assert_eq!(watcher.roots(), [DiskVfs::path_to_uri(&dir).to_string()]);
+    let uri = |name: &str| DiskVfs::path_to_uri(&dir.join(name)).to_string();
+
+    // A burst of writes is one event, and ignored paths are not reported.
+    std::fs::write(dir.join("a.txt"), "1").unwrap();
+    std::fs::write(dir.join("a.txt"), "12").unwrap();
+    std::fs::write(dir.join("a.txt"), "123").unwrap();
+    std::fs::write(dir.join("debug.log"), "").unwrap();
+    std::fs::write(dir.join("scratch.tmp"), "").unwrap();
+    std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+    std::fs::write(dir.join("target/debug/out"), "").unwrap();
+    assert_eq!(collect(&receiver), [VfsEvent::Created(uri("a.txt"))]);
+
+    std::fs::rename(dir.join("a.txt"), dir.join("b.txt")).unwrap();
+    assert_eq!(collect(&receiver), [VfsEvent::Renamed { from: uri("a.txt"), to: uri("b.txt") }]);
+
+    // A file created and removed in the same burst is not reported.
+    std::fs::write(dir.join("c.txt"), "").unwrap();
+    std::fs::remove_file(dir.join("c.txt")).unwrap();
+    std::fs::remove_file(dir.join("b.txt")).unwrap();
+    assert_eq!(collect(&receiver), [VfsEvent::Removed(uri("b.txt"))]);
+
+    watcher.unwatch_all().unwrap();
+    assert!(watcher.roots().is_empty());
+    std::fs::write(dir.join("d.txt"), "").unwrap();
+    assert_eq!(collect(&receiver), []);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_debouncer() {
+    use oak_vfs::Debouncer;
+    use std::time::{Duration, Instant};
+
+    let start = Instant::now();
+    let at = |millis: u64| start + Duration::from_millis(millis);
+    let mut debouncer = Debouncer::new(Duration::from_millis(50), Duration::from_millis(200));
+    assert_eq!(debouncer.deadline(), None);
+    assert!(!debouncer.is_ready(at(1000)));
+
+    // A burst is ready once it has been quiet for the debounce duration.
+    debouncer.push(1, at(0));
+    debouncer.push(2, at(30));
+    assert_eq!(debouncer.deadline(), Some(at(80)));
+    assert!(!debouncer.is_ready(at(79)) && debouncer.is_ready(at(80)));
+    assert_eq!(debouncer.take(), [1, 2]);
+    assert_eq!(debouncer.deadline(), None);
+
+    // A steady stream is still flushed after the maximum latency.
+    for (i, millis) in (1000..1200).step_by(40).enumerate() {
+        debouncer.push(i, at(millis));
+        assert!(!debouncer.is_ready(at(millis)));
+    }
+    assert_eq!(debouncer.deadline(), Some(at(1200)));
+    assert!(debouncer.is_ready(at(1200)));
+    assert_eq!(debouncer.take(), [0, 1, 2, 3, 4]);
+}
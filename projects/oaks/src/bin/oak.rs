@@ -0,0 +1,56 @@
+//! The `oak` language server: one process that serves every bundled Oak language
+//! over LSP or MCP.
+
+use clap::{Parser, Subcommand};
+use oak_vfs::OverlayVfs;
+use oaks::server::multi_language_service;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::net::TcpListener;
+
+#[derive(Parser)]
+#[command(name = "oak", version, about = "Language server for every Oak language")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs the Language Server Protocol server.
+    Lsp {
+        /// Accept LSP clients over TCP on this address instead of using stdio.
+        #[arg(long)]
+        tcp: Option<SocketAddr>,
+        /// Accept LSP clients over WebSocket on this address instead of using stdio.
+        #[arg(long, conflicts_with = "tcp")]
+        websocket: Option<SocketAddr>,
+    },
+    /// Runs the Model Context Protocol server.
+    Mcp {
+        /// Serve the streamable HTTP transport on this address instead of using stdio.
+        #[arg(long)]
+        http: Option<SocketAddr>,
+    },
+    /// Lists the languages this server handles.
+    Languages,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    // Open documents live in memory, everything else is read from the working directory.
+    let service = multi_language_service(OverlayVfs::new(std::env::current_dir()?));
+    match cli.command {
+        Command::Lsp { tcp: Some(address), .. } => oak_lsp::transport::serve_tcp(TcpListener::bind(address).await?, Arc::new(service)).await?,
+        Command::Lsp { websocket: Some(address), .. } => oak_lsp::transport::serve_websocket(TcpListener::bind(address).await?, Arc::new(service)).await?,
+        Command::Lsp { .. } => oak_lsp::LspServer::new(Arc::new(service)).run(tokio::io::stdin(), tokio::io::stdout()).await?,
+        Command::Mcp { http: Some(address) } => oak_mcp::McpServer::new(service).serve_http(TcpListener::bind(address).await?).await?,
+        Command::Mcp { http: None } => oak_mcp::McpServer::new(service).run(tokio::io::BufReader::new(tokio::io::stdin()), tokio::io::stdout()).await?,
+        Command::Languages => {
+            for language in service.languages() {
+                println!("{}", language)
+            }
+        }
+    }
+    Ok(())
+}
@@ -11,21 +11,28 @@
 
 /// Re-export of the core parsing and tree structures.
 pub use oak_core::*;
+/// Re-export of the code completion engine.
+pub use oak_completion::{Completion, CompletionKind, CompletionProvider, Snippet, UniversalCompletionProvider};
 /// Re-export of the folding range provider.
 pub use oak_folding::{FoldingProvider, FoldingRange};
 /// Re-export of the hover information provider.
-pub use oak_hover::{Hover, HoverProvider};
+pub use oak_hover::{Hover, HoverProvider, UniversalHoverProvider};
+/// Re-export of the lint engine.
+pub use oak_lint::{Interest, LintConfig, LintContext, LintRule, Linter};
 /// Re-export of the Language Server Protocol types and service.
 pub use oak_lsp::{FoldingRangeKind, LanguageService, Location, LspRange, Position, Range};
 /// Re-export of the definition and reference providers.
 pub use oak_navigation::{DefinitionProvider, ReferencesProvider};
 /// Re-export of the semantic tokens provider.
-pub use oak_semantic_tokens::{SemanticToken, SemanticTokensProvider};
+pub use oak_semantic_tokens::{SemanticToken, SemanticTokensProvider, UniversalSemanticTokensProvider};
 /// Re-export of the document structural view provider.
-pub use oak_structural_view::{StructureItem, StructureProvider};
+pub use oak_structural_view::{StructureItem, StructureProvider, UniversalStructureProvider, breadcrumbs};
 /// Re-export of the symbol information provider.
 pub use oak_symbols::{SymbolInformation, SymbolProvider};
 /// Re-export of the Virtual File System.
 pub use oak_vfs::{FileMetadata, FileType, MemoryVfs, Vfs};
 
 mod languages;
+/// The multi-language service behind the `oak` server binary.
+#[cfg(feature = "server")]
+pub mod server;
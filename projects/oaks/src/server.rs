@@ -0,0 +1,25 @@
+use oak_lsp::MultiLanguageService;
+use oak_vfs::WritableVfs;
+
+/// Creates a service that serves every language bundled with this crate.
+///
+/// All language services share the given VFS, so a document opened through the returned
+/// service is visible to the service of its language.
+pub fn multi_language_service<V: WritableVfs + Clone + Send + Sync + 'static>(vfs: V) -> MultiLanguageService<V> {
+    register_languages(MultiLanguageService::new(vfs.clone()), vfs)
+}
+
+/// Registers the services of the bundled languages, each on a clone of `vfs`.
+pub fn register_languages<V: WritableVfs + Clone + Send + Sync + 'static>(service: MultiLanguageService<V>, vfs: V) -> MultiLanguageService<V> {
+    service
+        .with_language("css", &["css"], oak_css::lsp::CssLanguageService::new(vfs.clone()))
+        .with_language("html", &["html", "htm", "xhtml"], oak_html::HtmlLanguageService::new(vfs.clone()))
+        .with_language("javascript", &["js", "mjs", "cjs", "jsx"], oak_javascript::lsp::JavaScriptLanguageService::new(vfs.clone()))
+        .with_language("json", &["json", "jsonc", "json5"], oak_json::JsonLanguageService::new(vfs.clone()))
+        .with_language("markdown", &["md", "markdown"], oak_markdown::lsp::MarkdownLanguageService::new(vfs.clone()))
+        .with_language("python", &["py", "pyi"], oak_python::lsp::PythonLanguageService::new(vfs.clone()))
+        .with_language("rust", &["rs"], oak_rust::RustLanguageService::new(vfs.clone()))
+        .with_language("sql", &["sql"], oak_sql::SqlLanguageService::new(vfs.clone()))
+        .with_language("toml", &["toml", "Cargo.lock"], oak_toml::TomlLanguageService::new(vfs.clone()))
+        .with_language("yaml", &["yaml", "yml"], oak_yaml::YamlLanguageService::new(vfs))
+}